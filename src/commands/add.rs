@@ -1,18 +1,39 @@
 use super::Command;
-use crate::rgl::{Config, ConfigCst, RemoteFilter, Session};
+use crate::fs::{copy_dir, read_json};
+use crate::rgl::{
+    backup_path, get_cache_dir, get_current_dir, get_settings_skeleton, normalize_path,
+    update_dependencies, Config, ConfigCst, FilterContext, FilterDefinition, RemoteFilter,
+    RemoteFilterConfig, Resolver, Session, SettingsSkeletonEntry,
+};
 use crate::{info, warn};
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::Args;
+use dialoguer::{theme::ColorfulTheme, Input, MultiSelect};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
 
-/// Add filter(s) to current project
+/// Add filter(s) to current project, prompts for filters if none are specified
 #[derive(Args)]
 pub struct Add {
-    #[arg(required = true)]
     filters: Vec<String>,
     #[arg(short, long, default_missing_value = "default", num_args = 0..)]
     profile: Vec<String>,
     #[arg(short, long)]
     force: bool,
+    /// Add a local directory as a filter, detecting its runtime from its contents
+    #[arg(long, value_name = "DIR", conflicts_with = "filters")]
+    path: Option<PathBuf>,
+    /// Copy the directory to `filters/<name>` instead of referencing it in place
+    #[arg(long, requires = "path")]
+    copy: bool,
+    /// Name of the filter, defaults to the directory name
+    #[arg(long, requires = "path")]
+    name: Option<String>,
 }
 
 impl Command for Add {
@@ -22,19 +43,29 @@ impl Command for Add {
         let mut session = Session::lock()?;
         let data_path = config.get_data_path();
 
-        for arg in &self.filters {
+        if let Some(path) = &self.path {
+            self.add_local(&config, &config_cst, path)?;
+            config_cst.save()?;
+            return session.unlock();
+        }
+        let (filters, profiles) = match self.filters.is_empty() {
+            true => pick_filters(&config, &self.profile)?,
+            false => (self.filters.to_owned(), self.profile.to_owned()),
+        };
+        for arg in &filters {
             info!("Adding filter <filter>{arg}</>...");
             let (filter_name, remote) = RemoteFilter::parse(arg)?;
-            remote.install(&filter_name, Some(&data_path), self.force)?;
-
-            for profile_name in &self.profile {
-                if config_cst.add_filter_to_profile(&filter_name, profile_name) {
-                    info!("Added filter <filter>{filter_name}</> to <profile>{profile_name}</> profile");
-                } else {
-                    warn!("Profile <profile>{profile_name}</> not found, skipping...")
-                }
-            }
+            remote.install(&filter_name, None, self.force)?;
+            remote.copy_data(&filter_name, &data_path, self.force)?;
+            let context =
+                FilterContext::new(&filter_name, &FilterDefinition::Remote(remote.clone()))?;
+            let settings = context
+                .remote_config
+                .and_then(|config| config.settings_schema)
+                .map(|schema| get_settings_skeleton(&schema))
+                .unwrap_or_default();
 
+            add_to_profiles(&config_cst, &filter_name, &profiles, &settings);
             config_cst.add_filter(&filter_name, remote);
             info!("Filter <filter>{filter_name}</> successfully added");
         }
@@ -46,3 +77,299 @@ impl Command for Add {
         "Error adding filter".to_owned()
     }
 }
+
+impl Add {
+    fn add_local(&self, config: &Config, config_cst: &ConfigCst, path: &Path) -> Result<()> {
+        if !path.is_dir() {
+            bail!("Directory <b>{}</> not found", path.display());
+        }
+        let name = match &self.name {
+            Some(name) => name.to_owned(),
+            None => normalize_path(path)?
+                .file_name()
+                .context("Failed to get directory name")?
+                .to_string_lossy()
+                .into_owned(),
+        };
+        if config.get_filter(&name).is_ok() && !self.force {
+            bail!("Filter <filter>{name}</> already exists, use --force to replace it");
+        }
+        info!("Adding local filter <filter>{name}</>...");
+
+        let filter_dir = match self.copy {
+            true => {
+                let target = Path::new("filters").join(&name);
+                // The target would be removed or copied into itself
+                let source = dunce::canonicalize(path)?;
+                let target_path = match dunce::canonicalize(&target) {
+                    Ok(target) => target,
+                    Err(_) => get_current_dir()?.join(&target),
+                };
+                if target_path.starts_with(&source) {
+                    bail!(
+                        "Directory <b>{}</> can't be copied to <b>{}</>, add it without --copy",
+                        path.display(),
+                        target.display()
+                    );
+                }
+                if target.exists() {
+                    if !self.force {
+                        bail!("Directory <b>{}</> already exists", target.display());
+                    }
+                    let backup = backup_path(&target)?;
+                    info!(
+                        "Replacing <b>{}</>, the old directory was moved to <b>{}</>",
+                        target.display(),
+                        backup.display()
+                    );
+                }
+                copy_dir(path, &target)?;
+                target
+            }
+            false => path.to_owned(),
+        };
+        let (definition, settings_schema) = detect_local_filter(&filter_dir)?;
+        let filter = FilterDefinition::from_value(definition.clone())?;
+        let context = FilterContext::new(&name, &filter)?;
+        if let FilterDefinition::Local(local) = &filter {
+            info!("Installing dependencies for <filter>{name}</>...");
+            update_dependencies(local, &context, true)?;
+        }
+
+        let settings = settings_schema
+            .map(|schema| get_settings_skeleton(&schema))
+            .unwrap_or_default();
+        add_to_profiles(config_cst, &name, &self.profile, &settings);
+        config_cst.add_filter_definition(&name, &definition);
+        info!(
+            "Filter <filter>{name}</> successfully added from <b>{}</>",
+            filter_dir.display()
+        );
+        Ok(())
+    }
+}
+
+fn add_to_profiles(
+    config_cst: &ConfigCst,
+    filter_name: &str,
+    profiles: &[String],
+    settings: &[SettingsSkeletonEntry],
+) {
+    for profile_name in profiles {
+        if config_cst.add_filter_to_profile(filter_name, profile_name, settings) {
+            info!("Added filter <filter>{filter_name}</> to <profile>{profile_name}</> profile");
+        } else {
+            warn!("Profile <profile>{profile_name}</> not found, skipping...")
+        }
+    }
+}
+
+/// Entry points that identify the runtime of a filter without a `filter.json`
+const ENTRY_POINTS: [(&str, &str); 6] = [
+    ("main.ts", "deno"),
+    ("index.ts", "deno"),
+    ("main.js", "nodejs"),
+    ("index.js", "nodejs"),
+    ("main.py", "python"),
+    ("main.go", "go"),
+];
+
+/// Returns the filter definition for the directory, and the settings schema from its
+/// `filter.json` if there's one.
+fn detect_local_filter(dir: &Path) -> Result<(Value, Option<Value>)> {
+    let filter_json = dir.join("filter.json");
+    if filter_json.is_file() {
+        let config: RemoteFilterConfig = read_json(&filter_json)?;
+        let [entry] = config.filters.as_slice() else {
+            bail!(
+                "Filter at <b>{}</> has more than one entry in filter.json, add it as a remote filter instead",
+                dir.display()
+            );
+        };
+        let mut definition = serde_json::to_value(&entry.filter)?;
+        // Paths in filter.json are relative to the filter directory
+        for key in ["script", "exe"] {
+            if let Some(path) = definition[key].as_str() {
+                definition[key] = Value::String(to_config_path(&dir.join(path)));
+            }
+        }
+        return Ok((definition, config.settings_schema));
+    }
+    for (file, run_with) in ENTRY_POINTS {
+        let script = dir.join(file);
+        if script.is_file() {
+            let definition = json!({ "runWith": run_with, "script": to_config_path(&script) });
+            return Ok((definition, None));
+        }
+    }
+    let expected = ENTRY_POINTS.map(|(file, _)| file).join(", ");
+    bail!(
+        "Couldn't detect the runtime of the filter\n\
+         <yellow> >></> Path: {}\n\
+         <yellow> >></> Expected filter.json or one of: {expected}",
+        dir.display()
+    )
+}
+
+/// Formats a path like the paths in `config.json`, e.g. `./filters/name/main.py`
+fn to_config_path(path: &Path) -> String {
+    let path = path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    match Path::new(&path).is_relative() && !path.starts_with("..") {
+        true => format!("./{path}"),
+        false => path,
+    }
+}
+
+struct FilterItem {
+    /// Argument passed to `RemoteFilter::parse`
+    arg: String,
+    label: String,
+}
+
+/// Prompts for filters from the cache and the resolver, and the profiles to add them to if
+/// none were specified.
+fn pick_filters(config: &Config, profiles: &[String]) -> Result<(Vec<String>, Vec<String>)> {
+    if !io::stdin().is_terminal() {
+        bail!("No filters specified");
+    }
+    let theme = ColorfulTheme::default();
+
+    let entries = Resolver::list().unwrap_or_else(|e| {
+        warn!("{e}, only showing recently used filters");
+        vec![]
+    });
+    let mut items = vec![];
+    for (name, url) in get_cached_filters() {
+        // Filters known to the resolver can be added by name
+        let resolved = entries.iter().any(|e| e.name == name && e.url == url);
+        items.push(FilterItem {
+            arg: match resolved {
+                true => name.to_owned(),
+                false => format!("{url}/{name}"),
+            },
+            label: format!("{name} (recently used, {url})"),
+        });
+    }
+    for entry in entries {
+        if items.iter().any(|item| item.arg == entry.name) {
+            continue;
+        }
+        let label = match entry.description {
+            Some(description) => format!("{} - {description}", entry.name),
+            None => entry.name.to_owned(),
+        };
+        items.push(FilterItem {
+            arg: entry.name,
+            label,
+        });
+    }
+    if items.is_empty() {
+        bail!("No filters available to pick from");
+    }
+
+    let query: String = Input::with_theme(&theme)
+        .with_prompt("Search filters")
+        .allow_empty(true)
+        .interact_text()?;
+    let items: Vec<_> = items
+        .into_iter()
+        .filter(|item| fuzzy_match(&query, &item.label))
+        .collect();
+    if items.is_empty() {
+        bail!("No filters matching <b>{query}</>");
+    }
+    let labels: Vec<_> = items.iter().map(|item| &item.label).collect();
+    let selected = MultiSelect::with_theme(&theme)
+        .with_prompt("Filters to add (space to select, enter to confirm)")
+        .items(&labels)
+        .interact()?;
+    if selected.is_empty() {
+        bail!("No filters selected");
+    }
+    let filters = selected
+        .into_iter()
+        .map(|i| items[i].arg.to_owned())
+        .collect();
+
+    if !profiles.is_empty() {
+        return Ok((filters, profiles.to_vec()));
+    }
+    let names: Vec<_> = config.get_profiles().keys().collect();
+    let defaults: Vec<_> = names.iter().map(|name| *name == "default").collect();
+    let selected = MultiSelect::with_theme(&theme)
+        .with_prompt("Profiles to add the filters to")
+        .items(&names)
+        .defaults(&defaults)
+        .interact()?;
+    let profiles = selected.into_iter().map(|i| names[i].to_owned()).collect();
+    Ok((filters, profiles))
+}
+
+/// Whether every character of the query appears in the text, in order
+fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|q| chars.any(|c| c == q))
+}
+
+/// Name and URL of the filters in the cache, most recently installed first
+fn get_cached_filters() -> Vec<(String, String)> {
+    fn visit(
+        dir: &Path,
+        components: &mut Vec<String>,
+        filters: &mut Vec<(Vec<String>, SystemTime)>,
+    ) {
+        if dir.join("filter.json").is_file() {
+            let modified = dir
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            filters.push((components.clone(), modified));
+            return;
+        }
+        // The deepest layout is `<host>/<user>/<repo>/<name>/<version>`
+        if components.len() >= 5 {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                components.push(entry.file_name().to_string_lossy().into_owned());
+                visit(&entry.path(), components, filters);
+                components.pop();
+            }
+        }
+    }
+
+    let Ok(cache_dir) = get_cache_dir() else {
+        return vec![];
+    };
+    let mut found = vec![];
+    visit(&cache_dir.join("filters"), &mut vec![], &mut found);
+    found.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    let mut filters: Vec<(String, String)> = vec![];
+    for (components, _) in found {
+        // Drop the version, multiple versions of the same filter are listed once
+        let [url @ .., name, _] = components.as_slice() else {
+            continue;
+        };
+        if url.is_empty() {
+            continue;
+        }
+        let filter = (name.to_owned(), url.join("/"));
+        if !filters.contains(&filter) {
+            filters.push(filter);
+        }
+    }
+    filters
+}
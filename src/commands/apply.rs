@@ -1,8 +1,7 @@
 use super::Command;
-use crate::fs::{copy_dir, empty_dir, sync_dir};
-use crate::info;
-use crate::rgl::{Config, Session, Temp};
-use anyhow::Result;
+use crate::rgl::{apply_temp_dir, run_in_temp, Config, Session};
+use crate::{info, log};
+use anyhow::{bail, Result};
 use clap::Args;
 
 /// Runs a profile and apply changes to the current project
@@ -19,34 +18,28 @@ impl Command for Apply {
         let bp = config.get_behavior_pack();
         let rp = config.get_resource_pack();
         let data = config.get_data_path();
-
-        let profile = config.get_profile(&self.profile)?;
-
-        let temp = Temp::from_dot_regolith();
-
-        empty_dir(&temp.root)?;
-        if let Some(bp) = &bp {
-            copy_dir(bp, &temp.bp)?;
-        }
-        if let Some(rp) = &rp {
-            copy_dir(rp, &temp.rp)?;
-        }
-        copy_dir(&data, &temp.data)?;
-
-        info!("Running <profile>{}</> profile", self.profile);
-        smol::block_on(profile.run(&config, &temp.root, &self.profile))?;
+        let symlinks = config.get_symlink_policy();
+        let (temp, failed_filters) = smol::block_on(run_in_temp(&config, &self.profile))?;
 
         info!("Applying changes to source directory:");
         if let Some(bp) = bp {
-            println!("\tBP: {}", bp.display());
-            sync_dir(temp.bp, bp)?;
+            log!("\tBP: {}", bp.display());
+            apply_temp_dir(&temp.bp, &bp, symlinks)?;
         }
         if let Some(rp) = rp {
-            println!("\tRP: {}", rp.display());
-            sync_dir(temp.rp, rp)?;
+            log!("\tRP: {}", rp.display());
+            apply_temp_dir(&temp.rp, &rp, symlinks)?;
+        }
+        apply_temp_dir(&temp.data, &data, symlinks)?;
+
+        if !failed_filters.is_empty() {
+            bail!(
+                "Applied profile <profile>{}</> with {} failed filter(s): {}",
+                self.profile,
+                failed_filters.len(),
+                failed_filters.join(", ")
+            );
         }
-        sync_dir(temp.data, data)?;
-
         info!("Successfully applied profile <profile>{}</>", self.profile);
         session.unlock()
     }
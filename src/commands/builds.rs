@@ -0,0 +1,89 @@
+use super::status::format_elapsed;
+use super::Command;
+use crate::rgl::{get_builds_dir, list_builds, prune_builds, Config};
+use crate::{info, log};
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use enum_dispatch::enum_dispatch;
+use indicatif::HumanBytes;
+
+/// Manage the archives in the builds directory
+#[derive(Args)]
+pub struct Builds {
+    #[command(subcommand)]
+    subcommand: BuildsSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum BuildsSubcommands {
+    List(BuildsList),
+    Clean(BuildsClean),
+}
+
+impl Command for Builds {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+/// List the archives, newest first
+#[derive(Args)]
+pub struct BuildsList;
+
+impl Command for BuildsList {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let builds = list_builds(&config)?;
+        if builds.is_empty() {
+            info!("No builds in {}", get_builds_dir(&config).display());
+            return Ok(());
+        }
+        for build in builds {
+            let elapsed = build.modified.elapsed().unwrap_or_default();
+            log!(
+                "{} <d>({}, {})</>",
+                build.path.display(),
+                HumanBytes(build.size),
+                format_elapsed(elapsed)
+            );
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error listing builds".to_owned()
+    }
+}
+
+/// Remove old archives of the project, keeping the number set with `--keep` or in `builds.keep`
+/// of the config
+#[derive(Args)]
+pub struct BuildsClean {
+    /// Number of archives of each kind to keep, overrides the config
+    #[arg(long)]
+    keep: Option<usize>,
+}
+
+impl Command for BuildsClean {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let Some(keep) = self.keep.or(config.get_builds().keep) else {
+            bail!(
+                "Number of builds to keep is not set\n\
+                 <yellow> >></> Pass <b>--keep</> or set <b>builds.keep</> in the config"
+            );
+        };
+        let removed = prune_builds(&config, keep)?;
+        for path in &removed {
+            log!("\tRemoved: {}", path.display());
+        }
+        info!("Removed {} build(s)", removed.len());
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error cleaning builds".to_owned()
+    }
+}
@@ -0,0 +1,160 @@
+use super::Command;
+use crate::fs::write_json;
+use crate::log;
+use crate::logger::Logger;
+use crate::rgl::{
+    find_mojang_dir, get_build_cache_dir, get_cache_dir, get_filters_cache_dir,
+    get_global_filters_path, get_repo_cache_dir, get_session_lock_path, get_temp,
+    get_user_config_path, normalize_path, Config, Export, ExportPaths, MinecraftBuild,
+};
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use enum_dispatch::enum_dispatch;
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::path::PathBuf;
+use strum::IntoEnumIterator;
+
+/// Inspect the files rgl keeps outside of the project
+#[derive(Args)]
+pub struct Cache {
+    #[command(subcommand)]
+    subcommand: CacheSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum CacheSubcommands {
+    Dir(CacheDir),
+}
+
+impl Command for Cache {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+/// Print where rgl keeps its files
+#[derive(Args)]
+pub struct CacheDir {
+    /// Profile used to resolve the temp and export directories
+    #[arg(short, long, default_value = "default")]
+    profile: String,
+    /// Print the paths as JSON
+    #[arg(long)]
+    json: bool,
+    /// Write the JSON to this file instead of printing it, so it's not mixed with other output
+    #[arg(long, value_name = "FILE", requires = "json")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalPaths {
+    cache_dir: PathBuf,
+    user_config: PathBuf,
+    global_filters: PathBuf,
+    filter_cache: PathBuf,
+    repo_cache: PathBuf,
+    build_cache: PathBuf,
+    /// com.mojang directory of each Minecraft build, if it can be resolved
+    mojang_dirs: IndexMap<String, Option<PathBuf>>,
+    /// Only set inside a project
+    project: Option<ProjectPaths>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectPaths {
+    data: PathBuf,
+    temp: PathBuf,
+    session_lock: PathBuf,
+    export_bp: Option<PathBuf>,
+    export_rp: Option<PathBuf>,
+}
+
+impl Command for CacheDir {
+    fn dispatch(&self) -> Result<()> {
+        let paths = GlobalPaths {
+            cache_dir: get_cache_dir()?,
+            user_config: get_user_config_path()?,
+            global_filters: get_global_filters_path()?,
+            filter_cache: get_filters_cache_dir()?,
+            repo_cache: get_repo_cache_dir()?,
+            build_cache: get_build_cache_dir()?,
+            mojang_dirs: MinecraftBuild::iter()
+                .map(|build| (build.to_string(), find_mojang_dir(Some(&build)).ok()))
+                .collect(),
+            project: match Config::exists() {
+                true => Some(self.get_project_paths()?),
+                false => None,
+            },
+        };
+        if let Some(output) = &self.output {
+            return write_json(output, &paths);
+        }
+        if self.json {
+            Logger::json(serde_json::to_value(&paths)?);
+            return Ok(());
+        }
+
+        let print = |name: &str, path: Option<&PathBuf>| match path {
+            Some(path) if path.exists() => log!("{name}: {}", path.display()),
+            Some(path) => log!("{name}: {} <d>(missing)</>", path.display()),
+            None => log!("{name}: <d>-</>"),
+        };
+        print("Cache directory", Some(&paths.cache_dir));
+        print("User config", Some(&paths.user_config));
+        print("Global filters", Some(&paths.global_filters));
+        print("Filter cache", Some(&paths.filter_cache));
+        print("Repository cache", Some(&paths.repo_cache));
+        print("Build cache", Some(&paths.build_cache));
+        for (build, path) in &paths.mojang_dirs {
+            print(&format!("com.mojang ({build})"), path.as_ref());
+        }
+        if let Some(project) = &paths.project {
+            print("Data", Some(&project.data));
+            print(
+                &format!("Temp (<profile>{}</>)", self.profile),
+                Some(&project.temp),
+            );
+            print("Session lock", Some(&project.session_lock));
+            print(
+                &format!("Export BP (<profile>{}</>)", self.profile),
+                project.export_bp.as_ref(),
+            );
+            print(
+                &format!("Export RP (<profile>{}</>)", self.profile),
+                project.export_rp.as_ref(),
+            );
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error getting rgl paths".to_owned()
+    }
+}
+
+impl CacheDir {
+    fn get_project_paths(&self) -> Result<ProjectPaths> {
+        let config = Config::load()?;
+        let profile = config.get_profile(&self.profile)?;
+        let (export_bp, export_rp) = match profile.export {
+            Export::None(_) => (None, None),
+            _ => {
+                let (bp, rp) = profile.export.get_paths(&config, &self.profile)?;
+                (Some(normalize_path(&bp)?), Some(normalize_path(&rp)?))
+            }
+        };
+        Ok(ProjectPaths {
+            data: normalize_path(&config.get_data_path())?,
+            temp: normalize_path(&get_temp(&config, profile)?.root)?,
+            session_lock: normalize_path(&get_session_lock_path())?,
+            export_bp: config.get_behavior_pack().and(export_bp),
+            export_rp: config.get_resource_pack().and(export_rp),
+        })
+    }
+}
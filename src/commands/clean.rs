@@ -1,7 +1,7 @@
 use super::Command;
 use crate::fs::rimraf;
 use crate::info;
-use crate::rgl::{Config, Session};
+use crate::rgl::{detach_packs, Config, Session};
 use anyhow::Result;
 use clap::Args;
 
@@ -14,6 +14,7 @@ impl Command for Clean {
         // Make sure it's a valid project
         let _ = Config::load()?;
         let mut session = Session::lock()?;
+        detach_packs()?;
         info!("Cleaning .regolith folder...");
         rimraf(".regolith")?;
         info!("Cleaning build files...");
@@ -0,0 +1,132 @@
+use super::Command;
+use crate::fs::{write_file, write_json};
+use crate::rgl::{Config, ConfigCst, Session};
+use crate::{info, warn};
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use dialoguer::{theme::ColorfulTheme, Select};
+use serde_json::json;
+use std::{fs, path::Path};
+use strum::Display;
+
+/// Create a new local filter in the filters directory
+#[derive(Args)]
+pub struct CreateFilter {
+    name: String,
+    /// Runtime of the filter, prompted if not specified
+    #[arg(short, long)]
+    runtime: Option<Runtime>,
+    #[arg(short, long, default_missing_value = "default", num_args = 0..)]
+    profile: Vec<String>,
+}
+
+#[derive(Clone, Copy, Display, ValueEnum)]
+#[strum(serialize_all = "lowercase")]
+enum Runtime {
+    Python,
+    Nodejs,
+    Deno,
+    Shell,
+}
+
+impl Command for CreateFilter {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let config_cst = ConfigCst::load()?;
+        let mut session = Session::lock()?;
+
+        let name = &self.name;
+        if config.get_filter(name).is_ok() {
+            bail!("Filter <filter>{name}</> already exists");
+        }
+        let filter_dir = Path::new("filters").join(name);
+        if filter_dir.exists() {
+            bail!("Directory <b>{}</> already exists", filter_dir.display());
+        }
+
+        let runtime = match self.runtime {
+            Some(runtime) => runtime,
+            None => {
+                let runtimes = Runtime::value_variants();
+                let index = Select::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Runtime")
+                    .items(runtimes)
+                    .default(0)
+                    .interact()?;
+                runtimes[index]
+            }
+        };
+
+        info!("Creating filter <filter>{name}</>...");
+        fs::create_dir_all(&filter_dir)?;
+        let script = match runtime {
+            Runtime::Python => Some(("main.py", PYTHON_TEMPLATE)),
+            Runtime::Nodejs => Some(("main.js", NODEJS_TEMPLATE)),
+            Runtime::Deno => Some(("main.ts", DENO_TEMPLATE)),
+            Runtime::Shell => None,
+        };
+        let run_with = runtime.to_string();
+        let (definition, filter_json) = match script {
+            Some((file_name, template)) => {
+                write_file(filter_dir.join(file_name), template)?;
+                (
+                    json!({ "runWith": run_with, "script": format!("./filters/{name}/{file_name}") }),
+                    json!({ "runWith": run_with, "script": format!("./{file_name}") }),
+                )
+            }
+            None => {
+                let command = format!("echo Running {name}");
+                (
+                    json!({ "runWith": run_with, "command": command }),
+                    json!({ "runWith": run_with, "command": command }),
+                )
+            }
+        };
+        write_json(
+            filter_dir.join("filter.json"),
+            &json!({ "filters": [filter_json] }),
+        )?;
+
+        // The template filters don't change anything, so the expected output equals the input
+        let example = json!({ "format_version": "1.21.90" });
+        for dir in ["input", "output"] {
+            let dir = filter_dir.join("tests/example").join(dir).join("BP");
+            fs::create_dir_all(&dir)?;
+            write_json(dir.join("example.json"), &example)?;
+        }
+
+        config_cst.add_filter_definition(name, &definition);
+        for profile_name in &self.profile {
+            if config_cst.add_filter_to_profile(name, profile_name, &[]) {
+                info!("Added filter <filter>{name}</> to <profile>{profile_name}</> profile");
+            } else {
+                warn!("Profile <profile>{profile_name}</> not found, skipping...")
+            }
+        }
+        config_cst.save()?;
+
+        info!(
+            "Filter <filter>{name}</> created at <b>{}</>",
+            filter_dir.display()
+        );
+        session.unlock()
+    }
+    fn error_context(&self) -> String {
+        format!("Error creating filter <filter>{}</>", self.name)
+    }
+}
+
+const PYTHON_TEMPLATE: &str = r#"import json
+import sys
+
+# The working directory contains the BP, RP and data folders
+settings = json.loads(sys.argv[1]) if len(sys.argv) > 1 else {}
+"#;
+
+const NODEJS_TEMPLATE: &str = r#"// The working directory contains the BP, RP and data folders
+const settings = process.argv[2] ? JSON.parse(process.argv[2]) : {};
+"#;
+
+const DENO_TEMPLATE: &str = r#"// The working directory contains the BP, RP and data folders
+const settings = Deno.args[0] ? JSON.parse(Deno.args[0]) : {};
+"#;
@@ -0,0 +1,199 @@
+use super::Command;
+use crate::fs::{is_dir_empty, read_json};
+use crate::rgl::{
+    get_filter_cache_dir, get_filter_runtime, is_runtime_installed, Config, FilterDefinition,
+    RemoteFilterConfig, RuntimePackage, Subprocess,
+};
+use crate::{info, log, warn};
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use enum_dispatch::enum_dispatch;
+use std::io::{self, IsTerminal};
+
+/// Manage the runtimes filters run with
+#[derive(Args)]
+pub struct Env {
+    #[command(subcommand)]
+    subcommand: EnvSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum EnvSubcommands {
+    Install(EnvInstall),
+}
+
+impl Command for Env {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+/// Install missing filter runtimes with the package manager of the platform
+#[derive(Args)]
+pub struct EnvInstall {
+    /// Runtimes to install, e.g. `node`, defaults to the ones the filters of the project need
+    runtimes: Vec<String>,
+    /// Install without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl Command for EnvInstall {
+    fn dispatch(&self) -> Result<()> {
+        let runtimes = match self.runtimes.is_empty() {
+            true => get_project_runtimes()?,
+            false => self.runtimes.to_owned(),
+        };
+        let mut packages: Vec<&RuntimePackage> = vec![];
+        for runtime in runtimes {
+            if is_runtime_installed(&runtime) {
+                continue;
+            }
+            match RuntimePackage::find(&runtime) {
+                Some(package) if !packages.iter().any(|p| p.program == package.program) => {
+                    packages.push(package)
+                }
+                Some(_) => {}
+                None => warn!("No package known for <b>{runtime}</>, install it manually"),
+            }
+        }
+        if packages.is_empty() {
+            info!("All filter runtimes are installed");
+            return Ok(());
+        }
+
+        let names = packages.iter().map(|p| p.program).collect::<Vec<_>>();
+        info!("Missing runtimes: {}", names.join(", "));
+        let Some(manager) = find_package_manager() else {
+            print_install_hints(&packages);
+            return Ok(());
+        };
+        let commands = packages
+            .iter()
+            .map(|package| manager.install_args(package))
+            .collect::<Vec<_>>();
+        for args in &commands {
+            log!("\t{} {}", manager.program(), args.join(" "));
+        }
+        if !self.yes {
+            if !io::stdin().is_terminal() {
+                bail!("Pass <b>--yes</> to install without confirmation");
+            }
+            let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("Install with {}?", manager.program()))
+                .default(true)
+                .interact()?;
+            if !confirmed {
+                return Ok(());
+            }
+        }
+        for (package, args) in packages.iter().zip(commands) {
+            info!("Installing <b>{}</>...", package.program);
+            Subprocess::new(manager.program()).args(args).run()?;
+        }
+        info!("Installed {}", names.join(", "));
+        warn!("Open a new terminal if the runtimes are not found, so PATH is updated");
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error installing runtimes".to_owned()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PackageManager {
+    Winget,
+    Scoop,
+    Brew,
+}
+
+impl PackageManager {
+    fn program(&self) -> &'static str {
+        match self {
+            Self::Winget => "winget",
+            Self::Scoop => "scoop",
+            Self::Brew => "brew",
+        }
+    }
+
+    fn install_args(&self, package: &RuntimePackage) -> Vec<String> {
+        let args: &[&str] = match self {
+            Self::Winget => &[
+                "install",
+                "--exact",
+                "--id",
+                package.winget,
+                "--accept-package-agreements",
+                "--accept-source-agreements",
+            ],
+            Self::Scoop => &["install", package.scoop],
+            Self::Brew => &["install", package.brew],
+        };
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+}
+
+fn find_package_manager() -> Option<PackageManager> {
+    let candidates: &[PackageManager] = match std::env::consts::OS {
+        "windows" => &[PackageManager::Winget, PackageManager::Scoop],
+        "macos" => &[PackageManager::Brew],
+        _ => &[],
+    };
+    candidates
+        .iter()
+        .find(|manager| {
+            Subprocess::new(manager.program())
+                .arg("--version")
+                .run_silent()
+                .is_ok()
+        })
+        .copied()
+}
+
+/// Prints how to install the runtimes where rgl can't, e.g. on Linux, where the package manager
+/// needs root
+fn print_install_hints(packages: &[&RuntimePackage]) {
+    let apt = packages.iter().filter_map(|p| p.apt).collect::<Vec<_>>();
+    if cfg!(target_os = "linux") && !apt.is_empty() {
+        log!("Install with apt:\n\tsudo apt install {}", apt.join(" "));
+    }
+    for package in packages {
+        if !cfg!(target_os = "linux") || package.apt.is_none() {
+            log!(
+                "Install <b>{}</> from {}",
+                package.program,
+                package.download
+            );
+        }
+    }
+}
+
+/// Runtimes of the local filters and installed remote filters of the project
+fn get_project_runtimes() -> Result<Vec<String>> {
+    let config = Config::load()?;
+    let mut runtimes = vec![];
+    for (name, definition) in config.get_filters()? {
+        match definition {
+            FilterDefinition::Local(filter) => runtimes.extend(get_filter_runtime(&filter)),
+            FilterDefinition::Remote(remote) => {
+                let filter_dir = get_filter_cache_dir(&name, &remote)?;
+                if is_dir_empty(&filter_dir)? {
+                    warn!("Filter <filter>{name}</> is not installed, run <b>rgl install</> to include its runtime");
+                    continue;
+                }
+                let remote_config: RemoteFilterConfig = read_json(filter_dir.join("filter.json"))?;
+                for entry in remote_config.filters {
+                    runtimes.extend(get_filter_runtime(&entry.filter));
+                }
+            }
+        }
+    }
+    runtimes.sort();
+    runtimes.dedup();
+    Ok(runtimes)
+}
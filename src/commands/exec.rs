@@ -1,7 +1,9 @@
 use super::Command;
-use crate::fs::{copy_dir, empty_dir, sync_dir};
-use crate::info;
-use crate::rgl::{Config, Filter, FilterContext, GlobalFilters, Session, Temp};
+use crate::fs::empty_dir;
+use crate::rgl::{
+    apply_temp_dir, copy_to_temp, Config, Filter, FilterContext, GlobalFilters, Session, Temp,
+};
+use crate::{info, log};
 use anyhow::Result;
 use clap::Args;
 
@@ -21,17 +23,18 @@ impl Command for Exec {
         let bp = config.get_behavior_pack();
         let rp = config.get_resource_pack();
         let data = config.get_data_path();
+        let symlinks = config.get_symlink_policy();
 
         let temp = Temp::from_dot_regolith();
 
         empty_dir(&temp.root)?;
         if let Some(bp) = &bp {
-            copy_dir(bp, &temp.bp)?;
+            copy_to_temp(bp, &temp.bp, symlinks)?;
         }
         if let Some(rp) = &rp {
-            copy_dir(rp, &temp.rp)?;
+            copy_to_temp(rp, &temp.rp, symlinks)?;
         }
-        copy_dir(&data, &temp.data)?;
+        copy_to_temp(&data, &temp.data, symlinks)?;
 
         if let Ok(filter) = config.get_filter(&self.filter) {
             info!("Running filter <filter>{}</>", self.filter);
@@ -47,14 +50,14 @@ impl Command for Exec {
 
         info!("Applying changes to source directory:");
         if let Some(bp) = bp {
-            println!("\tBP: {}", bp.display());
-            sync_dir(temp.bp, bp)?;
+            log!("\tBP: {}", bp.display());
+            apply_temp_dir(&temp.bp, &bp, symlinks)?;
         }
         if let Some(rp) = rp {
-            println!("\tRP: {}", rp.display());
-            sync_dir(temp.rp, rp)?;
+            log!("\tRP: {}", rp.display());
+            apply_temp_dir(&temp.rp, &rp, symlinks)?;
         }
-        sync_dir(temp.data, data)?;
+        apply_temp_dir(&temp.data, &data, symlinks)?;
 
         info!("Successfully executed filter <filter>{}</>", self.filter);
         session.unlock()
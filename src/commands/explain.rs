@@ -0,0 +1,34 @@
+use super::Command;
+use crate::log;
+use crate::rgl::ErrorCode;
+use anyhow::{bail, Result};
+use clap::Args;
+use strum::IntoEnumIterator;
+
+/// Explain an error code, or list all error codes
+#[derive(Args)]
+pub struct Explain {
+    /// Error code, e.g. RGL0001
+    code: Option<String>,
+}
+
+impl Command for Explain {
+    fn dispatch(&self) -> Result<()> {
+        let Some(code) = &self.code else {
+            for code in ErrorCode::iter() {
+                log!("<b>{}</> {}", code.code(), code.title());
+            }
+            return Ok(());
+        };
+        let Some(code) = ErrorCode::from_code(code) else {
+            bail!("Unknown error code <b>{code}</>, run <b>rgl explain</> to list all codes");
+        };
+        log!("<b>{}</> {}", code.code(), code.title());
+        log!("{}", code.help());
+        Ok(())
+    }
+
+    fn error_context(&self) -> String {
+        "Error explaining error code".to_owned()
+    }
+}
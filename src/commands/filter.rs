@@ -0,0 +1,52 @@
+use super::Command;
+use crate::rgl::test_filter;
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use enum_dispatch::enum_dispatch;
+use std::path::PathBuf;
+
+/// Tools for filter authors
+#[derive(Args)]
+pub struct Filter {
+    #[command(subcommand)]
+    subcommand: FilterSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum FilterSubcommands {
+    Test(FilterTest),
+}
+
+impl Command for Filter {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+/// Run the test cases in the `tests` directory of a filter
+#[derive(Args)]
+pub struct FilterTest {
+    /// Directory containing the filter.json
+    #[arg(default_value = ".")]
+    path: PathBuf,
+    /// Overwrite the expected output with the actual output
+    #[arg(long)]
+    update: bool,
+}
+
+impl Command for FilterTest {
+    fn dispatch(&self) -> Result<()> {
+        let failed = test_filter(&self.path, self.update)?;
+        if !failed.is_empty() {
+            bail!("{} test(s) failed: {}", failed.len(), failed.join(", "));
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error testing filter".to_owned()
+    }
+}
@@ -1,6 +1,6 @@
 use super::Command;
 use crate::info;
-use crate::rgl::{Config, Filter, FilterContext, FilterDefinition, Session};
+use crate::rgl::{update_dependencies, Config, FilterContext, FilterDefinition, Session};
 use anyhow::Result;
 use clap::Args;
 
@@ -17,15 +17,15 @@ impl Command for Get {
         let mut session = Session::lock()?;
         let data_path = config.get_data_path();
         for (name, filter) in config.get_filters()? {
-            match filter {
+            match &filter {
                 FilterDefinition::Remote(remote) => {
                     info!("Downloading filter <filter>{name}</>...");
                     remote.install(&name, Some(&data_path), false)?;
                 }
-                filter => {
+                FilterDefinition::Local(local) => {
                     info!("Installing dependencies for <filter>{name}</>...");
                     let context = FilterContext::new(&name, &filter)?;
-                    filter.install_dependencies(&context)?;
+                    update_dependencies(local, &context, true)?;
                 }
             };
         }
@@ -0,0 +1,206 @@
+use super::Command;
+use crate::logger::Logger;
+use crate::rgl::{Config, Export, FilterContext, FilterRunner, Workspace};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use std::path::Path;
+
+/// Print the relationships between profiles, filters, data and export targets
+#[derive(Args)]
+pub struct Graph {
+    #[arg(short, long, default_value = "dot")]
+    format: GraphFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Default)]
+struct GraphData {
+    /// ID, label and shape
+    nodes: Vec<(String, String, NodeShape)>,
+    /// From, to and label
+    edges: Vec<(String, String, Option<String>)>,
+}
+
+#[derive(Clone, Copy)]
+enum NodeShape {
+    Box,
+    Rounded,
+    Folder,
+}
+
+impl GraphData {
+    fn node(&mut self, id: String, label: String, shape: NodeShape) -> String {
+        if !self.nodes.iter().any(|(node_id, ..)| *node_id == id) {
+            self.nodes.push((id.to_owned(), label, shape));
+        }
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: Option<String>) {
+        self.edges.push((from.to_owned(), to.to_owned(), label));
+    }
+
+    fn to_dot(&self) -> String {
+        let mut out = String::from("digraph rgl {\n    rankdir=LR;\n");
+        for (id, label, shape) in &self.nodes {
+            let shape = match shape {
+                NodeShape::Box => "box",
+                NodeShape::Rounded => "box, style=rounded",
+                NodeShape::Folder => "folder",
+            };
+            out.push_str(&format!(
+                "    \"{id}\" [label=\"{label}\", shape={shape}];\n"
+            ));
+        }
+        for (from, to, label) in &self.edges {
+            match label {
+                Some(label) => out.push_str(&format!(
+                    "    \"{from}\" -> \"{to}\" [label=\"{label}\"];\n"
+                )),
+                None => out.push_str(&format!("    \"{from}\" -> \"{to}\";\n")),
+            }
+        }
+        out.push('}');
+        out
+    }
+
+    fn to_mermaid(&self) -> String {
+        // Mermaid IDs can't contain most special characters
+        let id = |id: &str| {
+            let index = self.nodes.iter().position(|(node_id, ..)| node_id == id);
+            format!("n{}", index.unwrap_or_default())
+        };
+        let mut out = String::from("flowchart LR\n");
+        for (node_id, label, shape) in &self.nodes {
+            let node_id = id(node_id);
+            let node = match shape {
+                NodeShape::Box => format!("{node_id}[\"{label}\"]"),
+                NodeShape::Rounded => format!("{node_id}(\"{label}\")"),
+                NodeShape::Folder => format!("{node_id}[/\"{label}\"/]"),
+            };
+            out.push_str(&format!("    {node}\n"));
+        }
+        for (from, to, label) in &self.edges {
+            let (from, to) = (id(from), id(to));
+            match label {
+                Some(label) => out.push_str(&format!("    {from} -->|\"{label}\"| {to}\n")),
+                None => out.push_str(&format!("    {from} --> {to}\n")),
+            }
+        }
+        out
+    }
+}
+
+impl Command for Graph {
+    fn dispatch(&self) -> Result<()> {
+        let mut graph = GraphData::default();
+        if Path::new("rgl-workspace.json").is_file() {
+            add_workspace(&mut graph, &Workspace::load()?);
+        }
+        if Config::exists() {
+            add_project(&mut graph, &Config::load()?)?;
+        }
+        match self.format {
+            GraphFormat::Dot => Logger::output(graph.to_dot()),
+            GraphFormat::Mermaid => Logger::output(graph.to_mermaid()),
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error generating graph".to_owned()
+    }
+}
+
+fn add_workspace(graph: &mut GraphData, workspace: &Workspace) {
+    for member in workspace.members() {
+        let path = member.path();
+        let id = graph.node(format!("member:{path}"), path.to_owned(), NodeShape::Folder);
+        for dependency in member.depends_on() {
+            graph.edge(
+                &id,
+                &format!("member:{dependency}"),
+                Some("depends on".to_owned()),
+            );
+        }
+    }
+}
+
+fn add_project(graph: &mut GraphData, config: &Config) -> Result<()> {
+    for (profile_name, profile) in config.get_profiles() {
+        let profile_id = graph.node(
+            format!("profile:{profile_name}"),
+            format!("profile {profile_name}"),
+            NodeShape::Rounded,
+        );
+        let target = match &profile.export {
+            Export::Development(_) => "development",
+            Export::Local(_) => "local",
+            Export::Exact(_) => "exact",
+            Export::None(_) => "none",
+            Export::World(_) => "world",
+        };
+        let export_id = graph.node(
+            format!("export:{target}"),
+            format!("{target} export"),
+            NodeShape::Folder,
+        );
+        graph.edge(&profile_id, &export_id, Some("exports to".to_owned()));
+
+        for (index, entry) in profile.filters.iter().enumerate() {
+            let position = Some(format!("#{}", index + 1));
+            match entry {
+                FilterRunner::Filter {
+                    filter_name,
+                    inputs,
+                    ..
+                } => {
+                    let filter_id = graph.node(
+                        format!("filter:{filter_name}"),
+                        filter_name.to_owned(),
+                        NodeShape::Box,
+                    );
+                    graph.edge(&profile_id, &filter_id, position);
+
+                    let filter = config.get_filter(filter_name)?;
+                    let context = FilterContext::new(filter_name, &filter)?;
+                    let mut inputs = inputs.clone().unwrap_or_default();
+                    if let Some(remote_config) = &context.remote_config {
+                        inputs.extend(remote_config.inputs.iter().cloned());
+                        if remote_config.export_data {
+                            let data = format!("data/{filter_name}");
+                            let data_id =
+                                graph.node(format!("data:{data}"), data, NodeShape::Folder);
+                            graph.edge(&filter_id, &data_id, Some("writes".to_owned()));
+                        }
+                    }
+                    // Filters that read data are shown as depending on it
+                    for input in inputs {
+                        let input = input.trim_start_matches("./").trim_end_matches('/');
+                        if input.starts_with("data") {
+                            let data_id = graph.node(
+                                format!("data:{input}"),
+                                input.to_owned(),
+                                NodeShape::Folder,
+                            );
+                            graph.edge(&data_id, &filter_id, Some("read by".to_owned()));
+                        }
+                    }
+                }
+                FilterRunner::ProfileFilter { profile_name } => {
+                    let nested_id = graph.node(
+                        format!("profile:{profile_name}"),
+                        format!("profile {profile_name}"),
+                        NodeShape::Rounded,
+                    );
+                    graph.edge(&profile_id, &nested_id, position);
+                }
+            }
+        }
+    }
+    Ok(())
+}
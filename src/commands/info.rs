@@ -1,4 +1,5 @@
 use super::Command;
+use crate::log;
 use crate::rgl::{get_cache_dir, get_global_filters_path, get_user_config_path};
 use anyhow::Result;
 use clap::{crate_version, Args};
@@ -7,13 +8,13 @@ use clap::{crate_version, Args};
 pub struct Info {}
 impl Command for Info {
     fn dispatch(&self) -> Result<()> {
-        println!("rgl version: {}", crate_version!());
-        println!("RGL_DIR location: {}", get_cache_dir()?.display());
-        println!(
+        log!("rgl version: {}", crate_version!());
+        log!("RGL_DIR location: {}", get_cache_dir()?.display());
+        log!(
             "User config location: {}",
             get_user_config_path()?.display()
         );
-        println!(
+        log!(
             "Global filters location: {}",
             get_global_filters_path()?.display()
         );
@@ -96,7 +96,7 @@ impl Command for Init {
             format!("{rp}/texts/en_US.lang"),
             create_lang(PackType::Resource, &name),
         )?;
-        write_file(".gitignore", "/build\n/.regolith\n")?;
+        write_file(".gitignore", "/build\n/builds\n/.regolith\n/.rgl\n")?;
 
         Config::new(name).save()?;
         info!("Project initialized");
@@ -0,0 +1,91 @@
+use super::Command;
+use crate::logger::Logger;
+use crate::rgl::{get_repo_cache_dir, Config, FilterContext, FilterDefinition};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use std::{fs, path::Path};
+
+/// Print the licenses of the remote filters used in the project
+#[derive(Args)]
+pub struct Licenses {
+    #[arg(short, long, default_value = "md")]
+    format: LicenseFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LicenseFormat {
+    Md,
+    Json,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilterLicense {
+    name: String,
+    url: String,
+    version: String,
+    /// License from `filter.json`
+    license: Option<String>,
+    /// Contents of the LICENSE file
+    license_text: Option<String>,
+}
+
+impl Command for Licenses {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+
+        let mut licenses = vec![];
+        for (name, definition) in config.get_filters()? {
+            let FilterDefinition::Remote(remote) = &definition else {
+                continue;
+            };
+            let context = FilterContext::new(&name, &definition)?;
+            // The LICENSE file is usually at the root of the repository
+            let license_text = find_license_file(&context.filter_dir)
+                .or_else(|| find_license_file(&get_repo_cache_dir().ok()?.join(&remote.url)));
+            licenses.push(FilterLicense {
+                license: context.remote_config.and_then(|cfg| cfg.license),
+                license_text,
+                name,
+                url: remote.url.to_owned(),
+                version: remote.version.to_owned(),
+            });
+        }
+
+        match self.format {
+            LicenseFormat::Json => Logger::json(serde_json::to_value(&licenses)?),
+            LicenseFormat::Md => {
+                let mut output = "# Filter licenses\n".to_owned();
+                for filter in licenses {
+                    output += &format!(
+                        "\n## {} ({}@{})\n\nLicense: {}\n",
+                        filter.name,
+                        filter.url,
+                        filter.version,
+                        filter.license.as_deref().unwrap_or("Unspecified")
+                    );
+                    if let Some(text) = filter.license_text {
+                        output += &format!("\n```\n{}\n```\n", text.trim_end());
+                    }
+                }
+                Logger::output(output.trim_end());
+            }
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error collecting filter licenses".to_owned()
+    }
+}
+
+fn find_license_file(dir: &Path) -> Option<String> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_uppercase();
+            name.starts_with("LICENSE") || name.starts_with("LICENCE")
+        })
+        .find_map(|entry| fs::read_to_string(entry.path()).ok())
+}
@@ -0,0 +1,41 @@
+use super::Command;
+use crate::rgl::{ConfigMigration, Session, CONFIG_FORMAT_VERSION};
+use crate::{info, log, success};
+use anyhow::Result;
+use clap::Args;
+
+/// Upgrade `config.json` to the current config format, keeping comments and formatting
+#[derive(Args)]
+pub struct MigrateConfig {
+    /// Print the changes without saving them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl Command for MigrateConfig {
+    fn dispatch(&self) -> Result<()> {
+        let mut session = Session::lock()?;
+        let Some(migration) = ConfigMigration::prepare()? else {
+            info!("config.json is already at format version {CONFIG_FORMAT_VERSION}");
+            return session.unlock();
+        };
+        info!(
+            "Migrating config.json from format version {} to {CONFIG_FORMAT_VERSION}:",
+            migration.from
+        );
+        migration.log_changes();
+        if self.dry_run {
+            log!("");
+            migration.log_diff();
+            info!("Dry run, config.json was not changed");
+            return session.unlock();
+        }
+        migration.save()?;
+        success!("Migrated config.json");
+        session.unlock()
+    }
+
+    fn error_context(&self) -> String {
+        "Error migrating config".to_owned()
+    }
+}
@@ -1,34 +1,69 @@
 mod add;
 mod apply;
+mod builds;
+mod cache;
 mod clean;
+mod create_filter;
+mod env;
 mod exec;
+mod explain;
+mod filter;
 mod get;
+mod graph;
 mod info;
 mod init;
 mod install;
+mod licenses;
 mod list;
+mod migrate_config;
+mod new;
+mod outdated;
+mod plugin;
+mod publish;
 mod remove;
 mod run;
+mod status;
 mod uninstall;
 mod update;
 mod upgrade;
+mod validate;
+mod vanilla;
 mod watch;
+mod why;
+mod world;
 
 pub use self::add::*;
 pub use self::apply::*;
+pub use self::builds::*;
+pub use self::cache::*;
 pub use self::clean::*;
+pub use self::create_filter::*;
+pub use self::env::*;
 pub use self::exec::*;
+pub use self::explain::*;
+pub use self::filter::*;
 pub use self::get::*;
+pub use self::graph::*;
 pub use self::info::*;
 pub use self::init::*;
 pub use self::install::*;
+pub use self::licenses::*;
 pub use self::list::*;
+pub use self::migrate_config::*;
+pub use self::new::*;
+pub use self::outdated::*;
+pub use self::publish::*;
 pub use self::remove::*;
 pub use self::run::*;
+pub use self::status::*;
 pub use self::uninstall::*;
 pub use self::update::*;
 pub use self::upgrade::*;
+pub use self::validate::*;
+pub use self::vanilla::*;
 pub use self::watch::*;
+pub use self::why::*;
+pub use self::world::*;
 
 use anyhow::Result;
 use enum_dispatch::enum_dispatch;
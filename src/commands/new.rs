@@ -0,0 +1,354 @@
+use super::Command;
+use crate::info;
+use crate::rgl::{get_content_template_dir, Config, ContentTemplate, Session};
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use enum_dispatch::enum_dispatch;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Create new content in the packs from templates. Project templates in `templates/<name>` of the
+/// data folder are used instead of the built-in ones when they exist.
+#[derive(Args)]
+pub struct New {
+    #[command(subcommand)]
+    subcommand: NewSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum NewSubcommands {
+    Block(NewBlock),
+    Entity(NewEntity),
+    Item(NewItem),
+}
+
+impl Command for New {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+#[derive(Args)]
+struct NewArgs {
+    /// Identifier of the content, e.g. `wiki:ghost`
+    identifier: String,
+    /// Project template to use, from `templates/<name>` in the data folder
+    #[arg(long, value_name = "NAME")]
+    template: Option<String>,
+    /// Variable for the template, in addition to identifier, namespace, name and display_name
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    variables: Vec<String>,
+    /// Overwrite the files of existing content
+    #[arg(long)]
+    force: bool,
+}
+
+/// Create an entity with its behavior, client entity, geometry, texture, names and spawn egg
+#[derive(Args)]
+pub struct NewEntity {
+    #[command(flatten)]
+    args: NewArgs,
+    /// Don't add a spawn egg, only used by the built-in template
+    #[arg(long)]
+    no_spawn_egg: bool,
+}
+
+impl Command for NewEntity {
+    fn dispatch(&self) -> Result<()> {
+        create_content("entity", &self.args, || entity_template(!self.no_spawn_egg))
+    }
+    fn error_context(&self) -> String {
+        format!("Error creating entity <b>{}</>", self.args.identifier)
+    }
+}
+
+/// Create an item with its icon and name
+#[derive(Args)]
+pub struct NewItem {
+    #[command(flatten)]
+    args: NewArgs,
+}
+
+impl Command for NewItem {
+    fn dispatch(&self) -> Result<()> {
+        create_content("item", &self.args, item_template)
+    }
+    fn error_context(&self) -> String {
+        format!("Error creating item <b>{}</>", self.args.identifier)
+    }
+}
+
+/// Create a block with its texture and name
+#[derive(Args)]
+pub struct NewBlock {
+    #[command(flatten)]
+    args: NewArgs,
+}
+
+impl Command for NewBlock {
+    fn dispatch(&self) -> Result<()> {
+        create_content("block", &self.args, block_template)
+    }
+    fn error_context(&self) -> String {
+        format!("Error creating block <b>{}</>", self.args.identifier)
+    }
+}
+
+/// Format version of the generated behavior pack files
+const FORMAT_VERSION: &str = "1.21.90";
+
+fn create_content(
+    kind: &str,
+    args: &NewArgs,
+    builtin: impl FnOnce() -> ContentTemplate,
+) -> Result<()> {
+    let config = Config::load()?;
+    let mut session = Session::lock()?;
+    let variables = get_variables(args)?;
+    let template = match &args.template {
+        Some(name) => {
+            let dir = get_content_template_dir(&config, name);
+            if !dir.is_dir() {
+                bail!(
+                    "Template <b>{name}</> not found\n\
+                     <yellow> >></> Path: {}",
+                    dir.display()
+                );
+            }
+            ContentTemplate::load(&dir)?
+        }
+        None => {
+            let dir = get_content_template_dir(&config, kind);
+            match dir.is_dir() {
+                true => ContentTemplate::load(&dir)?,
+                false => builtin(),
+            }
+        }
+    };
+
+    let id = &args.identifier;
+    info!("Creating {kind} <b>{id}</>...");
+    template.render(&config, &variables, args.force)?;
+    info!("Created {kind} <b>{id}</>");
+    session.unlock()
+}
+
+fn get_variables(args: &NewArgs) -> Result<BTreeMap<String, String>> {
+    let id = &args.identifier;
+    let Some((namespace, name)) = id.split_once(':') else {
+        bail!("Identifier must have a namespace, e.g. <b>wiki:{id}</>");
+    };
+    let is_valid = |part: &str| {
+        !part.is_empty()
+            && !part.contains("..")
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_.".contains(c))
+    };
+    if !is_valid(namespace) || !is_valid(name) {
+        bail!("Identifier can only contain lowercase letters, digits, `_` and single `.`");
+    }
+    if namespace == "minecraft" {
+        bail!("The <b>minecraft</> namespace is reserved for vanilla content");
+    }
+    let display_name = name
+        .split(['_', '.'])
+        .filter(|word| !word.is_empty())
+        .map(|word| word[..1].to_uppercase() + &word[1..])
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut variables = BTreeMap::from([
+        ("identifier".to_owned(), id.to_owned()),
+        ("namespace".to_owned(), namespace.to_owned()),
+        ("name".to_owned(), name.to_owned()),
+        ("display_name".to_owned(), display_name),
+    ]);
+    for variable in &args.variables {
+        let Some((key, value)) = variable.split_once('=') else {
+            bail!("Invalid variable <b>{variable}</>, expected KEY=VALUE");
+        };
+        // Values are used in the paths of the created files
+        if value.contains(['/', '\\']) || value.contains("..") {
+            bail!("Invalid variable <b>{variable}</>, values can't contain `/`, `\\` or `..`");
+        }
+        variables.insert(key.trim().to_owned(), value.to_owned());
+    }
+    Ok(variables)
+}
+
+fn entity_template(spawn_egg: bool) -> ContentTemplate {
+    let behavior = json!({
+        "format_version": FORMAT_VERSION,
+        "minecraft:entity": {
+            "description": {
+                "identifier": "{{identifier}}",
+                "is_spawnable": spawn_egg,
+                "is_summonable": true
+            },
+            "components": {
+                "minecraft:type_family": { "family": ["{{name}}", "mob"] },
+                "minecraft:health": { "value": 20, "max": 20 },
+                "minecraft:collision_box": { "width": 0.6, "height": 1.0 },
+                "minecraft:physics": {},
+                "minecraft:pushable": { "is_pushable": true, "is_pushable_by_piston": true },
+                "minecraft:movement": { "value": 0.25 },
+                "minecraft:movement.basic": {},
+                "minecraft:navigation.walk": { "avoid_water": true },
+                "minecraft:jump.static": {},
+                "minecraft:behavior.random_stroll": { "priority": 6, "speed_multiplier": 1.0 },
+                "minecraft:behavior.look_at_player": { "priority": 7, "look_distance": 6.0 }
+            }
+        }
+    });
+    let mut description = json!({
+        "identifier": "{{identifier}}",
+        "materials": { "default": "entity_alphatest" },
+        "textures": { "default": "textures/entity/{{namespace}}/{{name}}" },
+        "geometry": { "default": "geometry.{{namespace}}.{{name}}" },
+        "render_controllers": ["controller.render.default"]
+    });
+    let mut lang = "entity.{{identifier}}.name={{display_name}}\n".to_owned();
+    if spawn_egg {
+        description["spawn_egg"] = json!({ "base_color": "#4f8ad1", "overlay_color": "#1d3d66" });
+        lang.push_str("item.spawn_egg.entity.{{identifier}}.name=Spawn {{display_name}}\n");
+    }
+    let client = json!({
+        "format_version": "1.10.0",
+        "minecraft:client_entity": { "description": description }
+    });
+    // A single cube standing on the ground, to be replaced with the model of the entity
+    let geometry = json!({
+        "format_version": "1.12.0",
+        "minecraft:geometry": [{
+            "description": {
+                "identifier": "geometry.{{namespace}}.{{name}}",
+                "texture_width": 64,
+                "texture_height": 64,
+                "visible_bounds_width": 2,
+                "visible_bounds_height": 2,
+                "visible_bounds_offset": [0, 0.5, 0]
+            },
+            "bones": [{
+                "name": "body",
+                "pivot": [0, 0, 0],
+                "cubes": [{ "origin": [-4, 0, -4], "size": [8, 8, 8], "uv": [0, 0] }]
+            }]
+        }]
+    });
+    ContentTemplate::new(vec![
+        json_file("BP/entities/{{namespace}}/{{name}}.json", &behavior),
+        json_file("RP/entity/{{namespace}}/{{name}}.entity.json", &client),
+        json_file(
+            "RP/models/entity/{{namespace}}/{{name}}.geo.json",
+            &geometry,
+        ),
+        (
+            "RP/textures/entity/{{namespace}}/{{name}}.tga".to_owned(),
+            placeholder_texture(64),
+        ),
+        ("RP/texts/en_US.lang".to_owned(), lang.into_bytes()),
+        json_file("RP/texts/languages.json", &json!(["en_US"])),
+    ])
+}
+
+fn item_template() -> ContentTemplate {
+    let item = json!({
+        "format_version": FORMAT_VERSION,
+        "minecraft:item": {
+            "description": {
+                "identifier": "{{identifier}}",
+                "menu_category": { "category": "items" }
+            },
+            "components": {
+                "minecraft:icon": "{{namespace}}_{{name}}",
+                "minecraft:display_name": { "value": "item.{{identifier}}.name" },
+                "minecraft:max_stack_size": 64
+            }
+        }
+    });
+    let atlas = json!({
+        "texture_name": "atlas.items",
+        "texture_data": {
+            "{{namespace}}_{{name}}": { "textures": "textures/items/{{namespace}}/{{name}}" }
+        }
+    });
+    ContentTemplate::new(vec![
+        json_file("BP/items/{{namespace}}/{{name}}.json", &item),
+        (
+            "RP/textures/items/{{namespace}}/{{name}}.tga".to_owned(),
+            placeholder_texture(16),
+        ),
+        json_file("RP/textures/item_texture.json", &atlas),
+        (
+            "RP/texts/en_US.lang".to_owned(),
+            b"item.{{identifier}}.name={{display_name}}\n".to_vec(),
+        ),
+        json_file("RP/texts/languages.json", &json!(["en_US"])),
+    ])
+}
+
+fn block_template() -> ContentTemplate {
+    let block = json!({
+        "format_version": FORMAT_VERSION,
+        "minecraft:block": {
+            "description": {
+                "identifier": "{{identifier}}",
+                "menu_category": { "category": "construction" }
+            },
+            "components": {
+                "minecraft:geometry": "minecraft:geometry.full_block",
+                "minecraft:material_instances": {
+                    "*": { "texture": "{{namespace}}_{{name}}", "render_method": "opaque" }
+                },
+                "minecraft:destructible_by_mining": { "seconds_to_destroy": 1.5 },
+                "minecraft:destructible_by_explosion": { "explosion_resistance": 3 }
+            }
+        }
+    });
+    let atlas = json!({
+        "texture_name": "atlas.terrain",
+        "padding": 8,
+        "num_mip_levels": 4,
+        "texture_data": {
+            "{{namespace}}_{{name}}": { "textures": "textures/blocks/{{namespace}}/{{name}}" }
+        }
+    });
+    ContentTemplate::new(vec![
+        json_file("BP/blocks/{{namespace}}/{{name}}.json", &block),
+        (
+            "RP/textures/blocks/{{namespace}}/{{name}}.tga".to_owned(),
+            placeholder_texture(16),
+        ),
+        json_file("RP/textures/terrain_texture.json", &atlas),
+        (
+            "RP/texts/en_US.lang".to_owned(),
+            b"tile.{{identifier}}.name={{display_name}}\n".to_vec(),
+        ),
+        json_file("RP/texts/languages.json", &json!(["en_US"])),
+    ])
+}
+
+fn json_file(path: &str, value: &Value) -> (String, Vec<u8>) {
+    let contents = serde_json::to_string_pretty(value).unwrap_or_default() + "\n";
+    (path.to_owned(), contents.into_bytes())
+}
+
+/// Uncompressed 32-bit TGA filled with a single color, which the game loads like a PNG
+fn placeholder_texture(size: u16) -> Vec<u8> {
+    let mut data = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    data.extend(size.to_le_bytes());
+    data.extend(size.to_le_bytes());
+    // 32 bits per pixel, 8 of them alpha, stored from the top-left corner
+    data.extend([32, 0x28]);
+    // Blue like the spawn egg, in BGRA order
+    let pixel = [0xd1, 0x8a, 0x4f, 0xff];
+    for _ in 0..usize::from(size) * usize::from(size) {
+        data.extend(pixel);
+    }
+    data
+}
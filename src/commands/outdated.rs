@@ -0,0 +1,118 @@
+use super::Command;
+use crate::logger::Logger;
+use crate::rgl::{get_repo_cache_dir, Config, FilterDefinition, Resolver, Subprocess};
+use crate::{log, warn};
+use anyhow::Result;
+use clap::Args;
+use semver::Version;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// List remote filters that have a newer version available
+#[derive(Args)]
+pub struct Outdated {
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct OutdatedFilter {
+    name: String,
+    current: String,
+    latest: String,
+    /// Days since the current version was released, if known
+    age: Option<u64>,
+}
+
+impl Command for Outdated {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+
+        let mut outdated = vec![];
+        for (name, definition) in config.get_filters()? {
+            let FilterDefinition::Remote(remote) = definition else {
+                continue;
+            };
+            let latest =
+                match Resolver::resolve_version(&name, &remote.url, Some("latest".to_owned())) {
+                    Ok(latest) => latest,
+                    Err(e) => {
+                        // Keep the JSON output parsable
+                        if !self.json {
+                            warn!("Failed to get the latest version of <filter>{name}</>: {e}");
+                        }
+                        continue;
+                    }
+                };
+            if latest != remote.version {
+                outdated.push(OutdatedFilter {
+                    age: get_version_age(&name, &remote.url, &remote.version),
+                    name,
+                    current: remote.version,
+                    latest,
+                });
+            }
+        }
+
+        if self.json {
+            Logger::json(serde_json::to_value(&outdated)?);
+            return Ok(());
+        }
+        if outdated.is_empty() {
+            log!("<green>All filters are up to date</>");
+            return Ok(());
+        }
+        let width = |f: fn(&OutdatedFilter) -> usize, header: &str| {
+            outdated.iter().map(f).max().unwrap_or(0).max(header.len())
+        };
+        let name_width = width(|f| f.name.len(), "Name");
+        let current_width = width(|f| f.current.len(), "Current");
+        let latest_width = width(|f| f.latest.len(), "Latest");
+        log!(
+            "<green>{:name_width$}  {:current_width$}  {:latest_width$}  Age</>",
+            "Name",
+            "Current",
+            "Latest"
+        );
+        for filter in &outdated {
+            let age = match filter.age {
+                Some(days) => format!("{days} days"),
+                None => "-".to_owned(),
+            };
+            log!(
+                "{:name_width$}  {:current_width$}  <cyan>{:latest_width$}</>  {age}",
+                filter.name,
+                filter.current,
+                filter.latest
+            );
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error checking for outdated filters".to_owned()
+    }
+}
+
+/// Looks up the release date of the version in the cached repository
+fn get_version_age(name: &str, url: &str, version: &str) -> Option<u64> {
+    let repo_dir = get_repo_cache_dir().ok()?.join(url);
+    if !repo_dir.is_dir() {
+        return None;
+    }
+    let git_ref = match Version::parse(version) {
+        Ok(_) => format!("refs/tags/{name}-{version}"),
+        Err(_) => version.to_owned(),
+    };
+    let output = Subprocess::new("git")
+        .args(["log", "-1", "--format=%ct", &git_ref])
+        .current_dir(repo_dir)
+        .run_silent()
+        .ok()?;
+    let timestamp: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(now.saturating_sub(timestamp) / 86400)
+}
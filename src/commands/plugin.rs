@@ -0,0 +1,42 @@
+use super::Command;
+use crate::rgl::Config;
+use anyhow::{bail, Result};
+
+/// Commands that aren't built into rgl are looked up in the plugins of the project, the
+/// arguments start with the name of the command.
+impl Command for Vec<String> {
+    fn dispatch(&self) -> Result<()> {
+        let Some((command, args)) = self.split_first() else {
+            bail!("No command specified");
+        };
+        let plugins = match Config::exists() {
+            true => Config::load()?.get_plugins()?,
+            false => vec![],
+        };
+        let plugin = plugins
+            .iter()
+            .find(|plugin| plugin.manifest.commands.iter().any(|c| c.name == *command));
+        match plugin {
+            Some(plugin) => plugin.run_command(command, args),
+            None => {
+                let mut message = format!("Unrecognized command <b>{command}</>");
+                let commands = plugins
+                    .iter()
+                    .flat_map(|plugin| &plugin.manifest.commands)
+                    .map(|c| format!("\n<yellow> >></> {}: {}", c.name, c.description))
+                    .collect::<String>();
+                if !commands.is_empty() {
+                    message.push_str("\nCommands registered by plugins:");
+                    message.push_str(&commands);
+                }
+                bail!(message)
+            }
+        }
+    }
+    fn error_context(&self) -> String {
+        match self.first() {
+            Some(command) => format!("Error running command <b>{command}</>"),
+            None => "Error running command".to_owned(),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use super::Command;
+use crate::rgl::{
+    apply_build_retention, get_education_addon_path, get_marketplace_path, get_realms_world_path,
+    package_education, package_marketplace, package_realms_world, runner, validate, Config,
+    ExportPaths, FilterSelection, Session, Severity, UserConfig, ValidationContext, WorldRef,
+};
+use crate::{error, info, log, warn};
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use enum_dispatch::enum_dispatch;
+use std::path::PathBuf;
+
+/// Package the project for distribution
+#[derive(Args)]
+pub struct Publish {
+    #[command(subcommand)]
+    subcommand: PublishSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum PublishSubcommands {
+    Education(PublishEducation),
+    Marketplace(PublishMarketplace),
+    Realms(PublishRealms),
+}
+
+impl Command for Publish {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+/// Build a .mcworld with the packs embedded, ready to upload to a Realm
+#[derive(Args)]
+pub struct PublishRealms {
+    #[arg(default_value = "default")]
+    profile: String,
+    /// World to package, defaults to the world of the `world` export target
+    #[arg(long, value_name = "NAME")]
+    world: Option<String>,
+    /// Folder of the world to package in minecraftWorlds, or its path
+    #[arg(long, value_name = "DIR", conflicts_with = "world")]
+    world_dir: Option<String>,
+    /// Path of the .mcworld file, defaults to `<project name>-<version>.mcworld` in the builds
+    /// directory
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Enable this if filters are not working correctly
+    #[arg(long)]
+    compat: bool,
+}
+
+impl Command for PublishRealms {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let mut session = Session::lock()?;
+        smol::block_on(runner(
+            &config,
+            &self.profile,
+            false,
+            self.compat || UserConfig::force_compat(),
+            &[],
+            &FilterSelection::default(),
+        ))?;
+        let output = match &self.output {
+            Some(output) => output.to_owned(),
+            None => get_realms_world_path(&config),
+        };
+        let world = WorldRef::from_args(self.world.as_deref(), self.world_dir.as_deref());
+        package_realms_world(&config, &self.profile, world.as_ref(), &output)?;
+        if self.output.is_none() {
+            apply_build_retention(&config)?;
+        }
+        session.unlock()?;
+
+        log!("\tPath: {}", output.display());
+        info!("To upload it, import the file in Minecraft, then use <b>Replace World</> in the Realm settings");
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        format!(
+            "Error publishing <profile>{}</> profile to Realms",
+            self.profile
+        )
+    }
+}
+
+/// Build a .mcaddon for Education Edition, checking the packs against its restrictions
+#[derive(Args)]
+pub struct PublishEducation {
+    #[arg(default_value = "default")]
+    profile: String,
+    /// Path of the .mcaddon file, defaults to `<project name>-<version>.mcaddon` in the builds
+    /// directory
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Enable this if filters are not working correctly
+    #[arg(long)]
+    compat: bool,
+}
+
+impl Command for PublishEducation {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let profile = config.get_profile(&self.profile)?;
+        let mut session = Session::lock()?;
+        smol::block_on(runner(
+            &config,
+            &self.profile,
+            false,
+            self.compat || UserConfig::force_compat(),
+            &[],
+            &FilterSelection::default(),
+        ))?;
+
+        // The exported packs are checked, as filters generate and change files
+        let (target_bp, target_rp) = profile.export.get_paths(&config, &self.profile)?;
+        let mut context = ValidationContext::new(&config, Some(profile));
+        context.bp = context.bp.map(|_| target_bp);
+        context.rp = context.rp.map(|_| target_rp);
+        let diagnostics = validate(&context)?;
+        let errors = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            for diagnostic in &errors {
+                error!("{diagnostic}");
+            }
+            bail!(
+                "Found {} error(s) in the exported packs, run <b>rgl validate</> for details",
+                errors.len()
+            );
+        }
+        let output = match &self.output {
+            Some(output) => output.to_owned(),
+            None => get_education_addon_path(&config),
+        };
+        let size = package_education(&config, &self.profile, &output)?;
+        if self.output.is_none() {
+            apply_build_retention(&config)?;
+        }
+        session.unlock()?;
+
+        log!("\tPath: {}", output.display());
+        info!(
+            "Packaged the packs for Education Edition, {:.1} MB",
+            size as f64 / 1024.0 / 1024.0
+        );
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        format!(
+            "Error packaging <profile>{}</> profile for Education Edition",
+            self.profile
+        )
+    }
+}
+
+/// Build the Content folder of a Marketplace submission, with `contents.json` generated and the
+/// pack icons and texts checked
+#[derive(Args)]
+pub struct PublishMarketplace {
+    #[arg(default_value = "default")]
+    profile: String,
+    /// Directory of the submission, defaults to `build/<project name>_marketplace`
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Enable this if filters are not working correctly
+    #[arg(long)]
+    compat: bool,
+}
+
+impl Command for PublishMarketplace {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let mut session = Session::lock()?;
+        smol::block_on(runner(
+            &config,
+            &self.profile,
+            false,
+            self.compat || UserConfig::force_compat(),
+            &[],
+            &FilterSelection::default(),
+        ))?;
+        let output = match &self.output {
+            Some(output) => output.to_owned(),
+            None => get_marketplace_path(&config),
+        };
+        let diagnostics = package_marketplace(&config, &self.profile, &output)?;
+        session.unlock()?;
+
+        for diagnostic in diagnostics.iter() {
+            match diagnostic.severity {
+                Severity::Error => error!("{diagnostic}"),
+                Severity::Warning => warn!("{diagnostic}"),
+            }
+        }
+        let errors = diagnostics.count(Severity::Error);
+        if errors > 0 {
+            bail!("Found {errors} problem(s) the submission would be rejected for");
+        }
+        log!("\tPath: {}", output.display());
+        info!("Packaged the packs for the Marketplace");
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        format!(
+            "Error packaging <profile>{}</> profile for the Marketplace",
+            self.profile
+        )
+    }
+}
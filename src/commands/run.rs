@@ -1,12 +1,16 @@
 use super::Command;
-use crate::rgl::{runner, Config, Session, UserConfig};
-use anyhow::Result;
+use crate::rgl::{
+    attach_packs, detach_packs, runner, touch_manifests, Config, Export, FilterSelection, Profile,
+    Session, UserConfig, Workspace, WorldRef,
+};
+use anyhow::{bail, Context, Result};
 use clap::Args;
+use std::io::{self, Read};
 
 /// Runs rgl with specified profile
 #[derive(Args)]
 pub struct Run {
-    #[arg(default_value = "default")]
+    #[arg(default_value = "default", conflicts_with = "inline")]
     profile: String,
     /// Removes previous run output before running
     #[arg(long)]
@@ -14,23 +18,108 @@ pub struct Run {
     /// Enable this if filters are not working correctly
     #[arg(long)]
     compat: bool,
+    /// Run the profile in every member of the workspace
+    #[arg(long)]
+    workspace: bool,
+    /// Attach the exported development packs to the world with this name
+    #[arg(long, value_name = "NAME", conflicts_with = "workspace")]
+    world: Option<String>,
+    /// Attach the exported development packs to the world in this folder of minecraftWorlds, or
+    /// at this path
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["workspace", "world"])]
+    world_dir: Option<String>,
+    /// Only run these filters, or a range of them like `first..last`
+    #[arg(long = "filter", value_name = "NAME")]
+    filters: Vec<String>,
+    /// Don't run these filters
+    #[arg(long, value_name = "NAME")]
+    skip: Vec<String>,
+    /// Run this JSON or YAML profile instead of one from the config, or read it from stdin with
+    /// `-`. It runs as the `inline` profile, must export to `exact` paths or `none`, and is not
+    /// saved.
+    #[arg(long, value_name = "PROFILE", conflicts_with = "workspace")]
+    inline: Option<String>,
 }
 
 impl Command for Run {
     fn dispatch(&self) -> Result<()> {
-        let config = Config::load()?;
+        if self.workspace {
+            let mut args = vec!["run".to_owned(), self.profile.to_owned()];
+            if self.clean {
+                args.push("--clean".to_owned());
+            }
+            if self.compat {
+                args.push("--compat".to_owned());
+            }
+            for filter in &self.filters {
+                args.extend(["--filter".to_owned(), filter.to_owned()]);
+            }
+            for filter in &self.skip {
+                args.extend(["--skip".to_owned(), filter.to_owned()]);
+            }
+            return Workspace::load()?.run(&args);
+        }
+        let mut config = Config::load()?;
+        if let Some(source) = &self.inline {
+            let source = match source.as_str() {
+                "-" => {
+                    let mut source = String::new();
+                    io::stdin()
+                        .read_to_string(&mut source)
+                        .context("Failed to read profile from stdin")?;
+                    source
+                }
+                _ => source.to_owned(),
+            };
+            let mut profile = Profile::parse(&source)?;
+            // The default paths of the other targets are shared with the profiles in the config
+            if !matches!(profile.export, Export::Exact(_) | Export::None(_)) {
+                bail!(
+                    "Inline profiles must export to explicit paths\n\
+                     <yellow> >></> Use `\"export\": {{ \"target\": \"exact\", \"bpPath\": ..., \"rpPath\": ... }}`, or `\"target\": \"none\"`"
+                );
+            }
+            profile.inline = true;
+            config.insert_profile(INLINE_PROFILE, profile);
+        }
+        let profile = self.profile_name();
         let mut session = Session::lock()?;
+        if self.clean {
+            detach_packs()?;
+        }
+        if let Export::World(world) = &config.get_profile(profile)?.export {
+            world.pick_world()?;
+        }
 
         smol::block_on(runner(
             &config,
-            &self.profile,
+            profile,
             self.clean,
             self.compat || UserConfig::force_compat(),
+            &[],
+            &FilterSelection::new(&self.filters, &self.skip),
         ))?;
+        if UserConfig::touch_manifests() {
+            touch_manifests(&config, profile)?;
+        }
+        if let Some(world) = WorldRef::from_args(self.world.as_deref(), self.world_dir.as_deref()) {
+            attach_packs(&config, profile, &world)?;
+        }
 
         session.unlock()
     }
     fn error_context(&self) -> String {
-        format!("Error running <profile>{}</> profile", self.profile)
+        format!("Error running <profile>{}</> profile", self.profile_name())
+    }
+}
+
+const INLINE_PROFILE: &str = "inline";
+
+impl Run {
+    fn profile_name(&self) -> &str {
+        match self.inline {
+            Some(_) => INLINE_PROFILE,
+            None => &self.profile,
+        }
     }
 }
@@ -0,0 +1,126 @@
+use super::Command;
+use crate::fs::is_dir_empty;
+use crate::logger::Logger;
+use crate::rgl::{find_active_sessions, get_filter_cache_dir, Config, FilterDefinition, RunState};
+use crate::{error, info, log, warn};
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use std::{path::PathBuf, time::Duration};
+
+/// Show an overview of the project: filters that are not installed, running instances, and the
+/// last run of each profile
+#[derive(Args)]
+pub struct Status {
+    /// Print the status as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusReport {
+    uninstalled_filters: Vec<String>,
+    active_sessions: Vec<ActiveSession>,
+    #[serde(flatten)]
+    state: RunState,
+}
+
+#[derive(Serialize)]
+struct ActiveSession {
+    path: PathBuf,
+    pid: Option<String>,
+}
+
+impl Command for Status {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let mut uninstalled_filters = vec![];
+        for (name, definition) in config.get_filters()? {
+            if let FilterDefinition::Remote(remote) = definition {
+                if is_dir_empty(&get_filter_cache_dir(&name, &remote)?)? {
+                    uninstalled_filters.push(name);
+                }
+            }
+        }
+        let report = StatusReport {
+            uninstalled_filters,
+            active_sessions: find_active_sessions()?
+                .into_iter()
+                .map(|(path, pid)| ActiveSession { path, pid })
+                .collect(),
+            state: RunState::load()?,
+        };
+        if self.json {
+            Logger::json(serde_json::to_value(&report)?);
+            return Ok(());
+        }
+
+        if !report.uninstalled_filters.is_empty() {
+            warn!(
+                "Filters not installed: {}, run <b>rgl install</> to install them",
+                report.uninstalled_filters.join(", ")
+            );
+        }
+        for session in &report.active_sessions {
+            match &session.pid {
+                Some(pid) => info!("rgl is running in this project (PID {pid})"),
+                None => info!("rgl is running in this project"),
+            }
+            log!("\tLock: {}", session.path.display());
+        }
+
+        let state = &report.state;
+        for name in config.get_profiles().keys() {
+            let record = state.profiles.get(name);
+            let failure = state.failures.get(name);
+            match record {
+                Some(record) => {
+                    let elapsed = record.finished_at().elapsed().unwrap_or_default();
+                    log!("<profile>{name}</>: last run {}", format_elapsed(elapsed));
+                }
+                None if failure.is_some() => log!("<profile>{name}</>: never succeeded"),
+                None => log!("<profile>{name}</>: <d>never run</>"),
+            }
+            if let Some(failure) = failure {
+                let elapsed = failure.failed_at().elapsed().unwrap_or_default();
+                error!("Failed {}: {}", format_elapsed(elapsed), failure.error);
+            }
+            let Some(record) = record else {
+                continue;
+            };
+            if let Some(commit) = &record.git_commit {
+                log!("\tCommit: {}", &commit[..commit.len().min(7)]);
+            }
+            for (filter, version) in &record.filter_versions {
+                log!("\tFilter: <filter>{filter}</> {version}");
+            }
+            for target in &record.export_targets {
+                match target.exists() {
+                    true => log!("\tExport: {}", target.display()),
+                    false => log!("\tExport: {} <d>(missing)</>", target.display()),
+                }
+            }
+            if let Some(path) = record.find_newer_source(&config) {
+                warn!(
+                    "The export of <profile>{name}</> is older than the sources, e.g. {}",
+                    path.display()
+                );
+            }
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Error getting the project status".to_owned()
+    }
+}
+
+pub(super) fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => "just now".to_owned(),
+        60..3600 => format!("{} minute(s) ago", secs / 60),
+        3600..86400 => format!("{} hour(s) ago", secs / 3600),
+        _ => format!("{} day(s) ago", secs / 86400),
+    }
+}
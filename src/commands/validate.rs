@@ -0,0 +1,67 @@
+use super::Command;
+use crate::rgl::{run_in_temp, validate, Config, Session, Severity, ValidationContext};
+use crate::{error, info, warn};
+use anyhow::{bail, Result};
+use clap::Args;
+
+/// Check the packs for common mistakes that the game only reports at runtime, if at all
+#[derive(Args)]
+pub struct Validate {
+    /// Profile whose output is checked, defaults to the `default` profile
+    #[arg(short, long)]
+    profile: Option<String>,
+    /// Check the source packs instead of the output of the profile's filters
+    #[arg(long)]
+    source: bool,
+}
+
+impl Command for Validate {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let profile_name = match &self.profile {
+            Some(profile_name) => Some(profile_name.as_str()),
+            None => config
+                .get_profiles()
+                .contains_key("default")
+                .then_some("default"),
+        };
+        let profile = profile_name
+            .map(|profile_name| config.get_profile(profile_name))
+            .transpose()?;
+        let mut context = ValidationContext::new(&config, profile);
+        let mut session = None;
+        // Filters generate and change files, so their output is what the game loads
+        if let (Some(profile_name), false) = (profile_name, self.source) {
+            session = Some(Session::lock()?);
+            let (temp, failed_filters) = smol::block_on(run_in_temp(&config, profile_name))?;
+            if !failed_filters.is_empty() {
+                warn!(
+                    "Validating the output of the profile with failed filter(s): {}",
+                    failed_filters.join(", ")
+                );
+            }
+            context.bp = context.bp.map(|_| temp.bp);
+            context.rp = context.rp.map(|_| temp.rp);
+        }
+        let diagnostics = validate(&context)?;
+        if let Some(mut session) = session {
+            session.unlock()?;
+        }
+        for diagnostic in diagnostics.iter() {
+            match diagnostic.severity {
+                Severity::Error => error!("{diagnostic}"),
+                Severity::Warning => warn!("{diagnostic}"),
+            }
+        }
+        let errors = diagnostics.count(Severity::Error);
+        let warnings = diagnostics.count(Severity::Warning);
+        if errors > 0 {
+            bail!("Found {errors} error(s) and {warnings} warning(s)");
+        }
+        info!("Validation passed with {warnings} warning(s)");
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        "Validation failed".to_owned()
+    }
+}
@@ -0,0 +1,130 @@
+use super::Command;
+use crate::rgl::{
+    fetch_vanilla_samples, get_min_engine_version, resolve_vanilla_samples_tag, Config,
+};
+use crate::{info, log, warn};
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use enum_dispatch::enum_dispatch;
+use std::{fs, path::PathBuf};
+use walkdir::WalkDir;
+
+/// Fetch vanilla files from the official bedrock-samples repository
+#[derive(Args)]
+pub struct Vanilla {
+    #[command(subcommand)]
+    subcommand: VanillaSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum VanillaSubcommands {
+    Get(VanillaGet),
+}
+
+impl Command for Vanilla {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+/// Copy a vanilla file or directory into the project, e.g. `behavior_pack/entities/player.json`
+#[derive(Args)]
+pub struct VanillaGet {
+    /// Path in bedrock-samples
+    path: String,
+    /// Release tag or branch to fetch from, defaults to the release matching the `min_engine_version` of the
+    /// project
+    #[arg(long = "ref", value_name = "REF")]
+    git_ref: Option<String>,
+    /// Copy into this directory instead of the project's packs
+    #[arg(long, value_name = "DIR")]
+    out: Option<PathBuf>,
+    /// Overwrite existing files
+    #[arg(short, long)]
+    force: bool,
+}
+
+impl Command for VanillaGet {
+    fn dispatch(&self) -> Result<()> {
+        let config = match Config::exists() {
+            true => Some(Config::load()?),
+            false => None,
+        };
+        let git_ref = match &self.git_ref {
+            Some(git_ref) => git_ref.to_owned(),
+            None => {
+                let min_engine_version = config.as_ref().and_then(|config| {
+                    [config.get_behavior_pack(), config.get_resource_pack()]
+                        .into_iter()
+                        .flatten()
+                        .find_map(|pack| get_min_engine_version(&pack))
+                });
+                resolve_vanilla_samples_tag(min_engine_version.as_ref())?
+            }
+        };
+        let path = self.path.trim_matches('/');
+        let target = self.get_target(config.as_ref(), path)?;
+        let source = fetch_vanilla_samples(&git_ref, path)?;
+
+        let mut copied = 0;
+        let mut skipped = 0;
+        for entry in WalkDir::new(&source) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&source)?;
+            // A fetched file is copied to the target itself
+            let target = match relative.as_os_str().is_empty() {
+                true => target.to_owned(),
+                false => target.join(relative),
+            };
+            if target.exists() && !self.force {
+                skipped += 1;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(entry.path(), &target).with_context(|| {
+                format!(
+                    "Failed to copy vanilla file\n\
+                     <yellow> >></> Path: {}",
+                    target.display()
+                )
+            })?;
+            copied += 1;
+        }
+        if skipped > 0 {
+            warn!("Skipped {skipped} existing file(s), use <b>--force</> to overwrite them");
+        }
+        info!("Copied {copied} vanilla file(s) from bedrock-samples <b>{git_ref}</>");
+        log!("\tPath: {}", target.display());
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        format!("Error getting vanilla <yellow>{}</>", self.path)
+    }
+}
+
+impl VanillaGet {
+    /// Where the fetched path is copied to. Pack files keep their path inside the pack.
+    fn get_target(&self, config: Option<&Config>, path: &str) -> Result<PathBuf> {
+        if let Some(out) = &self.out {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            return Ok(out.join(name));
+        }
+        let (pack, rest) = path.split_once('/').unwrap_or((path, ""));
+        let pack_dir = match (pack, config) {
+            ("behavior_pack", Some(config)) => config.get_behavior_pack(),
+            ("resource_pack", Some(config)) => config.get_resource_pack(),
+            _ => bail!("Only pack files can be copied into a project, use <b>--out</> to choose a directory"),
+        };
+        let pack_dir = pack_dir.with_context(|| format!("The project has no {pack}"))?;
+        Ok(pack_dir.join(rest))
+    }
+}
@@ -1,80 +1,461 @@
 use super::Command;
-use crate::rgl::{runner, Config, MinecraftServer, Session, UserConfig};
+use crate::rgl::{
+    get_current_dir, get_temp, runner, set_profile_scope, sync_assets, touch_manifests,
+    watch_content_log, Config, Dashboard, Export, ExportPaths, FilterSelection, GameEvent,
+    MinecraftServer, Plugin, ProfileSwitcher, Session, SourceMaps, Subprocess, UserConfig,
+    Workspace,
+};
 use crate::{error, info, log, warn};
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Args;
-use std::time::Duration;
+use std::{collections::HashMap, env, path::PathBuf, sync::Arc, thread, time::Duration};
 
 /// Watch for file changes and restart automatically
 #[derive(Args)]
 pub struct Watch {
+    /// Profiles to switch between while watching, the first one is run initially
     #[arg(default_value = "default")]
-    profile: String,
+    profiles: Vec<String>,
     /// Removes previous run output before running
     #[arg(long)]
     clean: bool,
+    /// Removes previous run output before the first run only
+    #[arg(long, conflicts_with = "clean")]
+    initial_clean_only: bool,
     /// Enable this if filters are not working correctly
     #[arg(long)]
     compat: bool,
     /// Automatically reload scripts via WebSocket
     #[arg(long)]
     ws: bool,
+    /// Address to run the WebSocket server on, `0.0.0.0` accepts phones and consoles on the
+    /// same network
+    #[arg(long, default_value = "127.0.0.1", requires = "ws")]
+    ws_host: String,
+    /// Chat message that triggers a rebuild when sent by a player connected with `--ws`
+    #[arg(
+        long,
+        value_name = "MESSAGE",
+        default_value = "!build",
+        requires = "ws"
+    )]
+    chat_command: String,
+    /// Print script errors from the content log, mapped to the original sources
+    #[arg(long)]
+    logs: bool,
+    /// Watch every member of the workspace
+    #[arg(long)]
+    workspace: bool,
+    /// Serve a build status page on the given port
+    #[arg(long, value_name = "PORT")]
+    serve: Option<u16>,
+    /// Address to serve the build status page on
+    #[arg(long, default_value = "127.0.0.1", requires = "serve")]
+    serve_host: String,
+    /// Only run these filters, or a range of them like `first..last`
+    #[arg(long = "filter", value_name = "NAME")]
+    filters: Vec<String>,
+    /// Don't run these filters
+    #[arg(long, value_name = "NAME")]
+    skip: Vec<String>,
+    /// Watch the given profiles at the same time instead of switching between them
+    #[arg(long, conflicts_with_all = ["ws", "logs", "serve"])]
+    parallel: bool,
+    /// Watch every profile with an export target at the same time
+    #[arg(long, conflicts_with_all = ["profiles", "ws", "logs", "serve"])]
+    all_profiles: bool,
+    /// Set when started by `--parallel`, uses a separate session lock and temp directory
+    #[arg(long, hide = true)]
+    scoped: bool,
 }
 
 impl Command for Watch {
     fn dispatch(&self) -> Result<()> {
+        if self.workspace {
+            let mut args = vec!["watch".to_owned()];
+            args.extend(self.profiles.iter().cloned());
+            if self.parallel {
+                args.push("--parallel".to_owned());
+            }
+            if self.all_profiles {
+                args.push("--all-profiles".to_owned());
+            }
+            args.extend(self.get_forwarded_args());
+            return Workspace::load()?.run_all(&args);
+        }
+        if self.parallel || self.all_profiles {
+            return self.watch_parallel();
+        }
+        if self.scoped {
+            set_profile_scope(&self.profiles[0]);
+        }
         let compat = self.compat || UserConfig::force_compat();
+        let selection = FilterSelection::new(&self.filters, &self.skip);
+        let config = Config::load()?;
+        for profile in &self.profiles {
+            if let Export::World(world) = &config.get_profile(profile)?.export {
+                world.pick_world()?;
+            }
+        }
+        // Source maps and the content log follow the initial profile
+        let profile = &self.profiles[0];
+        let source_maps = match self.ws || self.logs {
+            true => SourceMaps::from_profile(&config, profile)?,
+            false => None,
+        };
         let server = if self.ws {
             Some(MinecraftServer::bind_and_accept(
+                &self.ws_host,
                 UserConfig::websocket_port(),
+                source_maps.clone(),
             )?)
         } else {
             None
         };
+        // Plugins that handle game events received over WebSocket
+        let plugins: Vec<_> = match &server {
+            Some(server) => {
+                smol::block_on(server.subscribe("PlayerMessage"));
+                let plugins: Vec<_> = config
+                    .get_plugins()?
+                    .into_iter()
+                    .filter(|plugin| !plugin.manifest.events.is_empty())
+                    .map(Arc::new)
+                    .collect();
+                for event_name in plugins.iter().flat_map(|p| &p.manifest.events) {
+                    smol::block_on(server.subscribe(event_name));
+                }
+                plugins
+            }
+            None => vec![],
+        };
+        if self.logs {
+            let build = config.get_profile(profile)?.export.get_build();
+            watch_content_log(build.as_ref(), source_maps)?;
+        }
+        let dashboard = match self.serve {
+            Some(port) => Some(Dashboard::bind(&self.serve_host, port)?),
+            None => None,
+        };
+        let switcher = match self.profiles.len() > 1 {
+            true => Some(ProfileSwitcher::new(&self.profiles)),
+            false => None,
+        };
+        let wait_control = || async {
+            let rebuild = async {
+                match &dashboard {
+                    Some(dashboard) => dashboard.wait_rebuild().await,
+                    None => smol::future::pending().await,
+                }
+                Control::Rebuild
+            };
+            let switch = async {
+                match &switcher {
+                    Some(switcher) => Control::Switch(switcher.wait_switch().await),
+                    None => smol::future::pending().await,
+                }
+            };
+            let game_event = async {
+                match &server {
+                    Some(server) => loop {
+                        let event = server.wait_event().await;
+                        let is_chat_command = is_chat_command(&event, &self.chat_command);
+                        if handle_game_event(&plugins, &event).await || is_chat_command {
+                            if is_chat_command {
+                                let sender = event.body["sender"].as_str().unwrap_or("A player");
+                                info!("<b>{sender}</> requested a rebuild from the chat");
+                                server
+                                    .run_player_command(|player| {
+                                        format!(r#"tellraw {player} {{"rawtext": [{{"text": "Building..."}}]}}"#)
+                                    })
+                                    .await;
+                            }
+                            break Control::Rebuild;
+                        }
+                    },
+                    None => smol::future::pending().await,
+                }
+            };
+            smol::future::or(rebuild, smol::future::or(switch, game_event)).await
+        };
 
         smol::block_on(async {
+            // Changes since the last successful run, empty if a full run is required
+            let mut changes = vec![];
+            let mut active = 0;
+            // Cleared once a run completes without being interrupted
+            let mut clean = self.clean || self.initial_clean_only;
             loop {
+                let profile = &self.profiles[active];
                 let config = Config::load()?;
-                let watcher = config.get_watcher()?;
+                let generated_paths = get_generated_paths(&config, profile)?;
+                let mut watcher = config.get_watcher()?;
+                for path in generated_paths {
+                    watcher.ignore_path(path)?;
+                }
                 let mut session = Session::lock()?;
 
-                let is_interrupted = smol::future::or(
+                let mut success = false;
+                let interrupt = smol::future::or(
                     async {
-                        if let Err(e) = runner(&config, &self.profile, self.clean, compat).await {
-                            error!("{}", self.error_context());
-                            e.chain().for_each(|e| log!("<red>[+]</> {e}"));
+                        let result = match sync_assets(&config, profile, compat, &changes) {
+                            Ok(true) => Ok(()),
+                            Ok(false) => {
+                                runner(&config, profile, clean, compat, &changes, &selection).await
+                            }
+                            Err(e) => Err(e),
+                        };
+                        match result {
+                            Ok(()) => success = true,
+                            Err(e) => {
+                                error!("Error running <profile>{profile}</> profile");
+                                e.chain().for_each(|e| log!("<red>[+]</> {e}"));
+                            }
                         }
-                        false
+                        None
                     },
                     async {
-                        watcher.wait_changes().await;
-                        true
+                        let control = smol::future::or(
+                            async {
+                                watcher.wait_changes().await;
+                                None
+                            },
+                            async { Some(wait_control().await) },
+                        )
+                        .await;
+                        Some(control)
                     },
                 )
                 .await;
+                // Let other instances run in the project while waiting for changes
+                session.unlock()?;
 
+                let is_interrupted = interrupt.is_some();
                 if !is_interrupted {
-                    if let Some(server) = &server {
-                        server.run_command("reload").await;
-                        server
-                            .run_command(
-                                r#"tellraw @s {"rawtext": [{"translate": "commands.reload.success"}]}"#,
-                            )
-                            .await;
-                    }
-
-                    info!("Watching for changes...");
-                    info!("Press Ctrl+C to stop watching");
-                    watcher.flush();
-                    watcher.wait_debounced(Duration::from_millis(100)).await;
+                    clean = self.clean;
                 }
+                let control = match interrupt {
+                    Some(control) => control,
+                    None => {
+                        if let Some(server) = &server {
+                            server.run_command("reload").await;
+                            server
+                                .run_player_command(|player| {
+                                    format!(
+                                        r#"tellraw {player} {{"rawtext": [{{"translate": "commands.reload.success"}}]}}"#
+                                    )
+                                })
+                                .await;
+                        } else if success && UserConfig::touch_manifests() {
+                            if let Err(e) = touch_manifests(&config, profile) {
+                                warn!("Failed to update the pack manifests: {e}");
+                            }
+                        }
 
-                warn!("Changes detected, restarting...");
-                session.unlock()?;
+                        info!("Watching for changes...");
+                        info!("Press Ctrl+C to stop watching");
+                        if switcher.is_some() {
+                            ProfileSwitcher::print_help(&self.profiles, active);
+                        }
+                        watcher.flush();
+                        smol::future::or(
+                            async {
+                                match watcher.wait_debounced(Duration::from_millis(100)).await {
+                                    Ok(paths) => {
+                                        changes = paths;
+                                        None
+                                    }
+                                    Err(e) => Some(Control::WatcherFailed(e)),
+                                }
+                            },
+                            async { Some(wait_control().await) },
+                        )
+                        .await
+                    }
+                };
+                match control {
+                    Some(Control::Switch(index)) if index != active => {
+                        active = index;
+                        changes.clear();
+                        warn!(
+                            "Switched to <profile>{}</> profile, restarting...",
+                            self.profiles[active]
+                        );
+                    }
+                    Some(Control::Rebuild | Control::Switch(_)) if !is_interrupted => {
+                        changes.clear();
+                        warn!("Rebuild requested, restarting...");
+                    }
+                    Some(Control::WatcherFailed(e)) => {
+                        changes.clear();
+                        warn!("The file watcher stopped working ({e}), restarting...");
+                    }
+                    _ if !is_interrupted => {
+                        warn!("Changes detected, restarting...");
+                        log_changes(&changes);
+                    }
+                    _ => warn!("Changes detected, restarting..."),
+                }
+                if is_interrupted || !success || self.clean {
+                    changes.clear();
+                }
             }
         })
     }
     fn error_context(&self) -> String {
-        format!("Error running <profile>{}</> profile", self.profile)
+        format!("Error running <profile>{}</> profile", self.profiles[0])
+    }
+}
+
+impl Watch {
+    fn get_forwarded_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if self.clean {
+            args.push("--clean".to_owned());
+        }
+        if self.initial_clean_only {
+            args.push("--initial-clean-only".to_owned());
+        }
+        if self.compat {
+            args.push("--compat".to_owned());
+        }
+        for filter in &self.filters {
+            args.extend(["--filter".to_owned(), filter.to_owned()]);
+        }
+        for filter in &self.skip {
+            args.extend(["--skip".to_owned(), filter.to_owned()]);
+        }
+        args
+    }
+
+    /// Watches each profile in its own instance of rgl
+    fn watch_parallel(&self) -> Result<()> {
+        let config = Config::load()?;
+        let profiles: Vec<_> = match self.all_profiles {
+            true => config
+                .get_profiles()
+                .iter()
+                .filter(|(_, profile)| !matches!(profile.export, Export::None(_)))
+                .map(|(name, _)| name.to_owned())
+                .collect(),
+            false => self.profiles.to_owned(),
+        };
+        if profiles.is_empty() {
+            bail!("No profile with an export target found");
+        }
+        let mut targets = HashMap::new();
+        for profile_name in &profiles {
+            let profile = config.get_profile(profile_name)?;
+            let (bp, rp) = profile.export.get_paths(&config, profile_name)?;
+            for target in [bp, rp] {
+                if let Some(other) = targets.insert(target.to_owned(), profile_name) {
+                    bail!(
+                        "Profiles <profile>{other}</> and <profile>{profile_name}</> export to the same directory\n\
+                         <yellow> >></> Path: {}",
+                        target.display()
+                    );
+                }
+            }
+        }
+
+        let exe = env::current_exe()?;
+        let failed: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = profiles
+                .iter()
+                .map(|profile_name| {
+                    let mut args = vec!["watch".to_owned(), profile_name.to_owned()];
+                    args.push("--scoped".to_owned());
+                    args.extend(self.get_forwarded_args());
+                    let exe = &exe;
+                    s.spawn(move || {
+                        info!("Watching <profile>{profile_name}</> profile");
+                        (profile_name, Subprocess::new(exe).args(args).run())
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|handle| match handle.join().unwrap() {
+                    (profile_name, Err(e)) => Some((profile_name, e)),
+                    _ => None,
+                })
+                .collect()
+        });
+        for (profile_name, e) in &failed {
+            error!("Watching <profile>{profile_name}</> profile failed");
+            e.chain().for_each(|e| log!("<red>[+]</> {e}"));
+        }
+        if !failed.is_empty() {
+            bail!("{} profile(s) failed", failed.len());
+        }
+        Ok(())
+    }
+}
+
+/// Temp directory and export targets of the profile. They are ignored by the watcher in case they
+/// are inside a watched directory, so a run doesn't trigger the next one.
+fn get_generated_paths(config: &Config, profile_name: &str) -> Result<Vec<PathBuf>> {
+    let profile = config.get_profile(profile_name)?;
+    let mut paths = vec![get_temp(config, profile)?.root];
+    let (bp, rp) = profile.export.get_paths(config, profile_name)?;
+    let mirrors = profile.export.get_mirror_paths(config, profile_name)?;
+    for (bp, rp) in std::iter::once((bp, rp)).chain(mirrors) {
+        paths.extend([bp, rp]);
     }
+    Ok(paths)
+}
+
+/// Whether the event is a chat message with the command that triggers a rebuild
+fn is_chat_command(event: &GameEvent, command: &str) -> bool {
+    if event.name != "PlayerMessage" || event.body["type"] != "chat" {
+        return false;
+    }
+    event.body["message"].as_str().map(str::trim) == Some(command)
+}
+
+/// Passes a game event to the plugins subscribed to it, returns whether any of them requested a
+/// rebuild
+async fn handle_game_event(plugins: &[Arc<Plugin>], event: &GameEvent) -> bool {
+    let mut rebuild = false;
+    for plugin in plugins {
+        if !plugin.manifest.events.contains(&event.name) {
+            continue;
+        }
+        let handler = plugin.clone();
+        let (name, body) = (event.name.to_owned(), event.body.clone());
+        let result = smol::unblock(move || handler.on_event(&name, &body)).await;
+        match result {
+            Ok(requested) => rebuild |= requested,
+            Err(e) => {
+                error!("Plugin <b>{}</> failed", plugin.manifest.name);
+                e.chain().for_each(|e| log!("<red>[+]</> {e}"));
+            }
+        }
+    }
+    rebuild
+}
+
+/// Number of changed paths listed when restarting
+const MAX_LISTED_CHANGES: usize = 5;
+
+fn log_changes(changes: &[PathBuf]) {
+    let current_dir = get_current_dir().unwrap_or_default();
+    for path in changes.iter().take(MAX_LISTED_CHANGES) {
+        let path = path.strip_prefix(&current_dir).unwrap_or(path);
+        let path = path.strip_prefix(".").unwrap_or(path);
+        log!("<yellow> >></> {}", path.display());
+    }
+    if changes.len() > MAX_LISTED_CHANGES {
+        log!(
+            "<yellow> >></> and {} more",
+            changes.len() - MAX_LISTED_CHANGES
+        );
+    }
+}
+
+/// Requests that restart the run besides file changes
+enum Control {
+    Rebuild,
+    Switch(usize),
+    WatcherFailed(anyhow::Error),
 }
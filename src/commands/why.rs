@@ -0,0 +1,121 @@
+use super::Command;
+use crate::log;
+use crate::rgl::{Config, FilterContext, FilterRunner};
+use anyhow::Result;
+use clap::Args;
+use std::collections::HashSet;
+
+/// Show where a filter is used
+#[derive(Args)]
+pub struct Why {
+    filter: String,
+}
+
+impl Command for Why {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let name = &self.filter;
+        config.get_filter(name)?;
+
+        let mut usages = vec![];
+        for profile_name in config.get_profiles().keys() {
+            let mut chain = vec![];
+            let mut visited = HashSet::new();
+            find_usages(
+                &config,
+                profile_name,
+                name,
+                &mut chain,
+                &mut visited,
+                &mut usages,
+            );
+        }
+        if usages.is_empty() {
+            log!("Filter <filter>{name}</> is not used by any profile");
+        } else {
+            log!("Filter <filter>{name}</> is used by:");
+            for usage in &usages {
+                log!("  {usage}");
+            }
+        }
+
+        // Filters that read the data exported by this filter
+        let data_dir = format!("data/{name}");
+        let mut dependents = vec![];
+        for profile in config.get_profiles().values() {
+            for entry in &profile.filters {
+                let FilterRunner::Filter {
+                    filter_name,
+                    inputs,
+                    ..
+                } = entry
+                else {
+                    continue;
+                };
+                if filter_name == name || dependents.contains(filter_name) {
+                    continue;
+                }
+                let mut inputs = inputs.clone().unwrap_or_default();
+                let filter = config.get_filter(filter_name)?;
+                if let Some(remote_config) = FilterContext::new(filter_name, &filter)?.remote_config
+                {
+                    inputs.extend(remote_config.inputs);
+                }
+                let reads_data = inputs.iter().any(|input| {
+                    let input = input.trim_start_matches("./").trim_end_matches('/');
+                    input == "data" || input.starts_with(&data_dir)
+                });
+                if reads_data {
+                    dependents.push(filter_name.to_owned());
+                }
+            }
+        }
+        if !dependents.is_empty() {
+            log!("Filters that read its data:");
+            for dependent in dependents {
+                log!("  <filter>{dependent}</>");
+            }
+        }
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        format!(
+            "Error explaining usage of filter <filter>{}</>",
+            self.filter
+        )
+    }
+}
+
+/// Collects the positions of the filter in the profile, including the ones in nested profiles
+fn find_usages(
+    config: &Config,
+    profile_name: &str,
+    filter: &str,
+    chain: &mut Vec<String>,
+    visited: &mut HashSet<String>,
+    usages: &mut Vec<String>,
+) {
+    let Ok(profile) = config.get_profile(profile_name) else {
+        return;
+    };
+    if !visited.insert(profile_name.to_owned()) {
+        return;
+    }
+    for (index, entry) in profile.filters.iter().enumerate() {
+        let position = format!("<profile>{profile_name}</> #{}", index + 1);
+        match entry {
+            FilterRunner::Filter { filter_name, .. } if filter_name == filter => {
+                let mut path = chain.clone();
+                path.push(position);
+                usages.push(path.join(" > "));
+            }
+            FilterRunner::ProfileFilter { profile_name } => {
+                chain.push(position);
+                find_usages(config, profile_name, filter, chain, visited, usages);
+                chain.pop();
+            }
+            _ => {}
+        }
+    }
+    visited.remove(profile_name);
+}
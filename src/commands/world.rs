@@ -0,0 +1,63 @@
+use super::Command;
+use crate::log;
+use crate::rgl::{create_test_world, get_world_template_path, Config};
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use enum_dispatch::enum_dispatch;
+use std::path::PathBuf;
+
+/// Manage test worlds
+#[derive(Args)]
+pub struct World {
+    #[command(subcommand)]
+    subcommand: WorldSubcommands,
+}
+
+#[derive(Subcommand)]
+#[enum_dispatch(Command)]
+pub enum WorldSubcommands {
+    Create(WorldCreate),
+}
+
+impl Command for World {
+    fn dispatch(&self) -> Result<()> {
+        self.subcommand.dispatch()
+    }
+    fn error_context(&self) -> String {
+        self.subcommand.error_context()
+    }
+}
+
+/// Create a test world with the project packs attached
+#[derive(Args)]
+pub struct WorldCreate {
+    name: String,
+    /// Profile whose Minecraft build and experiments are used
+    #[arg(short, long, default_value = "default")]
+    profile: String,
+    /// World directory to copy, defaults to `world_template` if it exists
+    #[arg(long)]
+    template: Option<PathBuf>,
+    /// Generate a flat world, ignored when using a template
+    #[arg(long)]
+    flat: bool,
+}
+
+impl Command for WorldCreate {
+    fn dispatch(&self) -> Result<()> {
+        let config = Config::load()?;
+        let default_template = get_world_template_path();
+        let template = match &self.template {
+            Some(template) => Some(template.as_path()),
+            None => default_template
+                .is_dir()
+                .then_some(default_template.as_path()),
+        };
+        let world_dir = create_test_world(&config, &self.profile, &self.name, template, self.flat)?;
+        log!("\tPath: {}", world_dir.display());
+        Ok(())
+    }
+    fn error_context(&self) -> String {
+        format!("Error creating world <yellow>{}</>", self.name)
+    }
+}
@@ -1,31 +1,144 @@
-use anyhow::{Context, Result};
-use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::rgl::normalize_path;
+use anyhow::{bail, Context, Result};
+use notify::{
+    event::{ModifyKind, RenameMode},
+    recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
 use smol::{channel, Timer};
-use std::{path::Path, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+use walkdir::WalkDir;
+
+/// Modification times of the files rgl wrote into watched directories itself, e.g. the data
+/// exported by filters. Events for these files are ignored while they are unchanged.
+static OWN_WRITES: LazyLock<Mutex<HashMap<PathBuf, OwnWrite>>> = LazyLock::new(Default::default);
+
+/// Time the events of a write are ignored for after the first one, a single write raises several
+/// events, e.g. a create and a modify
+const OWN_WRITE_GRACE: Duration = Duration::from_secs(1);
+
+struct OwnWrite {
+    modified: SystemTime,
+    /// When the first event of the write was ignored
+    consumed: Option<Instant>,
+}
+
+impl OwnWrite {
+    fn is_expired(&self) -> bool {
+        self.consumed
+            .is_some_and(|consumed| consumed.elapsed() > OWN_WRITE_GRACE)
+    }
+}
+
+/// Records the files in a directory as written by rgl, so writing them doesn't trigger a rebuild.
+/// Replaces the files recorded for the directory before.
+pub fn record_own_writes(dir: &Path) {
+    let Ok(dir) = normalize_path(dir) else {
+        return;
+    };
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    own_writes.retain(|path, own_write| !path.starts_with(&dir) && !own_write.is_expired());
+    for entry in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()) {
+        let Ok(Ok(modified)) = entry.metadata().map(|m| m.modified()) else {
+            continue;
+        };
+        if let Ok(path) = normalize_path(entry.path()) {
+            let own_write = OwnWrite {
+                modified,
+                consumed: None,
+            };
+            own_writes.insert(path, own_write);
+        }
+    }
+}
+
+/// Checks if an event is for a file rgl wrote. Entries are removed once their events were
+/// consumed, or the file was changed by something else, the rest on the next record.
+fn is_own_write(path: &Path) -> bool {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    let Some(own_write) = own_writes.get_mut(path) else {
+        return false;
+    };
+    if own_write.is_expired() || modified != Some(own_write.modified) {
+        own_writes.remove(path);
+        return false;
+    }
+    own_write.consumed.get_or_insert_with(Instant::now);
+    true
+}
+
+/// Interval of checking that the watched directories weren't replaced, e.g. by a git checkout
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(2);
 
 pub struct FileWatcher {
-    rx: channel::Receiver<()>,
+    /// Events, or the reason the watcher stopped working
+    rx: channel::Receiver<Result<Event, String>>,
     watcher: RecommendedWatcher,
+    ignored: Arc<Mutex<Vec<PathBuf>>>,
+    /// Watched paths with their creation time
+    roots: Vec<(PathBuf, Option<SystemTime>)>,
 }
 
 impl FileWatcher {
     pub fn new() -> Result<Self> {
         let (tx, rx) = channel::unbounded();
+        let ignored = Arc::new(Mutex::new(Vec::<PathBuf>::new()));
+        let ignored_paths = ignored.clone();
         let watcher = recommended_watcher(move |event: notify::Result<Event>| {
-            if let Ok(e) = event {
-                if e.kind.is_access() || e.kind.is_other() {
+            let mut e = match event {
+                Ok(e) if e.need_rescan() => {
+                    let _ = tx.send_blocking(Err("events were dropped".to_owned()));
                     return;
                 }
-                #[cfg(windows)]
-                if e.kind.is_modify() && e.paths.iter().all(|p| p.is_dir()) {
+                Ok(e) => e,
+                Err(e) => {
+                    let _ = tx.send_blocking(Err(e.to_string()));
                     return;
                 }
-                let _ = tx.send_blocking(());
+            };
+            if e.kind.is_access() || e.kind.is_other() {
+                return;
+            }
+            #[cfg(windows)]
+            if e.kind.is_modify() && e.paths.iter().all(|p| p.is_dir()) {
+                return;
             }
+            let ignored = ignored_paths.lock().unwrap();
+            let is_ignored = |path: &PathBuf| match normalize_path(path) {
+                Ok(path) => ignored.iter().any(|dir| path.starts_with(dir)) || is_own_write(&path),
+                Err(_) => false,
+            };
+            if e.paths.iter().all(is_ignored) {
+                return;
+            }
+            // Keep both sides of renames to match them up
+            if !matches!(e.kind, EventKind::Modify(ModifyKind::Name(_))) {
+                e.paths.retain(|path| !is_ignored(path));
+            }
+            drop(ignored);
+            let _ = tx.send_blocking(Ok(e));
         })
         .context("Failed to create file watcher")?;
 
-        Ok(Self { rx, watcher })
+        Ok(Self {
+            rx,
+            watcher,
+            ignored,
+            roots: vec![],
+        })
+    }
+
+    /// Ignores changes inside a watched directory, e.g. an export target inside the project
+    pub fn ignore_path(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        self.ignored.lock().unwrap().push(path);
+        Ok(())
     }
 
     pub fn add_path(&mut self, path: impl AsRef<Path>) -> Result<()> {
@@ -38,34 +151,161 @@ impl FileWatcher {
                      <yellow> >></> Path: {}",
                     path.display()
                 )
-            })
+            })?;
+        self.roots.push((path.to_owned(), get_created_time(path)));
+        Ok(())
     }
 
-    pub async fn wait_changes(&self) {
-        let _ = self.rx.recv().await;
+    /// Waits for any change. A failure of the watcher counts as a change, since changes may have
+    /// been missed.
+    pub async fn wait_changes(&self) -> Vec<PathBuf> {
+        match self.rx.recv().await {
+            Ok(Ok(event)) => event.paths,
+            _ => vec![],
+        }
     }
 
-    pub async fn wait_debounced(&self, timeout: Duration) {
-        self.wait_changes().await;
+    /// Waits for changes and keeps collecting them until no new change arrives within `timeout`.
+    /// Returns every changed path seen during that time, without duplicates and without the
+    /// temporary files that were created and moved or deleted in the meantime.
+    ///
+    /// Fails if the watcher stopped working, in which case it has to be recreated.
+    pub async fn wait_debounced(&self, timeout: Duration) -> Result<Vec<PathBuf>> {
+        let mut changes = ChangeSet::default();
+        let event = smol::future::or(self.recv(), async {
+            loop {
+                Timer::after(HEALTH_CHECK_INTERVAL).await;
+                if let Some(reason) = self.check_roots() {
+                    bail!(reason);
+                }
+            }
+        })
+        .await?;
+        changes.add(event);
         loop {
-            let stop = smol::future::or(
-                async {
-                    self.wait_changes().await;
-                    false
-                },
-                async {
-                    Timer::after(timeout).await;
-                    true
-                },
-            )
+            let event = smol::future::or(async { Some(self.recv().await) }, async {
+                Timer::after(timeout).await;
+                None
+            })
             .await;
-            if stop {
-                break;
+            match event {
+                Some(event) => changes.add(event?),
+                None => break,
             }
         }
+        Ok(changes.into_paths())
+    }
+
+    async fn recv(&self) -> Result<Event> {
+        match self.rx.recv().await {
+            Ok(Ok(event)) => Ok(event),
+            Ok(Err(reason)) => bail!(reason),
+            Err(_) => bail!("the watcher was closed"),
+        }
+    }
+
+    /// Checks that the watched paths weren't replaced, which silently stops watching them on some
+    /// platforms. Deleted paths are only reported once they are created again, as they can't be
+    /// watched until then.
+    fn check_roots(&self) -> Option<String> {
+        for (path, created) in &self.roots {
+            if path.exists() && get_created_time(path) != *created {
+                return Some(format!("{} was replaced", path.display()));
+            }
+        }
+        None
     }
 
     pub fn flush(&self) {
         while self.rx.try_recv().is_ok() {}
     }
 }
+
+fn get_created_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.created()).ok()
+}
+
+/// Changed paths collected from a batch of events
+#[derive(Default)]
+struct ChangeSet {
+    paths: BTreeSet<PathBuf>,
+    /// Paths created in this batch, a rename or removal of them is not a change
+    created: HashSet<PathBuf>,
+    /// Source paths of renames waiting for their destination, by tracker id
+    renames: HashMap<usize, PathBuf>,
+    /// Renames that were already applied. Some platforms report a rename both as separate
+    /// events and as a single one
+    moved: HashSet<(PathBuf, PathBuf)>,
+}
+
+impl ChangeSet {
+    fn add(&mut self, event: Event) {
+        match event.kind {
+            EventKind::Create(_) => {
+                for path in event.paths {
+                    self.created.insert(path.to_owned());
+                    self.paths.insert(path);
+                }
+            }
+            EventKind::Remove(_) => {
+                for path in event.paths {
+                    self.remove(path);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let mut paths = event.paths.into_iter();
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    self.rename(from, to);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                match (event.attrs.tracker(), event.paths.into_iter().next()) {
+                    (Some(tracker), Some(from)) => {
+                        self.renames.insert(tracker, from);
+                    }
+                    (None, Some(from)) => self.remove(from),
+                    _ => {}
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                let from = event.attrs.tracker().and_then(|t| self.renames.remove(&t));
+                for to in event.paths {
+                    match &from {
+                        Some(from) => self.rename(from.to_owned(), to),
+                        None => {
+                            self.paths.insert(to);
+                        }
+                    }
+                }
+            }
+            _ => self.paths.extend(event.paths),
+        }
+    }
+
+    fn remove(&mut self, path: PathBuf) {
+        if self.created.remove(&path) {
+            self.paths.remove(&path);
+        } else {
+            self.paths.insert(path);
+        }
+    }
+
+    fn rename(&mut self, from: PathBuf, to: PathBuf) {
+        if !self.moved.insert((from.to_owned(), to.to_owned())) {
+            return;
+        }
+        if self.created.remove(&from) {
+            // Files saved through a temporary file only change the destination
+            self.paths.remove(&from);
+        } else {
+            self.paths.insert(from);
+        }
+        self.paths.insert(to);
+    }
+
+    fn into_paths(mut self) -> Vec<PathBuf> {
+        // Moved out of the watched directories
+        self.paths.extend(self.renames.into_values());
+        self.paths.into_iter().collect()
+    }
+}
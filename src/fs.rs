@@ -1,16 +1,238 @@
+use crate::progress::Progress;
+use crate::warn;
 use anyhow::{anyhow, bail, Context, Result};
 use dashmap::DashMap;
 use dunce::canonicalize;
+use indicatif::HumanBytes;
 use jsonc_parser::ParseOptions;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::{
-    fs, io,
-    path::{Path, PathBuf},
-    sync::LazyLock,
-    time::SystemTime,
+    collections::HashSet,
+    fmt, fs,
+    io::{self, Write},
+    ops::AddAssign,
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
+use walkdir::WalkDir;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
-fn copy_dir_impl(from: &Path, to: &Path) -> Result<()> {
+/// Retries the operation with backoff while the file is locked by another process. Antivirus
+/// software and Minecraft itself briefly lock files on Windows.
+fn retry<T>(mut f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = Duration::from_millis(50);
+    for _ in 0..5 {
+        match f() {
+            Err(e) if is_lock_error(&e) => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    f()
+}
+
+fn is_lock_error(e: &io::Error) -> bool {
+    // ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+    if cfg!(windows) {
+        return matches!(e.raw_os_error(), Some(5 | 32 | 33));
+    }
+    // EBUSY and ETXTBSY, e.g. a pack on a network drive the game has open
+    matches!(
+        e.kind(),
+        io::ErrorKind::ResourceBusy | io::ErrorKind::ExecutableFileBusy
+    )
+}
+
+/// How long to wait for files that stay locked, e.g. while Minecraft saves the world
+const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs the operation again until the files in `path` are no longer locked by another process,
+/// instead of leaving it half done. The operation must be safe to repeat, like `sync_dir`.
+pub fn wait_for_unlock<T>(path: &Path, mut f: impl FnMut() -> Result<T>) -> Result<T> {
+    let start = Instant::now();
+    let mut warned = false;
+    loop {
+        let e = match f() {
+            Err(e)
+                if e.chain()
+                    .any(|e| e.downcast_ref().is_some_and(is_lock_error)) =>
+            {
+                e
+            }
+            result => return result,
+        };
+        if start.elapsed() > LOCK_TIMEOUT {
+            return Err(e).with_context(|| {
+                format!(
+                    "Files are still in use after {} seconds\n\
+                     <yellow> >></> Path: {}\n\
+                     <yellow> >></> Leave the world or close Minecraft, then run again",
+                    LOCK_TIMEOUT.as_secs(),
+                    path.display()
+                )
+            });
+        }
+        if !warned {
+            warn!(
+                "Files in <b>{}</> are in use by another process, e.g. Minecraft saving the world, waiting for them to be released",
+                path.display()
+            );
+            warned = true;
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Converts the path to the extended-length form (`\\?\C:\...`) to lift the MAX_PATH limit for
+/// deeply nested files, e.g. inside `node_modules`.
+#[cfg(windows)]
+fn long_path(path: &Path) -> PathBuf {
+    use std::path::{Component, Prefix};
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_owned();
+    };
+    let Some(Component::Prefix(prefix)) = absolute.components().next() else {
+        return absolute;
+    };
+    let Some(absolute_str) = absolute.to_str() else {
+        return absolute;
+    };
+    match prefix.kind() {
+        Prefix::Disk(_) => PathBuf::from(format!(r"\\?\{absolute_str}")),
+        Prefix::UNC(_, _) => PathBuf::from(format!(r"\\?\UNC\{}", &absolute_str[2..])),
+        _ => absolute,
+    }
+}
+
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> PathBuf {
+    path.to_owned()
+}
+
+/// How symlinks inside copied and synced directories are handled
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Copies the files and directories the symlinks point to
+    #[default]
+    Follow,
+    /// Recreates the symlinks, filters that write to linked files change the originals
+    Preserve,
+    /// Fails on any symlink
+    Error,
+}
+
+/// Metadata of the file or directory a followed symlink points to
+fn resolve_symlink(path: &Path) -> Result<fs::Metadata> {
+    let metadata = path.metadata().with_context(|| {
+        format!(
+            "Broken symlink\n\
+             <yellow> >></> Path: {}",
+            path.display()
+        )
+    })?;
+    if metadata.is_dir() {
+        let target = canonicalize(path)?;
+        let parent = canonicalize(path.parent().unwrap_or(path))?;
+        if parent.starts_with(&target) {
+            bail!(
+                "Symlink points to one of its parent directories\n\
+                 <yellow> >></> Path: {}",
+                path.display()
+            );
+        }
+    }
+    Ok(metadata)
+}
+
+fn symlink_error(path: &Path) -> anyhow::Error {
+    anyhow!(
+        "Symlinks are not allowed in the project\n\
+         <yellow> >></> Path: {}\n\
+         <yellow> >></> Set `symlinks` to `follow` or `preserve` in config.json to allow them",
+        path.display()
+    )
+}
+
+/// Copies the file along with its modified time, so `sync_dir` sees the copy as unchanged
+fn copy_file(from: &Path, to: &Path) -> io::Result<u64> {
+    let size = retry(|| fs::copy(from, to))?;
+    // CopyFileEx already keeps the modified time on Windows
+    #[cfg(unix)]
+    fs::File::open(to)?.set_modified(fs::metadata(from)?.modified()?)?;
+    Ok(size)
+}
+
+/// Files changed in the target directory by `sync_dir`
+#[derive(Clone, Copy, Default)]
+pub struct SyncStats {
+    pub added: u64,
+    pub updated: u64,
+    pub removed: u64,
+    /// Bytes written to the target
+    pub bytes: u64,
+}
+
+impl AddAssign for SyncStats {
+    fn add_assign(&mut self, other: Self) {
+        self.added += other.added;
+        self.updated += other.updated;
+        self.removed += other.removed;
+        self.bytes += other.bytes;
+    }
+}
+
+impl fmt::Display for SyncStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} removed ({} written)",
+            self.added,
+            self.updated,
+            self.removed,
+            HumanBytes(self.bytes)
+        )
+    }
+}
+
+/// Counts changes while directories are copied in parallel
+#[derive(Default)]
+struct SyncCounters {
+    added: AtomicU64,
+    updated: AtomicU64,
+    removed: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl SyncCounters {
+    fn add(&self, counter: &AtomicU64, bytes: u64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> SyncStats {
+        SyncStats {
+            added: self.added.load(Ordering::Relaxed),
+            updated: self.updated.load(Ordering::Relaxed),
+            removed: self.removed.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn copy_dir_impl(
+    from: &Path,
+    to: &Path,
+    root: &Path,
+    policy: SymlinkPolicy,
+    progress: &Progress,
+    counters: &SyncCounters,
+) -> Result<()> {
     fs::create_dir_all(to)?;
     fs::read_dir(from)?
         .par_bridge()
@@ -18,19 +240,61 @@ fn copy_dir_impl(from: &Path, to: &Path) -> Result<()> {
             let entry = entry?;
             let path = entry.path();
             let to = to.join(entry.file_name());
-            if path.is_dir() {
-                copy_dir_impl(&path, &to)?;
+            let is_dir = match entry.file_type()? {
+                file_type if file_type.is_symlink() => match policy {
+                    SymlinkPolicy::Follow => resolve_symlink(&path)?.is_dir(),
+                    SymlinkPolicy::Preserve => {
+                        copy_symlink(&path, &to, root)?;
+                        counters.add(&counters.added, 0);
+                        return Ok(());
+                    }
+                    SymlinkPolicy::Error => return Err(symlink_error(&path)),
+                },
+                file_type => file_type.is_dir(),
+            };
+            if is_dir {
+                copy_dir_impl(&path, &to, root, policy, progress, counters)?;
             } else {
-                fs::copy(path, to)?;
+                let bytes = copy_file(&path, &to)?;
+                progress.inc_file(bytes);
+                counters.add(&counters.added, bytes);
             }
             Ok(())
         })
 }
 
+/// Copies the directory, following the symlinks in it
 pub fn copy_dir(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
-    let from = from.as_ref();
-    let to = to.as_ref();
-    copy_dir_impl(from, to).with_context(|| {
+    copy_dir_with_symlinks(from, to, SymlinkPolicy::Follow)
+}
+
+/// Copies the directory, handling the symlinks in it with the policy of the project
+pub fn copy_dir_with_symlinks(
+    from: impl AsRef<Path>,
+    to: impl AsRef<Path>,
+    policy: SymlinkPolicy,
+) -> Result<()> {
+    copy_dir_counted(from.as_ref(), to.as_ref(), policy, &SyncCounters::default())
+}
+
+fn copy_dir_counted(
+    from: &Path,
+    to: &Path,
+    policy: SymlinkPolicy,
+    counters: &SyncCounters,
+) -> Result<()> {
+    let progress = Progress::files(format!("Copying {}", from.display()));
+    let long_from = long_path(from);
+    let result = copy_dir_impl(
+        &long_from,
+        &long_path(to),
+        &long_from,
+        policy,
+        &progress,
+        counters,
+    );
+    progress.finish();
+    result.with_context(|| {
         format!(
             "Failed to copy directory\n\
              <yellow> >></> From: {}\n\
@@ -49,7 +313,7 @@ fn empty_dir_impl(path: &Path) -> Result<()> {
 
 pub fn empty_dir(path: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref();
-    empty_dir_impl(path).with_context(|| {
+    empty_dir_impl(&long_path(path)).with_context(|| {
         format!(
             "Failed to empty directory\n\
              <yellow> >></> Path: {}",
@@ -80,22 +344,30 @@ where
 }
 
 pub fn rimraf(path: impl AsRef<Path>) -> Result<()> {
+    #[allow(clippy::permissions_set_readonly_false)]
     fn remove_entry(path: &Path, metadata: &fs::Metadata) -> Result<()> {
-        let rm = if cfg!(windows) && metadata.is_symlink() {
+        #[cfg(windows)]
+        let is_dir_link = {
+            use std::os::windows::fs::FileTypeExt;
+            metadata.file_type().is_symlink_dir()
+        };
+        #[cfg(not(windows))]
+        let is_dir_link = false;
+        let rm = if is_dir_link {
             fs::remove_dir
         } else {
             fs::remove_file
         };
-        if let Err(e) = rm(&path) {
-            match e.kind() {
-                io::ErrorKind::PermissionDenied => {
-                    let mut perm = metadata.permissions();
-                    perm.set_readonly(false);
-                    fs::set_permissions(path, perm)?;
-                    rm(&path)?;
-                }
-                _ => bail!(e),
+        if let Err(e) = rm(path) {
+            if e.kind() != io::ErrorKind::PermissionDenied && !is_lock_error(&e) {
+                bail!(e);
+            }
+            let mut perm = metadata.permissions();
+            if perm.readonly() {
+                perm.set_readonly(false);
+                fs::set_permissions(path, perm)?;
             }
+            retry(|| rm(path))?;
         }
         Ok(())
     }
@@ -113,18 +385,19 @@ pub fn rimraf(path: impl AsRef<Path>) -> Result<()> {
                     remove_entry(&path, &metadata)
                 }
             })?;
-        fs::remove_dir(path)?;
+        retry(|| fs::remove_dir(path))?;
         Ok(())
     }
 
     let path = path.as_ref();
-    let metadata = match path.symlink_metadata() {
+    let long_path = long_path(path);
+    let metadata = match long_path.symlink_metadata() {
         Ok(val) => val,
         Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
         Err(e) => bail!(e),
     };
     if metadata.is_dir() {
-        rimraf_impl(path).with_context(|| {
+        rimraf_impl(&long_path).with_context(|| {
             format!(
                 "Failed to remove directory\n\
                  <yellow> >></> Path: {}",
@@ -132,7 +405,7 @@ pub fn rimraf(path: impl AsRef<Path>) -> Result<()> {
             )
         })
     } else {
-        remove_entry(path, &metadata).with_context(|| {
+        remove_entry(&long_path, &metadata).with_context(|| {
             format!(
                 "Failed to remove\n\
                  <yellow> >></> Path: {}",
@@ -164,20 +437,93 @@ pub fn set_modified_time(path: impl AsRef<Path>, time: SystemTime) -> Result<()>
 }
 
 #[cfg(unix)]
-fn symlink_impl(from: &Path, to: &Path) -> io::Result<()> {
+fn symlink_impl(from: &Path, to: &Path) -> Result<()> {
+    use std::os::unix;
+    unix::fs::symlink(canonicalize(from)?, to)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn symlink_impl(from: &Path, to: &Path) -> Result<()> {
+    use crate::rgl::ErrorCode;
+    use std::os::windows;
+    let from = canonicalize(from)?;
+    windows::fs::symlink_dir(&from, to).or_else(|e| match e.raw_os_error() {
+        // Junctions don't require any privilege, unlike symlinks
+        Some(1314) => junction::create(&from, to).map_err(|_| {
+            ErrorCode::SymlinkPrivilege.error(
+                "A required privilege is not held by the client. (os error 1314)\n\
+                 <blue>[?]</> Try enabling developer mode in Windows settings or run the terminal as an administrator",
+            )
+        }),
+        _ => Err(e.into()),
+    })
+}
+
+/// Target of a copy of the symlink `from`, which is inside the copied directory `root`. Relative
+/// targets outside of the directory are made absolute, they would point elsewhere from the copy.
+fn get_copied_link(from: &Path, root: &Path) -> Result<PathBuf> {
+    let link = fs::read_link(from)?;
+    if link.is_absolute() {
+        return Ok(link);
+    }
+    let mut resolved = std::path::absolute(from.parent().unwrap_or(Path::new("")))?;
+    for component in link.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => resolved.push(name),
+            _ => {}
+        }
+    }
+    match resolved.starts_with(std::path::absolute(root)?) {
+        true => Ok(link),
+        false => Ok(resolved),
+    }
+}
+
+/// Creates a symlink at `to` pointing where the symlink `from` points
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path, root: &Path) -> Result<()> {
     use std::os::unix;
-    unix::fs::symlink(canonicalize(from)?, to)
+    unix::fs::symlink(get_copied_link(from, root)?, to)?;
+    Ok(())
 }
 
+/// Creates a symlink at `to` pointing where the symlink `from` points
 #[cfg(windows)]
-fn symlink_impl(from: &Path, to: &Path) -> io::Result<()> {
+fn copy_symlink(from: &Path, to: &Path, root: &Path) -> Result<()> {
+    use crate::rgl::ErrorCode;
     use std::os::windows;
-    windows::fs::symlink_dir(canonicalize(from)?, to).map_err(|e| match e.raw_os_error() {
-        Some(1314) => io::Error::other(
+    let link = get_copied_link(from, root)?;
+    let is_dir = from.is_dir();
+    let result = match is_dir {
+        true => windows::fs::symlink_dir(&link, to),
+        false => windows::fs::symlink_file(&link, to),
+    };
+    result.or_else(|e| match e.raw_os_error() {
+        Some(1314) if is_dir => Ok(junction::create(canonicalize(from)?, to)?),
+        Some(1314) => Err(ErrorCode::SymlinkPrivilege.error(
             "A required privilege is not held by the client. (os error 1314)\n\
              <blue>[?]</> Try enabling developer mode in Windows settings or run the terminal as an administrator",
-        ),
-        _ => e,
+        )),
+        _ => Err(e.into()),
+    })
+}
+
+/// Renames a file or directory, the target must be on the same filesystem.
+pub fn move_path(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+    let from = from.as_ref();
+    let to = to.as_ref();
+    retry(|| fs::rename(long_path(from), long_path(to))).with_context(|| {
+        format!(
+            "Failed to move\n\
+             <yellow> >></> From: {}\n\
+             <yellow> >></> To: {}",
+            from.display(),
+            to.display()
+        )
     })
 }
 
@@ -225,35 +571,90 @@ where
     })
 }
 
+/// Options of [`sync_dir`]
+#[derive(Clone, Copy, Default)]
+pub struct SyncOptions<'a> {
+    /// Manifest of the files the sync copied from the source, written by every sync so a later
+    /// incremental sync knows which files were removed from the source since then
+    pub manifest: Option<&'a Path>,
+    /// Only remove the files that were removed from the source since the manifest was written,
+    /// other files in the target are kept. Without it the target mirrors the source.
+    pub incremental: bool,
+    /// How symlinks in the source are handled, they are followed by default
+    pub symlinks: SymlinkPolicy,
+}
+
+impl<'a> SyncOptions<'a> {
+    pub fn tracked(manifest: &'a Path) -> Self {
+        Self {
+            manifest: Some(manifest),
+            ..Default::default()
+        }
+    }
+
+    pub fn incremental(self, incremental: bool) -> Self {
+        Self {
+            incremental,
+            ..self
+        }
+    }
+
+    pub fn symlinks(self, symlinks: SymlinkPolicy) -> Self {
+        Self { symlinks, ..self }
+    }
+}
+
 /// Sync target directory with source directory.
 ///
-/// **Not thread-safe!** Uses a global cache internally. Do NOT call in parallel or from async code.
-pub fn sync_dir(source: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<()> {
-    static METADATA_CACHE: LazyLock<DashMap<PathBuf, Option<fs::Metadata>>> =
-        LazyLock::new(DashMap::new);
+/// Metadata is cached for the duration of the call, so directories that are synced at the same
+/// time must not overlap. Symlinks in the target are replaced, never synced through. Files that
+/// are not in the source are removed from the target, unless the sync is incremental. Returns
+/// the number of files that changed in the target.
+pub fn sync_dir(
+    source: impl AsRef<Path>,
+    target: impl AsRef<Path>,
+    options: SyncOptions,
+) -> Result<SyncStats> {
+    type MetadataCache = DashMap<PathBuf, Option<fs::Metadata>>;
 
-    fn get_metadata(path: impl AsRef<Path>) -> Option<fs::Metadata> {
+    /// Metadata of a source path, following symlinks
+    fn get_metadata(cache: &MetadataCache, path: impl AsRef<Path>) -> Option<fs::Metadata> {
         let path = path.as_ref();
 
-        if let Some(entry) = METADATA_CACHE.get(path) {
+        if let Some(entry) = cache.get(path) {
             return entry.value().clone();
         }
 
         let metadata = path.metadata().ok();
-        METADATA_CACHE.insert(path.to_owned(), metadata.clone());
+        cache.insert(path.to_owned(), metadata.clone());
         metadata
     }
 
-    /// Compare two files by size and modified time. Returns true if both are equal.
-    fn compare_files(a: &Path, b: &Path) -> Result<bool> {
-        if let (Some(a), Some(b)) = (get_metadata(a), get_metadata(b)) {
-            return Ok(a.len() == b.len() && a.modified()? == b.modified()?);
+    /// Metadata of a target path, symlinks are not followed
+    fn get_target_metadata(cache: &MetadataCache, path: &Path) -> Option<fs::Metadata> {
+        if let Some(entry) = cache.get(path) {
+            return entry.value().clone();
         }
-        Ok(false)
+
+        let metadata = path.symlink_metadata().ok();
+        cache.insert(path.to_owned(), metadata.clone());
+        metadata
     }
 
-    fn sync(source: &Path, target: &Path) -> Result<()> {
-        if get_metadata(target).is_none() {
+    /// Compare two files by size and modified time. Returns true if both are equal.
+    fn compare_files(a: &fs::Metadata, b: &fs::Metadata) -> Result<bool> {
+        Ok(a.len() == b.len() && a.modified()? == b.modified()?)
+    }
+
+    fn sync(
+        cache: &MetadataCache,
+        policy: SymlinkPolicy,
+        counters: &SyncCounters,
+        root: &Path,
+        source: &Path,
+        target: &Path,
+    ) -> Result<()> {
+        if get_target_metadata(cache, target).is_none() {
             fs::create_dir_all(target)?;
         }
         fs::read_dir(source)?
@@ -262,45 +663,89 @@ pub fn sync_dir(source: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<()
                 let entry = entry?;
                 let source = entry.path();
                 let target = target.join(entry.file_name());
-                if get_metadata(&source).is_some_and(|m| m.is_dir()) {
-                    if get_metadata(&target).is_some_and(|m| m.is_file()) {
-                        fs::remove_file(&target)?;
+                let mut target_metadata = get_target_metadata(cache, &target);
+                let source_metadata = match entry.file_type()?.is_symlink() {
+                    true => match policy {
+                        SymlinkPolicy::Follow => resolve_symlink(&source)?,
+                        SymlinkPolicy::Preserve => {
+                            let link = get_copied_link(&source, root)?;
+                            if target_metadata.as_ref().is_some_and(|m| m.is_symlink())
+                                && fs::read_link(&target).is_ok_and(|t| t == link)
+                            {
+                                return Ok(());
+                            }
+                            let counter = match target_metadata {
+                                Some(_) => {
+                                    rimraf(&target)?;
+                                    &counters.updated
+                                }
+                                None => &counters.added,
+                            };
+                            copy_symlink(&source, &target, root)?;
+                            counters.add(counter, 0);
+                            return Ok(());
+                        }
+                        SymlinkPolicy::Error => return Err(symlink_error(&source)),
+                    },
+                    false => match get_metadata(cache, &source) {
+                        Some(metadata) => metadata,
+                        // Removed while syncing
+                        None => return Ok(()),
+                    },
+                };
+                let mut counter = &counters.added;
+                if let Some(metadata) = &target_metadata {
+                    if metadata.is_symlink() || metadata.is_dir() != source_metadata.is_dir() {
+                        rimraf(&target)?;
+                        cache.insert(target.to_owned(), None);
+                        target_metadata = None;
+                    } else {
+                        counter = &counters.updated;
                     }
-                    return sync(&source, &target);
                 }
-                if get_metadata(&target).is_some_and(|m| m.is_dir()) {
-                    rimraf(&target)?;
+                if source_metadata.is_dir() {
+                    return sync(cache, policy, counters, root, &source, &target);
                 }
-                if !compare_files(&source, &target)? {
-                    fs::copy(source, target)?;
+                let unchanged = match &target_metadata {
+                    Some(metadata) => compare_files(&source_metadata, metadata)?,
+                    None => false,
+                };
+                if !unchanged {
+                    counters.add(counter, copy_file(&source, &target)?);
                 }
                 Ok(())
             })
     }
 
     /// Remove files that are not present in the source directory.
-    fn cleanup(source: &Path, target: &Path) -> Result<()> {
+    fn cleanup(
+        cache: &MetadataCache,
+        counters: &SyncCounters,
+        source: &Path,
+        target: &Path,
+    ) -> Result<()> {
         fs::read_dir(target)?
             .par_bridge()
             .try_for_each(|entry| -> Result<()> {
                 let entry = entry?;
                 let source = source.join(entry.file_name());
                 let target = entry.path();
-                let is_dir = get_metadata(&target).is_some_and(|m| m.is_dir());
-                if get_metadata(&source).is_none() {
-                    if is_dir {
-                        rimraf(target)?;
-                    } else {
-                        fs::remove_file(&target).with_context(|| {
-                            format!(
-                                "Failed to remove file\n\
-                                 <yellow> >></> Path: {}",
-                                target.display(),
-                            )
-                        })?;
-                    }
+                // Preserved symlinks may be broken
+                let exists = get_metadata(cache, &source).is_some() || source.is_symlink();
+                let is_dir = entry.file_type()?.is_dir();
+                if !exists {
+                    let removed = match is_dir {
+                        true => WalkDir::new(&target)
+                            .into_iter()
+                            .filter_map(|entry| entry.ok())
+                            .filter(|entry| !entry.file_type().is_dir())
+                            .count() as u64,
+                        false => 1,
+                    };
+                    rimraf(target)?;
+                    counters.removed.fetch_add(removed, Ordering::Relaxed);
                 } else if is_dir {
-                    cleanup(&source, &target)?;
+                    cleanup(cache, counters, &source, &target)?;
                 }
                 Ok(())
             })
@@ -308,8 +753,21 @@ pub fn sync_dir(source: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<()
 
     let source = source.as_ref();
     let target = target.as_ref();
-    if get_metadata(target).is_some_and(|m| m.is_dir()) {
-        sync(source, target).with_context(|| {
+    let cache = MetadataCache::new();
+    let cache = &cache;
+    let counters = SyncCounters::default();
+    let (long_source, long_target) = (long_path(source), long_path(target));
+    let policy = options.symlinks;
+    if long_target.is_dir() {
+        sync(
+            cache,
+            policy,
+            &counters,
+            &long_source,
+            &long_source,
+            &long_target,
+        )
+        .with_context(|| {
             format!(
                 "Failed to copy directory\n\
                  <yellow> >></> From: {}\n\
@@ -318,10 +776,175 @@ pub fn sync_dir(source: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<()
                 target.display(),
             )
         })?;
-        cleanup(source, target)?;
+        if !options.incremental {
+            cleanup(cache, &counters, &long_source, &long_target)?;
+        }
     } else {
-        copy_dir(source, target)?;
+        copy_dir_counted(source, target, policy, &counters)?;
+    }
+    match options.manifest {
+        Some(manifest) if options.incremental => {
+            remove_deleted_files(source, target, manifest, policy, &counters).with_context(
+                || {
+                    format!(
+                        "Failed to remove deleted files\n\
+                         <yellow> >></> Path: {}",
+                        target.display(),
+                    )
+                },
+            )?;
+        }
+        Some(manifest) => write_sync_manifest(manifest, source, policy)?,
+        None => {}
+    }
+    Ok(counters.get())
+}
+
+/// Writes the files currently in the directory to the manifest, for a later [`sync_dir`] into
+/// the directory to remove the ones that are gone by then.
+fn write_sync_manifest(manifest: &Path, dir: &Path, policy: SymlinkPolicy) -> Result<()> {
+    if let Some(parent) = manifest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_json(manifest, &list_files(dir, policy))
+}
+
+/// Removes the files the previous sync copied that are no longer in the source, then records the
+/// files of the source for the next sync
+fn remove_deleted_files(
+    source: &Path,
+    target: &Path,
+    manifest: &Path,
+    policy: SymlinkPolicy,
+    counters: &SyncCounters,
+) -> Result<()> {
+    let files = list_files(source, policy);
+    // A manifest that can't be read is treated like a first sync, nothing gets removed
+    let previous = read_json::<Vec<String>>(manifest).unwrap_or_default();
+    let current: HashSet<_> = files.iter().collect();
+    for file in previous.iter().filter(|file| !current.contains(file)) {
+        let relative = Path::new(file);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            continue;
+        }
+        let path = target.join(relative);
+        if path.symlink_metadata().is_ok_and(|m| !m.is_dir()) {
+            rimraf(&path)?;
+            counters.removed.fetch_add(1, Ordering::Relaxed);
+            // Directories left empty by the removed files were removed from the source too
+            let mut dir = path.parent();
+            while let Some(parent) = dir.filter(|dir| *dir != target) {
+                if fs::remove_dir(parent).is_err() {
+                    break;
+                }
+                dir = parent.parent();
+            }
+        }
+    }
+    write_sync_manifest(manifest, source, policy)
+}
+
+/// Files in the directory relative to it, with `/` separators
+fn list_files(dir: &Path, policy: SymlinkPolicy) -> Vec<String> {
+    WalkDir::new(dir)
+        .follow_links(policy == SymlinkPolicy::Follow)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_type().is_dir())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(dir).ok()?;
+            let parts: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            Some(parts.join("/"))
+        })
+        .collect()
+}
+
+/// Writes the directories into a zip archive, each under its own folder, or at the root if the
+/// folder name is empty. Returns the size of the archive.
+pub fn write_zip(dirs: &[(&Path, &str)], output: &Path) -> Result<u64> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::File::create(output).with_context(|| {
+        format!(
+            "Failed to create file\n\
+             <yellow> >></> Path: {}",
+            output.display()
+        )
+    })?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (dir, folder) in dirs {
+        for entry in WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(dir)?;
+            let name = Path::new(folder)
+                .join(relative)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            zip.start_file(name, options)?;
+            zip.write_all(&fs::read(entry.path())?)?;
+        }
+    }
+    zip.finish()?;
+    Ok(fs::metadata(output)?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(name: &str) -> (PathBuf, PathBuf, PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("rgl-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let source = root.join("source");
+        let target = root.join("target");
+        fs::create_dir_all(source.join("dir")).unwrap();
+        fs::write(source.join("kept.json"), "{}").unwrap();
+        fs::write(source.join("dir").join("deleted.json"), "{}").unwrap();
+        (root.to_owned(), source, target, root.join("manifest.json"))
+    }
+
+    #[test]
+    fn plain_sync_removes_files_not_in_source() {
+        let (root, source, target, manifest) = setup("sync-plain");
+        sync_dir(&source, &target, SyncOptions::tracked(&manifest)).unwrap();
+        fs::remove_file(source.join("dir").join("deleted.json")).unwrap();
+        fs::write(target.join("output.json"), "{}").unwrap();
+
+        let stats = sync_dir(&source, &target, SyncOptions::tracked(&manifest)).unwrap();
+        assert!(target.join("kept.json").is_file());
+        assert!(!target.join("dir").join("deleted.json").exists());
+        assert!(!target.join("output.json").exists());
+        assert_eq!(stats.removed, 2);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn incremental_sync_keeps_previous_outputs() {
+        let (root, source, target, manifest) = setup("sync-incremental");
+        sync_dir(&source, &target, SyncOptions::tracked(&manifest)).unwrap();
+        fs::remove_file(source.join("dir").join("deleted.json")).unwrap();
+        fs::write(target.join("output.json"), "{}").unwrap();
+
+        let options = SyncOptions::tracked(&manifest).incremental(true);
+        let stats = sync_dir(&source, &target, options).unwrap();
+        assert!(target.join("kept.json").is_file());
+        assert!(!target.join("dir").join("deleted.json").exists());
+        assert!(target.join("output.json").is_file());
+        assert_eq!(stats.removed, 1);
+        fs::remove_dir_all(root).unwrap();
     }
-    METADATA_CACHE.clear();
-    Ok(())
 }
@@ -1,3 +1,4 @@
+use serde_json::{json, Value};
 use std::{
     fmt::Display,
     sync::{
@@ -7,6 +8,7 @@ use std::{
 };
 
 static DEBUG_FLAG: AtomicBool = AtomicBool::new(false);
+static JSON_FLAG: AtomicBool = AtomicBool::new(false);
 
 fn get_logger() -> MutexGuard<'static, paris::Logger<'static>> {
     static LOGGER: OnceLock<Mutex<paris::Logger<'static>>> = OnceLock::new();
@@ -23,20 +25,54 @@ fn get_logger() -> MutexGuard<'static, paris::Logger<'static>> {
 pub struct Logger;
 
 impl Logger {
+    /// Prints messages as JSON lines with the level and the message without styles, for tools
+    /// that read the output of rgl
+    pub fn set_json(json: bool) {
+        JSON_FLAG.store(json, Ordering::Relaxed);
+    }
+
+    pub fn get_json() -> bool {
+        JSON_FLAG.load(Ordering::Relaxed)
+    }
+
+    /// Prints a JSON line, e.g. an error with its code
+    pub fn json(value: Value) {
+        println!("{value}");
+    }
+
+    /// Prints the output of a command as is, e.g. a graph or a QR code. Styles are not applied, so
+    /// it can contain any character.
+    pub fn output<T: Display>(output: T) {
+        if Logger::get_json() {
+            Logger::json(json!({ "level": "output", "message": output.to_string() }));
+        } else {
+            println!("{output}");
+        }
+    }
+
+    fn emit<T: Display>(level: &str, tag: &str, message: T) {
+        if Logger::get_json() {
+            let message = strip_styles(&message.to_string());
+            Logger::json(json!({ "level": level, "message": message }));
+        } else {
+            get_logger().log(format!("{tag}{message}"));
+        }
+    }
+
     pub fn log<T: Display>(message: T) {
-        get_logger().log(message);
+        Logger::emit("log", "", message);
     }
 
     pub fn info<T: Display>(message: T) {
-        Logger::log(format!("<blue>[INFO]</> {message}"));
+        Logger::emit("info", "<blue>[INFO]</> ", message);
     }
 
     pub fn warn<T: Display>(message: T) {
-        Logger::log(format!("<yellow>[WARN]</> {message}"));
+        Logger::emit("warn", "<yellow>[WARN]</> ", message);
     }
 
     pub fn error<T: Display>(message: T) {
-        Logger::log(format!("<red>[ERROR]</> {message}"));
+        Logger::emit("error", "<red>[ERROR]</> ", message);
     }
 
     pub fn get_debug() -> bool {
@@ -49,19 +85,46 @@ impl Logger {
 
     pub fn debug<T: Display>(message: T) {
         if DEBUG_FLAG.load(Ordering::Relaxed) {
-            Logger::log(format!("<magenta>[DEBUG]</> {message}"))
+            Logger::emit("debug", "<magenta>[DEBUG]</> ", message);
         }
     }
 
     pub fn loading<T: Display>(message: T) {
-        get_logger().loading(message);
+        if Logger::get_json() {
+            Logger::emit("info", "", message);
+        } else {
+            get_logger().loading(message);
+        }
     }
 
     pub fn success<T: Display>(message: T) {
-        Logger::log(format!("<green>[DONE]</> {message}"));
+        Logger::emit("success", "<green>[DONE]</> ", message);
     }
 }
 
+/// Removes style tags like `<b>` and `</>` from a message
+pub fn strip_styles(message: &str) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        let tag = rest[start + 1..].split_once('>').map(|(tag, _)| tag);
+        match tag {
+            Some(tag) if is_style_tag(tag) => rest = &rest[start + tag.len() + 2..],
+            _ => {
+                result.push('<');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn is_style_tag(tag: &str) -> bool {
+    tag == "/" || (!tag.is_empty() && tag.chars().all(|c| c.is_ascii_lowercase() || c == '-'))
+}
+
 #[macro_export]
 macro_rules! log {
     ($($arg:tt)*) => {
@@ -2,25 +2,66 @@ mod commands;
 mod file_watcher;
 mod fs;
 mod logger;
+mod progress;
 mod rgl;
 
 use anyhow::{Context, Result};
-use clap::{crate_name, Parser, Subcommand};
+use clap::{crate_name, Parser, Subcommand, ValueEnum};
 use commands::*;
 use enum_dispatch::enum_dispatch;
-use logger::Logger;
+use logger::{strip_styles, Logger};
+use serde_json::json;
 use std::thread;
 
 fn main() {
     let cli = Cli::parse();
     Logger::set_debug(cli.debug);
-    if let Err(e) = run_command(cli) {
-        error!("{e}");
-        e.chain().skip(1).for_each(|e| log!("<red>[+]</> {e}"));
+    Logger::set_json(matches!(cli.log_format, LogFormat::Json));
+    if let Err(e) = ctrlc::set_handler(on_interrupt) {
+        debug!("Failed to set interrupt handler: {e}");
+    }
+    let result = run_command(cli);
+    rgl::remove_memory_temps();
+    if let Err(e) = result {
+        print_error(&e);
         std::process::exit(1);
     }
 }
 
+/// Prints the error with its causes, and the error code if it has one
+fn print_error(e: &anyhow::Error) {
+    let code = rgl::get_error_code(e);
+    if Logger::get_json() {
+        Logger::json(json!({
+            "level": "error",
+            "code": code.map(|code| code.code()),
+            "message": strip_styles(&e.to_string()),
+            "causes": e.chain().skip(1).map(|e| strip_styles(&e.to_string())).collect::<Vec<_>>(),
+        }));
+        return;
+    }
+    error!("{e}");
+    e.chain().skip(1).for_each(|e| log!("<red>[+]</> {e}"));
+    if let Some(code) = code {
+        log!(
+            "<blue>[?]</> {} ({}), run <b>rgl explain {}</> to see how to fix it",
+            code.code(),
+            code.title(),
+            code.code()
+        );
+    }
+}
+
+/// Stops running filters before exiting, otherwise they keep running in the background and hold
+/// on to the session lock.
+fn on_interrupt() {
+    warn!("Interrupted, stopping...");
+    rgl::kill_subprocesses();
+    rgl::remove_memory_temps();
+    let _ = fs::rimraf(rgl::get_changed_files_path());
+    std::process::exit(130);
+}
+
 fn run_command(cli: Cli) -> Result<()> {
     let cache_dir = rgl::get_cache_dir()?;
     if !cache_dir.exists() {
@@ -61,6 +102,15 @@ struct Cli {
     /// Print debug messages
     #[arg(long, global = true)]
     debug: bool,
+    /// Format of the messages, `json` prints a JSON object per line
+    #[arg(long, global = true, default_value = "text")]
+    log_format: LogFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
 }
 
 #[derive(Subcommand)]
@@ -68,17 +118,36 @@ struct Cli {
 enum Subcommands {
     Add(Add),
     Apply(Apply),
+    Builds(Builds),
+    Cache(Cache),
     Clean(Clean),
+    CreateFilter(CreateFilter),
+    Env(Env),
     Exec(Exec),
+    Explain(Explain),
+    Filter(Filter),
     Get(Get),
+    Graph(Graph),
     Info(Info),
     Init(Init),
     Install(Install),
+    Licenses(Licenses),
     List(List),
+    MigrateConfig(MigrateConfig),
+    New(New),
+    Outdated(Outdated),
+    Publish(Publish),
     Remove(Remove),
     Run(Run),
+    Status(Status),
     Uninstall(Uninstall),
     Update(Update),
     Upgrade(Upgrade),
+    Validate(Validate),
+    Vanilla(Vanilla),
     Watch(Watch),
+    Why(Why),
+    World(World),
+    #[command(external_subcommand)]
+    Plugin(Vec<String>),
 }
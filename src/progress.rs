@@ -0,0 +1,90 @@
+use crate::logger::Logger;
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        LazyLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Progress indicators that are shown at the same time, e.g. while exporting to several targets
+static MULTI: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+/// Only show progress for operations that take longer than this
+const SHOW_DELAY: Duration = Duration::from_secs(1);
+
+/// Progress indicator printed to stderr. Hidden when stderr is not a terminal, or when logging
+/// JSON.
+pub struct Progress {
+    bar: ProgressBar,
+    bytes: AtomicU64,
+    shown: AtomicBool,
+    start: Instant,
+}
+
+impl Progress {
+    /// Counts processed files and bytes, shown once the operation takes longer than a second.
+    pub fn files(message: impl Into<String>) -> Self {
+        let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::hidden())
+            .with_style(
+                ProgressStyle::with_template("{spinner:.green} {prefix} {human_pos} files {msg}")
+                    .unwrap(),
+            )
+            .with_prefix(message.into());
+        Self {
+            bar,
+            bytes: AtomicU64::new(0),
+            shown: AtomicBool::new(Logger::get_json()),
+            start: Instant::now(),
+        }
+    }
+
+    /// Spinner for operations that don't report any progress, e.g. subprocesses.
+    pub fn spinner(message: impl Into<String>) -> Self {
+        // Progress would be mixed with the JSON lines
+        if Logger::get_json() {
+            return Self::hidden();
+        }
+        let bar = MULTI.add(
+            ProgressBar::new_spinner()
+                .with_style(
+                    ProgressStyle::with_template("{spinner:.green} {msg} [{elapsed}]").unwrap(),
+                )
+                .with_message(message.into()),
+        );
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Self {
+            bar,
+            bytes: AtomicU64::new(0),
+            shown: AtomicBool::new(true),
+            start: Instant::now(),
+        }
+    }
+
+    fn hidden() -> Self {
+        Self {
+            bar: ProgressBar::hidden(),
+            bytes: AtomicU64::new(0),
+            shown: AtomicBool::new(true),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn inc_file(&self, bytes: u64) {
+        let total = self.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.bar.inc(1);
+        if !self.shown.load(Ordering::Relaxed)
+            && self.start.elapsed() > SHOW_DELAY
+            && !self.shown.swap(true, Ordering::Relaxed)
+        {
+            MULTI.add(self.bar.clone());
+        }
+        self.bar.set_message(format!("({})", HumanBytes(total)));
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+        MULTI.remove(&self.bar);
+    }
+}
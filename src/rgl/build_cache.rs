@@ -0,0 +1,241 @@
+use super::{get_build_cache_dir, FilterContext, FilterDefinition, UserConfig};
+use crate::fs::rimraf;
+use crate::{debug, warn};
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+/// Bump when the key or artifact format changes
+const CACHE_VERSION: &str = "1";
+
+/// Directories that are never part of a filter's source hash
+const IGNORED_SOURCE_DIRS: [&str; 4] = [".git", ".venv", "__pycache__", "node_modules"];
+
+/// Cached output of a single filter run, addressed by the hash of everything that can affect the
+/// output: the filter definition and source, its arguments, and the contents of the temp directory.
+///
+/// Artifacts are stored in the rgl cache directory and, if `build_cache_url` is set in the user
+/// config, shared through a remote that supports `GET` and `PUT` of `<url>/<key>.zip`.
+pub struct BuildCache {
+    key: String,
+}
+
+impl BuildCache {
+    pub fn new(
+        context: &FilterContext,
+        filter: &FilterDefinition,
+        run_args: &[String],
+        temp: &Path,
+    ) -> Result<Self> {
+        let mut hasher = Sha256::new();
+        hasher.update(CACHE_VERSION);
+        hasher.update(serde_json::to_vec(filter)?);
+        for arg in run_args {
+            hasher.update(arg);
+            hasher.update([0]);
+        }
+        if let FilterDefinition::Local(local) = filter {
+            let script = serde_json::to_value(local)?["script"]
+                .as_str()
+                .map(|script| script.to_owned());
+            if let Some(script) = script {
+                let dir = context.filter_dir(&script);
+                // Scripts in the project root can't be told apart from the rest of the project
+                if dir.as_os_str().is_empty() || dir == Path::new(".") {
+                    hasher.update(hash_tree(Path::new(&script), &[])?);
+                } else {
+                    hasher.update(hash_tree(&dir, &IGNORED_SOURCE_DIRS)?);
+                }
+            }
+        }
+        hasher.update(hash_tree(temp, &[])?);
+        Ok(Self {
+            key: format!("{:x}", hasher.finalize()),
+        })
+    }
+
+    fn local_path(&self) -> Result<PathBuf> {
+        Ok(get_build_cache_dir()?.join(format!("{}.zip", self.key)))
+    }
+
+    /// Replaces the contents of the temp directory with the cached output, if any.
+    pub fn restore(&self, temp: &Path) -> Result<bool> {
+        let path = self.local_path()?;
+        if !path.is_file() && !self.download(&path) {
+            return Ok(false);
+        }
+        debug!("Restoring build cache <b>{}</>", self.key);
+        let inner = || -> Result<()> {
+            for dir in ["BP", "RP", "data"] {
+                clear_dir(&temp.join(dir))?;
+            }
+            ZipArchive::new(fs::File::open(&path)?)?.extract(temp)?;
+            Ok(())
+        };
+        inner().with_context(|| {
+            format!(
+                "Failed to restore build cache\n\
+                 <yellow> >></> Path: {}",
+                path.display()
+            )
+        })?;
+        Ok(true)
+    }
+
+    /// Saves the contents of the temp directory. Failures only produce a warning, since the
+    /// filter output itself is still valid.
+    pub fn store(&self, temp: &Path) {
+        let result = self
+            .local_path()
+            .and_then(|path| write_archive(temp, &path).map(|_| path));
+        match result {
+            Ok(path) => self.upload(&path),
+            Err(e) => warn!("Failed to write build cache: {}", e.root_cause()),
+        }
+    }
+
+    fn download(&self, path: &Path) -> bool {
+        let Some(url) = UserConfig::build_cache_url() else {
+            return false;
+        };
+        let url = format!("{}/{}.zip", url.trim_end_matches('/'), self.key);
+        debug!("Downloading build cache from {url}");
+        let result = with_auth(ureq::get(&url)).call();
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return false,
+            Err(e) => {
+                warn!("Failed to download build cache: {e}");
+                return false;
+            }
+        };
+        let mut bytes = vec![];
+        let inner = || -> Result<()> {
+            response.into_reader().read_to_end(&mut bytes)?;
+            write_atomic(path, &bytes)
+        };
+        match inner() {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to download build cache: {}", e.root_cause());
+                false
+            }
+        }
+    }
+
+    fn upload(&self, path: &Path) {
+        let Some(url) = UserConfig::build_cache_url() else {
+            return;
+        };
+        let url = format!("{}/{}.zip", url.trim_end_matches('/'), self.key);
+        debug!("Uploading build cache to {url}");
+        let result = fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| {
+                with_auth(ureq::put(&url))
+                    .set("Content-Type", "application/zip")
+                    .send_bytes(&bytes)?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            warn!("Failed to upload build cache: {e}");
+        }
+    }
+}
+
+fn with_auth(request: ureq::Request) -> ureq::Request {
+    match UserConfig::build_cache_token() {
+        Some(token) => request.set("Authorization", &format!("Bearer {token}")),
+        None => request,
+    }
+}
+
+/// Files under the path, relative to it with `/` separators. Symlinks are followed since the
+/// packs in the temp directory may be linked to the export target.
+fn get_files(path: &Path, ignored_dirs: &[&str]) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+    let walker = WalkDir::new(path)
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.file_type().is_dir()
+                && ignored_dirs.contains(&entry.file_name().to_string_lossy().as_ref()))
+        });
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(path)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((relative, entry.into_path()));
+    }
+    Ok(files)
+}
+
+fn hash_tree(path: &Path, ignored_dirs: &[&str]) -> Result<Vec<u8>> {
+    if path.is_file() {
+        return Ok(Sha256::digest(fs::read(path)?).to_vec());
+    }
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let hashes = get_files(path, ignored_dirs)?
+        .into_par_iter()
+        .map(|(relative, path)| -> Result<(String, Vec<u8>)> {
+            Ok((relative, Sha256::digest(fs::read(path)?).to_vec()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut hasher = Sha256::new();
+    for (relative, hash) in hashes {
+        hasher.update(relative);
+        hasher.update([0]);
+        hasher.update(hash);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+fn write_archive(temp: &Path, path: &Path) -> Result<()> {
+    let mut zip = ZipWriter::new(io::Cursor::new(vec![]));
+    for (relative, file) in get_files(temp, &[])? {
+        zip.start_file(relative, FileOptions::default())?;
+        zip.write_all(&fs::read(file)?)?;
+    }
+    write_atomic(path, zip.finish()?.get_ref())
+}
+
+/// Writes through a temporary file so other processes never see a partial artifact.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&temp_path, bytes)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Removes everything inside the directory, keeping the directory itself since it may be a
+/// symlink to the export target.
+fn clear_dir(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(path)? {
+        rimraf(entry?.path())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Observers that receive every build event
+static OBSERVERS: Mutex<Vec<Arc<dyn BuildObserver>>> = Mutex::new(vec![]);
+
+/// Progress of a profile run, in the order it happens.
+pub enum BuildEvent<'a> {
+    RunStarted {
+        profile: &'a str,
+    },
+    FilterStarted {
+        name: &'a str,
+    },
+    /// The `when` expression of the filter evaluated to false
+    FilterSkipped {
+        name: &'a str,
+    },
+    FilterFinished {
+        name: &'a str,
+        duration: Duration,
+        /// The output was restored from the build cache instead of running the filter
+        cached: bool,
+        error: Option<&'a anyhow::Error>,
+    },
+    /// Emitted once for the export target and once for each mirror
+    Export {
+        bp: Option<&'a Path>,
+        rp: Option<&'a Path>,
+    },
+    RunFinished {
+        error: Option<&'a anyhow::Error>,
+    },
+}
+
+/// Receives build events as they happen, e.g. to show the build state in a user interface.
+pub trait BuildObserver: Send + Sync {
+    fn on_event(&self, event: &BuildEvent);
+}
+
+pub fn add_build_observer(observer: Arc<dyn BuildObserver>) {
+    OBSERVERS.lock().unwrap().push(observer);
+}
+
+pub fn emit_build_event(event: BuildEvent) {
+    // Observers are cloned so they can emit events or add observers themselves
+    let observers = OBSERVERS.lock().unwrap().clone();
+    for observer in observers {
+        observer.on_event(&event);
+    }
+}
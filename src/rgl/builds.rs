@@ -0,0 +1,144 @@
+use super::{get_pack_version, Config};
+use crate::info;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Extensions of the archives written to the builds directory
+const BUILD_EXTENSIONS: [&str; 4] = ["mcaddon", "mcpack", "mcworld", "zip"];
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildsConfig {
+    /// Directory the archives are written to, defaults to `./builds`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Number of archives of each kind to keep, older ones are removed after each build. Every
+    /// archive is kept if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+}
+
+pub struct Build {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+pub fn get_builds_dir(config: &Config) -> PathBuf {
+    let path = config.get_builds().path.unwrap_or("./builds".to_owned());
+    PathBuf::from(path)
+}
+
+/// Path of the archive for the current version of the packs, e.g. `builds/name-1.2.0.mcaddon`
+pub fn get_build_path(config: &Config, extension: &str) -> PathBuf {
+    let file_name = match get_pack_version(config) {
+        Some(version) => format!("{}-{version}.{extension}", config.get_name()),
+        None => format!("{}.{extension}", config.get_name()),
+    };
+    get_builds_dir(config).join(file_name)
+}
+
+/// Archives of the project in the builds directory, newest first. Other files are left out, the
+/// directory can be shared with other projects.
+pub fn list_builds(config: &Config) -> Result<Vec<Build>> {
+    let dir = get_builds_dir(config);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut builds = vec![];
+    let entries = fs::read_dir(&dir).with_context(|| {
+        format!(
+            "Failed to read builds directory\n\
+             <yellow> >></> Path: {}",
+            dir.display()
+        )
+    })?;
+    for entry in entries {
+        let path = entry?.path();
+        if !path.is_file()
+            || get_build_extension(&path).is_none()
+            || !is_project_build(config, &path)
+        {
+            continue;
+        }
+        let metadata = path.metadata()?;
+        builds.push(Build {
+            path,
+            size: metadata.len(),
+            modified: metadata.modified()?,
+        });
+    }
+    builds.sort_by_key(|build| std::cmp::Reverse(build.modified));
+    Ok(builds)
+}
+
+/// Removes all but the newest `keep` archives of each kind. Returns the removed archives.
+pub fn prune_builds(config: &Config, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut kept = HashMap::<&str, usize>::new();
+    let mut removed = vec![];
+    for build in list_builds(config)? {
+        let Some(extension) = get_build_extension(&build.path) else {
+            continue;
+        };
+        let count = kept.entry(extension).or_default();
+        if *count < keep {
+            *count += 1;
+            continue;
+        }
+        fs::remove_file(&build.path).with_context(|| {
+            format!(
+                "Failed to remove build\n\
+                 <yellow> >></> Path: {}",
+                build.path.display()
+            )
+        })?;
+        removed.push(build.path);
+    }
+    Ok(removed)
+}
+
+/// Applies the `keep` setting of the config after writing an archive to the builds directory
+pub fn apply_build_retention(config: &Config) -> Result<()> {
+    let Some(keep) = config.get_builds().keep else {
+        return Ok(());
+    };
+    let removed = prune_builds(config, keep)?;
+    if !removed.is_empty() {
+        info!("Removed {} old build(s), keeping {keep}", removed.len());
+    }
+    Ok(())
+}
+
+fn get_build_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?;
+    BUILD_EXTENSIONS
+        .into_iter()
+        .find(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Whether the archive was written by [`get_build_path`] for this project, `<name>.<ext>` or
+/// `<name>-<version>.<ext>`
+fn is_project_build(config: &Config, path: &Path) -> bool {
+    let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return false;
+    };
+    let Some(rest) = stem.strip_prefix(config.get_name()) else {
+        return false;
+    };
+    if rest.is_empty() {
+        return true;
+    }
+    let Some(version) = rest.strip_prefix('-') else {
+        return false;
+    };
+    version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+}
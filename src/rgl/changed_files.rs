@@ -0,0 +1,68 @@
+use super::{normalize_path, Config};
+use crate::fs::{rimraf, write_json};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// File listing the paths changed since the last successful run. Filters receive its location
+/// through the `RGL_CHANGED_FILES` environment variable.
+pub fn get_changed_files_path() -> PathBuf {
+    PathBuf::from(".regolith").join("changed_files.json")
+}
+
+/// Manifest of the files the last sync copied into the directory, so the next incremental sync
+/// only removes files that were deleted from its source and keeps the outputs of earlier filters.
+pub fn get_sync_manifest(dir: &Path) -> Result<PathBuf> {
+    let dir = normalize_path(dir)?;
+    let hash = format!("{:x}", Sha256::digest(dir.as_os_str().as_encoded_bytes()));
+    Ok(PathBuf::from(".regolith")
+        .join("sync")
+        .join(format!("{}.json", &hash[..16])))
+}
+
+/// Writes the changed paths as they appear inside the temp directory, e.g.
+/// `RP/textures/blocks/dirt.png`. The file is removed when a full run is required, filters that
+/// don't find it should process everything. Returns whether the file was written, only then the
+/// run is incremental.
+pub fn write_changed_files(config: &Config, changes: &[PathBuf]) -> Result<bool> {
+    let path = get_changed_files_path();
+    match map_changes(config, changes)? {
+        Some(files) => write_json(path, &files).map(|_| true),
+        None => rimraf(path).map(|_| false),
+    }
+}
+
+fn map_changes(config: &Config, changes: &[PathBuf]) -> Result<Option<Vec<String>>> {
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let mut roots = vec![];
+    if let Some(bp) = config.get_behavior_pack() {
+        roots.push(("BP", normalize_path(&bp)?));
+    }
+    if let Some(rp) = config.get_resource_pack() {
+        roots.push(("RP", normalize_path(&rp)?));
+    }
+    roots.push(("data", normalize_path(&config.get_data_path())?));
+
+    let mut files = vec![];
+    for path in changes {
+        let path = normalize_path(path)?;
+        let Some((root, relative)) = roots
+            .iter()
+            .find_map(|(root, dir)| Some((root, path.strip_prefix(dir).ok()?)))
+        else {
+            // Changes outside of the packs (e.g. config.json) may affect every filter
+            return Ok(None);
+        };
+        let mut file = root.to_string();
+        for component in relative.components() {
+            file.push('/');
+            file.push_str(&component.as_os_str().to_string_lossy());
+        }
+        files.push(file);
+    }
+    files.sort();
+    files.dedup();
+    Ok(Some(files))
+}
@@ -0,0 +1,87 @@
+use crate::{log, warn};
+use std::{
+    collections::HashSet,
+    path::{Component, Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+use walkdir::{DirEntry, WalkDir};
+
+/// Warns about cloud storage placeholders ("files on demand") inside the directory. Their content
+/// is only downloaded when accessed, so syncing against them can produce corrupted exports.
+///
+/// Each directory is only checked once per process.
+pub fn check_cloud_files(dir: &Path) {
+    static CHECKED: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(Default::default);
+    if !dir.exists() || !CHECKED.lock().unwrap().insert(dir.to_owned()) {
+        return;
+    }
+    let absolute = dunce::canonicalize(dir).unwrap_or(dir.to_owned());
+    let provider = get_cloud_provider(&absolute);
+    if let Some(provider) = provider {
+        warn!(
+            "<b>{}</> is synced by {provider}, make sure its files are always kept on this device",
+            dir.display()
+        );
+    }
+    let placeholders: Vec<_> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(is_placeholder)
+        .collect();
+    if placeholders.is_empty() {
+        return;
+    }
+    warn!(
+        "Found {} cloud placeholder file(s) in <b>{}</>, these files are not available offline",
+        placeholders.len(),
+        dir.display()
+    );
+    for entry in placeholders.iter().take(5) {
+        log!("<yellow> >></> {}", entry.path().display());
+    }
+    if placeholders.len() > 5 {
+        log!("<yellow> >></> ...and {} more", placeholders.len() - 5);
+    }
+}
+
+fn get_cloud_provider(path: &Path) -> Option<&'static str> {
+    path.components().find_map(|component| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            if name.starts_with("OneDrive") {
+                Some("OneDrive")
+            } else if name == "iCloud Drive" || name == "Mobile Documents" {
+                Some("iCloud")
+            } else if name == "Dropbox" {
+                Some("Dropbox")
+            } else if name == "Google Drive" {
+                Some("Google Drive")
+            } else {
+                None
+            }
+        }
+        _ => None,
+    })
+}
+
+#[cfg(windows)]
+fn is_placeholder(entry: &DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+    entry.metadata().is_ok_and(|metadata| {
+        metadata.file_attributes()
+            & (FILE_ATTRIBUTE_OFFLINE
+                | FILE_ATTRIBUTE_RECALL_ON_OPEN
+                | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+            != 0
+    })
+}
+
+/// iCloud replaces evicted files with hidden `.<name>.icloud` stubs
+#[cfg(unix)]
+fn is_placeholder(entry: &DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    name.starts_with('.') && name.ends_with(".icloud")
+}
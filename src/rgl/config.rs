@@ -1,21 +1,32 @@
 use super::{
-    DevelopmentExport, Export, FilterDefinition, FilterRunner, LocalExport, Profile, RemoteFilter,
-    UserConfig,
+    eval_config_script, get_config_script, BuildsConfig, ConfigMigration, DevelopmentExport,
+    ErrorCode, Export, FilterDefinition, FilterRunner, FilterWasm, LocalExport, LocalFilter,
+    Plugin, Profile, ProjectDependency, RemoteFilter, SettingsSkeletonEntry, UserConfig,
+    CONFIG_FORMAT_VERSION,
 };
 use crate::file_watcher::FileWatcher;
-use crate::fs::{read_json, write_file, write_json};
-use anyhow::{anyhow, bail, Context, Result};
+use crate::fs::{read_json, write_file, write_json, SymlinkPolicy};
+use crate::warn;
+use anyhow::{anyhow, bail, Result};
 use indexmap::IndexMap;
-use jsonc_parser::cst::{CstObject, CstRootNode};
-use jsonc_parser::{json, ParseOptions};
+use jsonc_parser::common::Ranged;
+use jsonc_parser::cst::{CstInputValue, CstObject, CstRootNode};
+use jsonc_parser::{ast, json, parse_to_ast, CollectOptions, ParseOptions};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{collections::BTreeMap, path::PathBuf};
+use serde_path_to_error::Segment;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     #[serde(rename = "$schema", skip_serializing_if = "Option::is_none")]
     schema: Option<String>,
+    /// Version of the config format, configs without it are version 0
+    #[serde(rename = "formatVersion", default)]
+    format_version: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     author: Option<String>,
     name: String,
@@ -36,8 +47,27 @@ struct Packs {
 #[serde(rename_all = "camelCase")]
 struct Regolith {
     data_path: String,
+    /// Subdirectory of a shared `dataPath` holding this project's own filter data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_namespace: Option<String>,
+    /// Directory containing `.wasm` plugins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plugins_path: Option<String>,
     filter_definitions: BTreeMap<String, Value>,
     profiles: IndexMap<String, Profile>,
+    /// Other rgl projects built before each profile runs
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    dependencies: IndexMap<String, ProjectDependency>,
+    /// How symlinks inside the packs and the data path are copied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symlinks: Option<SymlinkPolicy>,
+    /// Where archives like `.mcaddon` files are written, and how many are kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    builds: Option<BuildsConfig>,
+    /// Validation passes of `rgl validate` that are turned on or off by name, passes that are
+    /// not listed run
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    validators: BTreeMap<String, bool>,
 }
 
 impl Config {
@@ -48,6 +78,16 @@ impl Config {
             Profile {
                 export: Export::Development(DevelopmentExport::default()),
                 filters: vec![],
+                content_hashes: false,
+                compat: false,
+                in_memory: false,
+                atomic_export: false,
+                temp_dir: None,
+                sandbox: false,
+                isolate_data: false,
+                experiments: vec![],
+                limits: None,
+                inline: false,
             },
         );
         profiles.insert(
@@ -57,6 +97,16 @@ impl Config {
                 filters: vec![FilterRunner::ProfileFilter {
                     profile_name: "default".to_owned(),
                 }],
+                content_hashes: false,
+                compat: false,
+                in_memory: false,
+                atomic_export: false,
+                temp_dir: None,
+                sandbox: false,
+                isolate_data: false,
+                experiments: vec![],
+                limits: None,
+                inline: false,
             },
         );
         Self {
@@ -64,6 +114,7 @@ impl Config {
                 "https://raw.githubusercontent.com/ink0rr/rgl-schemas/main/config/v1.1.json"
                     .to_owned(),
             ),
+            format_version: CONFIG_FORMAT_VERSION,
             author: Some(UserConfig::username()),
             name,
             packs: Packs {
@@ -72,16 +123,59 @@ impl Config {
             },
             regolith: Regolith {
                 data_path: "./data".to_owned(),
+                data_namespace: None,
+                plugins_path: None,
                 filter_definitions: BTreeMap::<String, Value>::new(),
                 profiles,
+                dependencies: IndexMap::new(),
+                symlinks: None,
+                builds: None,
+                validators: BTreeMap::new(),
             },
         }
     }
 
     pub fn load() -> Result<Self> {
-        let config = read_json::<Config>("./config.json")?;
+        let config: Config = match get_config_script() {
+            Some(script) => {
+                let data = eval_config_script(script)?;
+                // Line numbers of the generated config don't point to anything in the script
+                serde_path_to_error::deserialize(data).map_err(|e| config_error(script, "", e))?
+            }
+            None => {
+                let path = "./config.json";
+                let mut data =
+                    read_json::<Value>(path).map_err(|e| ErrorCode::InvalidConfig.wrap(e))?;
+                let format_version = data["formatVersion"].as_u64().unwrap_or_default();
+                if format_version < CONFIG_FORMAT_VERSION {
+                    // Migrated in memory only, rewriting the config is left to `rgl migrate-config`
+                    if let Some(migration) = ConfigMigration::prepare()? {
+                        warn!(
+                            "config.json uses format version {}, run <b>rgl migrate-config</> to update it to {CONFIG_FORMAT_VERSION}",
+                            migration.from
+                        );
+                        data = migration
+                            .to_value()
+                            .map_err(|e| ErrorCode::InvalidConfig.wrap(e))?;
+                    }
+                }
+                serde_path_to_error::deserialize(data).map_err(|e| {
+                    let data = std::fs::read_to_string(path).unwrap_or_default();
+                    config_error(path, &data, e)
+                })?
+            }
+        };
+        if config.format_version > CONFIG_FORMAT_VERSION {
+            return Err(ErrorCode::InvalidConfig.error(format!(
+                "The config uses format version {}, which requires a newer version of rgl\n\
+                 <yellow> >></> Run <b>rgl upgrade</> to update rgl",
+                config.format_version
+            )));
+        }
         if config.packs.behavior_pack.is_none() && config.packs.resource_pack.is_none() {
-            bail!("Must specify at least one of `behaviorPack` or `resourcePack` in 'packs'")
+            return Err(ErrorCode::InvalidConfig.error(
+                "Must specify at least one of `behaviorPack` or `resourcePack` in 'packs'",
+            ));
         }
         Ok(config)
     }
@@ -90,6 +184,11 @@ impl Config {
         write_json("./config.json", self)
     }
 
+    /// Whether the current directory has a config, either `config.json` or a config script
+    pub fn exists() -> bool {
+        Path::new("./config.json").is_file() || get_config_script().is_some()
+    }
+
     pub fn get_name(&self) -> &str {
         &self.name
     }
@@ -106,22 +205,75 @@ impl Config {
         PathBuf::from(&self.regolith.data_path)
     }
 
+    /// Directory inside the data path that overrides the shared data for this project, and
+    /// receives the data exported by filters.
+    pub fn get_data_namespace_path(&self) -> Option<PathBuf> {
+        let namespace = self.regolith.data_namespace.as_ref()?;
+        Some(self.get_data_path().join(namespace))
+    }
+
+    pub fn get_symlink_policy(&self) -> SymlinkPolicy {
+        self.regolith.symlinks.unwrap_or_default()
+    }
+
+    pub fn get_builds(&self) -> BuildsConfig {
+        self.regolith.builds.clone().unwrap_or_default()
+    }
+
+    pub fn get_validators(&self) -> &BTreeMap<String, bool> {
+        &self.regolith.validators
+    }
+
+    pub fn get_plugins(&self) -> Result<Vec<Plugin>> {
+        match &self.regolith.plugins_path {
+            Some(path) => Plugin::load_all(Path::new(path)),
+            None => Ok(vec![]),
+        }
+    }
+
     pub fn get_profile(&self, profile_name: &str) -> Result<&Profile> {
+        self.regolith.profiles.get(profile_name).ok_or_else(|| {
+            ErrorCode::ProfileNotFound
+                .error(format!("Profile <profile>{profile_name}</> not found"))
+        })
+    }
+
+    pub fn get_dependencies(&self) -> &IndexMap<String, ProjectDependency> {
+        &self.regolith.dependencies
+    }
+
+    pub fn get_profiles(&self) -> &IndexMap<String, Profile> {
+        &self.regolith.profiles
+    }
+
+    /// Adds a profile for this run only, replacing the one with the same name
+    pub fn insert_profile(&mut self, profile_name: &str, profile: Profile) {
         self.regolith
             .profiles
-            .get(profile_name)
-            .with_context(|| format!("Profile <profile>{profile_name}</> not found"))
+            .insert(profile_name.to_owned(), profile);
     }
 
     pub fn get_filter(&self, filter_name: &str) -> Result<FilterDefinition> {
-        let value = self
-            .regolith
-            .filter_definitions
-            .get(filter_name)
-            .with_context(|| {
-                format!("Filter <filter>{filter_name}</> is not defined in filterDefinitions")
-            })?
-            .to_owned();
+        let Some(value) = self.regolith.filter_definitions.get(filter_name) else {
+            // Filters registered by plugins don't need a definition
+            for plugin in self.get_plugins()? {
+                if plugin
+                    .manifest
+                    .filters
+                    .iter()
+                    .any(|name| name == filter_name)
+                {
+                    return Ok(FilterDefinition::Local(LocalFilter::Wasm(FilterWasm {
+                        path: plugin.path.to_string_lossy().into_owned(),
+                        filter: filter_name.to_owned(),
+                    })));
+                }
+            }
+            return Err(ErrorCode::FilterNotDefined.error(format!(
+                "Filter <filter>{filter_name}</> is not defined in filterDefinitions"
+            )));
+        };
+        let value = value.to_owned();
         FilterDefinition::from_value(value).map_err(|e| {
             anyhow!(
                 "Invalid filter definition for <filter>{filter_name}</>\n\
@@ -147,7 +299,10 @@ impl Config {
     pub fn get_watcher(&self) -> Result<FileWatcher> {
         let mut watcher = FileWatcher::new()?;
 
-        watcher.add_path("./config.json")?;
+        match get_config_script() {
+            Some(script) => watcher.add_path(script)?,
+            None => watcher.add_path("./config.json")?,
+        }
         if let Some(bp) = self.get_behavior_pack() {
             watcher.add_path(bp)?;
         }
@@ -168,6 +323,9 @@ pub struct ConfigCst {
 
 impl ConfigCst {
     pub fn load() -> Result<Self> {
+        if let Some(script) = get_config_script() {
+            bail!("The config is generated by <b>{script}</>, edit the script instead");
+        }
         let data = std::fs::read_to_string("./config.json")?;
         let root = CstRootNode::parse(&data, &ParseOptions::default())?;
         let regolith = root.object_value_or_set().object_value_or_set("regolith");
@@ -185,10 +343,51 @@ impl ConfigCst {
         Ok(())
     }
 
+    /// Root object of the config, for changes not covered by the other methods
+    pub fn root_object(&self) -> CstObject {
+        self.root.object_value_or_set()
+    }
+
+    pub fn get_format_version(&self) -> Result<u64> {
+        let Some(prop) = self.root_object().get("formatVersion") else {
+            return Ok(0);
+        };
+        prop.value()
+            .and_then(|value| value.as_number_lit())
+            .and_then(|value| value.to_string().parse().ok())
+            .ok_or_else(|| {
+                ErrorCode::InvalidConfig.error("`formatVersion` must be a non-negative integer")
+            })
+    }
+
+    /// Sets `formatVersion`, adding it after `$schema` if it's missing
+    pub fn set_format_version(&self, version: u64) {
+        let root = self.root_object();
+        let value = CstInputValue::Number(version.to_string());
+        match root.get("formatVersion") {
+            Some(prop) => prop.set_value(value),
+            None => {
+                let index = root
+                    .get("$schema")
+                    .map(|prop| prop.property_index() + 1)
+                    .unwrap_or_default();
+                root.insert(index, "formatVersion", value);
+            }
+        }
+    }
+
     pub fn add_filter(&self, filter_name: &str, remote: RemoteFilter) {
         let url = remote.url;
         let version = remote.version;
-        let value = json!({ "url": url, "version": version });
+        self.insert_filter_definition(filter_name, json!({ "url": url, "version": version }));
+    }
+
+    pub fn add_filter_definition(&self, filter_name: &str, definition: &Value) {
+        self.insert_filter_definition(filter_name, to_cst_value(definition));
+    }
+
+    /// Inserts the definition in alphabetical order, or replaces the existing one
+    fn insert_filter_definition(&self, filter_name: &str, value: CstInputValue) {
         if let Some(definition) = self.filter_definitions.get(filter_name) {
             definition.set_value(value);
         } else {
@@ -207,12 +406,44 @@ impl ConfigCst {
         }
     }
 
-    pub fn add_filter_to_profile(&self, filter_name: &str, profile_name: &str) -> bool {
+    pub fn add_filter_to_profile(
+        &self,
+        filter_name: &str,
+        profile_name: &str,
+        settings: &[SettingsSkeletonEntry],
+    ) -> bool {
         match self.profiles.object_value(profile_name) {
             Some(profile) => {
                 let filters = profile.array_value_or_set("filters");
                 let name = filter_name.to_owned();
-                filters.append(json!({ "filter": name }));
+                let entry = filters.append(json!({ "filter": name }));
+                if settings.is_empty() {
+                    return true;
+                }
+                let value = settings
+                    .iter()
+                    .map(|entry| (entry.name.to_owned(), to_cst_value(&entry.value)))
+                    .collect();
+                let Some(entry) = entry.as_object() else {
+                    return true;
+                };
+                let settings_object = entry
+                    .append("settings", CstInputValue::Object(value))
+                    .object_value();
+                if let Some(settings_object) = settings_object {
+                    // Describe each setting with a comment in front of it
+                    for (prop, entry) in settings_object.properties().iter().zip(settings) {
+                        if let (Some(description), Some(name)) = (&entry.description, prop.name()) {
+                            if let Some(name) = name.as_string_lit() {
+                                let description = description.replace("*/", "* /");
+                                name.set_raw_value(format!(
+                                    "/* {description} */ {}",
+                                    name.raw_value()
+                                ));
+                            }
+                        }
+                    }
+                }
                 true
             }
             None => false,
@@ -229,3 +460,80 @@ impl ConfigCst {
         }
     }
 }
+
+impl std::fmt::Display for ConfigCst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.root)
+    }
+}
+
+fn to_cst_value(value: &Value) -> CstInputValue {
+    match value {
+        Value::Null => CstInputValue::Null,
+        Value::Bool(value) => CstInputValue::Bool(*value),
+        Value::Number(value) => CstInputValue::Number(value.to_string()),
+        Value::String(value) => CstInputValue::String(value.to_owned()),
+        Value::Array(values) => CstInputValue::Array(values.iter().map(to_cst_value).collect()),
+        Value::Object(map) => CstInputValue::Object(
+            map.iter()
+                .map(|(key, value)| (key.to_owned(), to_cst_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Points to the location of the invalid value in the config file, with a snippet of the line.
+fn config_error(
+    path: &str,
+    data: &str,
+    error: serde_path_to_error::Error<serde_json::Error>,
+) -> anyhow::Error {
+    let key_path = error.path().clone();
+    let key = key_path.to_string();
+    let inner = error.into_inner();
+    let Some(offset) = find_offset(data, &key_path) else {
+        return ErrorCode::InvalidConfig.error(format!(
+            "Invalid config\n\
+             <yellow> >></> Path: {path}\n\
+             <yellow> >></> {key}: {inner}"
+        ));
+    };
+    let line_start = data[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = data[offset..]
+        .find('\n')
+        .map(|i| offset + i)
+        .unwrap_or(data.len());
+    let line = data[..offset].matches('\n').count() + 1;
+    let column = data[line_start..offset].chars().count() + 1;
+    let gutter = " ".repeat(line.to_string().len());
+    ErrorCode::InvalidConfig.error(format!(
+        "Invalid config\n\
+         <yellow> >></> Path: {path}:{line}:{column}\n\
+         <yellow> >></> {key}: {inner}\n\
+         {gutter} |\n\
+         {line} | {}\n\
+         {gutter} | {}<red>^</>",
+        data[line_start..line_end].trim_end(),
+        " ".repeat(column - 1),
+    ))
+}
+
+/// Finds the position of the deepest value along the path that exists in the JSON text
+fn find_offset(data: &str, path: &serde_path_to_error::Path) -> Option<usize> {
+    let parsed = parse_to_ast(data, &CollectOptions::default(), &ParseOptions::default()).ok()?;
+    let mut value = parsed.value.as_ref()?;
+    for segment in path {
+        let next = match (segment, value) {
+            (Segment::Map { key }, ast::Value::Object(object)) => {
+                object.get(key).map(|prop| &prop.value)
+            }
+            (Segment::Seq { index }, ast::Value::Array(array)) => array.elements.get(*index),
+            _ => None,
+        };
+        match next {
+            Some(next) => value = next,
+            None => break,
+        }
+    }
+    Some(value.start())
+}
@@ -0,0 +1,127 @@
+use super::{get_config_script, ConfigCst, ErrorCode};
+use crate::log;
+use anyhow::{bail, Result};
+use jsonc_parser::{cst::CstObject, ParseOptions};
+use serde_json::Value;
+
+/// Version of the config format written by this version of rgl. Every increase needs a migration
+/// from the previous version.
+pub const CONFIG_FORMAT_VERSION: u64 = 1;
+
+/// Upgrades the config by one format version, returning a description of each change
+type Migration = fn(&CstObject) -> Vec<String>;
+
+/// Migrations by the version they upgrade from, the first one upgrades configs from before
+/// `formatVersion` was introduced
+const MIGRATIONS: [Migration; CONFIG_FORMAT_VERSION as usize] = [migrate_v0];
+
+fn migrate_v0(_: &CstObject) -> Vec<String> {
+    // Version 1 only introduced `formatVersion`
+    vec![]
+}
+
+/// Changes made to `config.json` to bring it to the current format. Edits go through the CST so
+/// comments and formatting are preserved.
+pub struct ConfigMigration {
+    pub from: u64,
+    pub changes: Vec<String>,
+    original: String,
+    cst: ConfigCst,
+}
+
+impl ConfigMigration {
+    /// Migrates the config in memory, returns `None` if it's already in the current format
+    pub fn prepare() -> Result<Option<Self>> {
+        if let Some(script) = get_config_script() {
+            bail!("The config is generated by <b>{script}</>, it can't be migrated");
+        }
+        let original = std::fs::read_to_string("./config.json")?;
+        let cst = ConfigCst::load()?;
+        let from = cst.get_format_version()?;
+        if from > CONFIG_FORMAT_VERSION {
+            return Err(ErrorCode::InvalidConfig.error(format!(
+                "The config uses format version {from}, which requires a newer version of rgl"
+            )));
+        }
+        if from == CONFIG_FORMAT_VERSION {
+            return Ok(None);
+        }
+        let root = cst.root_object();
+        let mut changes = vec![];
+        for migration in &MIGRATIONS[from as usize..] {
+            changes.extend(migration(&root));
+        }
+        cst.set_format_version(CONFIG_FORMAT_VERSION);
+        changes.push(format!("Set `formatVersion` to {CONFIG_FORMAT_VERSION}"));
+        Ok(Some(Self {
+            from,
+            changes,
+            original,
+            cst,
+        }))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.cst.save()
+    }
+
+    /// The migrated config, to use it without saving the migration
+    pub fn to_value(&self) -> Result<Value> {
+        let data = self.cst.to_string();
+        let value = jsonc_parser::parse_to_serde_value(&data, &ParseOptions::default())?;
+        Ok(value.unwrap_or_default())
+    }
+
+    pub fn log_changes(&self) {
+        for change in &self.changes {
+            log!("  - {change}");
+        }
+    }
+
+    /// Prints the changed lines of the config
+    pub fn log_diff(&self) {
+        let before = self.original.lines().collect::<Vec<_>>();
+        let migrated = self.cst.to_string();
+        let after = migrated.lines().collect::<Vec<_>>();
+        for line in diff_lines(&before, &after) {
+            match line {
+                DiffLine::Removed(line) => log!("<red>- {line}</>"),
+                DiffLine::Added(line) => log!("<green>+ {line}</>"),
+            }
+        }
+    }
+}
+
+enum DiffLine<'a> {
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Lines removed from `before` and added in `after`, based on their longest common subsequence
+fn diff_lines<'a>(before: &[&'a str], after: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let (n, m) = (before.len(), after.len());
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = match before[i] == after[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && before[i] == after[j] {
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            result.push(DiffLine::Added(after[j]));
+            j += 1;
+        } else {
+            result.push(DiffLine::Removed(before[i]));
+            i += 1;
+        }
+    }
+    result
+}
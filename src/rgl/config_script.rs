@@ -0,0 +1,103 @@
+use super::{get_current_dir, resolve_runtime, Subprocess, UserConfig};
+use crate::fs::{read_json, write_file};
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::{ffi::OsString, path::Path};
+use tempfile::tempdir;
+
+/// Scripts that generate the config, used instead of `config.json` when present
+const CONFIG_SCRIPTS: [&str; 2] = ["rgl.config.ts", "rgl.config.js"];
+
+/// Imports the config script and writes its default export, or the result of calling it if
+/// it's a function, as JSON.
+const LOADER: &str = r#"import process from "node:process";
+import { writeFileSync } from "node:fs";
+import { pathToFileURL } from "node:url";
+
+const [script, output] = process.argv.slice(2);
+let config = (await import(pathToFileURL(script).href)).default;
+if (typeof config === "function") {
+  config = await config();
+}
+writeFileSync(output, JSON.stringify(config));
+"#;
+
+pub fn get_config_script() -> Option<&'static str> {
+    CONFIG_SCRIPTS
+        .into_iter()
+        .find(|script| Path::new(script).is_file())
+}
+
+/// Evaluates the config script with Deno for TypeScript, or the configured Node.js runtime. The
+/// script can only read the project and the environment, and write its output.
+pub fn eval_config_script(script: &str) -> Result<Value> {
+    let inner = || -> Result<Value> {
+        let dir = tempdir()?;
+        let loader = dir.path().join("loader.mjs");
+        let output = dir.path().join("config.json");
+        write_file(&loader, LOADER)?;
+        let script = dunce::canonicalize(script)?;
+        let project_dir = get_current_dir()?;
+        let mut subprocess = match script.extension().is_some_and(|ext| ext == "ts") {
+            true => {
+                let mut allow_read = OsString::from("--allow-read=");
+                allow_read.push(&project_dir);
+                allow_read.push(",");
+                allow_read.push(dir.path());
+                let mut allow_write = OsString::from("--allow-write=");
+                allow_write.push(&output);
+                let mut subprocess = Subprocess::new(resolve_runtime("deno")?);
+                subprocess
+                    .args(["run", "--no-lock", "--no-prompt", "--allow-env"])
+                    .arg(allow_read)
+                    .arg(allow_write);
+                subprocess
+            }
+            false => {
+                let runtime = resolve_runtime(&UserConfig::nodejs_runtime())?;
+                let permission_flag = get_node_permission_flag(&runtime)?;
+                let mut subprocess = Subprocess::new(runtime);
+                subprocess.args(permission_flag);
+                for path in [&project_dir, dir.path()] {
+                    let mut allow_read = OsString::from("--allow-fs-read=");
+                    allow_read.push(path);
+                    subprocess.arg(allow_read);
+                }
+                let mut allow_write = OsString::from("--allow-fs-write=");
+                allow_write.push(&output);
+                subprocess.arg(allow_write);
+                subprocess
+            }
+        };
+        subprocess.arg(&loader).arg(script).arg(&output).run()?;
+        read_json(output)
+    };
+    inner().with_context(|| {
+        format!(
+            "Failed to evaluate config script\n\
+             <yellow> >></> Path: {script}"
+        )
+    })
+}
+
+/// Flags that enable the permission model of Node.js, added in Node.js 20 and stable since 23
+fn get_node_permission_flag(runtime: &Path) -> Result<&'static [&'static str]> {
+    let output = Subprocess::new(runtime).arg("--version").run_silent()?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    let major = version
+        .trim()
+        .strip_prefix('v')
+        .and_then(|version| version.split('.').next())
+        .and_then(|major| major.parse::<u32>().ok());
+    match major {
+        Some(23..) => Ok(&["--permission"]),
+        // The permission model prints a warning while it's experimental
+        Some(20..=22) => Ok(&["--experimental-permission", "--no-warnings"]),
+        _ => bail!(
+            "Config scripts require Node.js 20 or newer, to run them with restricted permissions\n\
+             <yellow> >></> Runtime: {} {}",
+            runtime.display(),
+            version.trim()
+        ),
+    }
+}
@@ -0,0 +1,46 @@
+use crate::fs::write_json;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs, path::Path};
+use walkdir::WalkDir;
+
+pub const CONTENT_HASHES_FILE: &str = "content_hashes.json";
+
+/// Writes `content_hashes.json` to the pack root, mapping each file path to its sha256 hash.
+pub fn write_content_hashes(pack: &Path) -> Result<()> {
+    let inner = || -> Result<()> {
+        let files = WalkDir::new(pack)
+            .follow_links(true)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let relative = entry.path().strip_prefix(pack).ok()?.to_owned();
+                (relative != Path::new(CONTENT_HASHES_FILE)).then_some((relative, entry))
+            })
+            .collect::<Vec<_>>();
+        let hashes = files
+            .par_iter()
+            .map(|(relative, entry)| -> Result<(String, String)> {
+                let data = fs::read(entry.path())?;
+                let hash = format!("{:x}", Sha256::digest(data));
+                let path = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                Ok((path, hash))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        write_json(pack.join(CONTENT_HASHES_FILE), &hashes)
+    };
+    inner().with_context(|| {
+        format!(
+            "Failed to write content hashes\n\
+             <yellow> >></> Path: {}",
+            pack.display()
+        )
+    })
+}
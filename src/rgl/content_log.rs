@@ -0,0 +1,131 @@
+use super::{find_logs_dir, MinecraftBuild, SourceMaps};
+use crate::log;
+use anyhow::Result;
+use smol::Timer;
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Prints script errors and warnings written to the content log of a Minecraft build, with the
+/// locations in stack traces mapped to the original sources.
+pub fn watch_content_log(
+    build: Option<&MinecraftBuild>,
+    source_maps: Option<SourceMaps>,
+) -> Result<()> {
+    let logs_dir = find_logs_dir(build)?;
+    log!(
+        "<green>[CONTENT LOG]</> Watching script errors in {}",
+        logs_dir.display()
+    );
+    smol::spawn(async move {
+        let mut reader = ContentLogReader::default();
+        loop {
+            Timer::after(Duration::from_millis(500)).await;
+            let lines = match reader.read_lines(&logs_dir) {
+                Ok(lines) => lines,
+                Err(e) => {
+                    log!("<red>[CONTENT LOG]</> {e}");
+                    continue;
+                }
+            };
+            for line in lines {
+                reader.print(&line, source_maps.as_ref());
+            }
+        }
+    })
+    .detach();
+    Ok(())
+}
+
+#[derive(Default)]
+struct ContentLogReader {
+    /// Log file being read and the position read up to
+    current: Option<(PathBuf, u64)>,
+    /// Incomplete line at the end of the file
+    pending: String,
+    /// Whether the entry that the following indented lines belong to is printed
+    is_printing: bool,
+}
+
+impl ContentLogReader {
+    /// Reads the lines added since the last read. The game starts a new log file every session,
+    /// the file that was the latest when watching started is only read from its end.
+    fn read_lines(&mut self, logs_dir: &Path) -> Result<Vec<String>> {
+        let Some(latest) = find_latest_log(logs_dir) else {
+            return Ok(vec![]);
+        };
+        let offset = match &self.current {
+            Some((path, offset)) if *path == latest => *offset,
+            Some(_) => {
+                self.pending.clear();
+                0
+            }
+            None => latest.metadata()?.len(),
+        };
+        let mut file = File::open(&latest)?;
+        let len = file.metadata()?.len();
+        // The file was replaced by a new one with the same name
+        let offset = if len < offset { 0 } else { offset };
+        file.seek(SeekFrom::Start(offset))?;
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+        self.current = Some((latest, offset + data.len() as u64));
+
+        self.pending.push_str(&String::from_utf8_lossy(&data));
+        let Some(index) = self.pending.rfind('\n') else {
+            return Ok(vec![]);
+        };
+        let lines = self.pending[..index]
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_owned())
+            .collect();
+        self.pending.drain(..=index);
+        Ok(lines)
+    }
+
+    /// Entries look like `12:00:00[Scripting][error]-Error: message`, followed by indented stack
+    /// trace lines.
+    fn print(&mut self, line: &str, source_maps: Option<&SourceMaps>) {
+        let translate = |text: &str| match source_maps {
+            Some(source_maps) => source_maps.translate(text),
+            None => text.to_owned(),
+        };
+        if line.starts_with(char::is_whitespace) {
+            if self.is_printing {
+                log!("{}", translate(line));
+            }
+            return;
+        }
+        self.is_printing = false;
+        let Some((_, entry)) = line.split_once("[Scripting]") else {
+            return;
+        };
+        let Some((level, message)) = entry.split_once("]-") else {
+            return;
+        };
+        let color = match level.to_lowercase().as_str() {
+            "[error" => "red",
+            "[warning" => "yellow",
+            _ => return,
+        };
+        self.is_printing = true;
+        log!("<{color}>[SCRIPT]</> {}", translate(message));
+    }
+}
+
+fn find_latest_log(logs_dir: &Path) -> Option<PathBuf> {
+    logs_dir
+        .read_dir()
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            name.starts_with("contentlog") && name.ends_with(".txt")
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+}
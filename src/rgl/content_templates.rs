@@ -0,0 +1,213 @@
+use super::Config;
+use crate::fs::{read_json, write_file, write_json};
+use crate::{log, warn};
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Files that `rgl new` adds to the packs, by their path in the template, e.g.
+/// `BP/items/{{name}}.json`. Variables like `{{name}}` are replaced in the paths and the text
+/// files.
+///
+/// Files with a variable in their path belong to the new content and are never overwritten without
+/// `--force`. Other files are shared, e.g. `RP/texts/en_US.lang`: JSON files are merged into the
+/// existing ones, and lang files only get the keys they don't have yet.
+pub struct ContentTemplate {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+/// Directory of a project template, in the `templates` directory of the data path
+pub fn get_content_template_dir(config: &Config, name: &str) -> PathBuf {
+    config.get_data_path().join("templates").join(name)
+}
+
+impl ContentTemplate {
+    pub fn new(files: Vec<(String, Vec<u8>)>) -> Self {
+        Self { files }
+    }
+
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut files = vec![];
+        for entry in WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path().strip_prefix(dir)?;
+            let path = path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((path, fs::read(entry.path())?));
+        }
+        if files.is_empty() {
+            bail!(
+                "Template has no files\n\
+                 <yellow> >></> Path: {}",
+                dir.display()
+            );
+        }
+        Ok(Self { files })
+    }
+
+    /// Writes the files of the template to the packs of the project
+    pub fn render(
+        &self,
+        config: &Config,
+        variables: &BTreeMap<String, String>,
+        force: bool,
+    ) -> Result<()> {
+        let mut files = vec![];
+        for (template_path, contents) in &self.files {
+            let path = substitute(template_path, variables)
+                .with_context(|| format!("Invalid template file path <b>{template_path}</>"))?;
+            let (pack, relative) = path.split_once('/').unwrap_or((&path, ""));
+            let pack_dir = match pack {
+                "BP" => config.get_behavior_pack(),
+                "RP" => config.get_resource_pack(),
+                _ => bail!("Template file <b>{template_path}</> must be in the BP or RP directory"),
+            };
+            let Some(pack_dir) = pack_dir else {
+                warn!("The project has no {pack}, skipping <b>{template_path}</>");
+                continue;
+            };
+            let contents = match String::from_utf8(contents.to_owned()) {
+                Ok(text) => substitute(&text, variables)
+                    .with_context(|| format!("Invalid template file <b>{template_path}</>"))?
+                    .into_bytes(),
+                Err(_) => contents.to_owned(),
+            };
+            let owned = template_path.contains("{{");
+            files.push((pack_dir.join(relative), contents, owned));
+        }
+        if !force {
+            if let Some((path, ..)) = files
+                .iter()
+                .find(|(path, _, owned)| *owned && path.exists())
+            {
+                bail!(
+                    "File already exists, use <b>--force</> to overwrite it\n\
+                     <yellow> >></> Path: {}",
+                    path.display()
+                );
+            }
+        }
+
+        for (path, contents, owned) in files {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if owned || !path.exists() {
+                write_file(&path, contents)?;
+                log!("\tCreated: {}", path.display());
+            } else if merge_file(&path, &contents)? {
+                log!("\tUpdated: {}", path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the `{{variable}}` placeholders in the text
+fn substitute(text: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + end].trim();
+        let Some(value) = variables.get(name) else {
+            let names = variables.keys().cloned().collect::<Vec<_>>();
+            bail!(
+                "Unknown variable <b>{name}</>, expected one of {}, or one passed with --var",
+                names.join(", ")
+            );
+        };
+        result.push_str(&rest[..start]);
+        result.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Merges the contents of a shared template file into the existing file. Returns whether it
+/// changed.
+fn merge_file(path: &Path, contents: &[u8]) -> Result<bool> {
+    let text = String::from_utf8_lossy(contents);
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => {
+            let mut value = read_json::<Value>(path)?;
+            let template = serde_json::from_str(&text).with_context(|| {
+                format!(
+                    "Invalid JSON in template\n\
+                     <yellow> >></> Path: {}",
+                    path.display()
+                )
+            })?;
+            let original = value.clone();
+            merge_json(&mut value, template);
+            if value == original {
+                return Ok(false);
+            }
+            write_json(path, &value)?;
+            Ok(true)
+        }
+        Some("lang") => {
+            let mut lang = fs::read_to_string(path)?;
+            let mut changed = false;
+            for line in text.lines() {
+                let Some((key, _)) = line.split_once('=') else {
+                    continue;
+                };
+                let prefix = format!("{key}=");
+                if lang.lines().any(|line| line.starts_with(&prefix)) {
+                    continue;
+                }
+                if !lang.is_empty() && !lang.ends_with('\n') {
+                    lang.push('\n');
+                }
+                lang.push_str(line);
+                lang.push('\n');
+                changed = true;
+            }
+            if changed {
+                write_file(path, lang)?;
+            }
+            Ok(changed)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Adds the keys and array items of the template that the value doesn't have, existing values are
+/// kept
+fn merge_json(value: &mut Value, template: Value) {
+    match (value, template) {
+        (Value::Object(object), Value::Object(template)) => {
+            for (key, template) in template {
+                match object.get_mut(&key) {
+                    Some(value) => merge_json(value, template),
+                    None => {
+                        object.insert(key, template);
+                    }
+                }
+            }
+        }
+        (Value::Array(array), Value::Array(template)) => {
+            for item in template {
+                if !array.contains(&item) {
+                    array.push(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
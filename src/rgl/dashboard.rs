@@ -0,0 +1,309 @@
+use super::{add_build_observer, BuildEvent, BuildObserver};
+use crate::log;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use smol::{
+    channel,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Serialize)]
+struct FilterTiming {
+    name: String,
+    millis: u128,
+    /// Restored from the build cache
+    cached: bool,
+    skipped: bool,
+    failed: bool,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum BuildStatus {
+    #[default]
+    Starting,
+    Running,
+    Success,
+    Failed,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DashboardState {
+    profile: String,
+    status: BuildStatus,
+    current_filter: Option<String>,
+    exports: Vec<String>,
+    last_error: Option<String>,
+    /// Unix timestamp in milliseconds of the last finished run
+    last_run: Option<u128>,
+    timings: Vec<FilterTiming>,
+}
+
+/// Local web page showing the status of `rgl watch`, with a button to trigger a rebuild.
+pub struct Dashboard {
+    rebuild_rx: channel::Receiver<()>,
+}
+
+impl Dashboard {
+    pub fn bind(host: &str, port: u16) -> Result<Self> {
+        let state = Arc::new(Mutex::new(DashboardState::default()));
+        let (rebuild_tx, rebuild_rx) = channel::unbounded();
+        let listener = smol::block_on(TcpListener::bind(format!("{host}:{port}")))?;
+        let addr = listener.local_addr()?;
+        log!("<green>[DASHBOARD]</> Build status is available at <b>http://{addr}</>");
+        add_build_observer(Arc::new(DashboardObserver {
+            state: state.clone(),
+        }));
+
+        smol::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        log!("<red>[DASHBOARD]</> {e}");
+                        continue;
+                    }
+                };
+                let state = state.clone();
+                let rebuild_tx = rebuild_tx.clone();
+                smol::spawn(async move {
+                    if let Err(e) = handle_request(stream, &state, &rebuild_tx).await {
+                        log!("<red>[DASHBOARD]</> {e}");
+                    }
+                })
+                .detach();
+            }
+        })
+        .detach();
+
+        Ok(Self { rebuild_rx })
+    }
+
+    /// Waits until a rebuild is requested from the web page.
+    pub async fn wait_rebuild(&self) {
+        let _ = self.rebuild_rx.recv().await;
+        while self.rebuild_rx.try_recv().is_ok() {}
+    }
+}
+
+struct DashboardObserver {
+    state: Arc<Mutex<DashboardState>>,
+}
+
+impl BuildObserver for DashboardObserver {
+    fn on_event(&self, event: &BuildEvent) {
+        let mut state = self.state.lock().unwrap();
+        match *event {
+            BuildEvent::RunStarted { profile } => {
+                state.profile = profile.to_owned();
+                state.status = BuildStatus::Running;
+                state.timings.clear();
+                state.exports.clear();
+            }
+            BuildEvent::FilterStarted { name } => {
+                state.current_filter = Some(name.to_owned());
+            }
+            BuildEvent::FilterSkipped { name } => state.timings.push(FilterTiming {
+                name: name.to_owned(),
+                millis: 0,
+                cached: false,
+                skipped: true,
+                failed: false,
+            }),
+            BuildEvent::FilterFinished {
+                name,
+                duration,
+                cached,
+                error,
+            } => {
+                state.current_filter = None;
+                state.timings.push(FilterTiming {
+                    name: name.to_owned(),
+                    millis: duration.as_millis(),
+                    cached,
+                    skipped: false,
+                    failed: error.is_some(),
+                });
+            }
+            BuildEvent::Export { bp, rp } => {
+                for path in [bp, rp].into_iter().flatten() {
+                    state.exports.push(path.display().to_string());
+                }
+            }
+            BuildEvent::RunFinished { error } => {
+                state.current_filter = None;
+                state.last_run = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .ok()
+                    .map(|time| time.as_millis());
+                match error {
+                    Some(e) => {
+                        let message = e
+                            .chain()
+                            .map(|e| strip_styles(&e.to_string()))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        state.status = BuildStatus::Failed;
+                        state.last_error = Some(message);
+                    }
+                    None => state.status = BuildStatus::Success,
+                }
+            }
+        }
+    }
+}
+
+async fn handle_request(
+    stream: TcpStream,
+    state: &Mutex<DashboardState>,
+    rebuild_tx: &channel::Sender<()>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.clone());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    // Skip the headers, the requests don't have a body
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html", INDEX_HTML.to_owned()),
+        ("GET", "/status") => {
+            let body = serde_json::to_string(&*state.lock().unwrap())?;
+            ("200 OK", "application/json", body)
+        }
+        ("POST", "/rebuild") => {
+            let _ = rebuild_tx.try_send(());
+            let body = json!({ "ok": true }).to_string();
+            ("200 OK", "application/json", body)
+        }
+        _ => ("404 Not Found", "text/plain", "Not Found".to_owned()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: {content_type}; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-store\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let mut stream = stream;
+    stream.write_all(response.as_bytes()).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Removes logger style tags like `<filter>` and `</>` from a message.
+fn strip_styles(message: &str) -> String {
+    let mut result = String::new();
+    let mut rest = message;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let tag = rest.find('>').map(|end| &rest[1..end]);
+        match tag {
+            Some(tag)
+                if tag
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '/') =>
+            {
+                rest = &rest[tag.len() + 2..];
+            }
+            _ => {
+                result.push('<');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>rgl watch</title>
+<style>
+  body { font-family: sans-serif; background: #1e1e1e; color: #ddd; margin: 2rem; }
+  h1 { font-size: 1.4rem; }
+  .status { font-weight: bold; text-transform: uppercase; }
+  .running, .starting { color: #e5c07b; }
+  .success { color: #98c379; }
+  .failed { color: #e06c75; }
+  pre { background: #111; padding: 1rem; white-space: pre-wrap; }
+  table { border-collapse: collapse; }
+  td { padding: 0.2rem 1rem 0.2rem 0; }
+  td:last-child { text-align: right; }
+  button { font-size: 1rem; padding: 0.4rem 1rem; }
+</style>
+</head>
+<body>
+<h1>Profile <span id="profile"></span>: <span id="status" class="status"></span></h1>
+<p>Last run: <span id="last-run">never</span></p>
+<p id="current-filter"></p>
+<button onclick="fetch('/rebuild', { method: 'POST' })">Rebuild</button>
+<div id="error-section" hidden>
+  <h2>Last error</h2>
+  <pre id="error"></pre>
+</div>
+<h2>Filter timings</h2>
+<table id="timings"></table>
+<h2>Exported to</h2>
+<ul id="exports"></ul>
+<script>
+  async function update() {
+    try {
+      const state = await (await fetch("/status")).json();
+      document.getElementById("profile").textContent = state.profile;
+      const status = document.getElementById("status");
+      status.textContent = state.status;
+      status.className = "status " + state.status;
+      if (state.lastRun) {
+        document.getElementById("last-run").textContent = new Date(state.lastRun).toLocaleString();
+      }
+      document.getElementById("error-section").hidden = state.status !== "failed";
+      document.getElementById("error").textContent = state.lastError ?? "";
+      document.getElementById("current-filter").textContent =
+        state.currentFilter ? "Running filter " + state.currentFilter : "";
+      const timings = document.getElementById("timings");
+      timings.replaceChildren(...state.timings.map(({ name, millis, cached, skipped, failed }) => {
+        const row = document.createElement("tr");
+        const note = skipped ? "skipped" : failed ? "failed" : cached ? "cached" : "";
+        if (failed) {
+          row.className = "failed";
+        }
+        for (const text of [name, note, millis + "ms"]) {
+          const cell = document.createElement("td");
+          cell.textContent = text;
+          row.append(cell);
+        }
+        return row;
+      }));
+      const exports = document.getElementById("exports");
+      exports.replaceChildren(...state.exports.map((path) => {
+        const item = document.createElement("li");
+        item.textContent = path;
+        return item;
+      }));
+    } catch {
+      document.getElementById("status").textContent = "disconnected";
+    }
+  }
+  update();
+  setInterval(update, 1000);
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,70 @@
+use super::CONTENT_HASHES_FILE;
+use crate::fs::SyncStats;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+/// Size and modified time of every file in a directory, used to detect other processes writing to
+/// the data directory or the source packs during a run, and to count the files an export changed.
+pub struct DataSnapshot(HashMap<PathBuf, (u64, Option<SystemTime>)>);
+
+impl DataSnapshot {
+    pub fn new(dir: &Path) -> Self {
+        let files = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let key = (metadata.len(), metadata.modified().ok());
+                Some((entry.into_path(), key))
+            })
+            .collect();
+        Self(files)
+    }
+
+    /// Returns a file inside `dir` that was added, removed, or modified since the snapshot was taken.
+    pub fn find_change(&self, dir: &Path) -> Option<PathBuf> {
+        let current = Self::new(dir);
+        let added_or_modified = current
+            .0
+            .iter()
+            .find(|(path, key)| self.0.get(*path) != Some(key))
+            .map(|(path, _)| path);
+        let removed = || {
+            self.0
+                .keys()
+                .find(|path| path.starts_with(dir) && !current.0.contains_key(*path))
+        };
+        added_or_modified.or_else(removed).cloned()
+    }
+
+    /// Counts the files inside `dir` that were added, removed, or modified since the snapshot was
+    /// taken. The content hashes are written after the export and not counted.
+    pub fn get_stats(&self, dir: &Path) -> SyncStats {
+        let current = Self::new(dir);
+        let content_hashes = dir.join(CONTENT_HASHES_FILE);
+        let mut stats = SyncStats::default();
+        for (path, key) in &current.0 {
+            if *path == content_hashes {
+                continue;
+            }
+            match self.0.get(path) {
+                Some(previous) if previous == key => continue,
+                Some(_) => stats.updated += 1,
+                None => stats.added += 1,
+            }
+            stats.bytes += key.0;
+        }
+        stats.removed = self
+            .0
+            .keys()
+            .filter(|path| path.starts_with(dir) && **path != content_hashes)
+            .filter(|path| !current.0.contains_key(*path))
+            .count() as u64;
+        stats
+    }
+}
@@ -0,0 +1,65 @@
+use super::{Filter, FilterContext, LocalFilter};
+use crate::fs::{read_json, write_json};
+use crate::info;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+/// File storing the hash of the dependency files of each local filter, e.g. `package.json`, from
+/// when its dependencies were last installed.
+fn get_dependency_hashes_path() -> PathBuf {
+    PathBuf::from(".regolith").join("dependency_hashes.json")
+}
+
+/// Installs the dependencies of a local filter if its dependency files changed since the last
+/// install, or always if `force` is set.
+pub fn update_dependencies(
+    filter: &LocalFilter,
+    context: &FilterContext,
+    force: bool,
+) -> Result<()> {
+    let hash = hash_dependency_files(filter, context)?;
+    let path = get_dependency_hashes_path();
+    let mut hashes: BTreeMap<String, String> = read_json(&path).unwrap_or_default();
+    if !force {
+        let name = &context.name;
+        let is_installed = filter
+            .installed_dir(context)
+            .is_some_and(|dir| dir.is_dir());
+        match hashes.get(name) {
+            _ if hash.is_none() => return Ok(()),
+            Some(previous) if Some(previous) == hash.as_ref() => return Ok(()),
+            Some(_) => info!("Dependencies of <filter>{name}</> changed, reinstalling..."),
+            // Dependencies installed before the hashes were tracked are assumed to be up to date
+            None if is_installed => {
+                hashes.insert(name.to_owned(), hash.unwrap_or_default());
+                return write_json(path, &hashes);
+            }
+            None => info!("Installing dependencies for <filter>{name}</>..."),
+        }
+    }
+    filter.install_dependencies(context)?;
+    match hash {
+        Some(hash) => hashes.insert(context.name.to_owned(), hash),
+        None => hashes.remove(&context.name),
+    };
+    write_json(path, &hashes)
+}
+
+fn hash_dependency_files(filter: &LocalFilter, context: &FilterContext) -> Result<Option<String>> {
+    let files: Vec<_> = filter
+        .dependency_files(context)
+        .into_iter()
+        .filter(|file| file.is_file())
+        .collect();
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(fs::read(file)?);
+    }
+    Ok(Some(format!("{:x}", hasher.finalize())))
+}
@@ -0,0 +1,76 @@
+use super::{get_build_path, get_experiment_key, Config, ExportPaths, MinecraftBuild, Profile};
+use crate::fs::write_zip;
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+/// Keys of the experiments that can be enabled in Education Edition worlds. Beta APIs aren't
+/// available in Education Edition.
+const EDUCATION_EXPERIMENTS: [&str; 3] = [
+    "data_driven_items",
+    "experimental_molang_features",
+    "upcoming_creator_features",
+];
+
+/// Checks that a profile targeting Education Edition only enables the experiments it allows.
+pub fn check_education_profile(profile: &Profile) -> Result<()> {
+    if profile.export.get_build() != Some(MinecraftBuild::Education) {
+        return Ok(());
+    }
+    let unsupported = profile
+        .experiments
+        .iter()
+        .filter(|experiment| {
+            let key = get_experiment_key(experiment).unwrap_or(experiment);
+            !EDUCATION_EXPERIMENTS.contains(&key)
+        })
+        .map(|experiment| experiment.as_str())
+        .collect::<Vec<_>>();
+    if !unsupported.is_empty() {
+        bail!(
+            "Education Edition doesn't support the experiments <b>{}</>, remove them from the profile\n\
+             <yellow> >></> Supported: {}",
+            unsupported.join(", "),
+            EDUCATION_EXPERIMENTS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Packages the packs exported by a profile as a `.mcaddon`, which Education Edition imports by
+/// opening the file. Returns the size of the archive.
+pub fn package_education(config: &Config, profile_name: &str, output: &Path) -> Result<u64> {
+    let profile = config.get_profile(profile_name)?;
+    if profile.export.get_build() != Some(MinecraftBuild::Education) {
+        bail!("The profile must export to the <b>education</> build");
+    }
+    let (target_bp, target_rp) = profile.export.get_paths(config, profile_name)?;
+    let packs = [
+        (config.get_behavior_pack(), target_bp),
+        (config.get_resource_pack(), target_rp),
+    ];
+    let mut dirs = vec![];
+    for (pack, target) in &packs {
+        if pack.is_none() {
+            continue;
+        }
+        if !target.is_dir() {
+            bail!(
+                "Exported pack not found, run the profile first\n\
+                 <yellow> >></> Path: {}",
+                target.display()
+            );
+        }
+        let folder = target.file_name().unwrap_or_default().to_string_lossy();
+        dirs.push((target.as_path(), folder));
+    }
+    let dirs = dirs
+        .iter()
+        .map(|(dir, folder)| (*dir, folder.as_ref()))
+        .collect::<Vec<_>>();
+    write_zip(&dirs, output)
+}
+
+/// Default location of the packaged add-on
+pub fn get_education_addon_path(config: &Config) -> PathBuf {
+    get_build_path(config, "mcaddon")
+}
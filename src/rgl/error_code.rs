@@ -0,0 +1,145 @@
+use std::fmt::{self, Display};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Stable codes of common failures, printed with the error and included in JSON logs so tools
+/// wrapping rgl can react to them. Codes are never reused or renumbered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter)]
+pub enum ErrorCode {
+    InvalidConfig,
+    ProfileNotFound,
+    FilterNotDefined,
+    FilterNotInstalled,
+    SymlinkPrivilege,
+    WorldNotFound,
+    ProtectedExportTarget,
+    ProgramNotFound,
+}
+
+impl ErrorCode {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::InvalidConfig => "RGL0001",
+            Self::ProfileNotFound => "RGL0002",
+            Self::FilterNotDefined => "RGL0003",
+            Self::FilterNotInstalled => "RGL0004",
+            Self::SymlinkPrivilege => "RGL0005",
+            Self::WorldNotFound => "RGL0006",
+            Self::ProtectedExportTarget => "RGL0007",
+            Self::ProgramNotFound => "RGL0008",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::InvalidConfig => "Invalid config",
+            Self::ProfileNotFound => "Profile not found",
+            Self::FilterNotDefined => "Filter not defined",
+            Self::FilterNotInstalled => "Filter not installed",
+            Self::SymlinkPrivilege => "Missing privilege to create symlinks",
+            Self::WorldNotFound => "World not found",
+            Self::ProtectedExportTarget => "Protected export target",
+            Self::ProgramNotFound => "Program not found",
+        }
+    }
+
+    /// How to fix the failure
+    pub fn help(self) -> &'static str {
+        match self {
+            Self::InvalidConfig => {
+                "The config.json file couldn't be read, or doesn't match the expected format. \
+                 Fix the location mentioned in the error, the `$schema` of the config enables \
+                 completions and validation in most editors."
+            }
+            Self::ProfileNotFound => {
+                "The profile is not in `regolith.profiles` of the config. Check the spelling of \
+                 the profile name, names are case sensitive."
+            }
+            Self::FilterNotDefined => {
+                "A profile uses a filter that is not in `regolith.filterDefinitions`. Add it with \
+                 `rgl add <filter>`, or remove it from the profile."
+            }
+            Self::FilterNotInstalled => {
+                "The filter is not installed globally. Install it with `rgl install <filter>`, or \
+                 run `rgl list` to see the installed filters."
+            }
+            Self::SymlinkPrivilege => {
+                "Windows only allows creating symlinks with developer mode enabled or as an \
+                 administrator. Enable developer mode in the Windows settings, or use a profile \
+                 without symlinks."
+            }
+            Self::WorldNotFound => {
+                "No world in the minecraftWorlds directory has the given name in its \
+                 levelname.txt. Check the name in the world list of the game, and that the \
+                 right Minecraft build is selected."
+            }
+            Self::ProtectedExportTarget => {
+                "rgl only overwrites directories it created, so a wrong export path can't delete \
+                 unrelated files. Check the export path of the profile, or empty the directory to \
+                 let rgl manage it."
+            }
+            Self::ProgramNotFound => {
+                "A filter needs a program that is not installed or not in the PATH. Install it \
+                 and restart the terminal."
+            }
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::iter().find(|c| c.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Creates an error with this code
+    pub fn error(self, message: impl Display) -> anyhow::Error {
+        anyhow::Error::new(CodedError {
+            code: self,
+            message: message.to_string(),
+            source: None,
+        })
+    }
+
+    /// Attaches this code to the error, keeping its message and causes
+    pub fn wrap(self, error: anyhow::Error) -> anyhow::Error {
+        anyhow::Error::new(CodedError {
+            code: self,
+            message: error.to_string(),
+            source: Some(error),
+        })
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title())
+    }
+}
+
+impl std::error::Error for ErrorCode {}
+
+#[derive(Debug)]
+struct CodedError {
+    code: ErrorCode,
+    message: String,
+    /// Wrapped error, its message is the message of this error so only its causes are shown
+    source: Option<anyhow::Error>,
+}
+
+impl Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CodedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref()?.source()
+    }
+}
+
+/// Code of the error, attached either by [`ErrorCode::error`] or as context to any error in the
+/// chain
+pub fn get_error_code(error: &anyhow::Error) -> Option<ErrorCode> {
+    error
+        .downcast_ref::<CodedError>()
+        .map(|e| e.code)
+        .or_else(|| error.downcast_ref::<ErrorCode>().copied())
+}
@@ -1,10 +1,16 @@
-use super::{find_mojang_dir, find_world_dir, get_current_dir, Eval, MinecraftBuild};
-use anyhow::{anyhow, bail, Result};
+use super::{
+    find_mojang_dir, find_world_dir, get_current_dir, pick_world_dir, Config, Eval, MinecraftBuild,
+    MinecraftBuilds, Subprocess,
+};
+use crate::fs::{read_json, set_modified_time};
+use anyhow::{anyhow, bail, Context, Result};
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{
     env, fs,
     path::{Component, PathBuf},
+    time::SystemTime,
 };
 
 #[derive(Serialize, Deserialize)]
@@ -18,27 +24,77 @@ pub enum Export {
     World(WorldExport),
 }
 
+impl Export {
+    /// Minecraft build the packs are exported to, if the target is inside a com.mojang directory
+    pub fn get_build(&self) -> Option<MinecraftBuild> {
+        let build = match self {
+            Export::Development(export) => export.build.as_ref().and_then(|b| b.primary()),
+            Export::World(export) => export.build.as_ref(),
+            _ => return None,
+        };
+        Some(build.cloned().unwrap_or(MinecraftBuild::Standard))
+    }
+}
+
 #[enum_dispatch(Export)]
 pub trait ExportPaths {
-    fn get_paths(&self, project_name: &str, profile_name: &str) -> Result<(PathBuf, PathBuf)>;
+    fn get_paths(&self, config: &Config, profile_name: &str) -> Result<(PathBuf, PathBuf)>;
+    /// Additional locations that receive a copy of the exported packs
+    #[allow(unused_variables)]
+    fn get_mirror_paths(
+        &self,
+        config: &Config,
+        profile_name: &str,
+    ) -> Result<Vec<(PathBuf, PathBuf)>> {
+        Ok(vec![])
+    }
+}
+
+/// Updates the modification time of the manifests of the exported packs. Some platforms only
+/// reload a development pack when its manifest changed, so this is used when scripts can't be
+/// reloaded over WebSocket.
+pub fn touch_manifests(config: &Config, profile_name: &str) -> Result<()> {
+    let profile = config.get_profile(profile_name)?;
+    if let Export::None(_) = profile.export {
+        return Ok(());
+    }
+    let (bp, rp) = profile.export.get_paths(config, profile_name)?;
+    let mirrors = profile.export.get_mirror_paths(config, profile_name)?;
+    let now = SystemTime::now();
+    for (bp, rp) in std::iter::once((bp, rp)).chain(mirrors) {
+        for manifest in [bp.join("manifest.json"), rp.join("manifest.json")] {
+            if manifest.is_file() {
+                set_modified_time(manifest, now)?;
+            }
+        }
+    }
+    Ok(())
 }
 
 #[derive(Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DevelopmentExport {
     #[serde(skip_serializing_if = "Option::is_none")]
-    build: Option<MinecraftBuild>,
+    build: Option<MinecraftBuilds>,
     #[serde(skip_serializing_if = "Option::is_none")]
     bp_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     rp_name: Option<String>,
 }
 
-impl ExportPaths for DevelopmentExport {
-    fn get_paths(&self, project_name: &str, profile_name: &str) -> Result<(PathBuf, PathBuf)> {
-        let mojang_dir = find_mojang_dir(self.build.as_ref())?;
+impl DevelopmentExport {
+    fn get_build_paths(
+        &self,
+        build: Option<&MinecraftBuild>,
+        config: &Config,
+        profile_name: &str,
+    ) -> Result<(PathBuf, PathBuf)> {
+        let mojang_dir = find_mojang_dir(build)?;
         if !mojang_dir.exists() {
-            bail!("Failed to find com.mojang directory")
+            match build {
+                Some(build) => bail!("Failed to find com.mojang directory for <b>{build}</> build"),
+                None => bail!("Failed to find com.mojang directory"),
+            }
         }
         let eval = Eval::new(profile_name, &get_current_dir()?, None);
         let bp = {
@@ -46,7 +102,7 @@ impl ExportPaths for DevelopmentExport {
             if let Some(bp_name) = &self.bp_name {
                 dir.join(eval.string(bp_name)?)
             } else {
-                dir.join(format!("{project_name}_bp"))
+                dir.join(format!("{}_bp", config.get_name()))
             }
         };
         let rp = {
@@ -54,13 +110,35 @@ impl ExportPaths for DevelopmentExport {
             if let Some(rp_name) = &self.rp_name {
                 dir.join(eval.string(rp_name)?)
             } else {
-                dir.join(format!("{project_name}_rp"))
+                dir.join(format!("{}_rp", config.get_name()))
             }
         };
         Ok((bp, rp))
     }
 }
 
+impl ExportPaths for DevelopmentExport {
+    fn get_paths(&self, config: &Config, profile_name: &str) -> Result<(PathBuf, PathBuf)> {
+        let build = self.build.as_ref().and_then(|b| b.primary());
+        self.get_build_paths(build, config, profile_name)
+    }
+
+    fn get_mirror_paths(
+        &self,
+        config: &Config,
+        profile_name: &str,
+    ) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let Some(builds) = &self.build else {
+            return Ok(vec![]);
+        };
+        builds
+            .mirrors()
+            .iter()
+            .map(|build| self.get_build_paths(Some(build), config, profile_name))
+            .collect()
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LocalExport {
@@ -71,7 +149,7 @@ pub struct LocalExport {
 }
 
 impl ExportPaths for LocalExport {
-    fn get_paths(&self, project_name: &str, profile_name: &str) -> Result<(PathBuf, PathBuf)> {
+    fn get_paths(&self, config: &Config, profile_name: &str) -> Result<(PathBuf, PathBuf)> {
         let build = PathBuf::from("build");
         if !build.exists() {
             fs::create_dir(&build)?;
@@ -80,17 +158,19 @@ impl ExportPaths for LocalExport {
         let bp = if let Some(bp_name) = &self.bp_name {
             build.join(eval.string(bp_name)?)
         } else {
-            build.join(format!("{project_name}_bp"))
+            build.join(format!("{}_bp", config.get_name()))
         };
         let rp = if let Some(rp_name) = &self.rp_name {
             build.join(eval.string(rp_name)?)
         } else {
-            build.join(format!("{project_name}_rp"))
+            build.join(format!("{}_rp", config.get_name()))
         };
         Ok((bp, rp))
     }
 }
 
+/// Export to the given paths. The paths may contain `{name}`, `{profile}`, `{version}` and
+/// `{build}` placeholders, e.g. `dist/{version}/{name}_bp`.
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExactExport {
@@ -99,9 +179,9 @@ pub struct ExactExport {
 }
 
 impl ExportPaths for ExactExport {
-    fn get_paths(&self, _project_name: &str, _profile_name: &str) -> Result<(PathBuf, PathBuf)> {
-        let bp = resolve_path(&self.bp_path)?;
-        let rp = resolve_path(&self.rp_path)?;
+    fn get_paths(&self, config: &Config, profile_name: &str) -> Result<(PathBuf, PathBuf)> {
+        let bp = resolve_path(&expand_placeholders(&self.bp_path, config, profile_name)?)?;
+        let rp = resolve_path(&expand_placeholders(&self.rp_path, config, profile_name)?)?;
         if bp == rp {
             bail!("Both `bpPath` and `rpPath` resolved to the same path")
         }
@@ -109,6 +189,71 @@ impl ExportPaths for ExactExport {
     }
 }
 
+/// Replaces the placeholders in an export path:
+/// - `{name}`: name of the project
+/// - `{profile}`: name of the profile
+/// - `{version}`: version in the manifest of the behavior pack, or the resource pack
+/// - `{build}`: short hash of the current git commit
+fn expand_placeholders(path: &str, config: &Config, profile_name: &str) -> Result<String> {
+    let mut result = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let placeholder = &rest[start + 1..start + end];
+        let value = match placeholder {
+            "name" => config.get_name().to_owned(),
+            "profile" => profile_name.to_owned(),
+            "version" => get_project_version(config)?,
+            "build" => get_commit_hash()?,
+            _ => bail!(
+                "Unknown placeholder <b>{{{placeholder}}}</> in export path, expected \
+                 {{name}}, {{profile}}, {{version}} or {{build}}"
+            ),
+        };
+        result.push_str(&rest[..start]);
+        result.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn get_project_version(config: &Config) -> Result<String> {
+    get_pack_version(config)
+        .context("The <b>{version}</> placeholder requires a pack manifest with a version")
+}
+
+/// Version in the manifest of the behavior pack, or the resource pack
+pub fn get_pack_version(config: &Config) -> Option<String> {
+    for pack in [config.get_behavior_pack(), config.get_resource_pack()]
+        .into_iter()
+        .flatten()
+    {
+        let Ok(manifest) = read_json::<Value>(pack.join("manifest.json")) else {
+            continue;
+        };
+        let version = &manifest["header"]["version"];
+        if let Some(parts) = version.as_array() {
+            let parts: Vec<_> = parts.iter().map(|part| part.to_string()).collect();
+            return Some(parts.join("."));
+        }
+        if let Some(version) = version.as_str() {
+            return Some(version.to_owned());
+        }
+    }
+    None
+}
+
+fn get_commit_hash() -> Result<String> {
+    let output = Subprocess::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .run_silent()
+        .context("The <b>{build}</> placeholder requires the project to be a git repository")?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
 fn resolve_path(path: &str) -> Result<PathBuf> {
     let mut res = PathBuf::new();
     for component in PathBuf::from(path).components() {
@@ -148,7 +293,7 @@ fn resolve_path(path: &str) -> Result<PathBuf> {
 pub struct NoneExport {}
 
 impl ExportPaths for NoneExport {
-    fn get_paths(&self, _project_name: &str, _profile_name: &str) -> Result<(PathBuf, PathBuf)> {
+    fn get_paths(&self, _config: &Config, _profile_name: &str) -> Result<(PathBuf, PathBuf)> {
         // Set the export target to temp just to not mess up the log messages
         let dot_regolith = PathBuf::from(".regolith");
         let temp = dot_regolith.join("tmp");
@@ -171,23 +316,38 @@ pub struct WorldExport {
     rp_name: Option<String>,
 }
 
-impl ExportPaths for WorldExport {
-    fn get_paths(&self, project_name: &str, profile_name: &str) -> Result<(PathBuf, PathBuf)> {
-        let world_dir = match (&self.world_name, &self.world_path) {
-            (Some(world_name), None) => find_world_dir(self.build.as_ref(), world_name)?,
-            (None, Some(world_path)) => resolve_path(world_path)?,
+impl WorldExport {
+    /// Prompts for the world if its name doesn't match exactly one world, so the builds that
+    /// follow don't fail to find it
+    pub fn pick_world(&self) -> Result<()> {
+        if let (Some(world_name), None) = (&self.world_name, &self.world_path) {
+            pick_world_dir(self.build.as_ref(), world_name)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_world_dir(&self) -> Result<PathBuf> {
+        match (&self.world_name, &self.world_path) {
+            (Some(world_name), None) => find_world_dir(self.build.as_ref(), world_name),
+            (None, Some(world_path)) => resolve_path(world_path),
             (Some(_), Some(_)) => bail!("Using both `worldName` and `worldPath` is not allowed"),
             (None, None) => bail!(
                 "The `world` export target requires either a `worldName` or `worldPath property`"
             ),
-        };
+        }
+    }
+}
+
+impl ExportPaths for WorldExport {
+    fn get_paths(&self, config: &Config, profile_name: &str) -> Result<(PathBuf, PathBuf)> {
+        let world_dir = self.get_world_dir()?;
         let eval = Eval::new(profile_name, &get_current_dir()?, None);
         let bp = {
             let dir = world_dir.join("behavior_packs");
             if let Some(bp_name) = &self.bp_name {
                 dir.join(eval.string(bp_name)?)
             } else {
-                dir.join(format!("{project_name}_bp"))
+                dir.join(format!("{}_bp", config.get_name()))
             }
         };
         let rp = {
@@ -195,7 +355,7 @@ impl ExportPaths for WorldExport {
             if let Some(rp_name) = &self.rp_name {
                 dir.join(eval.string(rp_name)?)
             } else {
-                dir.join(format!("{project_name}_rp"))
+                dir.join(format!("{}_rp", config.get_name()))
             }
         };
         Ok((bp, rp))
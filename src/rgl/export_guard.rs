@@ -0,0 +1,96 @@
+use super::{find_mojang_dir, get_current_dir, ErrorCode, MinecraftBuild};
+use crate::fs::{is_dir_empty, read_json, rimraf, write_json};
+use anyhow::{bail, Result};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use strum::IntoEnumIterator;
+
+/// File that older versions wrote into every export target, accepted in place of the registry
+const LEGACY_EXPORT_MARKER: &str = ".rgl_export";
+
+/// Export targets the project exported to before, kept out of the exported packs so they never
+/// end up in archives or uploads
+fn get_export_targets_path() -> PathBuf {
+    PathBuf::from(".regolith").join("export_targets.json")
+}
+
+fn read_export_targets() -> BTreeSet<PathBuf> {
+    read_json(get_export_targets_path()).unwrap_or_default()
+}
+
+/// Checks that an export target can be wiped before it is cleaned or synced. Only empty
+/// directories, directories inside com.mojang or the local build folder, and directories that
+/// rgl exported to before are accepted, so a typo in an export path can't delete e.g. the home
+/// directory of the user.
+pub fn check_export_target(target: &Path) -> Result<()> {
+    if !target.exists() {
+        return Ok(());
+    }
+    if !target.is_dir() {
+        bail!(
+            "The export target is not a directory\n\
+             <yellow> >></> Path: {}",
+            target.display()
+        );
+    }
+    if is_dir_empty(target)? || target.join(LEGACY_EXPORT_MARKER).is_file() {
+        return Ok(());
+    }
+    let target = dunce::canonicalize(target)?;
+    if read_export_targets().contains(&target)
+        || get_managed_dirs()?
+            .iter()
+            .any(|(dir, depth)| is_nested(&target, dir, *depth))
+    {
+        return Ok(());
+    }
+    Err(ErrorCode::ProtectedExportTarget.error(format!(
+        "Refusing to overwrite a directory that was not created by rgl\n\
+         <yellow> >></> Path: {}\n\
+         <yellow> >></> Check the export path in the profile, or empty the directory to let rgl \
+         manage it",
+        target.display()
+    )))
+}
+
+/// Adds a target to the registry after exporting to it, so later runs can clean it
+pub fn mark_export_target(target: &Path) -> Result<()> {
+    if !target.is_dir() {
+        return Ok(());
+    }
+    rimraf(target.join(LEGACY_EXPORT_MARKER))?;
+    let mut targets = read_export_targets();
+    if targets.insert(dunce::canonicalize(target)?) {
+        let path = get_export_targets_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_json(path, &targets)?;
+    }
+    Ok(())
+}
+
+/// Directories rgl may always overwrite below, with the minimum depth of the target. Packs are
+/// never exported directly into com.mojang, so e.g. `development_behavior_packs` is protected.
+fn get_managed_dirs() -> Result<Vec<(PathBuf, usize)>> {
+    let mut dirs = vec![(get_current_dir()?.join("build"), 1)];
+    dirs.extend(
+        MinecraftBuild::iter()
+            .filter_map(|build| find_mojang_dir(Some(&build)).ok())
+            .map(|dir| (dir, 2)),
+    );
+    Ok(dirs
+        .into_iter()
+        .filter_map(|(dir, depth)| Some((dunce::canonicalize(dir).ok()?, depth)))
+        .collect())
+}
+
+fn is_nested(path: &Path, dir: &Path, depth: usize) -> bool {
+    match path.strip_prefix(dir) {
+        Ok(relative) => relative.components().count() >= depth,
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::{bail, Result};
+use std::{collections::HashMap, path::Path};
+use walkdir::WalkDir;
+
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Checks that every file name in the pack is valid on all platforms and that no two names in the
+/// same directory only differ by case.
+pub fn check_file_names(pack: &Path) -> Result<()> {
+    let mut issues = vec![];
+    let mut names = HashMap::<_, Vec<_>>::new();
+    for entry in WalkDir::new(pack).min_depth(1).follow_links(true) {
+        let entry = entry?;
+        let path = entry.path().strip_prefix(pack)?;
+        let name = entry.file_name().to_string_lossy();
+        if let Some(reason) = get_invalid_reason(&name) {
+            issues.push(format!("{} ({reason})", path.display()));
+        }
+        names
+            .entry((path.parent().map(Path::to_owned), name.to_lowercase()))
+            .or_default()
+            .push(path.to_owned());
+    }
+    for paths in names.into_values().filter(|paths| paths.len() > 1) {
+        let paths: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+        issues.push(format!("{} (names only differ by case)", paths.join(", ")));
+    }
+    if !issues.is_empty() {
+        issues.sort();
+        bail!(
+            "Found invalid file names in {}\n{}",
+            pack.display(),
+            issues
+                .iter()
+                .map(|issue| format!("<yellow> >></> {issue}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+    Ok(())
+}
+
+fn get_invalid_reason(name: &str) -> Option<&'static str> {
+    if name.contains(['<', '>', ':', '"', '\\', '|', '?', '*']) {
+        return Some("contains a character that is not allowed on Windows");
+    }
+    if name.chars().any(char::is_control) {
+        return Some("contains a control character");
+    }
+    if name.ends_with(['.', ' ']) {
+        return Some("ends with a dot or space");
+    }
+    let stem = name.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.contains(&stem.to_uppercase().as_str()) {
+        return Some("reserved name on Windows");
+    }
+    None
+}
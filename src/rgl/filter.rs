@@ -1,14 +1,15 @@
 use super::{
-    get_current_dir, get_filter_cache_dir, FilterBun, FilterDeno, FilterExe, FilterGo,
-    FilterNodejs, FilterPython, FilterShell, RemoteFilter, RemoteFilterConfig,
+    get_current_dir, get_filter_cache_dir, FilterBun, FilterBundle, FilterDeno, FilterDocker,
+    FilterExe, FilterGo, FilterNodejs, FilterPython, FilterShell, FilterTextures, FilterWasm,
+    RemoteFilter, RemoteFilterConfig,
 };
 use crate::fs::{is_dir_empty, read_json};
-use crate::info;
-use anyhow::{Context, Result};
+use crate::{info, warn};
+use anyhow::{bail, Context, Result};
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use strum::Display;
 
 #[derive(Serialize, Deserialize)]
@@ -40,18 +41,58 @@ impl FilterDefinition {
 #[enum_dispatch]
 pub enum LocalFilter {
     Bun(FilterBun),
+    Bundle(FilterBundle),
     Deno(FilterDeno),
+    Docker(FilterDocker),
     Exe(FilterExe),
     Go(FilterGo),
     Nodejs(FilterNodejs),
     Python(FilterPython),
     Shell(FilterShell),
+    Textures(FilterTextures),
+    Wasm(FilterWasm),
+}
+
+impl LocalFilter {
+    /// Warns if the filter runtime can't restrict filesystem access to the temp directory.
+    pub fn check_sandbox(&self, context: &FilterContext) {
+        if context.sandbox
+            && !matches!(
+                self,
+                LocalFilter::Bundle(_)
+                    | LocalFilter::Deno(_)
+                    | LocalFilter::Docker(_)
+                    | LocalFilter::Textures(_)
+                    | LocalFilter::Wasm(_)
+            )
+        {
+            warn!(
+                "Filter <filter>{}</> can't be sandboxed when running with {self}, running without restrictions",
+                context.name
+            );
+        }
+    }
+
+    /// Warns if the filter runs inside rgl, where it can't be stopped when it times out.
+    pub fn check_timeout(&self, context: &FilterContext) {
+        if matches!(
+            self,
+            LocalFilter::Bundle(_) | LocalFilter::Textures(_) | LocalFilter::Wasm(_)
+        ) {
+            warn!(
+                "Filter <filter>{}</> runs inside rgl with {self}, its timeout is not enforced",
+                context.name
+            );
+        }
+    }
 }
 
 pub struct FilterContext {
     pub name: String,
     pub filter_dir: PathBuf,
     pub remote_config: Option<RemoteFilterConfig>,
+    /// Restrict filesystem access to the temp directory
+    pub sandbox: bool,
 }
 
 impl FilterContext {
@@ -61,6 +102,7 @@ impl FilterContext {
                 name: name.to_owned(),
                 filter_dir: get_current_dir()?,
                 remote_config: None,
+                sandbox: false,
             }),
             FilterDefinition::Remote(remote) => {
                 let filter_dir = get_filter_cache_dir(name, remote)?;
@@ -76,6 +118,7 @@ impl FilterContext {
                     name: name.to_owned(),
                     filter_dir,
                     remote_config,
+                    sandbox: false,
                 })
             }
         }
@@ -92,6 +135,24 @@ impl FilterContext {
     }
 }
 
+/// Directory a filter runs in, the temp directory unless the filter sets a directory relative to
+/// the project. The directory can't be outside of the project.
+pub fn get_working_dir(working_dir: Option<&str>, temp: &Path) -> Result<PathBuf> {
+    let Some(dir) = working_dir else {
+        return Ok(temp.to_owned());
+    };
+    let is_inside = Path::new(dir)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_inside {
+        bail!(
+            "The working directory of a filter must be inside the project\n\
+             <yellow> >></> Path: {dir}"
+        );
+    }
+    Ok(get_current_dir()?.join(dir))
+}
+
 #[enum_dispatch(FilterDefinition, LocalFilter)]
 pub trait Filter {
     fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()>;
@@ -99,4 +160,14 @@ pub trait Filter {
     fn install_dependencies(&self, context: &FilterContext) -> Result<()> {
         Ok(())
     }
+    /// Files listing the dependencies installed by `install_dependencies`
+    #[allow(unused_variables)]
+    fn dependency_files(&self, context: &FilterContext) -> Vec<PathBuf> {
+        vec![]
+    }
+    /// Directory `install_dependencies` installs the dependencies to, e.g. `node_modules`
+    #[allow(unused_variables)]
+    fn installed_dir(&self, context: &FilterContext) -> Option<PathBuf> {
+        None
+    }
 }
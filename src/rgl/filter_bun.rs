@@ -1,7 +1,7 @@
-use super::{Filter, FilterContext, Subprocess};
+use super::{resolve_runtime, Filter, FilterContext, Subprocess};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 pub struct FilterBun {
@@ -11,22 +11,30 @@ pub struct FilterBun {
 impl Filter for FilterBun {
     fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
         let script = context.filter_dir.join(&self.script);
-        Subprocess::new("bun")
+        Subprocess::new(resolve_runtime("bun")?)
             .arg("run")
             .arg(script)
             .args(run_args)
             .current_dir(temp)
-            .setup_env(&context.filter_dir)
+            .setup_env(context)
             .run()?;
         Ok(())
     }
 
     fn install_dependencies(&self, context: &FilterContext) -> Result<()> {
         let filter_dir = context.filter_dir(&self.script);
-        Subprocess::new("bun")
+        Subprocess::new(resolve_runtime("bun")?)
             .arg("i")
             .current_dir(filter_dir)
             .run()?;
         Ok(())
     }
+
+    fn dependency_files(&self, context: &FilterContext) -> Vec<PathBuf> {
+        vec![context.filter_dir(&self.script).join("package.json")]
+    }
+
+    fn installed_dir(&self, context: &FilterContext) -> Option<PathBuf> {
+        Some(context.filter_dir(&self.script).join("node_modules"))
+    }
 }
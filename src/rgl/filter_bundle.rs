@@ -0,0 +1,453 @@
+use super::{Filter, FilterContext};
+use crate::debug;
+use crate::fs::{rimraf, write_file};
+use anyhow::{anyhow, bail, Context, Result};
+use oxc::{
+    allocator::Allocator,
+    ast::ast::{
+        ExportAllDeclaration, ExportFromDeclaration, Expression, ImportDeclaration,
+        ImportExpression, Program, Statement, StringLiteral,
+    },
+    ast_visit::{walk_mut, VisitMut},
+    codegen::{Codegen, CodegenOptions},
+    diagnostics::{NamedSource, OxcDiagnostic},
+    parser::Parser,
+    semantic::SemanticBuilder,
+    span::SourceType,
+    str::Str,
+    transformer::{TransformOptions, Transformer},
+};
+use oxc_resolver::{ResolveOptions, Resolver};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Script modules provided by the game, imports of these are kept as is
+const ENGINE_MODULES: [&str; 8] = [
+    "@minecraft/common",
+    "@minecraft/debug-utilities",
+    "@minecraft/server",
+    "@minecraft/server-admin",
+    "@minecraft/server-editor",
+    "@minecraft/server-gametest",
+    "@minecraft/server-net",
+    "@minecraft/server-ui",
+];
+
+/// Extensions of the modules that can be compiled
+const MODULE_EXTENSIONS: [&str; 5] = ["ts", "mts", "js", "mjs", "cjs"];
+
+/// Directory in the output that imported packages are written to
+const PACKAGES_DIR: &str = "_modules";
+
+/// Compiles TypeScript and JavaScript modules in-process, configured with the filter settings.
+#[derive(Serialize, Deserialize)]
+pub struct FilterBundle {}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct BundleSettings {
+    /// Modules to compile, relative to the temp directory
+    entry_points: Vec<String>,
+    /// Directory the compiled modules are written to, relative to the temp directory
+    outdir: String,
+    /// Modules that are kept as imports, in addition to the ones provided by the game
+    external: Vec<String>,
+    /// Write a source map next to every compiled module
+    sourcemap: bool,
+}
+
+impl Default for BundleSettings {
+    fn default() -> Self {
+        Self {
+            entry_points: vec!["BP/scripts/main.ts".to_owned()],
+            outdir: "BP/scripts".to_owned(),
+            external: vec![],
+            sourcemap: false,
+        }
+    }
+}
+
+impl Filter for FilterBundle {
+    fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
+        let settings = match run_args.first().filter(|arg| arg.starts_with('{')) {
+            Some(settings) => serde_json::from_str(settings).context("Invalid bundle settings")?,
+            None => BundleSettings::default(),
+        };
+        Bundler::new(context, temp, settings)?.run()
+    }
+}
+
+struct CompiledModule {
+    output: PathBuf,
+    /// Modules imported at runtime
+    dependencies: Vec<PathBuf>,
+    /// Modules imported at runtime or for their types
+    imports: Vec<PathBuf>,
+}
+
+struct Bundler {
+    temp: PathBuf,
+    entry_points: Vec<PathBuf>,
+    /// Directory containing the entry points, the layout of the modules in it is kept
+    source_dir: PathBuf,
+    outdir: PathBuf,
+    external: Vec<String>,
+    sourcemap: bool,
+    resolver: Resolver,
+}
+
+impl Bundler {
+    fn new(context: &FilterContext, temp: &Path, settings: BundleSettings) -> Result<Self> {
+        let temp = &std::path::absolute(temp)?;
+        if settings.entry_points.is_empty() {
+            bail!("No entry points configured");
+        }
+        let mut entry_points = vec![];
+        for entry in &settings.entry_points {
+            let path = temp.join(entry);
+            if !path.is_file() {
+                bail!(
+                    "Entry point not found, configure it with the <b>entryPoints</> setting\n\
+                     <yellow> >></> Path: {entry}"
+                );
+            }
+            entry_points.push(path);
+        }
+        let source_dir = get_common_dir(&entry_points);
+        let external = ENGINE_MODULES
+            .iter()
+            .map(|module| module.to_string())
+            .chain(settings.external)
+            .collect();
+        let resolver = Resolver::new(ResolveOptions {
+            extensions: [".ts", ".mts", ".js", ".mjs", ".cjs"]
+                .map(str::to_owned)
+                .to_vec(),
+            // TypeScript imports other modules by the name of the compiled file
+            extension_alias: vec![(".js".to_owned(), vec![".ts".to_owned(), ".js".to_owned()])],
+            condition_names: vec!["import".to_owned(), "default".to_owned()],
+            main_fields: vec!["module".to_owned(), "main".to_owned()],
+            // Packages are installed in the project, not in the temp directory
+            modules: vec![
+                "node_modules".to_owned(),
+                context
+                    .filter_dir
+                    .join("node_modules")
+                    .display()
+                    .to_string(),
+            ],
+            // BP and RP may be symlinks to the export target
+            symlinks: false,
+            ..ResolveOptions::default()
+        });
+        Ok(Self {
+            temp: temp.to_owned(),
+            entry_points,
+            source_dir,
+            outdir: temp.join(settings.outdir),
+            external,
+            sourcemap: settings.sourcemap,
+            resolver,
+        })
+    }
+
+    fn run(&self) -> Result<()> {
+        let mut queue = VecDeque::from(self.entry_points.clone());
+        let mut visited = HashSet::new();
+        let mut compiled = vec![];
+        let mut type_imports = vec![];
+        while let Some(path) = queue.pop_front() {
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            let module = self.compile(&path)?;
+            queue.extend(module.dependencies);
+            type_imports.extend(module.imports);
+            compiled.push((path, module.output));
+        }
+        // Modules that only export types are never imported at runtime, but are part of the
+        // sources
+        let mut type_modules = vec![];
+        while let Some(path) = type_imports.pop() {
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            if let Ok(source) = fs::read_to_string(&path) {
+                let allocator = Allocator::default();
+                let source_type = SourceType::from_path(&path).unwrap_or_default();
+                let program = Parser::new(&allocator, &source, source_type)
+                    .parse()
+                    .program;
+                type_imports.extend(self.get_static_imports(&path, &program));
+            }
+            type_modules.push(path);
+        }
+        // Sources are removed after compiling everything, as they can be imported more than once
+        for (source, output) in &compiled {
+            if source != output && source.starts_with(&self.temp) {
+                rimraf(source)?;
+            }
+        }
+        for path in type_modules {
+            let extension = path.extension().unwrap_or_default();
+            if (extension == "ts" || extension == "mts") && path.starts_with(&self.source_dir) {
+                rimraf(path)?;
+            }
+        }
+        debug!("Compiled {} script modules", compiled.len());
+        Ok(())
+    }
+
+    /// Compiles a module and returns the output path and the modules it imports.
+    fn compile(&self, path: &Path) -> Result<CompiledModule> {
+        let output = self.get_output_path(path)?;
+        let source = fs::read_to_string(path).with_context(|| {
+            format!(
+                "Failed to read module\n\
+                 <yellow> >></> Path: {}",
+                path.display()
+            )
+        })?;
+        let allocator = Allocator::default();
+        let source_type = SourceType::from_path(path).unwrap_or_default();
+        let parsed = Parser::new(&allocator, &source, source_type).parse();
+        self.check_diagnostics(path, &source, parsed.diagnostics.into_vec())?;
+        let mut program = parsed.program;
+        let imports = self.get_static_imports(path, &program);
+
+        let scoping = SemanticBuilder::new()
+            .with_enum_eval(true)
+            .build(&program)
+            .semantic
+            .into_scoping();
+        let options = TransformOptions::default();
+        let transformed =
+            Transformer::new(&allocator, path, &options).build_with_scoping(scoping, &mut program);
+        self.check_diagnostics(path, &source, transformed.diagnostics.into_vec())?;
+
+        // Type-only imports are removed by the transformer, so only imports used at runtime are
+        // resolved.
+        let mut rewriter = ImportRewriter {
+            allocator: &allocator,
+            bundler: self,
+            path,
+            output: &output,
+            dependencies: vec![],
+            error: None,
+        };
+        rewriter.visit_program(&mut program);
+        if let Some(error) = rewriter.error {
+            return Err(error);
+        }
+        let dependencies = rewriter.dependencies;
+
+        let output_dir = output.parent().unwrap_or(&self.outdir);
+        let codegen = Codegen::new()
+            .with_options(CodegenOptions {
+                source_map_path: self.sourcemap.then(|| get_relative_path(output_dir, path)),
+                ..CodegenOptions::default()
+            })
+            .build(&program);
+        fs::create_dir_all(output_dir)?;
+        let mut code = codegen.code;
+        if let Some(map) = codegen.map {
+            let map_path = output.with_extension("js.map");
+            write_file(&map_path, map.to_json_string())?;
+            let file_name = map_path.file_name().unwrap_or_default().to_string_lossy();
+            code.push_str(&format!("//# sourceMappingURL={file_name}\n"));
+        }
+        write_file(&output, code)?;
+        Ok(CompiledModule {
+            output,
+            dependencies,
+            imports,
+        })
+    }
+
+    /// Modules imported by the module before type-only imports are removed, modules that can't
+    /// be resolved are skipped.
+    fn get_static_imports(&self, path: &Path, program: &Program) -> Vec<PathBuf> {
+        program
+            .body
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::ImportDeclaration(it) => Some(&it.source),
+                Statement::ExportAllDeclaration(it) => Some(&it.source),
+                Statement::ExportFromDeclaration(it) => Some(&it.source),
+                _ => None,
+            })
+            .filter_map(|source| self.resolve(&source.value, path, path).ok().flatten())
+            .map(|(_, path)| path)
+            .collect()
+    }
+
+    fn check_diagnostics(
+        &self,
+        path: &Path,
+        source: &str,
+        diagnostics: Vec<OxcDiagnostic>,
+    ) -> Result<()> {
+        let Some(diagnostic) = diagnostics.into_iter().next() else {
+            return Ok(());
+        };
+        let name = path.strip_prefix(&self.temp).unwrap_or(path);
+        let source = NamedSource::new(name.display().to_string(), source.to_owned());
+        bail!(
+            "Failed to compile module\n{}",
+            diagnostic.render_with_source_code(source)
+        )
+    }
+
+    /// Modules in the source directory keep their layout, packages are written to a separate
+    /// directory.
+    fn get_output_path(&self, path: &Path) -> Result<PathBuf> {
+        let components = path.components().collect::<Vec<_>>();
+        let package_index = components
+            .iter()
+            .rposition(|component| component.as_os_str() == "node_modules");
+        let output = match package_index {
+            Some(index) => {
+                let package_path = components[index + 1..].iter().collect::<PathBuf>();
+                self.outdir.join(PACKAGES_DIR).join(package_path)
+            }
+            None => match path.strip_prefix(&self.source_dir) {
+                Ok(relative) => self.outdir.join(relative),
+                Err(_) => bail!(
+                    "Module is outside of the source directory <b>{}</>\n\
+                     <yellow> >></> Path: {}",
+                    self.source_dir.display(),
+                    path.display()
+                ),
+            },
+        };
+        Ok(output.with_extension("js"))
+    }
+
+    /// Returns the specifier of the compiled module, and the path of the module if it needs to
+    /// be compiled.
+    fn resolve(
+        &self,
+        specifier: &str,
+        from: &Path,
+        output: &Path,
+    ) -> Result<Option<(String, PathBuf)>> {
+        let is_external = self
+            .external
+            .iter()
+            .any(|module| specifier == module || specifier.starts_with(&format!("{module}/")));
+        if is_external {
+            return Ok(None);
+        }
+        let dir = from.parent().unwrap_or(&self.temp);
+        let resolved = self
+            .resolver
+            .resolve(dir, specifier)
+            .map_err(|e| anyhow!(e))
+            .with_context(|| {
+                format!(
+                    "Failed to resolve <b>{specifier}</>, add it to the <b>external</> setting if it's provided by the game\n\
+                     <yellow> >></> Path: {}",
+                    from.display()
+                )
+            })?
+            .into_path_buf();
+        let extension = resolved.extension().unwrap_or_default().to_string_lossy();
+        if !MODULE_EXTENSIONS.contains(&extension.as_ref()) {
+            bail!(
+                "Only JavaScript and TypeScript modules can be imported\n\
+                 <yellow> >></> Path: {}",
+                resolved.display()
+            );
+        }
+        let target = self.get_output_path(&resolved)?;
+        let relative = get_relative_path(output.parent().unwrap_or(&self.outdir), &target);
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let specifier = match relative.starts_with("..") {
+            true => relative,
+            false => format!("./{relative}"),
+        };
+        Ok(Some((specifier, resolved)))
+    }
+}
+
+/// Rewrites the specifiers of static and dynamic imports to the compiled modules.
+struct ImportRewriter<'a, 'b> {
+    allocator: &'a Allocator,
+    bundler: &'b Bundler,
+    path: &'b Path,
+    output: &'b Path,
+    dependencies: Vec<PathBuf>,
+    error: Option<anyhow::Error>,
+}
+
+impl<'a> ImportRewriter<'a, '_> {
+    fn rewrite(&mut self, source: &mut StringLiteral<'a>) {
+        if self.error.is_some() {
+            return;
+        }
+        match self.bundler.resolve(&source.value, self.path, self.output) {
+            Ok(Some((specifier, path))) => {
+                source.value = Str::from(self.allocator.alloc_str(&specifier));
+                source.raw = None;
+                self.dependencies.push(path);
+            }
+            Ok(None) => {}
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
+
+impl<'a> VisitMut<'a> for ImportRewriter<'a, '_> {
+    fn visit_import_declaration(&mut self, it: &mut ImportDeclaration<'a>) {
+        self.rewrite(&mut it.source);
+    }
+
+    fn visit_export_all_declaration(&mut self, it: &mut ExportAllDeclaration<'a>) {
+        self.rewrite(&mut it.source);
+    }
+
+    fn visit_export_from_declaration(&mut self, it: &mut ExportFromDeclaration<'a>) {
+        self.rewrite(&mut it.source);
+    }
+
+    fn visit_import_expression(&mut self, it: &mut ImportExpression<'a>) {
+        if let Expression::StringLiteral(source) = &mut it.source {
+            self.rewrite(source);
+        }
+        walk_mut::walk_import_expression(self, it);
+    }
+}
+
+/// Deepest directory that contains all the paths
+fn get_common_dir(paths: &[PathBuf]) -> PathBuf {
+    let mut dir = paths[0].parent().map(Path::to_path_buf).unwrap_or_default();
+    for path in &paths[1..] {
+        while !path.starts_with(&dir) {
+            if !dir.pop() {
+                break;
+            }
+        }
+    }
+    dir
+}
+
+/// Path of `path` relative to the directory `base`, both paths must be absolute
+fn get_relative_path(base: &Path, path: &Path) -> PathBuf {
+    let base = base.components().collect::<Vec<_>>();
+    let path = path.components().collect::<Vec<_>>();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push(Component::ParentDir);
+    }
+    relative.extend(&path[common..]);
+    relative
+}
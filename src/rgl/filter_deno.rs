@@ -1,7 +1,8 @@
-use super::{Filter, FilterContext, Subprocess};
+use super::{resolve_runtime, Filter, FilterContext, Subprocess};
 use anyhow::Result;
+use dunce::canonicalize;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 pub struct FilterDeno {
@@ -11,13 +12,46 @@ pub struct FilterDeno {
 impl Filter for FilterDeno {
     fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
         let script = context.filter_dir.join(&self.script);
-        Subprocess::new("deno")
-            .args(vec!["run", "-A", "--no-lock"])
+        let permissions = if context.sandbox {
+            get_sandbox_permissions(context, temp)?
+        } else {
+            vec!["-A".to_owned()]
+        };
+        Subprocess::new(resolve_runtime("deno")?)
+            .arg("run")
+            .args(permissions)
+            .arg("--no-lock")
             .arg(script)
             .args(run_args)
             .current_dir(temp)
-            .setup_env(&context.filter_dir)
+            .setup_env(context)
             .run()?;
         Ok(())
     }
 }
+
+/// Only allows reading the filter and temp directories, and writing to the temp directory
+fn get_sandbox_permissions(context: &FilterContext, temp: &Path) -> Result<Vec<String>> {
+    let mut writable = vec![temp.to_owned()];
+    // BP and RP may be symlinks to the export target
+    for dir in ["BP", "RP", "data"] {
+        let path = temp.join(dir);
+        if path.exists() {
+            writable.push(canonicalize(path)?);
+        }
+    }
+    let join = |paths: &[PathBuf]| {
+        paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mut readable = writable.clone();
+    readable.push(context.filter_dir.to_owned());
+    Ok(vec![
+        format!("--allow-read={}", join(&readable)),
+        format!("--allow-write={}", join(&writable)),
+        "--allow-env".to_owned(),
+    ])
+}
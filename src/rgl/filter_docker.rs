@@ -0,0 +1,81 @@
+use super::{get_run_env_vars, Filter, FilterContext, Subprocess};
+use anyhow::Result;
+use dunce::canonicalize;
+use serde::{Deserialize, Serialize};
+use std::{ffi::OsString, path::Path};
+
+#[derive(Serialize, Deserialize)]
+pub struct FilterDocker {
+    pub image: String,
+    /// Runs with the image's default entrypoint if not specified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl Filter for FilterDocker {
+    fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
+        let mut docker = Subprocess::new("docker");
+        docker.args(["run", "--rm", "--init", "--workdir", "/rgl/tmp"]);
+        // BP and RP may be symlinks to the export target, so they're mounted separately
+        for dir in ["BP", "RP", "data"] {
+            let source = temp.join(dir);
+            if source.exists() {
+                let source = canonicalize(source)?;
+                docker.arg("--volume");
+                docker.arg(format!("{}:/rgl/tmp/{dir}", source.display()));
+            }
+        }
+        let filter_dir = canonicalize(&context.filter_dir)?;
+        docker
+            .arg("--volume")
+            .arg(format!("{}:/rgl/filter:ro", filter_dir.display()))
+            .args(["--env", "FILTER_DIR=/rgl/filter"]);
+        for (key, value) in get_run_env_vars(context) {
+            // Paths are passed as they're mounted in the container, the project root and export
+            // targets aren't mounted
+            let value = match key {
+                "RGL_TEMP_DIR" => "/rgl/tmp".into(),
+                "RGL_DATA_DIR" => "/rgl/tmp/data".into(),
+                "RGL_CHANGED_FILES" => {
+                    let mut volume = value.clone();
+                    volume.push(":/rgl/changed_files:ro");
+                    docker.arg("--volume").arg(volume);
+                    "/rgl/changed_files".into()
+                }
+                "RGL_ROOT_DIR" | "RGL_EXPORT_BP" | "RGL_EXPORT_RP" => continue,
+                _ => value,
+            };
+            let mut env = OsString::from(format!("{key}="));
+            env.push(value);
+            docker.arg("--env").arg(env);
+        }
+        if let Some(user) = get_user() {
+            docker.args(["--user", &user]);
+        }
+        docker.arg(&self.image);
+        if let Some(command) = &self.command {
+            docker.args(["sh", "-c", command, "sh"]);
+        }
+        docker
+            .args(run_args)
+            .current_dir(temp)
+            .setup_env(context)
+            .run()?;
+        Ok(())
+    }
+}
+
+/// Runs the container as the current user so the output isn't owned by root
+#[cfg(unix)]
+fn get_user() -> Option<String> {
+    let id = |flag| {
+        let output = Subprocess::new("id").arg(flag).run_silent().ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+    Some(format!("{}:{}", id("-u")?, id("-g")?))
+}
+
+#[cfg(not(unix))]
+fn get_user() -> Option<String> {
+    None
+}
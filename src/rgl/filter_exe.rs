@@ -1,4 +1,4 @@
-use super::{Filter, FilterContext, Subprocess};
+use super::{get_working_dir, quote_shell_arg, Filter, FilterContext, Subprocess};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -6,15 +6,41 @@ use std::path::Path;
 #[derive(Serialize, Deserialize)]
 pub struct FilterExe {
     pub exe: String,
+    /// Directory to run in, relative to the project. Defaults to the temp directory.
+    #[serde(rename = "workingDir", skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    /// Run the executable through the system shell, e.g. for scripts without a shebang on Windows
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shell: bool,
 }
 
 impl Filter for FilterExe {
     fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
         let exe = context.filter_dir.join(&self.exe);
-        Subprocess::new(exe)
-            .args(run_args)
-            .current_dir(temp)
-            .setup_env(&context.filter_dir)
+        let mut subprocess = match self.shell {
+            true => {
+                let quoted = quote_shell_arg(&exe.to_string_lossy());
+                match cfg!(windows) {
+                    // PowerShell only runs a quoted path with the call operator
+                    true => Subprocess::shell(&format!("& {quoted}"), run_args),
+                    // The arguments are forwarded from the positional parameters, `$0` is the
+                    // executable
+                    false => Subprocess::shell(
+                        &format!("{quoted} \"$@\""),
+                        std::iter::once(exe.as_os_str())
+                            .chain(run_args.iter().map(|arg| arg.as_ref())),
+                    ),
+                }
+            }
+            false => {
+                let mut subprocess = Subprocess::new(exe);
+                subprocess.args(run_args);
+                subprocess
+            }
+        };
+        subprocess
+            .current_dir(get_working_dir(self.working_dir.as_deref(), temp)?)
+            .setup_env(context)
             .run()?;
         Ok(())
     }
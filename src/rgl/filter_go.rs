@@ -1,4 +1,4 @@
-use super::{Filter, FilterContext, Subprocess};
+use super::{resolve_runtime, Filter, FilterContext, Subprocess};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::{env, path::Path};
@@ -22,7 +22,7 @@ impl Filter for FilterGo {
         }
 
         if should_rebuild(&context.filter_dir, &output)? {
-            Subprocess::new("go")
+            Subprocess::new(resolve_runtime("go")?)
                 .args(vec!["build", "-o"])
                 .arg(&output)
                 .arg(script)
@@ -33,13 +33,13 @@ impl Filter for FilterGo {
         Subprocess::new(output)
             .args(run_args)
             .current_dir(temp)
-            .setup_env(&context.filter_dir)
+            .setup_env(context)
             .run()?;
         Ok(())
     }
 
     fn install_dependencies(&self, context: &FilterContext) -> Result<()> {
-        Subprocess::new("go")
+        Subprocess::new(resolve_runtime("go")?)
             .args(vec!["mod", "download"])
             .current_dir(&context.filter_dir)
             .run()?;
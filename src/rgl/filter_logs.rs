@@ -0,0 +1,47 @@
+use crate::fs::rimraf;
+use anyhow::Result;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Log directory of the current run
+static LOG_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Number of runs to keep logs for
+const MAX_RUNS: usize = 10;
+
+fn get_logs_dir() -> PathBuf {
+    PathBuf::from(".regolith").join("logs")
+}
+
+/// Creates a new log directory for the filters of this run, removing the logs of old runs.
+pub fn start_filter_logs() -> Result<()> {
+    let logs_dir = get_logs_dir();
+    let run_id = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let log_dir = logs_dir.join(run_id.to_string());
+    fs::create_dir_all(&log_dir)?;
+
+    let mut runs: Vec<_> = fs::read_dir(&logs_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    if runs.len() > MAX_RUNS {
+        runs.sort();
+        for run in &runs[..runs.len() - MAX_RUNS] {
+            rimraf(run)?;
+        }
+    }
+
+    *LOG_DIR.lock().unwrap() = Some(log_dir);
+    Ok(())
+}
+
+/// Path of the log file for the filter in the current run
+pub fn get_filter_log_path(filter_name: &str) -> Option<PathBuf> {
+    let log_dir = LOG_DIR.lock().unwrap().clone()?;
+    let file_name = filter_name.replace(['/', '\\', ':'], "_");
+    Some(log_dir.join(format!("{file_name}.log")))
+}
@@ -1,7 +1,7 @@
-use super::{Filter, FilterContext, Subprocess, UserConfig};
+use super::{resolve_runtime, Filter, FilterContext, Subprocess, UserConfig};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 pub struct FilterNodejs {
@@ -10,13 +10,13 @@ pub struct FilterNodejs {
 
 impl Filter for FilterNodejs {
     fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
-        let runtime = UserConfig::nodejs_runtime();
+        let runtime = resolve_runtime(&UserConfig::nodejs_runtime())?;
         let script = context.filter_dir.join(&self.script);
         Subprocess::new(runtime)
             .arg(script)
             .args(run_args)
             .current_dir(temp)
-            .setup_env(&context.filter_dir)
+            .setup_env(context)
             .run()?;
         Ok(())
     }
@@ -32,4 +32,12 @@ impl Filter for FilterNodejs {
         }
         Ok(())
     }
+
+    fn dependency_files(&self, context: &FilterContext) -> Vec<PathBuf> {
+        vec![context.filter_dir(&self.script).join("package.json")]
+    }
+
+    fn installed_dir(&self, context: &FilterContext) -> Option<PathBuf> {
+        Some(context.filter_dir(&self.script).join("node_modules"))
+    }
 }
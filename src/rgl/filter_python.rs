@@ -1,7 +1,7 @@
-use super::{Filter, FilterContext, Subprocess, UserConfig};
+use super::{resolve_runtime, Filter, FilterContext, Subprocess, UserConfig};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 pub struct FilterPython {
@@ -17,14 +17,14 @@ impl Filter for FilterPython {
                 true => venv_dir.join("Scripts").join("python.exe"),
                 false => venv_dir.join("bin").join("python"),
             },
-            false => UserConfig::python_command().into(),
+            false => resolve_runtime(&UserConfig::python_command())?,
         });
         subprocess
             .arg("-u")
             .arg(script)
             .args(run_args)
             .current_dir(temp)
-            .setup_env(&context.filter_dir)
+            .setup_env(context)
             .run()?;
         Ok(())
     }
@@ -33,7 +33,7 @@ impl Filter for FilterPython {
         let filter_dir = context.filter_dir(&self.script);
         let requirements = filter_dir.join("requirements.txt");
         if requirements.exists() {
-            let py = UserConfig::python_command();
+            let py = resolve_runtime(&UserConfig::python_command())?;
             Subprocess::new(py)
                 .args(vec!["-m", "venv", ".venv"])
                 .current_dir(&filter_dir)
@@ -51,4 +51,12 @@ impl Filter for FilterPython {
         }
         Ok(())
     }
+
+    fn dependency_files(&self, context: &FilterContext) -> Vec<PathBuf> {
+        vec![context.filter_dir(&self.script).join("requirements.txt")]
+    }
+
+    fn installed_dir(&self, context: &FilterContext) -> Option<PathBuf> {
+        Some(context.filter_dir(&self.script).join(".venv"))
+    }
 }
@@ -1,12 +1,14 @@
 use super::{
-    get_filter_cache_dir, get_repo_cache_dir, Eval, Filter, FilterContext, LocalFilter, Resolver,
-    Subprocess,
+    backup_path, get_filter_cache_dir, get_repo_cache_dir, Eval, Filter, FilterContext,
+    LocalFilter, Resolver, Session, Subprocess,
 };
 use crate::fs::{copy_dir, empty_dir, is_dir_empty, rimraf};
+use crate::progress::Progress;
 use crate::{debug, info, warn};
 use anyhow::{bail, Context, Result};
 use semver::Version;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::path::Path;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -18,7 +20,46 @@ pub struct RemoteFilter {
 impl Filter for RemoteFilter {
     fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
         let config = context.remote_config.as_ref().unwrap();
-        for entry in &config.filters {
+        config.run(context, temp, run_args)
+    }
+    fn install_dependencies(&self, context: &FilterContext) -> Result<()> {
+        let config = context.remote_config.as_ref().unwrap();
+        config.install_dependencies(context)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RemoteFilterConfig {
+    #[serde(default, rename = "exportData")]
+    pub export_data: bool,
+    /// Paths relative to the temp root that this filter reads, e.g. `RP/textures`
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// JSON Schema for the settings of this filter
+    #[serde(
+        default,
+        rename = "settingsSchema",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub settings_schema: Option<Value>,
+    /// SPDX license identifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    pub filters: Vec<RemoteFilterEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RemoteFilterEntry {
+    pub arguments: Option<Vec<String>>,
+    #[serde(rename = "when", skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    #[serde(flatten)]
+    pub filter: LocalFilter,
+}
+
+impl RemoteFilterConfig {
+    pub fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
+        for entry in &self.filters {
             if let Some(expression) = &entry.expression {
                 let name = &context.name;
                 let eval = Eval::new(name, &context.filter_dir, None);
@@ -38,35 +79,20 @@ impl Filter for RemoteFilter {
             if let Some(arguments) = entry.arguments.to_owned() {
                 run_args.extend(arguments);
             };
+            entry.filter.check_sandbox(context);
             entry.filter.run(context, temp, &run_args)?;
         }
         Ok(())
     }
-    fn install_dependencies(&self, context: &FilterContext) -> Result<()> {
-        let config = context.remote_config.as_ref().unwrap();
-        for entry in &config.filters {
+
+    pub fn install_dependencies(&self, context: &FilterContext) -> Result<()> {
+        for entry in &self.filters {
             entry.filter.install_dependencies(context)?;
         }
         Ok(())
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct RemoteFilterConfig {
-    #[serde(default, rename = "exportData")]
-    pub export_data: bool,
-    pub filters: Vec<RemoteFilterEntry>,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct RemoteFilterEntry {
-    pub arguments: Option<Vec<String>>,
-    #[serde(rename = "when", skip_serializing_if = "Option::is_none")]
-    pub expression: Option<String>,
-    #[serde(flatten)]
-    pub filter: LocalFilter,
-}
-
 impl RemoteFilter {
     /// Parse RemoteFilter from string argument
     pub fn parse(arg: &str) -> Result<(String, Self)> {
@@ -96,21 +122,25 @@ impl RemoteFilter {
         let url = &self.url;
         let version = &self.version;
         let filter_dir = get_filter_cache_dir(name, self)?;
+        let repo_dir = get_repo_cache_dir()?.join(url);
+        // Other projects may be installing from the same repository
+        let mut repo_lock = Session::lock_shared(&repo_dir)?;
 
         if force {
             rimraf(&filter_dir)?;
         }
         let https_url = format!("https://{url}");
         if is_dir_empty(&filter_dir)? {
-            let repo_dir = get_repo_cache_dir()?.join(url);
             if is_dir_empty(&repo_dir)? {
                 empty_dir(&repo_dir)?;
                 debug!("Cloning repo: {https_url}");
-                Subprocess::new("git")
+                let progress = Progress::spinner(format!("Cloning {https_url}"));
+                let result = Subprocess::new("git")
                     .args(["clone", &https_url, "."])
                     .current_dir(&repo_dir)
-                    .run_silent()
-                    .with_context(|| format!("Failed to clone `{https_url}`"))?;
+                    .run_silent();
+                progress.finish();
+                result.with_context(|| format!("Failed to clone `{https_url}`"))?;
             } else {
                 debug!("Fetching tags...");
                 Subprocess::new("git")
@@ -130,12 +160,10 @@ impl RemoteFilter {
                 .with_context(|| format!("Failed to checkout `{git_ref}`"))?;
             copy_dir(repo_dir.join(name), &filter_dir)?;
         }
+        repo_lock.unlock()?;
         if let Some(data_path) = data_path {
-            let filter_data = filter_dir.join("data");
-            let target_path = data_path.join(name);
-            if filter_data.is_dir() && !target_path.exists() {
-                info!("Copying filter data to <b>{}</>", target_path.display());
-                copy_dir(filter_data, target_path)?;
+            if !data_path.join(name).exists() {
+                self.copy_data(name, data_path, false)?;
             }
         }
 
@@ -145,6 +173,34 @@ impl RemoteFilter {
         filter.install_dependencies(&context)
     }
 
+    /// Copies the default data shipped in the `data` directory of the filter to the data path,
+    /// under the name of the filter.
+    pub fn copy_data(&self, name: &str, data_path: &Path, force: bool) -> Result<()> {
+        let filter_data = get_filter_cache_dir(name, self)?.join("data");
+        if !filter_data.is_dir() {
+            return Ok(());
+        }
+        let target_path = data_path.join(name);
+        if target_path.exists() {
+            if !force {
+                info!(
+                    "Filter data already exists at <b>{}</>, skipping. Use --force to overwrite it",
+                    target_path.display()
+                );
+                return Ok(());
+            }
+            let backup = backup_path(&target_path)?;
+            info!(
+                "Overwriting filter data at <b>{}</>, the old data was moved to <b>{}</>",
+                target_path.display(),
+                backup.display()
+            );
+        } else {
+            info!("Copying filter data to <b>{}</>", target_path.display());
+        }
+        copy_dir(filter_data, target_path)
+    }
+
     pub fn update(&mut self, name: &str, data_path: Option<&Path>, force: bool) -> Result<()> {
         let current_version = self.version.to_owned();
         let latest_version = Resolver::resolve_version(name, &self.url, Some("latest".to_owned()))?;
@@ -1,4 +1,4 @@
-use super::{Filter, FilterContext, Subprocess};
+use super::{get_working_dir, Filter, FilterContext, Subprocess};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -6,17 +6,16 @@ use std::path::Path;
 #[derive(Serialize, Deserialize)]
 pub struct FilterShell {
     pub command: String,
+    /// Directory to run in, relative to the project. Defaults to the temp directory.
+    #[serde(rename = "workingDir", skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
 }
 
 impl Filter for FilterShell {
     fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
-        let shell = if cfg!(windows) { "powershell" } else { "sh" };
-        Subprocess::new(shell)
-            .arg("-c")
-            .arg(&self.command)
-            .args(run_args)
-            .current_dir(temp)
-            .setup_env(&context.filter_dir)
+        Subprocess::shell(&self.command, run_args)
+            .current_dir(get_working_dir(self.working_dir.as_deref(), temp)?)
+            .setup_env(context)
             .run()?;
         Ok(())
     }
@@ -0,0 +1,155 @@
+use super::{FilterContext, RemoteFilterConfig};
+use crate::fs::{copy_dir, empty_dir, read_json};
+use crate::{error, info, log, success};
+use anyhow::{Context, Result};
+use dunce::canonicalize;
+use serde_json::Value;
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+const TEST_DIRS: [&str; 3] = ["BP", "RP", "data"];
+
+/// Runs every test case in the `tests` directory of a filter. Each case contains the packs to run
+/// the filter on in `input`, the expected result in `output`, and optionally the filter settings
+/// in `settings.json`.
+///
+/// Returns the names of the failed cases. With `update`, the expected output is overwritten with
+/// the actual output instead.
+pub fn test_filter(filter_dir: &Path, update: bool) -> Result<Vec<String>> {
+    let filter_dir = canonicalize(filter_dir)?;
+    let config: RemoteFilterConfig = read_json(filter_dir.join("filter.json"))?;
+    let name = filter_dir
+        .file_name()
+        .context("Failed to get filter name")?
+        .to_string_lossy()
+        .to_string();
+    let context = FilterContext {
+        name: name.to_owned(),
+        filter_dir: filter_dir.to_owned(),
+        remote_config: None,
+        sandbox: false,
+    };
+    config.install_dependencies(&context)?;
+
+    let tests_dir = filter_dir.join("tests");
+    let mut cases: Vec<_> = fs::read_dir(&tests_dir)
+        .with_context(|| format!("Failed to read {}", tests_dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    cases.sort();
+
+    let mut failed = vec![];
+    for case in cases {
+        info!("Running test <b>{case}</>");
+        let case_dir = tests_dir.join(&case);
+        let temp = env::temp_dir()
+            .join("rgl-filter-test")
+            .join(&name)
+            .join(&case);
+        let result = run_case(&config, &context, &case_dir, &temp).and_then(|_| {
+            let output = case_dir.join("output");
+            if update {
+                for dir in TEST_DIRS {
+                    empty_dir(output.join(dir))?;
+                    copy_dir(temp.join(dir), output.join(dir))?;
+                }
+                return Ok(vec![]);
+            }
+            compare_output(&output, &temp)
+        });
+        match result {
+            Ok(differences) if differences.is_empty() => success!("{case}"),
+            Ok(differences) => {
+                error!("{case}");
+                differences.iter().for_each(|d| log!("<red>[+]</> {d}"));
+                failed.push(case);
+            }
+            Err(e) => {
+                error!("{case}");
+                e.chain().for_each(|e| log!("<red>[+]</> {e}"));
+                failed.push(case);
+            }
+        }
+    }
+    Ok(failed)
+}
+
+fn run_case(
+    config: &RemoteFilterConfig,
+    context: &FilterContext,
+    case_dir: &Path,
+    temp: &Path,
+) -> Result<()> {
+    empty_dir(temp)?;
+    for dir in TEST_DIRS {
+        let input = case_dir.join("input").join(dir);
+        if input.is_dir() {
+            copy_dir(input, temp.join(dir))?;
+        } else {
+            fs::create_dir_all(temp.join(dir))?;
+        }
+    }
+    let settings_path = case_dir.join("settings.json");
+    let run_args = match settings_path.is_file() {
+        true => vec![serde_json::to_string(&read_json::<Value>(settings_path)?)?],
+        false => vec![],
+    };
+    config.run(context, temp, &run_args)
+}
+
+/// Lists the differences between the expected and actual output. The data directory is only
+/// compared if the expected output includes it.
+fn compare_output(expected: &Path, actual: &Path) -> Result<Vec<String>> {
+    let mut differences = vec![];
+    for dir in TEST_DIRS {
+        let expected_dir = expected.join(dir);
+        if dir == "data" && !expected_dir.is_dir() {
+            continue;
+        }
+        let actual_dir = actual.join(dir);
+        let expected_files = list_files(&expected_dir);
+        let actual_files = list_files(&actual_dir);
+        for file in expected_files.union(&actual_files) {
+            let path = Path::new(dir).join(file);
+            match (expected_files.contains(file), actual_files.contains(file)) {
+                (true, false) => differences.push(format!("Missing {}", path.display())),
+                (false, true) => differences.push(format!("Unexpected {}", path.display())),
+                _ => {
+                    if !files_equal(&expected_dir.join(file), &actual_dir.join(file))? {
+                        differences.push(format!("Different {}", path.display()));
+                    }
+                }
+            }
+        }
+    }
+    Ok(differences)
+}
+
+fn list_files(dir: &Path) -> BTreeSet<PathBuf> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| Some(entry.path().strip_prefix(dir).ok()?.to_owned()))
+        .collect()
+}
+
+/// JSON files are compared by value, so formatting differences are ignored
+fn files_equal(a: &Path, b: &Path) -> Result<bool> {
+    let (a_data, b_data) = (fs::read(a)?, fs::read(b)?);
+    if a_data == b_data {
+        return Ok(true);
+    }
+    if a.extension().is_some_and(|ext| ext == "json") {
+        if let (Ok(a), Ok(b)) = (read_json::<Value>(a), read_json::<Value>(b)) {
+            return Ok(a == b);
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,155 @@
+use super::{collect_texture_paths, Filter, FilterContext, TEXTURE_FORMATS};
+use crate::debug;
+use crate::fs::{read_json, write_json};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+use walkdir::WalkDir;
+
+/// Suffixes of the texture set layers, which are referenced by `.texture_set.json` files instead
+/// of the atlases
+const LAYER_SUFFIXES: [&str; 4] = ["_mer", "_mers", "_normal", "_heightmap"];
+
+/// Adds the textures in `RP/textures/blocks` and `RP/textures/items` to `terrain_texture.json` and
+/// `item_texture.json`, configured with the filter settings.
+#[derive(Serialize, Deserialize)]
+pub struct FilterTextures {}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct TextureSettings {
+    /// Register the textures in `textures/blocks`
+    blocks: bool,
+    /// Register the textures in `textures/items`
+    items: bool,
+    /// Prepended to the short name of each texture, e.g. `namespace_`
+    prefix: String,
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self {
+            blocks: true,
+            items: true,
+            prefix: String::new(),
+        }
+    }
+}
+
+struct Atlas {
+    file_name: &'static str,
+    texture_name: &'static str,
+    dir: &'static str,
+}
+
+const TERRAIN_ATLAS: Atlas = Atlas {
+    file_name: "terrain_texture.json",
+    texture_name: "atlas.terrain",
+    dir: "blocks",
+};
+
+const ITEM_ATLAS: Atlas = Atlas {
+    file_name: "item_texture.json",
+    texture_name: "atlas.items",
+    dir: "items",
+};
+
+impl Filter for FilterTextures {
+    fn run(&self, _context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
+        let settings: TextureSettings = match run_args.first().filter(|arg| arg.starts_with('{')) {
+            Some(settings) => serde_json::from_str(settings).context("Invalid texture settings")?,
+            None => TextureSettings::default(),
+        };
+        let textures_dir = temp.join("RP").join("textures");
+        if settings.blocks {
+            register_textures(&textures_dir, &TERRAIN_ATLAS, &settings.prefix)?;
+        }
+        if settings.items {
+            register_textures(&textures_dir, &ITEM_ATLAS, &settings.prefix)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds an entry for each texture of the atlas directory that no entry references yet. Existing
+/// entries are left as they are, so they can still be written by hand.
+fn register_textures(textures_dir: &Path, atlas: &Atlas, prefix: &str) -> Result<()> {
+    let dir = textures_dir.join(atlas.dir);
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let atlas_path = textures_dir.join(atlas.file_name);
+    let mut value = match atlas_path.is_file() {
+        true => read_json::<Value>(&atlas_path)?,
+        false => json!({ "texture_name": atlas.texture_name }),
+    };
+    let Some(object) = value.as_object_mut() else {
+        return Ok(());
+    };
+    let texture_data = object
+        .entry("texture_data")
+        .or_insert_with(|| Value::Object(Map::new()));
+    let Some(texture_data) = texture_data.as_object_mut() else {
+        return Ok(());
+    };
+    let mut referenced = vec![];
+    for entry in texture_data.values() {
+        collect_texture_paths(&entry["textures"], &mut referenced);
+    }
+    let referenced = referenced
+        .into_iter()
+        .map(str::to_owned)
+        .collect::<HashSet<_>>();
+
+    // Sorted by name, so the generated entries don't depend on the order of the filesystem
+    let mut found = BTreeMap::new();
+    for entry in WalkDir::new(&dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        let path = entry.path();
+        let is_texture = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| TEXTURE_FORMATS.contains(&ext.to_lowercase().as_str()));
+        if !entry.file_type().is_file() || !is_texture {
+            continue;
+        }
+        let relative = path.strip_prefix(&dir)?.with_extension("");
+        let parts = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        let name = format!("{prefix}{}", parts.join("_"));
+        if LAYER_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+            continue;
+        }
+        let texture = format!("textures/{}/{}", atlas.dir, parts.join("/"));
+        found.insert(name, texture);
+    }
+
+    let mut added = 0;
+    for (name, texture) in found {
+        if referenced.contains(&texture) {
+            continue;
+        }
+        if texture_data.contains_key(&name) {
+            debug!(
+                "Skipped <b>{texture}</>, <b>{name}</> is already used in {}",
+                atlas.file_name
+            );
+            continue;
+        }
+        texture_data.insert(name, json!({ "textures": texture }));
+        added += 1;
+    }
+    if added > 0 {
+        debug!("Added {added} texture(s) to {}", atlas.file_name);
+        write_json(&atlas_path, &value)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use super::{Filter, FilterContext, Plugin};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize)]
+pub struct FilterWasm {
+    /// Path of the plugin file
+    pub path: String,
+    /// Name of the filter registered by the plugin
+    pub filter: String,
+}
+
+impl Filter for FilterWasm {
+    fn run(&self, context: &FilterContext, temp: &Path, run_args: &[String]) -> Result<()> {
+        let plugin = Plugin::load(&context.filter_dir.join(&self.path))?;
+        plugin.run_filter(&self.filter, temp, run_args)
+    }
+}
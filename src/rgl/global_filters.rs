@@ -1,6 +1,6 @@
-use super::{get_global_filters_path, RemoteFilter};
+use super::{get_global_filters_path, ErrorCode, RemoteFilter};
 use crate::fs::{read_json, write_json};
-use anyhow::{Context, Result};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::{btree_map::Iter, BTreeMap};
 
@@ -26,7 +26,10 @@ impl GlobalFilters {
         let filter = self
             .filters
             .get(name)
-            .with_context(|| format!("Filter <filter>{name}</> is not installed"))?
+            .ok_or_else(|| {
+                ErrorCode::FilterNotInstalled
+                    .error(format!("Filter <filter>{name}</> is not installed"))
+            })?
             .to_owned();
         Ok(filter)
     }
@@ -0,0 +1,367 @@
+use crate::fs::write_file;
+use crate::{info, warn};
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Experiment names as shown in the world settings and their key in `level.dat`
+const EXPERIMENTS: [(&str, &str); 8] = [
+    ("beta_apis", "gametest"),
+    ("holiday_creator_features", "data_driven_items"),
+    ("custom_biomes", "data_driven_biomes"),
+    ("upcoming_creator_features", "upcoming_creator_features"),
+    ("creator_cameras", "experimental_creator_camera"),
+    ("molang_features", "experimental_molang_features"),
+    ("villager_trade_rebalancing", "villager_trades_rebalance"),
+    ("jigsaw_structures", "jigsaw_structures"),
+];
+
+/// Key of an experiment in `level.dat`, given its name in the world settings or its key
+pub fn get_experiment_key(name: &str) -> Option<&'static str> {
+    EXPERIMENTS
+        .iter()
+        .find(|(alias, key)| *alias == name || *key == name)
+        .map(|(_, key)| *key)
+}
+
+/// Enables experiments in the `level.dat` of a world. The game overwrites the file when it
+/// closes the world, so this only works while the world is not open.
+pub fn enable_experiments(world_dir: &Path, experiments: &[String]) -> Result<()> {
+    if experiments.is_empty() {
+        return Ok(());
+    }
+    let path = world_dir.join("level.dat");
+    let mut level = LevelDat::read(&path).with_context(|| {
+        format!(
+            "Failed to read level.dat\n\
+             <yellow> >></> Path: {}",
+            path.display()
+        )
+    })?;
+    let Tag::Compound(root) = &mut level.root else {
+        bail!("Invalid level.dat, the root tag is not a compound");
+    };
+    let Tag::Compound(toggles) = root
+        .entry(b"experiments".to_vec())
+        .or_insert_with(|| Tag::Compound(IndexMap::new()))
+    else {
+        bail!("Invalid level.dat, `experiments` is not a compound");
+    };
+
+    let mut enabled = vec![];
+    for name in experiments {
+        let key = match get_experiment_key(name) {
+            Some(key) => key,
+            None => {
+                warn!("Unknown experiment <b>{name}</>, enabling it as is");
+                name.as_str()
+            }
+        };
+        if !matches!(toggles.get(key.as_bytes()), Some(Tag::Byte(1))) {
+            toggles.insert(key.as_bytes().to_vec(), Tag::Byte(1));
+            enabled.push(name.as_str());
+        }
+    }
+    if enabled.is_empty() {
+        return Ok(());
+    }
+    toggles.insert(b"experiments_ever_used".to_vec(), Tag::Byte(1));
+    toggles.insert(b"saved_with_toggled_experiments".to_vec(), Tag::Byte(1));
+    level.write(&path)?;
+    info!("Enabled experiments <b>{}</> in world", enabled.join(", "));
+    Ok(())
+}
+
+/// Layers of a flat world, the same as the default flat world preset
+const FLAT_WORLD_LAYERS: &str = r#"{"biome_id":1,"block_layers":[{"block_name":"minecraft:bedrock","count":1},{"block_name":"minecraft:dirt","count":2},{"block_name":"minecraft:grass_block","count":1}],"encoding_version":6,"structure_options":null,"world_version":"version.post_1_18"}"#;
+
+/// Writes the `level.dat` of a new test world. Worlds copied from a template keep their
+/// settings, other worlds are creative with cheats on, and without daylight, weather or mobs.
+pub fn write_test_level_dat(world_dir: &Path, name: &str, flat: bool) -> Result<()> {
+    let path = world_dir.join("level.dat");
+    let from_template = path.is_file();
+    let mut level = match from_template {
+        true => LevelDat::read(&path)?,
+        false => LevelDat {
+            storage_version: 10,
+            root_name: vec![],
+            root: Tag::Compound(IndexMap::new()),
+        },
+    };
+    let Tag::Compound(root) = &mut level.root else {
+        bail!("Invalid level.dat, the root tag is not a compound");
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    root.insert(b"LevelName".to_vec(), Tag::String(name.as_bytes().to_vec()));
+    root.insert(b"LastPlayed".to_vec(), Tag::Long(now));
+    if !from_template {
+        let defaults = [
+            ("StorageVersion", Tag::Int(10)),
+            ("GameType", Tag::Int(1)),
+            ("Difficulty", Tag::Int(0)),
+            ("Generator", Tag::Int(if flat { 2 } else { 1 })),
+            ("RandomSeed", Tag::Long(now)),
+            ("commandsEnabled", Tag::Byte(1)),
+            ("hasBeenLoadedInCreative", Tag::Byte(1)),
+            ("dodaylightcycle", Tag::Byte(0)),
+            ("doweathercycle", Tag::Byte(0)),
+            ("domobspawning", Tag::Byte(0)),
+            ("showcoordinates", Tag::Byte(1)),
+        ];
+        for (key, tag) in defaults {
+            root.insert(key.as_bytes().to_vec(), tag);
+        }
+        if flat {
+            root.insert(
+                b"FlatWorldLayers".to_vec(),
+                Tag::String(FLAT_WORLD_LAYERS.as_bytes().to_vec()),
+            );
+        }
+    }
+    level.write(&path)
+}
+
+/// Checks that the `level.dat` of a world can be read and has a compound root tag
+pub fn check_level_dat(world_dir: &Path) -> Result<()> {
+    let path = world_dir.join("level.dat");
+    let level = LevelDat::read(&path).with_context(|| {
+        format!(
+            "Failed to read level.dat\n\
+             <yellow> >></> Path: {}",
+            path.display()
+        )
+    })?;
+    if !matches!(level.root, Tag::Compound(_)) {
+        bail!("Invalid level.dat, the root tag is not a compound");
+    }
+    Ok(())
+}
+
+/// Time the world was last played as a Unix timestamp, if its `level.dat` has it
+pub fn get_last_played(world_dir: &Path) -> Option<i64> {
+    let level = LevelDat::read(&world_dir.join("level.dat")).ok()?;
+    let Tag::Compound(root) = level.root else {
+        return None;
+    };
+    match root.get(b"LastPlayed".as_slice()) {
+        Some(Tag::Long(time)) => Some(*time),
+        _ => None,
+    }
+}
+
+/// NBT tag in the little-endian format used by Bedrock. Strings are kept as bytes, as the game
+/// doesn't always write valid UTF-8.
+enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(Vec<u8>),
+    List(u8, Vec<Tag>),
+    Compound(IndexMap<Vec<u8>, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(..) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+}
+
+/// `level.dat` is an 8-byte header with the storage version and the data length, followed by a
+/// single named root tag.
+struct LevelDat {
+    storage_version: i32,
+    root_name: Vec<u8>,
+    root: Tag,
+}
+
+impl LevelDat {
+    fn read(path: &Path) -> Result<Self> {
+        let data = fs::read(path)?;
+        let mut reader = Reader {
+            data: &data,
+            pos: 0,
+        };
+        let storage_version = i32::from_le_bytes(reader.take()?);
+        let _length = i32::from_le_bytes(reader.take::<4>()?);
+        let id = reader.take::<1>()?[0];
+        let root_name = reader.string()?;
+        let root = reader.tag(id)?;
+        Ok(Self {
+            storage_version,
+            root_name,
+            root,
+        })
+    }
+
+    /// Writes the file, keeping the previous one as `level.dat_old` like the game does
+    fn write(&self, path: &Path) -> Result<()> {
+        let mut body = vec![self.root.id()];
+        write_string(&mut body, &self.root_name);
+        write_tag(&mut body, &self.root);
+        let mut data = self.storage_version.to_le_bytes().to_vec();
+        data.extend((body.len() as i32).to_le_bytes());
+        data.extend(body);
+        if path.is_file() {
+            let old = path.with_file_name("level.dat_old");
+            fs::copy(path, &old).with_context(|| {
+                format!(
+                    "Failed to back up level.dat\n\
+                     <yellow> >></> Path: {}",
+                    old.display()
+                )
+            })?;
+        }
+        write_file(path, data)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .context("Unexpected end of file")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.bytes(N)?.try_into()?)
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let len = i32::from_le_bytes(self.take()?);
+        usize::try_from(len).context("Invalid length")
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>> {
+        let len = u16::from_le_bytes(self.take()?) as usize;
+        Ok(self.bytes(len)?.to_vec())
+    }
+
+    fn tag(&mut self, id: u8) -> Result<Tag> {
+        let tag = match id {
+            1 => Tag::Byte(i8::from_le_bytes(self.take()?)),
+            2 => Tag::Short(i16::from_le_bytes(self.take()?)),
+            3 => Tag::Int(i32::from_le_bytes(self.take()?)),
+            4 => Tag::Long(i64::from_le_bytes(self.take()?)),
+            5 => Tag::Float(f32::from_le_bytes(self.take()?)),
+            6 => Tag::Double(f64::from_le_bytes(self.take()?)),
+            7 => {
+                let len = self.len()?;
+                Tag::ByteArray(self.bytes(len)?.to_vec())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_id = self.take::<1>()?[0];
+                let len = self.len()?;
+                let items = (0..len).map(|_| self.tag(item_id)).collect::<Result<_>>()?;
+                Tag::List(item_id, items)
+            }
+            10 => {
+                let mut entries = IndexMap::new();
+                loop {
+                    let id = self.take::<1>()?[0];
+                    if id == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.insert(name, self.tag(id)?);
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let len = self.len()?;
+                let items = (0..len)
+                    .map(|_| Ok(i32::from_le_bytes(self.take()?)))
+                    .collect::<Result<_>>()?;
+                Tag::IntArray(items)
+            }
+            12 => {
+                let len = self.len()?;
+                let items = (0..len)
+                    .map(|_| Ok(i64::from_le_bytes(self.take()?)))
+                    .collect::<Result<_>>()?;
+                Tag::LongArray(items)
+            }
+            id => bail!("Unknown tag type {id}"),
+        };
+        Ok(tag)
+    }
+}
+
+fn write_string(data: &mut Vec<u8>, value: &[u8]) {
+    data.extend((value.len() as u16).to_le_bytes());
+    data.extend(value);
+}
+
+fn write_tag(data: &mut Vec<u8>, tag: &Tag) {
+    match tag {
+        Tag::Byte(value) => data.extend(value.to_le_bytes()),
+        Tag::Short(value) => data.extend(value.to_le_bytes()),
+        Tag::Int(value) => data.extend(value.to_le_bytes()),
+        Tag::Long(value) => data.extend(value.to_le_bytes()),
+        Tag::Float(value) => data.extend(value.to_le_bytes()),
+        Tag::Double(value) => data.extend(value.to_le_bytes()),
+        Tag::ByteArray(bytes) => {
+            data.extend((bytes.len() as i32).to_le_bytes());
+            data.extend(bytes);
+        }
+        Tag::String(value) => write_string(data, value),
+        Tag::List(item_id, items) => {
+            data.push(*item_id);
+            data.extend((items.len() as i32).to_le_bytes());
+            for item in items {
+                write_tag(data, item);
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, tag) in entries {
+                data.push(tag.id());
+                write_string(data, name);
+                write_tag(data, tag);
+            }
+            data.push(0);
+        }
+        Tag::IntArray(items) => {
+            data.extend((items.len() as i32).to_le_bytes());
+            for item in items {
+                data.extend(item.to_le_bytes());
+            }
+        }
+        Tag::LongArray(items) => {
+            data.extend((items.len() as i32).to_le_bytes());
+            for item in items {
+                data.extend(item.to_le_bytes());
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use super::{
+    check_export_target, get_image_size, mark_export_target, Config, Diagnostics, Export,
+    ExportPaths,
+};
+use crate::fs::{copy_dir, read_json, rimraf, write_json};
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Size of the pack icon the Marketplace expects
+const PACK_ICON_SIZE: u32 = 256;
+
+/// Copies the packs exported by a profile into the `Content` folder of a Marketplace submission,
+/// generating their `contents.json`. Problems a submission gets rejected for are reported in the
+/// returned diagnostics.
+pub fn package_marketplace(
+    config: &Config,
+    profile_name: &str,
+    output: &Path,
+) -> Result<Diagnostics> {
+    let profile = config.get_profile(profile_name)?;
+    if matches!(profile.export, Export::None(_)) {
+        bail!("Packaging for the Marketplace requires a profile that exports the packs");
+    }
+    let (target_bp, target_rp) = profile.export.get_paths(config, profile_name)?;
+    check_export_target(output)?;
+    rimraf(output)?;
+
+    let mut diagnostics = Diagnostics::default();
+    let packs = [
+        (config.get_behavior_pack(), target_bp, "behavior_packs"),
+        (config.get_resource_pack(), target_rp, "resource_packs"),
+    ];
+    for (pack, target, packs_dir) in packs {
+        if pack.is_none() {
+            continue;
+        }
+        if !target.is_dir() {
+            bail!(
+                "Exported pack not found, run the profile first\n\
+                 <yellow> >></> Path: {}",
+                target.display()
+            );
+        }
+        let dir_name = target.file_name().unwrap_or_default();
+        let staged = output.join("Content").join(packs_dir).join(dir_name);
+        copy_dir(&target, &staged)?;
+        check_pack_icon(&staged, &mut diagnostics);
+        check_texts(&staged, &mut diagnostics);
+        write_contents_json(&staged)?;
+    }
+    mark_export_target(output)?;
+    Ok(diagnostics)
+}
+
+/// Writes the `contents.json` listing every file of the pack
+pub fn write_contents_json(pack: &Path) -> Result<()> {
+    let mut content = vec![];
+    for entry in WalkDir::new(pack).sort_by_file_name().min_depth(1) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()
+            .strip_prefix(pack)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path != "contents.json" {
+            content.push(json!({ "path": path }));
+        }
+    }
+    write_json(pack.join("contents.json"), &json!({ "content": content }))
+}
+
+fn check_pack_icon(pack: &Path, diagnostics: &mut Diagnostics) {
+    let path = pack.join("pack_icon.png");
+    if !path.is_file() {
+        diagnostics.error(&path, "Pack icon is missing");
+        return;
+    }
+    match get_image_size(&path) {
+        None => diagnostics.error(&path, "Pack icon is not a valid PNG"),
+        Some((width, height)) if width != PACK_ICON_SIZE || height != PACK_ICON_SIZE => diagnostics
+            .error(
+                &path,
+                format!(
+                    "Pack icon is {width}x{height}, the Marketplace expects \
+                     {PACK_ICON_SIZE}x{PACK_ICON_SIZE}"
+                ),
+            ),
+        Some(_) => {}
+    }
+}
+
+/// Checks that every language has a translation of each key of `en_US.lang`, including the name
+/// and description of the pack when the manifest refers to them.
+fn check_texts(pack: &Path, diagnostics: &mut Diagnostics) {
+    let texts = pack.join("texts");
+    let languages_path = texts.join("languages.json");
+    if !languages_path.is_file() {
+        diagnostics.error(&languages_path, "Missing the list of languages");
+        return;
+    }
+    let Some(languages) = diagnostics.read_json(&languages_path) else {
+        return;
+    };
+    let languages = languages
+        .as_array()
+        .map(|languages| {
+            languages
+                .iter()
+                .filter_map(|language| language.as_str())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if !languages.contains(&"en_US") {
+        diagnostics.error(&languages_path, "en_US is not one of the languages");
+    }
+
+    let en_us_path = texts.join("en_US.lang");
+    let Some(en_us) = read_lang_keys(&en_us_path) else {
+        diagnostics.error(&en_us_path, "Missing the en_US translations");
+        return;
+    };
+    let manifest = read_json::<Value>(pack.join("manifest.json")).unwrap_or_default();
+    for field in ["name", "description"] {
+        let key = manifest["header"][field].as_str().unwrap_or_default();
+        if key.starts_with("pack.") && !en_us.contains(key) {
+            diagnostics.error(&en_us_path, format!("Missing the pack {field} <b>{key}</>"));
+        }
+    }
+
+    for language in languages.into_iter().filter(|l| *l != "en_US") {
+        let path = texts.join(format!("{language}.lang"));
+        let Some(keys) = read_lang_keys(&path) else {
+            diagnostics.error(&path, format!("Missing the {language} translations"));
+            continue;
+        };
+        let missing = en_us.difference(&keys).collect::<Vec<_>>();
+        if let Some(first) = missing.first() {
+            diagnostics.error(
+                &path,
+                format!(
+                    "Missing {} translation(s) of en_US, e.g. <b>{first}</>",
+                    missing.len()
+                ),
+            );
+        }
+    }
+}
+
+/// Keys of a `.lang` file
+fn read_lang_keys(path: &Path) -> Option<BTreeSet<String>> {
+    let data = fs::read(path).ok()?;
+    let keys = String::from_utf8_lossy(&data)
+        .lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .filter(|line| !line.starts_with("##"))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, _)| key.trim().to_owned())
+        .collect();
+    Some(keys)
+}
+
+/// Default location of the Marketplace submission
+pub fn get_marketplace_path(config: &Config) -> PathBuf {
+    Path::new("build").join(format!("{}_marketplace", config.get_name()))
+}
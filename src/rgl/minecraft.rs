@@ -1,12 +1,20 @@
-use super::UserConfig;
-use anyhow::{bail, Context, Result};
+use super::{get_last_played, ErrorCode, UserConfig};
+use crate::warn;
+use anyhow::{anyhow, bail, Context, Result};
+use dialoguer::{theme::ColorfulTheme, Select};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use strum::{Display, EnumIter};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Display, EnumIter, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 pub enum MinecraftBuild {
     Standard,
     Preview,
@@ -15,6 +23,31 @@ pub enum MinecraftBuild {
     Education,
 }
 
+/// One or more Minecraft builds to export to. The first build is the primary target, the rest
+/// receive a copy of the exported packs.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MinecraftBuilds {
+    Single(MinecraftBuild),
+    Multiple(Vec<MinecraftBuild>),
+}
+
+impl MinecraftBuilds {
+    pub fn primary(&self) -> Option<&MinecraftBuild> {
+        match self {
+            MinecraftBuilds::Single(build) => Some(build),
+            MinecraftBuilds::Multiple(builds) => builds.first(),
+        }
+    }
+
+    pub fn mirrors(&self) -> &[MinecraftBuild] {
+        match self {
+            MinecraftBuilds::Single(_) => &[],
+            MinecraftBuilds::Multiple(builds) => builds.get(1..).unwrap_or_default(),
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn mojang_dir() -> Result<PathBuf> {
     let home = env::var("HOME")?;
@@ -110,28 +143,248 @@ pub fn find_mojang_dir(build: Option<&MinecraftBuild>) -> Result<PathBuf> {
     }
 }
 
-pub fn find_world_dir(build: Option<&MinecraftBuild>, world_name: &str) -> Result<PathBuf> {
+/// Worlds picked by the user, by the name they were looked up with
+static PICKED_WORLDS: LazyLock<Mutex<HashMap<String, PathBuf>>> = LazyLock::new(Default::default);
+
+/// Worlds whose name couldn't be read, to only warn about them once
+static WARNED_WORLDS: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(Default::default);
+
+/// World in the minecraftWorlds directory
+pub struct WorldEntry {
+    /// Name from `levelname.txt`, or the folder name if it can't be read
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Lists the worlds of a Minecraft build. Worlds that can't be read are skipped with a warning,
+/// imported worlds often lack a valid `levelname.txt`.
+pub fn list_worlds(build: Option<&MinecraftBuild>) -> Result<Vec<WorldEntry>> {
     let mojang_dir = find_mojang_dir(build)?;
     if !mojang_dir.exists() {
         bail!("Failed to find com.mojang directory")
     }
 
-    let mut worlds = HashMap::<String, PathBuf>::new();
-    for entry in mojang_dir.join("minecraftWorlds").read_dir()? {
-        let entry = entry?;
-        if !entry.file_type()?.is_dir() {
+    let worlds_dir = mojang_dir.join("minecraftWorlds");
+    let entries = worlds_dir.read_dir().with_context(|| {
+        format!(
+            "Failed to read worlds directory\n\
+             <yellow> >></> Path: {}",
+            worlds_dir.display()
+        )
+    })?;
+    let mut worlds = vec![];
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                warn!("Skipping unreadable world: {e}");
+                continue;
+            }
+        };
+        if !path.is_dir() {
             continue;
         }
-        let path = entry.path();
-        let name = std::fs::read_to_string(path.join("levelname.txt"))?;
-        if worlds.contains_key(&name) && name == world_name {
-            bail!("Found more than one world named <yellow>{name}</>");
+        let folder_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let name = match fs::read(path.join("levelname.txt")).map(String::from_utf8) {
+            Ok(Ok(name)) if !name.trim().is_empty() => name,
+            result => {
+                // The worlds are listed several times per run
+                if WARNED_WORLDS.lock().unwrap().insert(path.to_owned()) {
+                    let problem = match result {
+                        Ok(_) => "an invalid",
+                        Err(_) => "no",
+                    };
+                    warn!("World <yellow>{folder_name}</> has {problem} levelname.txt, using the folder name");
+                }
+                folder_name
+            }
+        };
+        worlds.push(WorldEntry { name, path });
+    }
+    Ok(worlds)
+}
+
+/// Finds a world by its name. If the name is ambiguous or no world has it, the error lists the
+/// worlds it could be. Worlds picked with [`pick_world_dir`] are used as is.
+pub fn find_world_dir(build: Option<&MinecraftBuild>, world_name: &str) -> Result<PathBuf> {
+    if let Some(path) = PICKED_WORLDS.lock().unwrap().get(world_name) {
+        return Ok(path.to_owned());
+    }
+    let worlds = list_worlds(build)?;
+    let path = match lookup_world(&worlds, world_name) {
+        WorldLookup::Found(path) => path.to_owned(),
+        lookup => {
+            let mut message = lookup.message(world_name);
+            for world in lookup.candidates() {
+                let folder = world.path.file_name().unwrap_or_default().to_string_lossy();
+                message += &format!("\n<yellow> >></> Candidate: {} ({folder})", world.name);
+            }
+            return Err(match lookup {
+                WorldLookup::NotFound(_) => ErrorCode::WorldNotFound.error(message),
+                _ => anyhow!(message),
+            });
+        }
+    };
+    check_world_dir(&path)?;
+    Ok(path)
+}
+
+/// Finds a world by its name like [`find_world_dir`], but prompts for the world when the name is
+/// ambiguous or no world has it and running in a terminal. The picked world is used by later
+/// lookups of the name, so only commands call this before anything is built.
+pub fn pick_world_dir(build: Option<&MinecraftBuild>, world_name: &str) -> Result<PathBuf> {
+    if let Some(path) = PICKED_WORLDS.lock().unwrap().get(world_name) {
+        return Ok(path.to_owned());
+    }
+    let worlds = list_worlds(build)?;
+    let lookup = lookup_world(&worlds, world_name);
+    if matches!(lookup, WorldLookup::Found(_))
+        || lookup.candidates().is_empty()
+        || !io::stdin().is_terminal()
+    {
+        return find_world_dir(build, world_name);
+    }
+    warn!("{}", lookup.message(world_name));
+    let path = pick_world(lookup.candidates().to_vec())?;
+    check_world_dir(&path)?;
+    PICKED_WORLDS
+        .lock()
+        .unwrap()
+        .insert(world_name.to_owned(), path.to_owned());
+    Ok(path)
+}
+
+enum WorldLookup<'a> {
+    Found(&'a Path),
+    /// Worlds that could be meant instead
+    NotFound(Vec<&'a WorldEntry>),
+    Ambiguous(Vec<&'a WorldEntry>),
+}
+
+impl WorldLookup<'_> {
+    fn message(&self, world_name: &str) -> String {
+        match self {
+            WorldLookup::Ambiguous(_) => {
+                format!("Found more than one world named <yellow>{world_name}</>")
+            }
+            _ => format!("World <yellow>{world_name}</> not found"),
+        }
+    }
+
+    fn candidates(&self) -> &[&WorldEntry] {
+        match self {
+            WorldLookup::Found(_) => &[],
+            WorldLookup::NotFound(worlds) | WorldLookup::Ambiguous(worlds) => worlds,
         }
-        worlds.insert(name, path);
     }
+}
+
+fn lookup_world<'a>(worlds: &'a [WorldEntry], world_name: &str) -> WorldLookup<'a> {
+    let mut matches = worlds
+        .iter()
+        .filter(|world| world.name == world_name)
+        .collect::<Vec<_>>();
+    match matches.len() {
+        0 => WorldLookup::NotFound(
+            worlds
+                .iter()
+                .filter(|world| world.path.join("level.dat").is_file())
+                .collect(),
+        ),
+        1 => WorldLookup::Found(&matches.remove(0).path),
+        _ => WorldLookup::Ambiguous(matches),
+    }
+}
 
-    worlds
-        .get(world_name)
-        .cloned()
-        .with_context(|| format!("World <yellow>{world_name}</> not found"))
+/// Prompts for one of the worlds, the most recently played first
+fn pick_world(mut worlds: Vec<&WorldEntry>) -> Result<PathBuf> {
+    worlds.sort_by_cached_key(|world| Reverse(get_last_played(&world.path)));
+    let labels = worlds
+        .iter()
+        .map(|world| {
+            let folder = world.path.file_name().unwrap_or_default().to_string_lossy();
+            format!("{} ({folder})", world.name)
+        })
+        .collect::<Vec<_>>();
+    let index = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select a world")
+        .items(&labels)
+        .default(0)
+        .interact()?;
+    Ok(worlds[index].path.to_owned())
+}
+
+/// Finds a world by the name of its folder in minecraftWorlds, or by its path
+pub fn find_world_dir_by_folder(build: Option<&MinecraftBuild>, folder: &str) -> Result<PathBuf> {
+    let path = Path::new(folder);
+    let path = match path.is_dir() {
+        true => path.to_owned(),
+        false => find_mojang_dir(build)?.join("minecraftWorlds").join(folder),
+    };
+    if !path.is_dir() {
+        return Err(ErrorCode::WorldNotFound.error(format!(
+            "World folder <yellow>{folder}</> not found\n\
+             <yellow> >></> Path: {}",
+            path.display()
+        )));
+    }
+    check_world_dir(&path)?;
+    Ok(path)
+}
+
+fn check_world_dir(path: &Path) -> Result<()> {
+    if !path.join("level.dat").is_file() {
+        bail!(
+            "World has no level.dat\n\
+             <yellow> >></> Path: {}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// World selected on the command line
+pub enum WorldRef<'a> {
+    /// Name from `levelname.txt`
+    Name(&'a str),
+    /// Folder name in minecraftWorlds, or path of the world
+    Folder(&'a str),
+}
+
+impl<'a> WorldRef<'a> {
+    pub fn from_args(name: Option<&'a str>, folder: Option<&'a str>) -> Option<Self> {
+        name.map(WorldRef::Name).or(folder.map(WorldRef::Folder))
+    }
+
+    pub fn find(&self, build: Option<&MinecraftBuild>) -> Result<PathBuf> {
+        match self {
+            WorldRef::Name(name) => find_world_dir(build, name),
+            WorldRef::Folder(folder) => find_world_dir_by_folder(build, folder),
+        }
+    }
+}
+
+impl std::fmt::Display for WorldRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorldRef::Name(name) | WorldRef::Folder(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Directory of the content log files, next to the directory containing com.mojang
+pub fn find_logs_dir(build: Option<&MinecraftBuild>) -> Result<PathBuf> {
+    let mojang_dir = find_mojang_dir(build)?;
+    // GDK builds keep com.mojang in `Users/Shared/games` instead of `games`
+    mojang_dir
+        .ancestors()
+        .skip(2)
+        .take(3)
+        .map(|dir| dir.join("logs"))
+        .find(|dir| dir.is_dir())
+        .context("Failed to find the content log directory, enable <b>Content Log File</> in the creator settings")
 }
@@ -1,49 +1,149 @@
+mod build_cache;
+mod build_events;
+mod builds;
+mod changed_files;
+mod cloud_files;
 mod config;
+mod config_migration;
+mod config_script;
+mod content_hashes;
+mod content_log;
+mod content_templates;
+mod dashboard;
+mod data_snapshot;
+mod dependency_hashes;
+mod education;
+mod error_code;
 mod eval;
 mod export;
+mod export_guard;
+mod file_names;
 mod filter;
 mod filter_bun;
+mod filter_bundle;
 mod filter_deno;
+mod filter_docker;
 mod filter_exe;
 mod filter_go;
+mod filter_logs;
 mod filter_nodejs;
 mod filter_python;
 mod filter_remote;
 mod filter_shell;
+mod filter_test;
+mod filter_textures;
+mod filter_wasm;
 mod global_filters;
+mod level_dat;
+mod marketplace;
 mod minecraft;
+mod pack_collisions;
+mod pack_limits;
 mod paths;
+mod plugin;
 mod profile;
+mod profile_switcher;
+mod project_dependency;
+mod qr_code;
+mod realms;
 mod resolver;
+mod run_state;
 mod runner;
+mod runtime;
 mod server;
 mod session;
+mod settings_schema;
+mod source_maps;
 mod subprocess;
 mod temp;
+mod test_world;
 mod user_config;
+mod validate;
+mod validate_entities;
+mod validate_environment;
+mod validate_functions;
+mod validate_identifiers;
+mod validate_scripts;
+mod validate_sounds;
+mod validate_spawn_rules;
+mod validate_textures;
+mod validate_trading;
+mod vanilla_samples;
 mod version_check;
+mod workspace;
+mod world_packs;
 
+pub use self::build_cache::*;
+pub use self::build_events::*;
+pub use self::builds::*;
+pub use self::changed_files::*;
+pub use self::cloud_files::*;
 pub use self::config::*;
+pub use self::config_migration::*;
+pub use self::config_script::*;
+pub use self::content_hashes::*;
+pub use self::content_log::*;
+pub use self::content_templates::*;
+pub use self::dashboard::*;
+pub use self::data_snapshot::*;
+pub use self::dependency_hashes::*;
+pub use self::education::*;
+pub use self::error_code::*;
 pub use self::eval::*;
 pub use self::export::*;
+pub use self::export_guard::*;
+pub use self::file_names::*;
 pub use self::filter::*;
 pub use self::filter_bun::*;
+pub use self::filter_bundle::*;
 pub use self::filter_deno::*;
+pub use self::filter_docker::*;
 pub use self::filter_exe::*;
 pub use self::filter_go::*;
+pub use self::filter_logs::*;
 pub use self::filter_nodejs::*;
 pub use self::filter_python::*;
 pub use self::filter_remote::*;
 pub use self::filter_shell::*;
+pub use self::filter_test::*;
+pub use self::filter_textures::*;
+pub use self::filter_wasm::*;
 pub use self::global_filters::*;
+pub use self::level_dat::*;
+pub use self::marketplace::*;
 pub use self::minecraft::*;
+pub use self::pack_collisions::*;
+pub use self::pack_limits::*;
 pub use self::paths::*;
+pub use self::plugin::*;
 pub use self::profile::*;
+pub use self::profile_switcher::*;
+pub use self::project_dependency::*;
+pub use self::qr_code::*;
+pub use self::realms::*;
 pub use self::resolver::*;
+pub use self::run_state::*;
 pub use self::runner::*;
+pub use self::runtime::*;
 pub use self::server::*;
 pub use self::session::*;
+pub use self::settings_schema::*;
+pub use self::source_maps::*;
 pub use self::subprocess::*;
 pub use self::temp::*;
+pub use self::test_world::*;
 pub use self::user_config::*;
+pub use self::validate::*;
+pub use self::validate_entities::*;
+pub use self::validate_environment::*;
+pub use self::validate_functions::*;
+pub use self::validate_identifiers::*;
+pub use self::validate_scripts::*;
+pub use self::validate_sounds::*;
+pub use self::validate_spawn_rules::*;
+pub use self::validate_textures::*;
+pub use self::validate_trading::*;
+pub use self::vanilla_samples::*;
 pub use self::version_check::*;
+pub use self::workspace::*;
+pub use self::world_packs::*;
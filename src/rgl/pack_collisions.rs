@@ -0,0 +1,161 @@
+use super::json_files;
+use crate::fs::read_json;
+use crate::{log, warn};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex},
+};
+
+/// Packs found in each directory the packs were exported to, scanned once per session
+static OTHER_PACKS: LazyLock<Mutex<HashMap<PathBuf, Arc<Vec<OtherPack>>>>> =
+    LazyLock::new(Default::default);
+
+struct OtherPack {
+    path: PathBuf,
+    uuids: Vec<String>,
+    identifiers: BTreeMap<String, &'static str>,
+}
+
+/// Number of shared identifiers listed per pack
+const MAX_LISTED: usize = 5;
+
+/// Directories of a behavior pack with definitions, and the key of the definition
+const DEFINITIONS: [(&str, &str); 3] = [
+    ("entities", "minecraft:entity"),
+    ("items", "minecraft:item"),
+    ("blocks", "minecraft:block"),
+];
+
+/// Warns about other packs in the directories the packs were exported to that share a UUID with
+/// them or define the same entities, items or blocks. The game loads only one of them without any
+/// error, so the exported pack may be silently shadowed. The other packs are only scanned once per
+/// session.
+pub fn check_pack_collisions(target_bp: Option<&Path>, target_rp: Option<&Path>) {
+    let targets: Vec<&Path> = [target_bp, target_rp].into_iter().flatten().collect();
+    let uuids: BTreeMap<String, &Path> = targets
+        .iter()
+        .flat_map(|target| {
+            get_uuids(target)
+                .into_iter()
+                .map(move |uuid| (uuid, *target))
+        })
+        .collect();
+    let identifiers = target_bp.map(get_identifiers).unwrap_or_default();
+
+    for (target, is_bp) in [(target_bp, true), (target_rp, false)] {
+        let Some(packs_dir) = target.and_then(Path::parent) else {
+            continue;
+        };
+        let other_packs = get_other_packs(packs_dir, is_bp);
+        for pack in other_packs.iter() {
+            if targets.iter().any(|target| same_file(&pack.path, target)) {
+                continue;
+            }
+            let name = pack
+                .path
+                .strip_prefix(packs_dir)
+                .unwrap_or(&pack.path)
+                .display();
+            for uuid in &pack.uuids {
+                if let Some(target) = uuids.get(uuid) {
+                    warn!(
+                        "Pack <b>{name}</> uses UUID {uuid} of the exported pack {}, only one of them will be loaded",
+                        target.display()
+                    );
+                }
+            }
+            if !is_bp || identifiers.is_empty() {
+                continue;
+            }
+            let shared: Vec<_> = pack
+                .identifiers
+                .keys()
+                .filter(|id| identifiers.contains_key(*id))
+                .collect();
+            if shared.is_empty() {
+                continue;
+            }
+            warn!(
+                "Pack <b>{name}</> defines {} identifier(s) of the exported behavior pack, they may be overridden",
+                shared.len()
+            );
+            for id in shared.iter().take(MAX_LISTED) {
+                log!("<yellow> >></> {id} ({})", identifiers[*id]);
+            }
+            if shared.len() > MAX_LISTED {
+                log!("<yellow> >></> and {} more", shared.len() - MAX_LISTED);
+            }
+        }
+    }
+}
+
+/// Packs in the directory, including the exported ones. Identifiers are only read from behavior
+/// packs.
+fn get_other_packs(packs_dir: &Path, is_bp: bool) -> Arc<Vec<OtherPack>> {
+    let mut cache = OTHER_PACKS.lock().unwrap();
+    if let Some(packs) = cache.get(packs_dir) {
+        return packs.clone();
+    }
+    let mut paths: Vec<_> = match packs_dir.read_dir() {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.join("manifest.json").is_file())
+            .collect(),
+        Err(_) => vec![],
+    };
+    paths.sort();
+    let packs = Arc::new(
+        paths
+            .into_iter()
+            .map(|path| OtherPack {
+                uuids: get_uuids(&path),
+                identifiers: match is_bp {
+                    true => get_identifiers(&path),
+                    false => BTreeMap::new(),
+                },
+                path,
+            })
+            .collect::<Vec<_>>(),
+    );
+    cache.insert(packs_dir.to_owned(), packs.clone());
+    packs
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (dunce::canonicalize(a), dunce::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// UUIDs of the header and modules of a pack manifest
+fn get_uuids(pack: &Path) -> Vec<String> {
+    let Ok(manifest) = read_json::<Value>(pack.join("manifest.json")) else {
+        return vec![];
+    };
+    let modules = manifest["modules"].as_array().into_iter().flatten();
+    std::iter::once(&manifest["header"])
+        .chain(modules)
+        .filter_map(|entry| entry["uuid"].as_str())
+        .map(|uuid| uuid.to_ascii_lowercase())
+        .collect()
+}
+
+/// Identifiers of the entities, items and blocks of a behavior pack, with their kind
+fn get_identifiers(pack: &Path) -> BTreeMap<String, &'static str> {
+    let mut identifiers = BTreeMap::new();
+    for (dir, key) in DEFINITIONS {
+        for path in json_files(&pack.join(dir)) {
+            let Ok(value) = read_json::<Value>(&path) else {
+                continue;
+            };
+            if let Some(id) = value[key]["description"]["identifier"].as_str() {
+                identifiers.insert(id.to_owned(), key.trim_start_matches("minecraft:"));
+            }
+        }
+    }
+    identifiers
+}
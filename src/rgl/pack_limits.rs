@@ -0,0 +1,161 @@
+use anyhow::{bail, Context, Result};
+use indicatif::HumanBytes;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Write, path::Path};
+use walkdir::WalkDir;
+
+/// Number of offending directories or files listed when a limit is exceeded
+const BREAKDOWN_SIZE: usize = 5;
+
+/// Limits checked before a pack is exported, e.g. to match Marketplace or Realms constraints
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PackLimits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_pack_size: Option<ByteSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_file_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_file_size: Option<ByteSize>,
+}
+
+struct PackFile {
+    /// Path relative to the pack, with `/` separators
+    path: String,
+    size: u64,
+}
+
+impl PackLimits {
+    /// Checks a pack, e.g. the behavior pack in the temp directory
+    pub fn check(&self, pack_name: &str, pack: &Path) -> Result<()> {
+        let mut files = vec![];
+        for entry in WalkDir::new(pack).follow_links(true) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry
+                .path()
+                .strip_prefix(pack)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let size = entry.metadata()?.len();
+            files.push(PackFile { path, size });
+        }
+
+        let mut errors = String::new();
+        if let Some(ByteSize(max)) = self.max_pack_size {
+            let size = files.iter().map(|f| f.size).sum::<u64>();
+            if size > max {
+                writeln!(
+                    errors,
+                    "Pack size is {}, the limit is {}",
+                    HumanBytes(size),
+                    HumanBytes(max)
+                )?;
+                for (dir, size) in top_dirs(&files, |f| f.size) {
+                    writeln!(errors, "<yellow> >></> {dir}: {}", HumanBytes(size))?;
+                }
+            }
+        }
+        if let Some(max) = self.max_file_count {
+            if files.len() > max {
+                writeln!(errors, "Pack has {} files, the limit is {max}", files.len())?;
+                for (dir, count) in top_dirs(&files, |_| 1) {
+                    writeln!(errors, "<yellow> >></> {dir}: {count} files")?;
+                }
+            }
+        }
+        if let Some(ByteSize(max)) = self.max_file_size {
+            let mut large: Vec<_> = files.iter().filter(|f| f.size > max).collect();
+            if !large.is_empty() {
+                large.sort_by_key(|f| Reverse(f.size));
+                writeln!(
+                    errors,
+                    "{} file(s) are larger than the limit of {}",
+                    large.len(),
+                    HumanBytes(max)
+                )?;
+                for file in large.iter().take(BREAKDOWN_SIZE) {
+                    writeln!(
+                        errors,
+                        "<yellow> >></> {}: {}",
+                        file.path,
+                        HumanBytes(file.size)
+                    )?;
+                }
+            }
+        }
+        if !errors.is_empty() {
+            bail!(
+                "{pack_name} exceeds the limits of the profile\n{}",
+                errors.trim_end()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Largest top-level directories of the pack by the given measure
+fn top_dirs(files: &[PackFile], measure: impl Fn(&PackFile) -> u64) -> Vec<(&str, u64)> {
+    let mut dirs = BTreeMap::<&str, u64>::new();
+    for file in files {
+        let dir = match file.path.split_once('/') {
+            Some((dir, _)) => dir,
+            None => ".",
+        };
+        *dirs.entry(dir).or_default() += measure(file);
+    }
+    let mut dirs: Vec<_> = dirs.into_iter().collect();
+    dirs.sort_by_key(|(_, value)| Reverse(*value));
+    dirs.truncate(BREAKDOWN_SIZE);
+    dirs
+}
+
+/// Size in bytes, written as a number of bytes or a string with a unit, e.g. `"50MB"`
+#[derive(Clone, Copy)]
+struct ByteSize(u64);
+
+impl ByteSize {
+    fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let split = value
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+        let number: f64 = number
+            .parse()
+            .with_context(|| format!("Invalid size `{value}`"))?;
+        let multiplier = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "KB" => 1024,
+            "MB" => 1024 * 1024,
+            "GB" => 1024 * 1024 * 1024,
+            unit => bail!("Unknown size unit `{unit}`, expected B, KB, MB or GB"),
+        };
+        Ok(Self((number * multiplier as f64) as u64))
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Bytes(u64),
+            Text(String),
+        }
+        match Value::deserialize(deserializer)? {
+            Value::Bytes(bytes) => Ok(Self(bytes)),
+            Value::Text(text) => Self::parse(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
@@ -1,8 +1,32 @@
 use super::RemoteFilter;
+use crate::fs::{copy_dir, move_path, rimraf};
 use anyhow::Result;
 use once_cell::sync::OnceCell;
-use std::env;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+/// Moves a file or directory that is about to be replaced to `.regolith/backups`, and returns
+/// where it was moved to.
+pub fn backup_path(path: &Path) -> Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let backup = PathBuf::from(".regolith")
+        .join("backups")
+        .join(format!("{name}-{timestamp}"));
+    fs::create_dir_all(backup.parent().unwrap())?;
+    // Moving fails if the path is on another filesystem
+    if move_path(path, &backup).is_err() {
+        match path.is_dir() {
+            true => copy_dir(path, &backup)?,
+            false => {
+                fs::copy(path, &backup)?;
+            }
+        }
+        rimraf(path)?;
+    }
+    Ok(backup)
+}
 
 pub fn get_current_dir() -> Result<PathBuf> {
     static CURRENT_DIR: OnceCell<PathBuf> = OnceCell::new();
@@ -10,6 +34,34 @@ pub fn get_current_dir() -> Result<PathBuf> {
     Ok(current_dir?.to_owned())
 }
 
+/// Makes the path absolute and resolves symlinks in its longest existing ancestor, so paths to
+/// deleted files can still be compared against canonical directories.
+pub fn normalize_path(path: &Path) -> Result<PathBuf> {
+    let path: PathBuf = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        get_current_dir()?.join(path)
+    }
+    .components()
+    .filter(|c| !matches!(c, Component::CurDir))
+    .collect();
+    let mut existing = path.as_path();
+    let mut rest = vec![];
+    loop {
+        if let Ok(mut resolved) = dunce::canonicalize(existing) {
+            resolved.extend(rest.iter().rev());
+            return Ok(resolved);
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return Ok(path),
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn get_user_cache_dir() -> Result<PathBuf> {
     let home = env::var("HOME")?;
@@ -46,18 +98,35 @@ pub fn get_global_filters_path() -> Result<PathBuf> {
     Ok(get_cache_dir()?.join("global_filters.json"))
 }
 
+pub fn get_filters_cache_dir() -> Result<PathBuf> {
+    Ok(get_cache_dir()?.join("filters"))
+}
+
 pub fn get_filter_cache_dir(name: &str, remote: &RemoteFilter) -> Result<PathBuf> {
-    Ok(get_cache_dir()?
-        .join("filters")
+    Ok(get_filters_cache_dir()?
         .join(&remote.url)
         .join(name)
         .join(&remote.version))
 }
 
+pub fn get_build_cache_dir() -> Result<PathBuf> {
+    Ok(get_cache_dir()?.join("build"))
+}
+
 pub fn get_repo_cache_dir() -> Result<PathBuf> {
     Ok(get_cache_dir()?.join("repo"))
 }
 
+/// Repositories of the projects used as dependencies
+pub fn get_project_cache_dir() -> Result<PathBuf> {
+    Ok(get_cache_dir()?.join("projects"))
+}
+
 pub fn get_resolver_cache_dir() -> Result<PathBuf> {
     Ok(get_cache_dir()?.join("resolver"))
 }
+
+/// Releases of bedrock-samples, with only the files that were fetched
+pub fn get_vanilla_cache_dir() -> Result<PathBuf> {
+    Ok(get_cache_dir()?.join("vanilla"))
+}
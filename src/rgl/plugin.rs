@@ -0,0 +1,406 @@
+//! WebAssembly plugins that add commands and filters to rgl.
+//!
+//! A plugin is a `.wasm` module that exports:
+//!
+//! - `memory`
+//! - `rgl_alloc(size: i32) -> i32`: allocates `size` bytes for data passed by the host
+//! - `rgl_plugin(api_version: i32) -> i64`: returns the plugin manifest as JSON, e.g.
+//!   `{ "name": "my-plugin", "commands": [{ "name": "hello", "description": "..." }], "filters": ["minify"] }`
+//! - `rgl_command(ptr: i32, len: i32) -> i32`: runs a command, given `{ "command", "args" }`
+//! - `rgl_filter(ptr: i32, len: i32) -> i32`: runs a filter, given `{ "filter", "args" }`
+//! - `rgl_event(ptr: i32, len: i32) -> i32`: handles a game event received by `rgl watch --ws`,
+//!   given `{ "event", "body" }`. The manifest lists the events in `"events"`, e.g.
+//!   `["PlayerMessage"]`. Returns 1 to trigger a rebuild
+//!
+//! The instance of a plugin is kept between calls, and each call can run a limited number of
+//! instructions.
+//!
+//! Commands, filters and events return 0 on success. Strings and byte arrays are returned as an `i64`
+//! holding the pointer in the upper and the length in the lower 32 bits, or -1 if there's none.
+//!
+//! Plugins can't access the system directly. Instead, they can import these functions from the
+//! `rgl` module, with paths relative to the temp directory for filters, or the project root for
+//! commands:
+//!
+//! - `log(level: i32, ptr: i32, len: i32)`: 0 = info, 1 = warn, 2 = error, 3 = debug
+//! - `read_file(path_ptr: i32, path_len: i32) -> i64`
+//! - `write_file(path_ptr: i32, path_len: i32, data_ptr: i32, data_len: i32) -> i32`
+//! - `list_dir(path_ptr: i32, path_len: i32) -> i64`: names of the entries as a JSON array
+//! - `remove(path_ptr: i32, path_len: i32) -> i32`
+use crate::fs::rimraf;
+use crate::{debug, error, info, warn};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+use wasmi::{
+    AsContextMut, Caller, Config, Engine, Instance, Linker, Memory, Module, Store, TypedFunc,
+};
+
+/// Version of the host API, bumped on breaking changes
+const HOST_API_VERSION: i32 = 1;
+
+/// Fuel a plugin gets for each call, roughly the number of instructions it can run. A plugin
+/// stuck in a loop fails instead of hanging rgl
+const PLUGIN_FUEL: u64 = 10_000_000_000;
+
+#[derive(Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default)]
+    pub commands: Vec<PluginCommand>,
+    #[serde(default)]
+    pub filters: Vec<String>,
+    /// Game events passed to the plugin while watching with `--ws`
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PluginCommand {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+pub struct Plugin {
+    pub path: PathBuf,
+    pub manifest: PluginManifest,
+    engine: Engine,
+    module: Module,
+    /// Instance reused between calls, the module is only instantiated again after a call failed
+    instance: Mutex<Option<(Store<HostState>, Instance)>>,
+}
+
+struct HostState {
+    /// Directory that paths given by the plugin are relative to
+    root: PathBuf,
+    name: String,
+}
+
+impl Plugin {
+    pub fn load(path: &Path) -> Result<Self> {
+        let inner = || -> Result<Self> {
+            let mut config = Config::default();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config);
+            let module = Module::new(&engine, fs::read(path)?).map_err(|e| anyhow!("{e}"))?;
+            let mut plugin = Self {
+                path: path.to_owned(),
+                manifest: PluginManifest {
+                    name: path
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into(),
+                    commands: vec![],
+                    filters: vec![],
+                    events: vec![],
+                },
+                engine,
+                module,
+                instance: Mutex::new(None),
+            };
+            let (mut store, instance) = plugin.instantiate(Path::new("."))?;
+            let manifest = instance
+                .get_typed_func::<i32, i64>(&store, "rgl_plugin")
+                .map_err(|e| anyhow!("{e}"))?
+                .call(&mut store, HOST_API_VERSION)
+                .map_err(|e| anyhow!("{e}"))?;
+            let manifest = read_packed(&mut store, &instance, manifest)?
+                .context("Plugin returned no manifest")?;
+            plugin.manifest =
+                serde_json::from_slice(&manifest).context("Invalid plugin manifest")?;
+            *plugin.instance.lock().unwrap() = Some((store, instance));
+            Ok(plugin)
+        };
+        inner().with_context(|| {
+            format!(
+                "Failed to load plugin\n\
+                 <yellow> >></> Path: {}",
+                path.display()
+            )
+        })
+    }
+
+    /// Loads every `.wasm` file in the directory.
+    pub fn load_all(dir: &Path) -> Result<Vec<Self>> {
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        paths
+            .iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+            .map(|path| Self::load(path))
+            .collect()
+    }
+
+    pub fn run_command(&self, command: &str, args: &[String]) -> Result<()> {
+        let input = json!({ "command": command, "args": args });
+        self.call("rgl_command", Path::new("."), &input)
+            .with_context(|| {
+                format!(
+                    "Command <b>{command}</> of plugin <b>{}</> failed",
+                    self.manifest.name
+                )
+            })
+    }
+
+    pub fn run_filter(&self, filter: &str, temp: &Path, args: &[String]) -> Result<()> {
+        if !self.manifest.filters.iter().any(|name| name == filter) {
+            bail!(
+                "Plugin <b>{}</> has no filter named <filter>{filter}</>",
+                self.manifest.name
+            );
+        }
+        let input = json!({ "filter": filter, "args": args });
+        self.call("rgl_filter", temp, &input)
+    }
+
+    /// Handles a game event, returns whether the plugin requested a rebuild
+    pub fn on_event(&self, event: &str, body: &Value) -> Result<bool> {
+        let input = json!({ "event": event, "body": body });
+        let code = self.call_raw("rgl_event", Path::new("."), &input)?;
+        match code {
+            0 => Ok(false),
+            1 => Ok(true),
+            code => bail!(
+                "Plugin <b>{}</> failed to handle event <b>{event}</> with status code {code}",
+                self.manifest.name
+            ),
+        }
+    }
+
+    fn call(&self, export: &str, root: &Path, input: &Value) -> Result<()> {
+        let code = self.call_raw(export, root, input)?;
+        if code != 0 {
+            bail!("Plugin exited with status code {code}");
+        }
+        Ok(())
+    }
+
+    /// Calls an export of the plugin, returning its status code. Plugins block while they run,
+    /// so async code should call them with `smol::unblock`.
+    fn call_raw(&self, export: &str, root: &Path, input: &Value) -> Result<i32> {
+        let mut cached = self.instance.lock().unwrap();
+        let (mut store, instance) = match cached.take() {
+            Some((mut store, instance)) => {
+                store.data_mut().root = root.to_owned();
+                store.set_fuel(PLUGIN_FUEL).map_err(|e| anyhow!("{e}"))?;
+                (store, instance)
+            }
+            None => self.instantiate(root)?,
+        };
+        let func = instance
+            .get_typed_func::<(i32, i32), i32>(&store, export)
+            .map_err(|e| anyhow!("{e}"))?;
+        let alloc = get_alloc(&mut store, &instance)?;
+        let memory = get_memory(&mut store, &instance)?;
+        let input = serde_json::to_vec(input)?;
+        let (ptr, len) = write_guest(&mut store, memory, alloc, &input)?;
+        // The instance is dropped if the plugin traps, it may have left its memory in an
+        // inconsistent state
+        let code = func
+            .call(&mut store, (ptr, len))
+            .map_err(|e| anyhow!("{e}"))?;
+        *cached = Some((store, instance));
+        Ok(code)
+    }
+
+    fn instantiate(&self, root: &Path) -> Result<(Store<HostState>, Instance)> {
+        let state = HostState {
+            root: root.to_owned(),
+            name: self.manifest.name.to_owned(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.set_fuel(PLUGIN_FUEL).map_err(|e| anyhow!("{e}"))?;
+        let mut linker = Linker::<HostState>::new(&self.engine);
+        define_host_functions(&mut linker)?;
+        let instance = linker
+            .instantiate_and_start(&mut store, &self.module)
+            .map_err(|e| anyhow!("{e}"))?;
+        Ok((store, instance))
+    }
+}
+
+fn get_memory(store: impl AsContextMut, instance: &Instance) -> Result<Memory> {
+    instance
+        .get_memory(store, "memory")
+        .context("Plugin doesn't export `memory`")
+}
+
+fn get_alloc(store: impl AsContextMut, instance: &Instance) -> Result<TypedFunc<i32, i32>> {
+    instance
+        .get_typed_func::<i32, i32>(store, "rgl_alloc")
+        .map_err(|e| anyhow!("Plugin doesn't export `rgl_alloc`: {e}"))
+}
+
+/// Copies the bytes into memory allocated by the plugin.
+fn write_guest(
+    mut ctx: impl AsContextMut,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    bytes: &[u8],
+) -> Result<(i32, i32)> {
+    let len = i32::try_from(bytes.len())?;
+    let ptr = alloc.call(&mut ctx, len).map_err(|e| anyhow!("{e}"))?;
+    memory
+        .write(&mut ctx, ptr as u32 as usize, bytes)
+        .map_err(|e| anyhow!("{e}"))?;
+    Ok((ptr, len))
+}
+
+fn read_guest(ctx: impl AsContextMut, memory: Memory, ptr: i32, len: i32) -> Result<Vec<u8>> {
+    let mut buf = vec![0; len as u32 as usize];
+    memory
+        .read(ctx, ptr as u32 as usize, &mut buf)
+        .map_err(|e| anyhow!("{e}"))?;
+    Ok(buf)
+}
+
+fn read_packed(
+    store: &mut Store<HostState>,
+    instance: &Instance,
+    packed: i64,
+) -> Result<Option<Vec<u8>>> {
+    if packed < 0 {
+        return Ok(None);
+    }
+    let memory = get_memory(&mut *store, instance)?;
+    let (ptr, len) = ((packed >> 32) as i32, packed as i32);
+    read_guest(store, memory, ptr, len).map(Some)
+}
+
+fn pack(ptr: i32, len: i32) -> i64 {
+    ((ptr as u32 as i64) << 32) | len as u32 as i64
+}
+
+/// Resolves a path given by a plugin, which must stay inside the root directory.
+fn resolve_path(root: &Path, path: &str) -> Result<PathBuf> {
+    let path = Path::new(path);
+    if !path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        bail!(
+            "Path must be relative and inside the working directory: {}",
+            path.display()
+        );
+    }
+    Ok(root.join(path))
+}
+
+fn define_host_functions(linker: &mut Linker<HostState>) -> Result<()> {
+    /// Converts errors to traps, which abort the plugin
+    fn trap(e: anyhow::Error) -> wasmi::Error {
+        wasmi::Error::new(format!("{e:#}"))
+    }
+    fn exports(caller: &mut Caller<'_, HostState>) -> Result<(Memory, TypedFunc<i32, i32>)> {
+        let memory = caller
+            .get_export("memory")
+            .and_then(|export| export.into_memory())
+            .context("Plugin doesn't export `memory`")?;
+        let alloc = caller
+            .get_export("rgl_alloc")
+            .and_then(|export| export.into_func())
+            .context("Plugin doesn't export `rgl_alloc`")?
+            .typed::<i32, i32>(&caller)
+            .map_err(|e| anyhow!("{e}"))?;
+        Ok((memory, alloc))
+    }
+    fn read_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String> {
+        let (memory, _) = exports(caller)?;
+        Ok(String::from_utf8(read_guest(caller, memory, ptr, len)?)?)
+    }
+    fn return_bytes(caller: &mut Caller<'_, HostState>, bytes: &[u8]) -> Result<i64> {
+        let (memory, alloc) = exports(caller)?;
+        let (ptr, len) = write_guest(caller, memory, alloc, bytes)?;
+        Ok(pack(ptr, len))
+    }
+
+    let define = |result: Result<&mut Linker<HostState>, wasmi::errors::LinkerError>| {
+        result.map(|_| ()).map_err(|e| anyhow!("{e}"))
+    };
+    define(linker.func_wrap(
+        "rgl",
+        "log",
+        |mut caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| {
+            let message = read_string(&mut caller, ptr, len).map_err(trap)?;
+            let tag = format!("<green>[{}]</>", caller.data().name);
+            match level {
+                1 => warn!("{tag} {message}"),
+                2 => error!("{tag} {message}"),
+                3 => debug!("{tag} {message}"),
+                _ => info!("{tag} {message}"),
+            }
+            Ok(())
+        },
+    ))?;
+    define(linker.func_wrap(
+        "rgl",
+        "read_file",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let path = read_string(&mut caller, ptr, len).map_err(trap)?;
+            let path = resolve_path(&caller.data().root, &path).map_err(trap)?;
+            match fs::read(path) {
+                Ok(bytes) => return_bytes(&mut caller, &bytes).map_err(trap),
+                Err(_) => Ok(-1),
+            }
+        },
+    ))?;
+    define(linker.func_wrap(
+        "rgl",
+        "write_file",
+        |mut caller: Caller<'_, HostState>,
+         path_ptr: i32,
+         path_len: i32,
+         data_ptr: i32,
+         data_len: i32| {
+            let path = read_string(&mut caller, path_ptr, path_len).map_err(trap)?;
+            let path = resolve_path(&caller.data().root, &path).map_err(trap)?;
+            let (memory, _) = exports(&mut caller).map_err(trap)?;
+            let data = read_guest(&mut caller, memory, data_ptr, data_len).map_err(trap)?;
+            let result = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(path, data));
+            Ok(if result.is_ok() { 0 } else { -1 })
+        },
+    ))?;
+    define(linker.func_wrap(
+        "rgl",
+        "list_dir",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let path = read_string(&mut caller, ptr, len).map_err(trap)?;
+            let path = resolve_path(&caller.data().root, &path).map_err(trap)?;
+            let Ok(entries) = fs::read_dir(path) else {
+                return Ok(-1);
+            };
+            let mut names = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            names.sort();
+            let json = serde_json::to_vec(&names).map_err(|e| trap(e.into()))?;
+            return_bytes(&mut caller, &json).map_err(trap)
+        },
+    ))?;
+    define(linker.func_wrap(
+        "rgl",
+        "remove",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let path = read_string(&mut caller, ptr, len).map_err(trap)?;
+            let path = resolve_path(&caller.data().root, &path).map_err(trap)?;
+            Ok(if rimraf(path).is_ok() { 0 } else { -1 })
+        },
+    ))?;
+    Ok(())
+}
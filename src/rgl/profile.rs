@@ -1,16 +1,57 @@
-use super::{Config, Eval, Export, Filter, FilterContext};
-use crate::{debug, info, measure_time};
-use anyhow::{bail, Context, Result};
+use super::{
+    emit_build_event, update_dependencies, validate_settings, with_timeout, BuildCache, BuildEvent,
+    Config, ErrorCode, Eval, Export, Filter, FilterContext, FilterDefinition, PackLimits, Temp,
+};
+use crate::{debug, error, info, log, measure_time, warn};
+use anyhow::{anyhow, bail, Context, Result};
 use async_recursion::async_recursion;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{collections::HashSet, path::Path};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Profile {
     pub export: Export,
     pub filters: Vec<FilterRunner>,
+    /// Write a `content_hashes.json` file to the root of each exported pack
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub content_hashes: bool,
+    /// Copy the packs to the temp directory instead of linking it to the export target, the same
+    /// as running with `--compat`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compat: bool,
+    /// Run filters in a RAM-backed temp directory, only writing to disk on export
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub in_memory: bool,
+    /// Export to a staging directory next to the target and swap it into place, so the game never
+    /// sees a partially exported pack. Implies `compat`, and copies the whole pack on each export
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub atomic_export: bool,
+    /// Directory to run filters in instead of `.regolith/tmp`, must be inside `.regolith`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_dir: Option<PathBuf>,
+    /// Prevent filters from accessing files outside the temp directory, where supported
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sandbox: bool,
+    /// Only give each filter access to its own `data/<filter>` directory
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub isolate_data: bool,
+    /// Experiments to enable in the world the packs are exported or attached to, e.g. `beta_apis`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub experiments: Vec<String>,
+    /// Size and file count limits the packs must stay within to be exported. Profiles with limits
+    /// are run in compat mode, so nothing is exported when they are exceeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<PackLimits>,
+    /// Given with `rgl run --inline`, the run is not recorded in the project state
+    #[serde(skip)]
+    pub inline: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -25,6 +66,32 @@ pub enum FilterRunner {
         settings: Option<IndexMap<String, Value>>,
         #[serde(rename = "when", skip_serializing_if = "Option::is_none")]
         expression: Option<String>,
+        /// Paths relative to the temp root that this filter reads, e.g. `RP/textures`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        inputs: Option<Vec<String>>,
+        /// Seconds before the filter is killed
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout: Option<f32>,
+        /// Number of times to rerun the filter if it fails
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retries: Option<u32>,
+        /// Report failures of this filter without aborting the run
+        #[serde(
+            rename = "continueOnError",
+            default,
+            skip_serializing_if = "std::ops::Not::not"
+        )]
+        continue_on_error: bool,
+        /// Reuse the output of a previous run with the same filter, settings and input files
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        cache: bool,
+        /// Give this filter access to the data of every filter when the profile isolates data
+        #[serde(
+            rename = "sharedData",
+            default,
+            skip_serializing_if = "std::ops::Not::not"
+        )]
+        shared_data: bool,
     },
     ProfileFilter {
         #[serde(rename = "profile")]
@@ -32,13 +99,136 @@ pub enum FilterRunner {
     },
 }
 
+/// Filters of a profile picked to run, by name or as an inclusive range in the order they run,
+/// e.g. `a..b`, `a..` or `..b`. Every filter runs when nothing is picked. Skipped filters never
+/// run, even when picked.
+#[derive(Clone, Default)]
+pub struct FilterSelection {
+    selectors: Vec<Selector>,
+    skip: Vec<String>,
+}
+
+#[derive(Clone)]
+enum Selector {
+    Name(String),
+    Range {
+        start: Option<String>,
+        end: Option<String>,
+        state: RangeState,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RangeState {
+    Pending,
+    Active,
+    Done,
+}
+
+impl FilterSelection {
+    pub fn new(filters: &[String], skip: &[String]) -> Self {
+        let selectors = filters.iter().map(|filter| match filter.split_once("..") {
+            Some((start, end)) => Selector::Range {
+                start: (!start.is_empty()).then(|| start.to_owned()),
+                end: (!end.is_empty()).then(|| end.to_owned()),
+                state: RangeState::Pending,
+            },
+            None => Selector::Name(filter.to_owned()),
+        });
+        Self {
+            selectors: selectors.collect(),
+            skip: skip.to_vec(),
+        }
+    }
+
+    /// Fails if a picked or skipped filter is not part of the profile
+    pub fn check(&self, profile_name: &str, filter_names: &[String]) -> Result<()> {
+        let picked = self.selectors.iter().flat_map(|selector| match selector {
+            Selector::Name(name) => vec![name],
+            Selector::Range { start, end, .. } => start.iter().chain(end).collect(),
+        });
+        for name in picked.chain(&self.skip) {
+            if !filter_names.contains(name) {
+                bail!("Filter <filter>{name}</> is not part of the <profile>{profile_name}</> profile");
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the next filter of the run is picked. Must be called once for every filter, in the
+    /// order they run, to track ranges.
+    fn includes(&mut self, filter_name: &str) -> bool {
+        let mut included = self.selectors.is_empty();
+        for selector in &mut self.selectors {
+            match selector {
+                Selector::Name(name) => included |= name == filter_name,
+                Selector::Range { start, end, state } => {
+                    if *state == RangeState::Pending
+                        && start.as_ref().is_none_or(|start| start == filter_name)
+                    {
+                        *state = RangeState::Active;
+                    }
+                    if *state == RangeState::Active {
+                        included = true;
+                        if end.as_ref().is_some_and(|end| end == filter_name) {
+                            *state = RangeState::Done;
+                        }
+                    }
+                }
+            }
+        }
+        included && !self.is_skipped(filter_name)
+    }
+
+    fn is_skipped(&self, filter_name: &str) -> bool {
+        self.skip.iter().any(|name| name == filter_name)
+    }
+}
+
 impl Profile {
-    #[async_recursion]
+    /// Parses a profile written as JSON or YAML. Only the first YAML document is read, so a
+    /// profile can also be given as front matter.
+    pub fn parse(source: &str) -> Result<Self> {
+        let document = serde_yaml_ng::Deserializer::from_str(source)
+            .next()
+            .context("The profile is empty")?;
+        let value = Value::deserialize(document).context("Failed to parse profile")?;
+        serde_path_to_error::deserialize(value).map_err(|e| {
+            let path = e.path().to_string();
+            anyhow!("Invalid profile\n<yellow> >></> {path}: {}", e.into_inner())
+        })
+    }
+
     pub async fn run(
         &self,
         config: &Config,
         temp: &Path,
         root_profile: &str,
+        selection: &mut FilterSelection,
+        failed_filters: &mut Vec<String>,
+    ) -> Result<HashSet<String>> {
+        self.run_filters(
+            config,
+            temp,
+            root_profile,
+            selection,
+            failed_filters,
+            self.sandbox,
+        )
+        .await
+    }
+
+    /// Runs the filters of the profile, nested profiles are sandboxed if the profile that runs
+    /// them is.
+    #[async_recursion]
+    async fn run_filters(
+        &self,
+        config: &Config,
+        temp: &Path,
+        root_profile: &str,
+        selection: &mut FilterSelection,
+        failed_filters: &mut Vec<String>,
+        sandbox: bool,
     ) -> Result<HashSet<String>> {
         let mut export_data_names = HashSet::new();
         for entry in self.filters.iter() {
@@ -48,7 +238,21 @@ impl Profile {
                     arguments,
                     settings,
                     expression,
+                    timeout,
+                    retries,
+                    continue_on_error,
+                    cache,
+                    shared_data,
+                    ..
                 } => {
+                    if !selection.includes(filter_name) {
+                        match selection.is_skipped(filter_name) {
+                            true => info!("Skipping filter <filter>{filter_name}</>"),
+                            false => debug!("Filter <filter>{filter_name}</> was not selected"),
+                        }
+                        emit_build_event(BuildEvent::FilterSkipped { name: filter_name });
+                        continue;
+                    }
                     let filter = config.get_filter(filter_name)?;
                     let mut run_args: Vec<String> = vec![];
                     if let Some(settings) = settings {
@@ -59,7 +263,8 @@ impl Profile {
                     }
 
                     measure_time!(filter_name, {
-                        let context = FilterContext::new(filter_name, &filter)?;
+                        let mut context = FilterContext::new(filter_name, &filter)?;
+                        context.sandbox = sandbox;
                         if let Some(expression) = expression {
                             let eval =
                                 Eval::new(root_profile, &context.filter_dir, settings.clone());
@@ -68,26 +273,121 @@ impl Profile {
                                 format!("Failed running evaluator for <filter>{filter_name}</>")
                             })? {
                                 info!("Skipping filter <filter>{filter_name}</>");
+                                emit_build_event(BuildEvent::FilterSkipped { name: filter_name });
                                 continue;
                             }
                         }
-                        info!("Running filter <filter>{filter_name}</>");
-                        filter.run(&context, temp, &run_args).with_context(|| {
-                            format!("Failed running filter <filter>{filter_name}</>")
-                        })?;
+                        emit_build_event(BuildEvent::FilterStarted { name: filter_name });
+                        let isolated_data = match self.isolate_data && !shared_data {
+                            true => Some(Temp::new(temp.to_owned()).isolate_data(filter_name)?),
+                            false => None,
+                        };
+                        let start_time = Instant::now();
+                        let cache = match cache {
+                            true => Some(BuildCache::new(&context, &filter, &run_args, temp)?),
+                            false => None,
+                        };
+                        let restored = match &cache {
+                            Some(cache) => cache.restore(temp)?,
+                            None => false,
+                        };
+                        if restored {
+                            info!("Restored filter <filter>{filter_name}</> output from cache");
+                            emit_build_event(BuildEvent::FilterFinished {
+                                name: filter_name,
+                                duration: start_time.elapsed(),
+                                cached: true,
+                                error: None,
+                            });
+                        } else {
+                            info!("Running filter <filter>{filter_name}</>");
+                            if let FilterDefinition::Local(local) = &filter {
+                                local.check_sandbox(&context);
+                                update_dependencies(local, &context, false)?;
+                            }
+                            let timeout = match timeout {
+                                Some(seconds) => match Duration::try_from_secs_f32(*seconds) {
+                                    Ok(timeout) if !timeout.is_zero() => Some(timeout),
+                                    _ => {
+                                        return Err(ErrorCode::InvalidConfig.error(format!(
+                                            "Invalid timeout {seconds} for filter <filter>{filter_name}</>, expected a positive number of seconds"
+                                        )))
+                                    }
+                                },
+                                None => None,
+                            };
+                            if let (Some(_), FilterDefinition::Local(local)) = (timeout, &filter) {
+                                local.check_timeout(&context);
+                            }
+                            let retries = retries.unwrap_or(0);
+                            for attempt in 0..=retries {
+                                let result =
+                                    with_timeout(timeout, || filter.run(&context, temp, &run_args))
+                                        .with_context(|| {
+                                            format!(
+                                                "Failed running filter <filter>{filter_name}</>"
+                                            )
+                                        });
+                                match result {
+                                    Err(e) if attempt < retries => {
+                                        warn!(
+                                            "{e} ({}), retrying ({}/{retries})",
+                                            e.root_cause(),
+                                            attempt + 1
+                                        );
+                                    }
+                                    Err(e) if *continue_on_error => {
+                                        emit_build_event(BuildEvent::FilterFinished {
+                                            name: filter_name,
+                                            duration: start_time.elapsed(),
+                                            cached: false,
+                                            error: Some(&e),
+                                        });
+                                        error!("{e}");
+                                        e.chain().skip(1).for_each(|e| log!("<red>[+]</> {e}"));
+                                        failed_filters.push(filter_name.to_owned());
+                                        break;
+                                    }
+                                    result => {
+                                        emit_build_event(BuildEvent::FilterFinished {
+                                            name: filter_name,
+                                            duration: start_time.elapsed(),
+                                            cached: false,
+                                            error: result.as_ref().err(),
+                                        });
+                                        result?;
+                                        if let Some(cache) = &cache {
+                                            cache.store(temp);
+                                        }
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                        if let Some(isolated_data) = isolated_data {
+                            isolated_data.restore()?;
+                        }
                         if context.remote_config.is_some_and(|cfg| cfg.export_data) {
                             export_data_names.insert(filter_name.to_owned());
                         }
                     });
                 }
                 FilterRunner::ProfileFilter { profile_name } => {
-                    if profile_name == root_profile {
-                        bail!("Found circular profile reference in <profile>{profile_name}</>");
-                    }
                     let profile = config.get_profile(profile_name)?;
 
                     info!("Running <profile>{profile_name}</> nested profile");
-                    export_data_names.extend(profile.run(config, temp, root_profile).await?);
+                    export_data_names.extend(
+                        profile
+                            .run_filters(
+                                config,
+                                temp,
+                                root_profile,
+                                selection,
+                                failed_filters,
+                                sandbox || profile.sandbox,
+                            )
+                            .await?,
+                    );
                 }
             }
             for _ in 0..5 {
@@ -96,4 +396,134 @@ impl Profile {
         }
         Ok(export_data_names)
     }
+
+    /// Fails if this profile runs itself through its nested profiles, which would never end.
+    pub fn check_nesting(&self, profile_name: &str, config: &Config) -> Result<()> {
+        fn check(profile: &Profile, config: &Config, stack: &mut Vec<String>) -> Result<()> {
+            for entry in profile.filters.iter() {
+                let FilterRunner::ProfileFilter { profile_name } = entry else {
+                    continue;
+                };
+                if let Some(index) = stack.iter().position(|name| name == profile_name) {
+                    let cycle = stack[index..]
+                        .iter()
+                        .chain([profile_name])
+                        .map(|name| format!("<profile>{name}</>"))
+                        .collect::<Vec<_>>()
+                        .join(" -> ");
+                    bail!("Found circular profile reference: {cycle}");
+                }
+                stack.push(profile_name.to_owned());
+                check(config.get_profile(profile_name)?, config, stack)?;
+                stack.pop();
+            }
+            Ok(())
+        }
+
+        check(self, config, &mut vec![profile_name.to_owned()])
+    }
+
+    /// Validates the settings of every filter in this profile, including the ones in nested
+    /// profiles, against the settings schema of the filter.
+    pub fn check_settings(&self, config: &Config) -> Result<()> {
+        fn check(profile: &Profile, config: &Config, visited: &mut HashSet<String>) -> Result<()> {
+            for entry in profile.filters.iter() {
+                match entry {
+                    FilterRunner::Filter {
+                        filter_name,
+                        settings,
+                        ..
+                    } => {
+                        let filter = config.get_filter(filter_name)?;
+                        let context = FilterContext::new(filter_name, &filter)?;
+                        let schema = context.remote_config.and_then(|cfg| cfg.settings_schema);
+                        if let Some(schema) = schema {
+                            let settings =
+                                serde_json::to_value(settings.clone().unwrap_or_default())?;
+                            validate_settings(filter_name, &schema, &settings)?;
+                        }
+                    }
+                    FilterRunner::ProfileFilter { profile_name } => {
+                        if visited.insert(profile_name.to_owned()) {
+                            let profile = config.get_profile(profile_name)?;
+                            check(profile, config, visited)?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        check(self, config, &mut HashSet::new())
+    }
+
+    /// Names of every filter in this profile, including the ones in nested profiles.
+    pub fn get_filter_names(&self, config: &Config) -> Result<Vec<String>> {
+        fn collect(
+            profile: &Profile,
+            config: &Config,
+            visited: &mut HashSet<String>,
+            names: &mut Vec<String>,
+        ) -> Result<()> {
+            for entry in profile.filters.iter() {
+                match entry {
+                    FilterRunner::Filter { filter_name, .. } => {
+                        names.push(filter_name.to_owned());
+                    }
+                    FilterRunner::ProfileFilter { profile_name } => {
+                        if visited.insert(profile_name.to_owned()) {
+                            let profile = config.get_profile(profile_name)?;
+                            collect(profile, config, visited, names)?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        let mut names = vec![];
+        collect(self, config, &mut HashSet::new(), &mut names)?;
+        Ok(names)
+    }
+
+    /// Collects the input paths declared by every filter in this profile, including the ones in
+    /// nested profiles.
+    pub fn get_declared_inputs(&self, config: &Config) -> Result<Vec<String>> {
+        fn collect(
+            profile: &Profile,
+            config: &Config,
+            visited: &mut HashSet<String>,
+            inputs: &mut Vec<String>,
+        ) -> Result<()> {
+            for entry in profile.filters.iter() {
+                match entry {
+                    FilterRunner::Filter {
+                        filter_name,
+                        inputs: entry_inputs,
+                        ..
+                    } => {
+                        if let Some(entry_inputs) = entry_inputs {
+                            inputs.extend(entry_inputs.iter().cloned());
+                        }
+                        let filter = config.get_filter(filter_name)?;
+                        let context = FilterContext::new(filter_name, &filter)?;
+                        if let Some(remote_config) = context.remote_config {
+                            inputs.extend(remote_config.inputs);
+                        }
+                    }
+                    FilterRunner::ProfileFilter { profile_name } => {
+                        if visited.insert(profile_name.to_owned()) {
+                            let profile = config.get_profile(profile_name)?;
+                            collect(profile, config, visited, inputs)?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        let mut inputs = vec![];
+        collect(self, config, &mut HashSet::new(), &mut inputs)?;
+        Ok(inputs)
+    }
 }
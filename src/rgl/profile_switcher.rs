@@ -0,0 +1,63 @@
+use crate::{log, warn};
+use smol::channel;
+use std::{io, thread};
+
+/// Reads profile names or numbers from stdin, letting `rgl watch` switch between the given
+/// profiles without restarting.
+pub struct ProfileSwitcher {
+    switch_rx: channel::Receiver<usize>,
+}
+
+impl ProfileSwitcher {
+    pub fn new(profiles: &[String]) -> Self {
+        let (switch_tx, switch_rx) = channel::unbounded();
+        let names = profiles.to_owned();
+        thread::spawn(move || {
+            for line in io::stdin().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let input = line.trim();
+                if input.is_empty() {
+                    continue;
+                }
+                let index = match input.parse::<usize>() {
+                    Ok(number) if (1..=names.len()).contains(&number) => Some(number - 1),
+                    _ => names.iter().position(|name| name == input),
+                };
+                match index {
+                    Some(index) => {
+                        if switch_tx.send_blocking(index).is_err() {
+                            break;
+                        }
+                    }
+                    None => warn!("Unknown profile <b>{input}</>"),
+                }
+            }
+        });
+        Self { switch_rx }
+    }
+
+    /// Prints the profiles that can be switched to
+    pub fn print_help(profiles: &[String], active: usize) {
+        log!("<cyan>[PROFILES]</> Type a profile name or number and press Enter to switch:");
+        for (i, profile) in profiles.iter().enumerate() {
+            match i == active {
+                true => log!("  {}) <profile>{profile}</> <d>(active)</>", i + 1),
+                false => log!("  {}) <profile>{profile}</>", i + 1),
+            }
+        }
+    }
+
+    /// Waits until another profile is selected, returning its index
+    pub async fn wait_switch(&self) -> usize {
+        let Ok(mut index) = self.switch_rx.recv().await else {
+            // Stdin was closed
+            return smol::future::pending().await;
+        };
+        while let Ok(next) = self.switch_rx.try_recv() {
+            index = next;
+        }
+        index
+    }
+}
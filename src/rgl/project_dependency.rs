@@ -0,0 +1,352 @@
+use super::{get_current_dir, get_project_cache_dir, normalize_path, Session, Subprocess};
+use crate::fs::{read_json, write_json};
+use crate::logger::Logger;
+use crate::{debug, info, warn};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    time::UNIX_EPOCH,
+};
+use walkdir::WalkDir;
+
+/// Projects whose dependencies are being built, passed down to catch circular dependencies
+const DEPENDENCY_CHAIN_VAR: &str = "RGL_DEPENDENCY_CHAIN";
+
+/// Dependencies built during this process, by their project directory. A dependency is built
+/// again when its files change, e.g. while watching.
+static BUILT: LazyLock<Mutex<HashMap<PathBuf, BuiltDependency>>> = LazyLock::new(Default::default);
+
+struct BuiltDependency {
+    /// Fingerprint of the project files after the build, see [`get_fingerprint`]
+    fingerprint: String,
+    packs: DependencyPacks,
+}
+
+/// Another rgl project whose packs are built before the profile runs
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectDependency {
+    /// Git repository of the project, cloned into the cache directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git: Option<String>,
+    /// Branch, tag or commit to check out, the default branch if unset
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    git_ref: Option<String>,
+    /// Directory of the project, relative to the repository if `git` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// Profile used to build the project
+    #[serde(default = "default_profile")]
+    profile: String,
+    #[serde(default)]
+    mode: DependencyMode,
+}
+
+fn default_profile() -> String {
+    "build".to_owned()
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyMode {
+    /// Copies the packs of the dependency into the packs of the project
+    #[default]
+    Merge,
+    /// Adds the packs of the dependency to the `dependencies` of the project's manifests, the
+    /// dependency must be exported where the game can find it
+    Attach,
+}
+
+/// Packs exported by a dependency
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DependencyPacks {
+    export_bp: Option<PathBuf>,
+    export_rp: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct PathsOutput {
+    project: Option<DependencyPacks>,
+}
+
+impl ProjectDependency {
+    /// Builds the dependency, then merges or attaches its packs to the temp packs of the project
+    pub fn apply(&self, name: &str, temp_bp: &Path, temp_rp: &Path) -> Result<()> {
+        let packs = self.build(name)?;
+        let pairs = [(&packs.export_bp, temp_bp), (&packs.export_rp, temp_rp)];
+        for (source, target) in pairs {
+            let Some(source) = source.as_ref().filter(|source| source.is_dir()) else {
+                continue;
+            };
+            // Packs without a manifest, e.g. an empty resource pack, can't be attached
+            let has_manifest = source.join("manifest.json").is_file();
+            if self.mode == DependencyMode::Attach && !has_manifest {
+                continue;
+            }
+            if !target.is_dir() {
+                warn!(
+                    "Dependency <b>{name}</> has a pack the project doesn't have\n\
+                     <yellow> >></> Path: {}",
+                    source.display()
+                );
+                continue;
+            }
+            match self.mode {
+                DependencyMode::Merge => merge_pack(source, target)?,
+                DependencyMode::Attach => attach_pack(source, target)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn build(&self, name: &str) -> Result<DependencyPacks> {
+        // The repository is kept locked until the build is done, so other projects can't check
+        // out another ref in the middle of it
+        let (project_dir, repo_lock) = self.resolve(name)?;
+        if !project_dir.is_dir() {
+            bail!(
+                "Failed to find dependency <b>{name}</>\n\
+                 <yellow> >></> Path: {}",
+                project_dir.display()
+            );
+        }
+        if let Some(built) = BUILT.lock().unwrap().get(&project_dir) {
+            if built.fingerprint == get_fingerprint(&project_dir) {
+                return Ok(built.packs.to_owned());
+            }
+        }
+
+        let mut chain = get_dependency_chain();
+        chain.push(normalize_path(Path::new("."))?);
+        if chain.contains(&project_dir) {
+            chain.push(project_dir);
+            let chain = chain
+                .iter()
+                .map(|dir| dir.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n<yellow> >></> ");
+            bail!("Found circular dependency between projects\n<yellow> >></> {chain}");
+        }
+        let chain_var = env::join_paths(&chain)?;
+
+        info!(
+            "Building dependency <b>{name}</> with the <profile>{}</> profile",
+            self.profile
+        );
+        let exe = env::current_exe()?;
+        let mut global_args = vec![];
+        if Logger::get_json() {
+            global_args.extend(["--log-format", "json"]);
+        }
+        Subprocess::new(&exe)
+            .args(["run", &self.profile])
+            .args(&global_args)
+            .current_dir(&project_dir)
+            .env(DEPENDENCY_CHAIN_VAR, &chain_var)
+            .run()
+            .with_context(|| format!("Failed to build dependency <b>{name}</>"))?;
+        // Written to a file, the output of the command can contain log messages
+        let hash = format!(
+            "{:x}",
+            Sha256::digest(project_dir.as_os_str().as_encoded_bytes())
+        );
+        let paths_file = get_current_dir()?
+            .join(".regolith")
+            .join("dependencies")
+            .join(format!("{}.json", &hash[..16]));
+        fs::create_dir_all(paths_file.parent().unwrap())?;
+        Subprocess::new(&exe)
+            .args(["paths", "--json", "--profile", &self.profile, "--output"])
+            .arg(&paths_file)
+            .current_dir(&project_dir)
+            .env(DEPENDENCY_CHAIN_VAR, &chain_var)
+            .run_silent()
+            .with_context(|| {
+                format!("Failed to get the export paths of dependency <b>{name}</>")
+            })?;
+        let packs = read_json::<PathsOutput>(&paths_file)?
+            .project
+            .context("Dependency is not an rgl project")?;
+        if packs.export_bp.is_none() && packs.export_rp.is_none() {
+            bail!(
+                "The <profile>{}</> profile of dependency <b>{name}</> doesn't export any packs",
+                self.profile
+            );
+        }
+        let fingerprint = get_fingerprint(&project_dir);
+        BUILT.lock().unwrap().insert(
+            project_dir,
+            BuiltDependency {
+                fingerprint,
+                packs: packs.to_owned(),
+            },
+        );
+        if let Some(mut repo_lock) = repo_lock {
+            repo_lock.unlock()?;
+        }
+        Ok(packs)
+    }
+
+    /// Directory of the dependency, cloning or updating its repository first. The repository is
+    /// returned locked.
+    fn resolve(&self, name: &str) -> Result<(PathBuf, Option<Session>)> {
+        let path = self.path.as_deref().unwrap_or(".");
+        let Some(git) = &self.git else {
+            if self.path.is_none() {
+                bail!("Dependency <b>{name}</> must have a `path` or `git`");
+            }
+            return Ok((normalize_path(Path::new(path))?, None));
+        };
+        let url = match git.contains("://") {
+            true => git.to_owned(),
+            false => format!("https://{git}"),
+        };
+        let repo_dir = get_project_cache_dir()?.join(get_repo_key(&url));
+        // Other projects may depend on the same repository
+        let repo_lock = Session::lock_shared(&repo_dir)?;
+        if repo_dir.join(".git").is_dir() {
+            debug!("Fetching {url}");
+            Subprocess::new("git")
+                .args(["fetch", "--all"])
+                .current_dir(&repo_dir)
+                .run_silent()
+                .with_context(|| format!("Failed to fetch `{url}`"))?;
+        } else {
+            fs::create_dir_all(&repo_dir)?;
+            info!("Cloning dependency <b>{name}</> from {url}");
+            Subprocess::new("git")
+                .args(["clone", &url, "."])
+                .current_dir(&repo_dir)
+                .run_silent()
+                .with_context(|| format!("Failed to clone `{url}`"))?;
+        }
+        let git_ref = match &self.git_ref {
+            // Branches are checked out at their latest commit
+            Some(git_ref) => match has_remote_branch(&repo_dir, git_ref) {
+                true => format!("origin/{git_ref}"),
+                false => git_ref.to_owned(),
+            },
+            None => "origin/HEAD".to_owned(),
+        };
+        debug!("Checkout ref: {git_ref}");
+        Subprocess::new("git")
+            .args(["checkout", "--detach", &git_ref])
+            .current_dir(&repo_dir)
+            .run_silent()
+            .with_context(|| format!("Failed to checkout `{git_ref}`"))?;
+        Ok((normalize_path(&repo_dir.join(path))?, Some(repo_lock)))
+    }
+}
+
+/// Relative path of the repository in the cache, e.g. `github.com/user/repo`
+fn get_repo_key(url: &str) -> PathBuf {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    url.trim_end_matches(".git")
+        .split(['/', '\\', ':'])
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect()
+}
+
+fn has_remote_branch(repo_dir: &Path, branch: &str) -> bool {
+    Subprocess::new("git")
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("refs/remotes/origin/{branch}"))
+        .current_dir(repo_dir)
+        .run_silent()
+        .is_ok()
+}
+
+/// Fingerprint of the files of a project, from their paths, sizes and modified times. Hidden
+/// directories like `.regolith` and `.git` are left out, they change without the project changing.
+fn get_fingerprint(project_dir: &Path) -> String {
+    let mut hasher = Sha256::new();
+    let entries = WalkDir::new(project_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(|entry| entry.ok());
+    for entry in entries {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        hasher.update(entry.path().as_os_str().as_encoded_bytes());
+        hasher.update(metadata.len().to_le_bytes());
+        hasher.update(modified.as_nanos().to_le_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn get_dependency_chain() -> Vec<PathBuf> {
+    env::var_os(DEPENDENCY_CHAIN_VAR)
+        .map(|chain| env::split_paths(&chain).collect())
+        .unwrap_or_default()
+}
+
+/// Copies the files of the dependency that the project doesn't have
+fn merge_pack(source: &Path, target: &Path) -> Result<()> {
+    for entry in WalkDir::new(source).min_depth(1) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(source)?;
+        let target = target.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if !target.exists() {
+            fs::copy(entry.path(), &target).with_context(|| {
+                format!(
+                    "Failed to copy dependency file\n\
+                     <yellow> >></> Path: {}",
+                    entry.path().display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the header of the dependency's manifest to the dependencies of the project's manifest
+fn attach_pack(source: &Path, target: &Path) -> Result<()> {
+    let header = read_json::<Value>(source.join("manifest.json"))?["header"].to_owned();
+    let (Some(uuid), Some(version)) = (header["uuid"].as_str(), header.get("version")) else {
+        bail!(
+            "Dependency manifest has no header uuid or version\n\
+             <yellow> >></> Path: {}",
+            source.join("manifest.json").display()
+        );
+    };
+    let manifest_path = target.join("manifest.json");
+    let mut manifest = read_json::<Value>(&manifest_path)?;
+    let Some(manifest) = manifest.as_object_mut() else {
+        bail!(
+            "Manifest is not an object\n\
+             <yellow> >></> Path: {}",
+            manifest_path.display()
+        );
+    };
+    let dependencies = manifest
+        .entry("dependencies")
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .context("Manifest `dependencies` is not an array")?;
+    let is_attached = dependencies
+        .iter()
+        .any(|dependency| dependency["uuid"].as_str() == Some(uuid));
+    if !is_attached {
+        dependencies.push(json!({ "uuid": uuid, "version": version }));
+        write_json(&manifest_path, &manifest)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use qrcode::{Color, EcLevel};
+
+/// QR code of a short text like the `/connect` command, so it can be scanned from the terminal
+/// by a phone.
+pub struct QrCode {
+    size: usize,
+    modules: Vec<Color>,
+}
+
+impl QrCode {
+    pub fn encode(text: &str) -> Result<Self> {
+        let code = qrcode::QrCode::with_error_correction_level(text, EcLevel::L)?;
+        Ok(Self {
+            size: code.width(),
+            modules: code.to_colors(),
+        })
+    }
+
+    /// Renders the code with half blocks, two modules per line, in black on white so it scans on
+    /// dark terminals as well
+    pub fn to_terminal_string(&self) -> String {
+        const QUIET_ZONE: i32 = 4;
+        let size = self.size as i32;
+        let mut lines = vec![];
+        for y in (-QUIET_ZONE..size + QUIET_ZONE).step_by(2) {
+            let line = (-QUIET_ZONE..size + QUIET_ZONE)
+                .map(|x| match (self.is_dark(x, y), self.is_dark(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect::<String>();
+            lines.push(format!("\x1b[30;107m{line}\x1b[0m"));
+        }
+        lines.join("\n")
+    }
+
+    fn is_dark(&self, x: i32, y: i32) -> bool {
+        let size = self.size as i32;
+        (0..size).contains(&x)
+            && (0..size).contains(&y)
+            && self.modules[y as usize * self.size + x as usize] == Color::Dark
+    }
+}
@@ -0,0 +1,149 @@
+use super::{
+    add_world_pack, check_level_dat, enable_experiments, get_build_path, get_pack_header, Config,
+    Export, ExportPaths, WorldRef,
+};
+use crate::fs::{copy_dir, read_json, write_zip};
+use crate::{info, warn};
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::tempdir;
+
+/// Largest world Realms accepts as an upload
+const MAX_UPLOAD_SIZE: u64 = 500 * 1024 * 1024;
+
+/// Packages a world with the packs exported by a profile embedded in it, as a `.mcworld` that
+/// can replace the world of a Realm. Development packs are not available on a Realm, so the packs
+/// are copied into the world and activated there.
+pub fn package_realms_world(
+    config: &Config,
+    profile_name: &str,
+    world: Option<&WorldRef>,
+    output: &Path,
+) -> Result<()> {
+    let profile = config.get_profile(profile_name)?;
+    if matches!(profile.export, Export::None(_)) {
+        bail!("Publishing to Realms requires a profile that exports the packs");
+    }
+    let world_dir = match (world, &profile.export) {
+        (Some(world), export) => world.find(export.get_build().as_ref())?,
+        (None, Export::World(export)) => export.get_world_dir()?,
+        (None, _) => bail!("No world to publish, use <b>--world</> to select one"),
+    };
+    if !world_dir.join("level.dat").is_file() {
+        bail!(
+            "World has no level.dat\n\
+             <yellow> >></> Path: {}",
+            world_dir.display()
+        );
+    }
+    let (target_bp, target_rp) = profile.export.get_paths(config, profile_name)?;
+
+    let staging = tempdir()?;
+    let staged_world = staging.path().join("world");
+    copy_dir(&world_dir, &staged_world)?;
+    let packs = [
+        (
+            config.get_behavior_pack(),
+            target_bp,
+            "behavior_packs",
+            "world_behavior_packs.json",
+        ),
+        (
+            config.get_resource_pack(),
+            target_rp,
+            "resource_packs",
+            "world_resource_packs.json",
+        ),
+    ];
+    for (pack, target, packs_dir, file_name) in packs {
+        if pack.is_none() {
+            continue;
+        }
+        if !target.is_dir() {
+            bail!(
+                "Exported pack not found, run the profile first\n\
+                 <yellow> >></> Path: {}",
+                target.display()
+            );
+        }
+        let (pack_id, version) = get_pack_header(&target)?;
+        // The world export already writes the packs into the world
+        if !target.starts_with(&world_dir) {
+            let dir_name = target.file_name().context("Invalid export path")?;
+            copy_dir(&target, staged_world.join(packs_dir).join(dir_name))?;
+        }
+        add_world_pack(&staged_world.join(file_name), &pack_id, version)?;
+    }
+    enable_experiments(&staged_world, &profile.experiments)?;
+    check_realms_world(&staged_world)?;
+
+    // The game expects level.dat at the root of the archive
+    let size = write_zip(&[(&staged_world, "")], output)?;
+    if size > MAX_UPLOAD_SIZE {
+        fs::remove_file(output)?;
+        bail!(
+            "World is too large for Realms, {} MB of {} MB allowed",
+            size / 1024 / 1024,
+            MAX_UPLOAD_SIZE / 1024 / 1024
+        );
+    }
+    info!(
+        "Packaged world for Realms, {:.1} MB",
+        size as f64 / 1024.0 / 1024.0
+    );
+    Ok(())
+}
+
+/// Checks the parts of a world Realms needs, warning about the ones the upload works without
+fn check_realms_world(world_dir: &Path) -> Result<()> {
+    check_level_dat(world_dir)?;
+    if !world_dir.join("db").is_dir() {
+        warn!("World has never been opened in the game, Realms will generate it from the seed");
+    }
+    for (packs_dir, file_name) in [
+        ("behavior_packs", "world_behavior_packs.json"),
+        ("resource_packs", "world_resource_packs.json"),
+    ] {
+        let world_file = world_dir.join(file_name);
+        if !world_file.is_file() {
+            continue;
+        }
+        let embedded = get_embedded_pack_ids(&world_dir.join(packs_dir))?;
+        let world_packs: Vec<Value> = read_json(&world_file)?;
+        for entry in world_packs {
+            let Some(pack_id) = entry["pack_id"].as_str() else {
+                continue;
+            };
+            if !embedded.iter().any(|id| id == pack_id) {
+                warn!(
+                    "Pack <b>{pack_id}</> in {file_name} is not embedded in the world, \
+                     it will be missing on the Realm unless it is a Marketplace pack"
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn get_embedded_pack_ids(packs_dir: &Path) -> Result<Vec<String>> {
+    let mut ids = vec![];
+    if !packs_dir.is_dir() {
+        return Ok(ids);
+    }
+    for entry in packs_dir.read_dir()? {
+        let path = entry?.path();
+        if path.join("manifest.json").is_file() {
+            ids.push(get_pack_header(&path)?.0);
+        }
+    }
+    Ok(ids)
+}
+
+/// Default location of the packaged world
+pub fn get_realms_world_path(config: &Config) -> PathBuf {
+    get_build_path(config, "mcworld")
+}
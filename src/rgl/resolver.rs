@@ -17,6 +17,14 @@ pub struct Resolver {
 struct ResolverData {
     url: String,
     versions: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+pub struct ResolverEntry {
+    pub name: String,
+    pub url: String,
+    pub description: Option<String>,
 }
 
 impl Resolver {
@@ -28,6 +36,22 @@ impl Resolver {
             .with_context(|| format!("Failed to resolve filter <filter>{name}</>"))
     }
 
+    /// Every filter known to the configured resolvers, sorted by name
+    pub fn list() -> Result<Vec<ResolverEntry>> {
+        let resolver = get_resolver().context("Failed to load filter resolver")?;
+        let mut entries = resolver
+            .filters
+            .iter()
+            .map(|(name, data)| ResolverEntry {
+                name: name.to_owned(),
+                url: data.url.to_owned(),
+                description: data.description.to_owned(),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
     pub fn resolve_url(name: &str) -> Result<String> {
         Self::get(name).map(|data| data.url.to_owned())
     }
@@ -0,0 +1,197 @@
+use super::{get_config_script, get_current_dir, normalize_path, Config, FilterDefinition};
+use crate::fs::{read_json, write_json};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use walkdir::WalkDir;
+
+/// Last successful run of each profile, and the last failed run if it came after it
+#[derive(Default, Serialize, Deserialize)]
+pub struct RunState {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, RunRecord>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures: BTreeMap<String, RunFailure>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    /// Milliseconds since the Unix epoch
+    pub finished_at: u64,
+    /// Commit checked out in the project, if it's a git repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// Version of each remote filter the profile ran
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub filter_versions: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub export_targets: Vec<PathBuf>,
+    /// Script the config was evaluated from, instead of config.json
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_script: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunFailure {
+    /// Milliseconds since the Unix epoch
+    pub failed_at: u64,
+    pub error: String,
+}
+
+pub fn get_run_state_path() -> PathBuf {
+    PathBuf::from(".rgl").join("state.json")
+}
+
+/// Where the state was kept before it moved to `.rgl`
+fn get_legacy_run_state_path() -> PathBuf {
+    PathBuf::from(".regolith").join("state.json")
+}
+
+impl RunState {
+    pub fn load() -> Result<Self> {
+        let path = get_run_state_path();
+        let legacy_path = get_legacy_run_state_path();
+        if path.is_file() {
+            read_json(path)
+        } else if legacy_path.is_file() {
+            read_json(legacy_path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = get_run_state_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_json(path, self)
+    }
+
+    /// Records a successful run of the profile, with the export targets the run resolved
+    pub fn record(config: &Config, profile_name: &str, export_targets: &[&Path]) -> Result<()> {
+        let profile = config.get_profile(profile_name)?;
+        let mut filter_versions = BTreeMap::new();
+        for name in profile.get_filter_names(config)? {
+            if let Ok(FilterDefinition::Remote(remote)) = config.get_filter(&name) {
+                filter_versions.insert(name, remote.version);
+            }
+        }
+        let mut targets = vec![];
+        for target in export_targets {
+            let target = normalize_path(target)?;
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        let record = RunRecord {
+            finished_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64,
+            git_commit: get_git_commit(),
+            filter_versions,
+            export_targets: targets,
+            config_script: get_config_script().map(PathBuf::from),
+        };
+
+        let mut state = Self::load().unwrap_or_default();
+        state.profiles.insert(profile_name.to_owned(), record);
+        state.failures.remove(profile_name);
+        state.save()
+    }
+
+    /// Records a failed run of the profile, keeping the last successful one
+    pub fn record_failure(profile_name: &str, error: &anyhow::Error) -> Result<()> {
+        let failure = RunFailure {
+            failed_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64,
+            error: format!("{error:#}"),
+        };
+        let mut state = Self::load().unwrap_or_default();
+        state.failures.insert(profile_name.to_owned(), failure);
+        state.save()
+    }
+}
+
+impl RunFailure {
+    pub fn failed_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.failed_at)
+    }
+}
+
+impl RunRecord {
+    pub fn finished_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.finished_at)
+    }
+
+    /// Returns a source file, or the config, modified after the run finished
+    pub fn find_newer_source(&self, config: &Config) -> Option<PathBuf> {
+        let finished_at = self.finished_at();
+        let dirs = [
+            config.get_behavior_pack(),
+            config.get_resource_pack(),
+            Some(config.get_data_path()),
+        ];
+        let is_newer = |path: &Path| {
+            path.metadata()
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified > finished_at)
+        };
+        let config_path = get_config_script().unwrap_or("config.json");
+        if is_newer(Path::new(config_path)) {
+            return Some(PathBuf::from(config_path));
+        }
+        dirs.into_iter()
+            .flatten()
+            .flat_map(WalkDir::new)
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .find(|path| is_newer(path))
+    }
+}
+
+/// Commit checked out in the git repository of the project, read from the repository files
+/// instead of running git, as it's recorded after every run. Projects outside a git repository
+/// have none.
+fn get_git_commit() -> Option<String> {
+    let mut dir = get_current_dir().ok()?;
+    let git_dir = loop {
+        let git = dir.join(".git");
+        if git.is_dir() {
+            break git;
+        }
+        // Worktrees and submodules have a file pointing to the git directory
+        if let Ok(file) = fs::read_to_string(&git) {
+            break dir.join(file.strip_prefix("gitdir:")?.trim());
+        }
+        if !dir.pop() {
+            return None;
+        }
+    };
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let Some(reference) = head.trim().strip_prefix("ref:") else {
+        return Some(head.trim().to_owned());
+    };
+    let reference = reference.trim();
+    if let Ok(commit) = fs::read_to_string(git_dir.join(reference)) {
+        return Some(commit.trim().to_owned());
+    }
+    // Worktrees share the refs of the main repository
+    let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => git_dir.join(common_dir.trim()),
+        Err(_) => git_dir,
+    };
+    if let Ok(commit) = fs::read_to_string(common_dir.join(reference)) {
+        return Some(commit.trim().to_owned());
+    }
+    let packed_refs = fs::read_to_string(common_dir.join("packed-refs")).ok()?;
+    packed_refs.lines().find_map(|line| {
+        let (commit, name) = line.split_once(' ')?;
+        (name == reference).then(|| commit.to_owned())
+    })
+}
@@ -1,28 +1,190 @@
-use super::{Config, Export, ExportPaths, Temp};
-use crate::fs::{rimraf, symlink, sync_dir};
-use crate::{debug, info, measure_time};
-use anyhow::{Context, Result};
-use std::fs;
+use super::{
+    check_cloud_files, check_education_profile, check_export_target, check_file_names,
+    check_pack_collisions, copy_to_temp, emit_build_event, enable_experiments, get_sync_manifest,
+    mark_export_target, normalize_path, start_filter_logs, write_changed_files,
+    write_content_hashes, BuildEvent, Config, DataSnapshot, Export, ExportPaths, FilterSelection,
+    Profile, RunEnv, RunState, Temp,
+};
+use crate::file_watcher::record_own_writes;
+use crate::fs::{
+    copy_dir, empty_dir, move_path, rimraf, symlink, sync_dir, wait_for_unlock, SymlinkPolicy,
+    SyncOptions, SyncStats,
+};
+use crate::progress::Progress;
+use crate::{debug, error, info, log, measure_time, warn};
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
 
-pub async fn runner(config: &Config, profile_name: &str, clean: bool, compat: bool) -> Result<()> {
+/// Set when the linked temp packs broke during a run, compat mode is used from then on
+static COMPAT_FALLBACK: AtomicBool = AtomicBool::new(false);
+
+/// Runs the profile on a copy of the sources in its temp directory without exporting, and returns
+/// the temp directory with the names of the filters that failed without aborting the run.
+pub async fn run_in_temp(config: &Config, profile_name: &str) -> Result<(Temp, Vec<String>)> {
+    let profile = config.get_profile(profile_name)?;
+    profile.check_nesting(profile_name, config)?;
+    profile.check_settings(config)?;
+    let data = config.get_data_path();
+    let symlinks = config.get_symlink_policy();
+
+    let temp = get_temp(config, profile)?;
+    empty_dir(&temp.root)?;
+    if let Some(bp) = config.get_behavior_pack() {
+        copy_to_temp(&bp, &temp.bp, symlinks)?;
+    }
+    if let Some(rp) = config.get_resource_pack() {
+        copy_to_temp(&rp, &temp.rp, symlinks)?;
+    }
+    copy_to_temp(&data, &temp.data, symlinks)?;
+
+    info!("Running <profile>{profile_name}</> profile");
+    start_filter_logs()?;
+    RunEnv {
+        project_name: config.get_name().to_owned(),
+        profile_name: profile_name.to_owned(),
+        temp_dir: normalize_path(&temp.root)?,
+        data_dir: normalize_path(&data)?,
+        export_paths: None,
+    }
+    .set();
+    let mut failed_filters = vec![];
+    profile
+        .run(
+            config,
+            &temp.root,
+            profile_name,
+            &mut FilterSelection::default(),
+            &mut failed_filters,
+        )
+        .await?;
+    Ok((temp, failed_filters))
+}
+
+pub async fn runner(
+    config: &Config,
+    profile_name: &str,
+    clean: bool,
+    compat: bool,
+    changes: &[PathBuf],
+    selection: &FilterSelection,
+) -> Result<()> {
+    emit_build_event(BuildEvent::RunStarted {
+        profile: profile_name,
+    });
+    let result = run_profile(config, profile_name, clean, compat, changes, selection).await;
+    let inline = config
+        .get_profile(profile_name)
+        .is_ok_and(|profile| profile.inline);
+    match &result {
+        // The run failed already, the history is not worth another error
+        Err(e) if !inline => {
+            let _ = RunState::record_failure(profile_name, e);
+        }
+        _ => {}
+    }
+    emit_build_event(BuildEvent::RunFinished {
+        error: result.as_ref().err(),
+    });
+    result
+}
+
+async fn run_profile(
+    config: &Config,
+    profile_name: &str,
+    clean: bool,
+    compat: bool,
+    changes: &[PathBuf],
+    selection: &FilterSelection,
+) -> Result<()> {
     let bp = config.get_behavior_pack();
     let rp = config.get_resource_pack();
     let data = config.get_data_path();
+    let data_namespace = config.get_data_namespace_path();
 
     let profile = config.get_profile(profile_name)?;
+    check_education_profile(profile)?;
     let (target_bp, target_rp) = profile
         .export
-        .get_paths(config.get_name(), profile_name)
+        .get_paths(config, profile_name)
         .context("Failed to get export paths")?;
+    let mirror_paths: Vec<_> = profile
+        .export
+        .get_mirror_paths(config, profile_name)
+        .context("Failed to get export paths")?
+        .into_iter()
+        .filter(|(bp, rp)| *bp != target_bp || *rp != target_rp)
+        .collect();
     let is_none_export = matches!(profile.export, Export::None(_));
+    profile.check_nesting(profile_name, config)?;
+    profile.check_settings(config)?;
+    selection.check(profile_name, &profile.get_filter_names(config)?)?;
 
-    let temp = Temp::from_dot_regolith();
+    let compat = is_compat(compat, profile);
+    let symlinks = config.get_symlink_policy();
+    let temp = get_temp(config, profile)?;
+    let source_snapshots: Vec<_> = [&bp, &rp]
+        .into_iter()
+        .flatten()
+        .map(|dir| (dir, DataSnapshot::new(dir)))
+        .collect();
 
+    // The export target and its mirrors
+    let export_paths: Vec<(PathBuf, PathBuf)> = match is_none_export {
+        true => vec![],
+        false => std::iter::once((target_bp.to_owned(), target_rp.to_owned()))
+            .chain(mirror_paths.iter().cloned())
+            .collect(),
+    };
+    // Directories the packs of the project are exported to
+    let mut export_targets: Vec<&Path> = vec![];
+    for (target_bp, target_rp) in &export_paths {
+        if bp.is_some() {
+            export_targets.push(target_bp);
+        }
+        if rp.is_some() {
+            export_targets.push(target_rp);
+        }
+    }
+    export_targets.sort();
+    export_targets.dedup();
+    // Filters write to linked packs directly and atomic exports replace the whole target, so the
+    // files an export changed are counted against the previous contents of the targets
+    let target_snapshots: Vec<_> = export_targets
+        .iter()
+        .map(|dir| (*dir, DataSnapshot::new(dir)))
+        .collect();
     measure_time!("Setup temp", {
+        for dir in [&bp, &rp].into_iter().flatten() {
+            check_cloud_files(dir);
+        }
+        check_cloud_files(&data);
+        if !is_none_export {
+            check_cloud_files(&target_bp);
+            check_cloud_files(&target_rp);
+            for (target_bp, target_rp) in &export_paths {
+                recover_export(target_bp)?;
+                recover_export(target_rp)?;
+                check_export_target(target_bp)?;
+                check_export_target(target_rp)?;
+            }
+        }
+        // Outputs of earlier runs are only kept when filters are told what changed
+        let incremental = write_changed_files(config, if clean { &[] } else { changes })?;
         if clean {
             rimraf(&temp.root)?;
             rimraf(&target_bp)?;
             rimraf(&target_rp)?;
+            for (mirror_bp, mirror_rp) in &mirror_paths {
+                rimraf(mirror_bp)?;
+                rimraf(mirror_rp)?;
+            }
         }
         fs::create_dir_all(&data)?;
         fs::create_dir_all(&temp.root)?;
@@ -37,60 +199,577 @@ pub async fn runner(config: &Config, profile_name: &str, clean: bool, compat: bo
                 rimraf(&temp.data)?;
             }
             if let Some(bp) = &bp {
-                sync_dir(bp, &temp.bp)?;
+                sync_tracked(bp, &temp.bp, symlinks, incremental)?;
             }
             if let Some(rp) = &rp {
-                sync_dir(rp, &temp.rp)?;
+                sync_tracked(rp, &temp.rp, symlinks, incremental)?;
+            }
+            sync_tracked(&data, &temp.data, symlinks, incremental)?;
+            if let Some(data_namespace) = &data_namespace {
+                if data_namespace.is_dir() {
+                    copy_dir(data_namespace, &temp.data)?;
+                }
             }
-            sync_dir(&data, &temp.data)?;
         } else {
             rimraf(&temp.bp)?;
             rimraf(&temp.rp)?;
             if temp.data.is_symlink() {
                 rimraf(&temp.data)?;
             }
+            // The packs are synced to the target before the filters run, so invalid names must be
+            // caught in the source
+            for pack in [&bp, &rp].into_iter().flatten() {
+                check_file_names(pack)?;
+            }
             if let Some(bp) = &bp {
-                sync_dir(bp, &target_bp)?;
+                wait_for_unlock(&target_bp, || {
+                    sync_tracked(bp, &target_bp, symlinks, incremental)
+                })?;
                 symlink(&target_bp, &temp.bp)?;
+                mark_export_target(&target_bp)?;
             }
             if let Some(rp) = &rp {
-                sync_dir(rp, &target_rp)?;
+                wait_for_unlock(&target_rp, || {
+                    sync_tracked(rp, &target_rp, symlinks, incremental)
+                })?;
                 symlink(&target_rp, &temp.rp)?;
+                mark_export_target(&target_rp)?;
+            }
+            check_filesystems(&temp, &target_bp, &target_rp);
+            sync_tracked(&data, &temp.data, symlinks, incremental)?;
+            if let Some(data_namespace) = &data_namespace {
+                if data_namespace.is_dir() {
+                    copy_dir(data_namespace, &temp.data)?;
+                }
             }
-            sync_dir(&data, &temp.data)?;
+        }
+        for (name, dependency) in config.get_dependencies() {
+            dependency.apply(name, &temp.bp, &temp.rp)?;
         }
     });
+    let data_export = data_namespace.as_ref().unwrap_or(&data);
+    let data_snapshot = DataSnapshot::new(data_export);
     smol::future::yield_now().await;
 
+    let mut failed_filters = vec![];
     measure_time!(profile_name, {
         info!("Running <profile>{profile_name}</> profile");
-        let export_data_names = profile.run(config, &temp.root, profile_name).await?;
+        start_filter_logs()?;
+        RunEnv {
+            project_name: config.get_name().to_owned(),
+            profile_name: profile_name.to_owned(),
+            temp_dir: normalize_path(&temp.root)?,
+            data_dir: normalize_path(&data)?,
+            export_paths: match is_none_export {
+                true => None,
+                false => Some((normalize_path(&target_bp)?, normalize_path(&target_rp)?)),
+            },
+        }
+        .set();
+        let export_data_names = profile
+            .run(
+                config,
+                &temp.root,
+                profile_name,
+                &mut selection.clone(),
+                &mut failed_filters,
+            )
+            .await?;
         for name in export_data_names {
             let filter_data = temp.data.join(&name);
             if filter_data.is_dir() {
                 debug!("Exporting data for filter <filter>{name}</>");
-                sync_dir(filter_data, data.join(name))?;
+                let target = data_export.join(&name);
+                if let Some(path) = data_snapshot.find_change(&target) {
+                    bail!(
+                        "Data for filter <filter>{name}</> was modified by another process during the run\n\
+                         <yellow> >></> Path: {}\n\
+                         <yellow> >></> Set `dataNamespace` in config.json if the data path is shared",
+                        path.display()
+                    );
+                }
+                sync_tracked(&filter_data, &target, symlinks, false)?;
+                record_own_writes(&target);
             }
         }
     });
 
     measure_time!("Export project", {
+        if bp.is_some() {
+            check_file_names(&temp.bp)?;
+        }
+        if rp.is_some() {
+            check_file_names(&temp.rp)?;
+        }
+        if let Some(limits) = &profile.limits {
+            if bp.is_some() {
+                limits.check("Behavior pack", &temp.bp)?;
+            }
+            if rp.is_some() {
+                limits.check("Resource pack", &temp.rp)?;
+            }
+        }
         info!("Exporting project to target location:");
-        let export = compat && !is_none_export;
+        let export =
+            !is_none_export && (compat || !check_temp_links(&temp, bp.is_some(), rp.is_some())?);
+        emit_build_event(BuildEvent::Export {
+            bp: bp.is_some().then_some(target_bp.as_path()),
+            rp: rp.is_some().then_some(target_rp.as_path()),
+        });
+        let mut exports = vec![];
         if bp.is_some() {
-            println!("\tBP: {}", target_bp.display());
+            log!("\tBP: {}", target_bp.display());
             if export {
-                sync_dir(&temp.bp, &target_bp)?;
+                exports.push((temp.bp.as_path(), target_bp.as_path()));
             }
         }
         if rp.is_some() {
-            println!("\tRP: {}", target_rp.display());
+            log!("\tRP: {}", target_rp.display());
             if export {
-                sync_dir(&temp.rp, &target_rp)?;
+                exports.push((temp.rp.as_path(), target_rp.as_path()));
+            }
+        }
+        for (mirror_bp, mirror_rp) in &mirror_paths {
+            emit_build_event(BuildEvent::Export {
+                bp: bp.is_some().then_some(mirror_bp.as_path()),
+                rp: rp.is_some().then_some(mirror_rp.as_path()),
+            });
+            if bp.is_some() {
+                log!("\tBP: {}", mirror_bp.display());
+                exports.push((temp.bp.as_path(), mirror_bp.as_path()));
+            }
+            if rp.is_some() {
+                log!("\tRP: {}", mirror_rp.display());
+                exports.push((temp.rp.as_path(), mirror_rp.as_path()));
+            }
+        }
+        export_packs(&exports, profile.atomic_export, symlinks)?;
+        if !is_none_export {
+            let mut export_stats = SyncStats::default();
+            for (dir, snapshot) in &target_snapshots {
+                export_stats += snapshot.get_stats(dir);
             }
+            log!("\tFiles: {export_stats}");
+        }
+        if profile.content_hashes && !is_none_export {
+            debug!("Writing content hashes");
+            for target in &export_targets {
+                write_content_hashes(target)?;
+            }
+        }
+        for target in &export_targets {
+            mark_export_target(target)?;
+        }
+        if let Export::Development(_) = &profile.export {
+            check_pack_collisions(
+                bp.is_some().then_some(target_bp.as_path()),
+                rp.is_some().then_some(target_rp.as_path()),
+            );
+        }
+        if let Export::World(world) = &profile.export {
+            enable_experiments(&world.get_world_dir()?, &profile.experiments)?;
         }
     });
 
+    for (dir, snapshot) in &source_snapshots {
+        if let Some(path) = snapshot.find_change(dir) {
+            warn!(
+                "Source files changed during the run, the export may mix old and new content, run the profile again\n\
+                 <yellow> >></> Path: {}",
+                path.display()
+            );
+        }
+    }
+    if !failed_filters.is_empty() {
+        bail!(
+            "Completed the <profile>{profile_name}</> profile with {} failed filter(s): {}",
+            failed_filters.len(),
+            failed_filters.join(", ")
+        );
+    }
+    // The build succeeded, failing to record it is not worth an error
+    if !profile.inline {
+        if let Err(e) = RunState::record(config, profile_name, &export_targets) {
+            debug!("Failed to record the run: {e:#}");
+        }
+    }
     info!("Successfully ran the <profile>{profile_name}</> profile");
     Ok(())
 }
+
+/// Copies changed resource pack assets straight to the export target without running any filter.
+///
+/// Returns `false` when the changes can't be handled this way, e.g. when a non-asset file was
+/// changed or a filter declared the changed assets as its inputs.
+pub fn sync_assets(
+    config: &Config,
+    profile_name: &str,
+    compat: bool,
+    changes: &[PathBuf],
+) -> Result<bool> {
+    let Some(rp) = config.get_resource_pack() else {
+        return Ok(false);
+    };
+    let profile = config.get_profile(profile_name)?;
+    if changes.is_empty() || matches!(profile.export, Export::None(_)) {
+        return Ok(false);
+    }
+    // Content hashes are only written and limits only checked by a full run, and atomic exports
+    // replace the whole pack
+    if profile.content_hashes || profile.atomic_export || profile.limits.is_some() {
+        return Ok(false);
+    }
+
+    let rp_dir = normalize_path(&rp)?;
+    let mut assets = vec![];
+    for path in changes {
+        let path = normalize_path(path)?;
+        let Ok(relative) = path.strip_prefix(&rp_dir) else {
+            return Ok(false);
+        };
+        if !is_asset(relative) {
+            return Ok(false);
+        }
+        assets.push(relative.to_owned());
+    }
+    assets.sort();
+    assets.dedup();
+
+    let inputs = profile.get_declared_inputs(config)?;
+    for asset in &assets {
+        let temp_path = Path::new("RP").join(asset);
+        if let Some(input) = inputs.iter().find(|i| temp_path.starts_with(i)) {
+            debug!(
+                "Asset {} is an input of a filter ({input})",
+                asset.display()
+            );
+            return Ok(false);
+        }
+    }
+
+    let (_, target_rp) = profile.export.get_paths(config, profile_name)?;
+    let mut exports = vec![target_rp];
+    exports.extend(
+        profile
+            .export
+            .get_mirror_paths(config, profile_name)?
+            .into_iter()
+            .map(|(_, rp)| rp),
+    );
+    exports.dedup();
+    let mut targets = exports.clone();
+    if is_compat(compat, profile) {
+        targets.push(get_temp(config, profile)?.rp);
+    }
+
+    // Reported like a run, so observers such as the dashboard show the sync
+    emit_build_event(BuildEvent::RunStarted {
+        profile: profile_name,
+    });
+    info!("Syncing {} changed asset(s)", assets.len());
+    let result = copy_assets(&rp, &assets, &targets);
+    if result.is_ok() {
+        for target in &exports {
+            emit_build_event(BuildEvent::Export {
+                bp: None,
+                rp: Some(target),
+            });
+        }
+    }
+    emit_build_event(BuildEvent::RunFinished {
+        error: result.as_ref().err(),
+    });
+    result.map(|_| true)
+}
+
+/// Copies the changed assets of the resource pack to the targets, or removes them if they were
+/// deleted
+fn copy_assets(rp: &Path, assets: &[PathBuf], targets: &[PathBuf]) -> Result<()> {
+    for asset in assets {
+        let source = rp.join(asset);
+        for target in targets {
+            let target = target.join(asset);
+            if source.is_file() {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&source, &target)?;
+            } else if let Err(e) = fs::remove_file(&target) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Exports the packs to every target at once. A target that fails doesn't stop the others, the
+/// failures are reported together after all of them finished.
+fn export_packs(exports: &[(&Path, &Path)], atomic: bool, symlinks: SymlinkPolicy) -> Result<()> {
+    // Mirrors may share one of the packs with another target
+    let mut seen = HashSet::new();
+    let exports: Vec<_> = exports
+        .iter()
+        .filter(|(_, target)| seen.insert(*target))
+        .collect();
+    if let [(source, target)] = exports.as_slice() {
+        return export_pack(source, target, atomic, symlinks);
+    }
+    let results: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = exports
+            .iter()
+            .map(|(source, target)| {
+                s.spawn(move || {
+                    let progress = Progress::spinner(format!("Exporting {}", target.display()));
+                    let result = export_pack(source, target, atomic, symlinks);
+                    progress.finish();
+                    (target, result)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    let failed: Vec<_> = results
+        .into_iter()
+        .filter_map(|(target, result)| Some((target, result.err()?)))
+        .collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+    for (target, e) in &failed {
+        error!("Failed to export to <b>{}</>", target.display());
+        e.chain().for_each(|e| log!("<red>[+]</> {e}"));
+    }
+    bail!(
+        "Failed to export to {} of {} target(s)",
+        failed.len(),
+        exports.len()
+    )
+}
+
+/// Copies a pack to the export target. An atomic export copies the pack into a staging directory
+/// first and renames it into place, falling back to syncing the target if it can't be renamed,
+/// e.g. because the game has a file in it open.
+///
+/// Each step waits for locked files on its own, repeating the whole export after a rename could
+/// remove the only copy of the previous pack.
+fn export_pack(source: &Path, target: &Path, atomic: bool, symlinks: SymlinkPolicy) -> Result<()> {
+    let manifest = get_sync_manifest(target)?;
+    let options = SyncOptions::tracked(&manifest).symlinks(symlinks);
+    if !atomic {
+        wait_for_unlock(target, || sync_dir(source, target, options))?;
+        return Ok(());
+    }
+    let (staging, old) = get_staging_paths(target)?;
+    recover_export(target)?;
+    rimraf(&staging)?;
+    sync_dir(source, &staging, options)?;
+    if target.exists() {
+        if let Err(e) = fs::rename(target, &old) {
+            debug!("Failed to move {}: {e}", target.display());
+            rimraf(&staging)?;
+            wait_for_unlock(target, || sync_dir(source, target, options))?;
+            return Ok(());
+        }
+    }
+    if let Err(e) = wait_for_unlock(target, || Ok(fs::rename(&staging, target)?)) {
+        if old.exists() {
+            wait_for_unlock(target, || Ok(fs::rename(&old, target)?))?;
+        }
+        return Err(e).with_context(|| {
+            format!(
+                "Failed to move the staged pack into place\n\
+                 <yellow> >></> Path: {}",
+                target.display()
+            )
+        });
+    }
+    // The export is done, the previous pack is removed on the next export if it's still locked
+    if let Err(e) = rimraf(&old) {
+        debug!("Failed to remove the previous pack: {e:#}");
+    }
+    Ok(())
+}
+
+/// Moves the previous pack back to the target if an atomic export was interrupted between moving
+/// it away and moving the staged pack into place. Only looks for leftovers, nothing is created.
+fn recover_export(target: &Path) -> Result<()> {
+    let name = get_staging_name(target)?;
+    for dir in get_staging_dirs(target) {
+        let old = dir.join(format!("{name}.old"));
+        if !old.exists() {
+            continue;
+        }
+        if target.exists() {
+            // The export finished, only removing the previous pack failed
+            rimraf(&old)?;
+            continue;
+        }
+        warn!(
+            "Restoring the pack of an interrupted export\n\
+             <yellow> >></> Path: {}",
+            target.display()
+        );
+        move_path(&old, target)?;
+    }
+    Ok(())
+}
+
+/// Directories an atomic export may stage the packs of a target in: `.regolith/staging`, or next
+/// to the directory of the target when it's on another filesystem
+fn get_staging_dirs(target: &Path) -> [PathBuf; 2] {
+    let parent = target.parent().unwrap_or(Path::new("."));
+    let mut dir = parent.as_os_str().to_owned();
+    dir.push(".rgl_staging");
+    [
+        PathBuf::from(".regolith").join("staging"),
+        PathBuf::from(dir),
+    ]
+}
+
+/// Name of the staged pack, mirrors may export packs with the same name at once
+fn get_staging_name(target: &Path) -> Result<String> {
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let hash = format!(
+        "{:x}",
+        Sha256::digest(normalize_path(target)?.as_os_str().as_encoded_bytes())
+    );
+    Ok(format!("{name}-{}", &hash[..8]))
+}
+
+/// Paths an atomic export stages the pack and keeps the previous pack at. They are outside of the
+/// directory of the target, so the game never loads them as duplicate packs, and on the same
+/// filesystem as the target, so they can be renamed into place.
+fn get_staging_paths(target: &Path) -> Result<(PathBuf, PathBuf)> {
+    let parent = target.parent().unwrap_or(Path::new("."));
+    let [dot_regolith, next_to_target] = get_staging_dirs(target);
+    fs::create_dir_all(&dot_regolith)?;
+    fs::create_dir_all(parent)?;
+    let dir = match is_same_filesystem(&dot_regolith, parent) {
+        Some(true) => dot_regolith,
+        _ => next_to_target,
+    };
+    fs::create_dir_all(&dir)?;
+    let name = get_staging_name(target)?;
+    Ok((
+        dir.join(format!("{name}.staging")),
+        dir.join(format!("{name}.old")),
+    ))
+}
+
+/// Syncs the directories and records the synced files. An incremental sync only removes the files
+/// that were deleted from the source since the last sync into the target.
+fn sync_tracked(
+    source: &Path,
+    target: &Path,
+    symlinks: SymlinkPolicy,
+    incremental: bool,
+) -> Result<SyncStats> {
+    let manifest = get_sync_manifest(target)?;
+    let options = SyncOptions::tracked(&manifest)
+        .incremental(incremental)
+        .symlinks(symlinks);
+    sync_dir(source, target, options)
+}
+
+/// In-memory temp and atomic exports are only possible if filters don't write to the export
+/// target directly
+fn is_compat(compat: bool, profile: &Profile) -> bool {
+    // Linked packs are written to while the filters run, limits must be checked before export
+    compat
+        || profile.compat
+        || profile.limits.is_some()
+        || profile.in_memory
+        || profile.atomic_export
+        || COMPAT_FALLBACK.load(Ordering::Relaxed)
+}
+
+/// Checks that the temp packs still link to the export target. Filters that delete and recreate
+/// the pack folders replace the links, so their output would never reach the target.
+fn check_temp_links(temp: &Temp, has_bp: bool, has_rp: bool) -> Result<bool> {
+    let mut is_linked = true;
+    for (has_pack, pack) in [(has_bp, &temp.bp), (has_rp, &temp.rp)] {
+        if !has_pack || pack.is_symlink() {
+            continue;
+        }
+        // Exporting a pack deleted by a filter would wipe the target
+        if !pack.is_dir() {
+            bail!(
+                "A filter deleted a pack folder from the temp directory\n\
+                 <yellow> >></> Path: {}",
+                pack.display()
+            );
+        }
+        is_linked = false;
+    }
+    if !is_linked {
+        warn!(
+            "A filter replaced the pack folders in the temp directory, \
+             switching to <b>--compat</> mode for the rest of the session"
+        );
+        COMPAT_FALLBACK.store(true, Ordering::Relaxed);
+    }
+    Ok(is_linked)
+}
+
+/// Warns once if the linked packs are on another filesystem than the temp directory, in which
+/// case filters can't move files between the packs and the data folder.
+fn check_filesystems(temp: &Temp, target_bp: &Path, target_rp: &Path) {
+    static CHECKED: AtomicBool = AtomicBool::new(false);
+    if CHECKED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let is_same = [target_bp, target_rp]
+        .into_iter()
+        .filter(|target| target.exists())
+        .all(|target| is_same_filesystem(&temp.root, target).unwrap_or(true));
+    if !is_same {
+        warn!(
+            "The temp directory and the export target are on different drives, filters that \
+             move files between the packs and the data folder may fail. Run with <b>--compat</> if they do"
+        );
+    }
+}
+
+#[cfg(unix)]
+fn is_same_filesystem(a: &Path, b: &Path) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Some(a.metadata().ok()?.dev() == b.metadata().ok()?.dev())
+}
+
+#[cfg(windows)]
+fn is_same_filesystem(a: &Path, b: &Path) -> Option<bool> {
+    let prefix = |path: &Path| match dunce::canonicalize(path).ok()?.components().next()? {
+        Component::Prefix(prefix) => Some(prefix.as_os_str().to_ascii_lowercase()),
+        _ => None,
+    };
+    Some(prefix(a)? == prefix(b)?)
+}
+
+pub fn get_temp(config: &Config, profile: &Profile) -> Result<Temp> {
+    if profile.in_memory {
+        if let Some(temp) = Temp::in_memory(config.get_name()) {
+            temp.claim_in_memory()?;
+            return Ok(temp);
+        }
+        warn!("RAM-backed temp directory is not available on this system");
+    }
+    match &profile.temp_dir {
+        Some(temp_dir) => Temp::from_temp_dir(temp_dir),
+        None => Ok(Temp::from_dot_regolith()),
+    }
+}
+
+/// Textures and sounds, excluding their JSON definition files
+fn is_asset(path: &Path) -> bool {
+    let is_asset_dir = matches!(
+        path.components().next(),
+        Some(Component::Normal(dir)) if dir == "textures" || dir == "sounds"
+    );
+    let is_json = path.extension().is_none_or(|ext| ext == "json");
+    is_asset_dir && !is_json && !path.is_dir()
+}
@@ -0,0 +1,341 @@
+use super::{LocalFilter, UserConfig};
+use crate::warn;
+use anyhow::{bail, Result};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+use strum::Display;
+
+/// Programs already resolved by [`resolve_runtime`], so each is only looked up and warned about once
+static RESOLVED: LazyLock<Mutex<HashMap<String, PathBuf>>> = LazyLock::new(Default::default);
+
+/// Packages of a filter runtime in the package managers `rgl env install` uses
+pub struct RuntimePackage {
+    pub program: &'static str,
+    pub winget: &'static str,
+    pub scoop: &'static str,
+    pub brew: &'static str,
+    /// Not every runtime is packaged for apt
+    pub apt: Option<&'static str>,
+    pub download: &'static str,
+}
+
+pub const RUNTIME_PACKAGES: [RuntimePackage; 5] = [
+    RuntimePackage {
+        program: "node",
+        winget: "OpenJS.NodeJS.LTS",
+        scoop: "nodejs-lts",
+        brew: "node",
+        apt: Some("nodejs npm"),
+        download: "https://nodejs.org/en/download/prebuilt-installer",
+    },
+    RuntimePackage {
+        program: "python",
+        winget: "Python.Python.3.12",
+        scoop: "python",
+        brew: "python",
+        apt: Some("python3 python3-venv python-is-python3"),
+        download: "https://www.python.org/downloads",
+    },
+    RuntimePackage {
+        program: "deno",
+        winget: "DenoLand.Deno",
+        scoop: "deno",
+        brew: "deno",
+        apt: None,
+        download: "https://docs.deno.com/runtime/#install-deno",
+    },
+    RuntimePackage {
+        program: "bun",
+        winget: "Oven-sh.Bun",
+        scoop: "bun",
+        brew: "oven-sh/bun/bun",
+        apt: None,
+        download: "https://bun.sh/docs/installation",
+    },
+    RuntimePackage {
+        program: "go",
+        winget: "GoLang.Go",
+        scoop: "go",
+        brew: "go",
+        apt: Some("golang-go"),
+        download: "https://go.dev/doc/install",
+    },
+];
+
+impl RuntimePackage {
+    /// Package of a runtime program, e.g. `python3` or `node.exe`
+    pub fn find(program: &str) -> Option<&'static RuntimePackage> {
+        let name = Path::new(program).file_stem()?.to_string_lossy();
+        let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        RUNTIME_PACKAGES
+            .iter()
+            .find(|package| package.program == name)
+    }
+}
+
+/// Program of the runtime a local filter runs with, if it needs one
+pub fn get_filter_runtime(filter: &LocalFilter) -> Option<String> {
+    match filter {
+        LocalFilter::Bun(_) => Some("bun".to_owned()),
+        LocalFilter::Deno(_) => Some("deno".to_owned()),
+        LocalFilter::Go(_) => Some("go".to_owned()),
+        LocalFilter::Nodejs(_) => Some(UserConfig::nodejs_runtime()),
+        LocalFilter::Python(_) => Some(UserConfig::python_command()),
+        _ => None,
+    }
+}
+
+/// Whether the runtime program can be found
+pub fn is_runtime_installed(program: &str) -> bool {
+    resolve_runtime(program).is_ok_and(|path| path.is_file())
+}
+
+#[derive(Clone, Copy, PartialEq, Display)]
+enum Arch {
+    #[strum(serialize = "x86")]
+    X86,
+    #[strum(serialize = "x64")]
+    X64,
+    #[strum(serialize = "ARM64")]
+    Arm64,
+}
+
+enum Support {
+    Native,
+    Emulated,
+    Unsupported,
+}
+
+/// Resolves the program of a filter runtime, e.g. `node`. The path set in `runtime_paths` of the
+/// user config is used as is, otherwise the first program in PATH that is built for the
+/// architecture of the machine is preferred.
+pub fn resolve_runtime(program: &str) -> Result<PathBuf> {
+    if let Some(path) = RESOLVED.lock().unwrap().get(program) {
+        return Ok(path.to_owned());
+    }
+    let path = find_runtime(program)?;
+    RESOLVED
+        .lock()
+        .unwrap()
+        .insert(program.to_owned(), path.to_owned());
+    Ok(path)
+}
+
+fn find_runtime(program: &str) -> Result<PathBuf> {
+    if let Some(path) = UserConfig::runtime_path(program) {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            bail!(
+                "Runtime path of <b>{program}</> not found\n\
+                 <yellow> >></> Path: {}\n\
+                 <yellow> >></> Check `runtime_paths` in the user config",
+                path.display()
+            );
+        }
+        return Ok(path);
+    }
+    let candidates = find_in_path(program);
+    let (Some(host), Some(first)) = (get_host_arch(), candidates.first()) else {
+        // Not found programs are reported when they are run
+        return Ok(PathBuf::from(program));
+    };
+    let arches = candidates
+        .iter()
+        .map(|path| get_binary_arch(path))
+        .collect::<Vec<_>>();
+    // Scripts and universal binaries run anywhere
+    let native = candidates.iter().zip(&arches).find(|(_, arch)| {
+        arch.is_none_or(|arch| matches!(get_support(host, arch), Support::Native))
+    });
+    if let Some((path, _)) = native {
+        return Ok(path.to_owned());
+    }
+    let arch = arches[0].unwrap();
+    match get_support(host, arch) {
+        Support::Emulated => {
+            warn!(
+                "<b>{program}</> is built for {arch} and runs under emulation on this {host} machine, \
+                 install the {host} build for better performance and native modules\n\
+                 <yellow> >></> Path: {}",
+                first.display()
+            );
+            Ok(first.to_owned())
+        }
+        _ => bail!(
+            "<b>{program}</> is built for {arch}, which can't run on this {host} machine\n\
+             <yellow> >></> Path: {}\n\
+             <yellow> >></> Install the {host} build, or set its path in `runtime_paths` of the user config",
+            first.display()
+        ),
+    }
+}
+
+/// Executables with the name in each directory of PATH, in order
+fn find_in_path(program: &str) -> Vec<PathBuf> {
+    if Path::new(program).components().count() > 1 {
+        return vec![PathBuf::from(program)];
+    }
+    let Some(path) = env::var_os("PATH") else {
+        return vec![];
+    };
+    // Windows runs programs without an extension with the first extension of PATHEXT that exists
+    let file_names = match cfg!(windows) && Path::new(program).extension().is_none() {
+        true => env::var("PATHEXT")
+            .unwrap_or(".COM;.EXE;.BAT;.CMD".to_owned())
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| format!("{program}{}", ext.to_ascii_lowercase()))
+            .collect(),
+        false => vec![program.to_owned()],
+    };
+    env::split_paths(&path)
+        .filter_map(|dir| {
+            file_names
+                .iter()
+                .map(|file_name| dir.join(file_name))
+                .find(|path| path.is_file())
+        })
+        .collect()
+}
+
+fn get_support(host: Arch, binary: Arch) -> Support {
+    match (host, binary) {
+        (host, binary) if host == binary => Support::Native,
+        (Arch::X64, Arch::X86) => Support::Native,
+        // Windows on ARM emulates both, macOS emulates x64 with Rosetta
+        (Arch::Arm64, Arch::X64) if cfg!(any(windows, target_os = "macos")) => Support::Emulated,
+        (Arch::Arm64, Arch::X86) if cfg!(windows) => Support::Emulated,
+        _ => Support::Unsupported,
+    }
+}
+
+/// Architecture of the machine, which differs from the one rgl is built for when rgl itself runs
+/// under emulation
+#[cfg(windows)]
+fn get_host_arch() -> Option<Arch> {
+    type IsWow64Process2 = unsafe extern "system" fn(isize, *mut u16, *mut u16) -> i32;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> isize;
+        fn GetModuleHandleA(name: *const u8) -> isize;
+        fn GetProcAddress(module: isize, name: *const u8) -> Option<unsafe extern "system" fn()>;
+        fn IsWow64Process(process: isize, is_wow64: *mut i32) -> i32;
+    }
+    // IsWow64Process2 was added in Windows 10 1709, importing it statically would keep rgl from
+    // starting on older versions
+    // SAFETY: the names are null-terminated and the function has the signature of its docs
+    let is_wow64_process2 = unsafe {
+        let kernel32 = GetModuleHandleA(c"kernel32.dll".as_ptr().cast());
+        match kernel32 {
+            0 => None,
+            _ => GetProcAddress(kernel32, c"IsWow64Process2".as_ptr().cast())
+                .map(|f| std::mem::transmute::<_, IsWow64Process2>(f)),
+        }
+    };
+    let Some(is_wow64_process2) = is_wow64_process2 else {
+        // Versions without IsWow64Process2 only run on x86 and x64
+        let mut is_wow64 = 0;
+        // SAFETY: the pointer is valid for the duration of the call
+        let ok = unsafe { IsWow64Process(GetCurrentProcess(), &mut is_wow64) };
+        return match ok != 0 && is_wow64 != 0 {
+            true => Some(Arch::X64),
+            false => get_build_arch(),
+        };
+    };
+    let mut process_machine = 0;
+    let mut native_machine = 0;
+    // SAFETY: both pointers are valid for the duration of the call
+    let ok = unsafe {
+        is_wow64_process2(
+            GetCurrentProcess(),
+            &mut process_machine,
+            &mut native_machine,
+        )
+    };
+    match ok {
+        0 => get_build_arch(),
+        _ => get_pe_arch(native_machine),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn get_host_arch() -> Option<Arch> {
+    use super::Subprocess;
+    let translated = Subprocess::new("sysctl")
+        .args(["-in", "sysctl.proc_translated"])
+        .run_silent()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1");
+    match translated {
+        true => Some(Arch::Arm64),
+        false => get_build_arch(),
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn get_host_arch() -> Option<Arch> {
+    get_build_arch()
+}
+
+fn get_build_arch() -> Option<Arch> {
+    match env::consts::ARCH {
+        "x86" => Some(Arch::X86),
+        "x86_64" => Some(Arch::X64),
+        "aarch64" => Some(Arch::Arm64),
+        _ => None,
+    }
+}
+
+fn get_pe_arch(machine: u16) -> Option<Arch> {
+    match machine {
+        0x014c => Some(Arch::X86),
+        0x8664 => Some(Arch::X64),
+        0xaa64 => Some(Arch::Arm64),
+        _ => None,
+    }
+}
+
+/// Architecture from the header of a PE, ELF, or Mach-O executable. Scripts, universal binaries,
+/// and files that can't be read have none.
+fn get_binary_arch(path: &Path) -> Option<Arch> {
+    let mut header = [0; 1024];
+    let len = fs::File::open(path).ok()?.read(&mut header).ok()?;
+    let header = &header[..len];
+    let u16_at = |offset: usize| {
+        Some(u16::from_le_bytes(
+            header.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |offset: usize| {
+        Some(u32::from_le_bytes(
+            header.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    if header.starts_with(b"MZ") {
+        let pe = u32_at(0x3c)? as usize;
+        if header.get(pe..pe + 4)? != b"PE\0\0" {
+            return None;
+        }
+        return get_pe_arch(u16_at(pe + 4)?);
+    }
+    if header.starts_with(b"\x7fELF") {
+        return match u16_at(0x12)? {
+            0x03 => Some(Arch::X86),
+            0x3e => Some(Arch::X64),
+            0xb7 => Some(Arch::Arm64),
+            _ => None,
+        };
+    }
+    if header.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        return match u32_at(4)? {
+            0x0100_0007 => Some(Arch::X64),
+            0x0100_000c => Some(Arch::Arm64),
+            _ => None,
+        };
+    }
+    None
+}
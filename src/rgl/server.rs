@@ -1,29 +1,97 @@
-use crate::log;
-use anyhow::Result;
-use async_tungstenite::{accept_async, WebSocketSender};
+use super::{QrCode, SourceMaps, UserConfig};
+use crate::logger::Logger;
+use crate::{log, warn};
+use anyhow::{bail, Context, Result};
+use async_tungstenite::{accept_async, tungstenite::Message, WebSocketSender};
 use dashmap::DashMap;
-use serde_json::json;
+use serde_json::{json, Value};
 use smol::{
+    channel,
     net::{TcpListener, TcpStream},
     stream::StreamExt,
 };
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    env,
+    io::{self, Write},
+    net::{IpAddr, SocketAddr, UdpSocket},
+    process,
+    sync::{Arc, Mutex},
+};
 use uuid::Uuid;
 
+/// Device the `/connect` command with the LAN address of the machine works on
+const LAN_DEVICE: &str = "Other devices on the network";
+
 pub struct MinecraftServer {
-    peers: Arc<DashMap<SocketAddr, WebSocketSender<TcpStream>>>,
+    peers: Arc<DashMap<SocketAddr, Peer>>,
+    /// Game events that every client is subscribed to
+    subscriptions: Arc<Mutex<Vec<String>>>,
+    events_rx: channel::Receiver<GameEvent>,
+}
+
+/// Event sent by the game to a subscribed client, e.g. `PlayerMessage`
+pub struct GameEvent {
+    pub name: String,
+    pub body: Value,
+}
+
+struct Peer {
+    /// Shared so messages can be sent without holding a reference into the map of peers
+    tx: Arc<WebSocketSender<TcpStream>>,
+    /// Name of the connected player, once the client answered `getlocalplayername`
+    player_name: Option<String>,
 }
 
 impl MinecraftServer {
-    pub fn bind_and_accept(port: u16) -> Result<Self> {
+    /// Failed commands are printed with script locations mapped to the original sources. A free
+    /// port is used if the given one is taken.
+    pub fn bind_and_accept(host: &str, port: u16, source_maps: Option<SourceMaps>) -> Result<Self> {
         let peers = Arc::new(DashMap::new());
-        let listener = smol::block_on(TcpListener::bind(format!("127.0.0.1:{port}")))?;
+        let subscriptions = Arc::new(Mutex::new(Vec::<String>::new()));
+        let (events_tx, events_rx) = channel::unbounded();
+        let listener = match smol::block_on(TcpListener::bind((host, port))) {
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::AddrInUse | io::ErrorKind::PermissionDenied
+                ) =>
+            {
+                warn!("Port {port} is not available ({e}), using a free port for the WebSocket server");
+                smol::block_on(TcpListener::bind((host, 0)))?
+            }
+            result => result?,
+        };
         let addr = listener.local_addr()?;
         let port = addr.port();
-        log!("<green>[SERVER]</> WebSocket is running at port {port}. Run <bright-yellow>/connect {addr}</> in-game to automatically reload scripts");
+        let commands = get_connect_commands(addr);
+        log!("<green>[SERVER]</> WebSocket is running at port {port}. Run the command in-game to automatically reload scripts:");
+        for (device, command) in &commands {
+            log!("<green>[SERVER]</> {device}: <bright-yellow>{command}</>");
+        }
+        let lan_command = commands.iter().find(|(device, _)| *device == LAN_DEVICE);
+        if let Some((_, command)) = lan_command.filter(|_| !Logger::get_json()) {
+            match QrCode::encode(command) {
+                Ok(qr) => {
+                    log!("<green>[SERVER]</> Scan the code on a phone to copy the command for other devices:");
+                    Logger::output(qr.to_terminal_string());
+                }
+                Err(e) => warn!("Failed to create a QR code for the connect command: {e}"),
+            }
+        }
+        if UserConfig::copy_connect_command() {
+            if let Some((_, command)) = commands.first() {
+                match copy_to_clipboard(command) {
+                    Ok(()) => log!(
+                        "<green>[SERVER]</> Copied <bright-yellow>{command}</> to the clipboard"
+                    ),
+                    Err(e) => warn!("Failed to copy the connect command to the clipboard: {e}"),
+                }
+            }
+        }
 
         smol::spawn({
             let peers = peers.clone();
+            let subscriptions = subscriptions.clone();
             async move {
                 loop {
                     let (stream, addr) = match listener.accept().await {
@@ -44,15 +112,57 @@ impl MinecraftServer {
                     log!("<green>[SERVER]</> Client {addr} connected");
 
                     let (tx, mut rx) = ws.split();
-                    peers.insert(addr, tx);
+                    let name_request_id = Uuid::new_v4().to_string();
+                    let event_names = subscriptions.lock().unwrap().clone();
+                    let requests = std::iter::once(command_request(
+                        "getlocalplayername",
+                        &name_request_id,
+                    ))
+                    .chain(event_names.iter().map(|name| subscribe_request(name)));
+                    for request in requests {
+                        if let Err(e) = tx.send(request.to_string().into()).await {
+                            log!("<red>[SERVER]</> Failed to send message: {e} (client {addr})");
+                        }
+                    }
+                    peers.insert(
+                        addr,
+                        Peer {
+                            tx: Arc::new(tx),
+                            player_name: None,
+                        },
+                    );
 
                     smol::spawn({
                         let peers = peers.clone();
+                        let source_maps = source_maps.clone();
+                        let events_tx = events_tx.clone();
                         async move {
                             while let Some(msg) = rx.next().await {
-                                if let Err(e) = msg {
-                                    log!("<red>[SERVER]</> {e} (client {addr})");
-                                    break;
+                                match msg {
+                                    Ok(Message::Text(text)) => {
+                                        match get_player_name(&text, &name_request_id) {
+                                            Some(name) => {
+                                                log!("<green>[SERVER]</> Client {addr} is <b>{name}</>");
+                                                if let Some(mut peer) = peers.get_mut(&addr) {
+                                                    peer.player_name = Some(name);
+                                                }
+                                            }
+                                            None => match get_event(&text) {
+                                                Some(event) => {
+                                                    let _ = events_tx.send(event).await;
+                                                }
+                                                None => print_command_error(
+                                                    &text,
+                                                    source_maps.as_ref(),
+                                                ),
+                                            },
+                                        }
+                                    }
+                                    Ok(_) => {}
+                                    Err(e) => {
+                                        log!("<red>[SERVER]</> {e} (client {addr})");
+                                        break;
+                                    }
                                 }
                             }
                             log!("<yellow>[SERVER]</> Client {addr} disconnected");
@@ -65,28 +175,194 @@ impl MinecraftServer {
         })
         .detach();
 
-        Ok(Self { peers })
+        Ok(Self {
+            peers,
+            subscriptions,
+            events_rx,
+        })
     }
 
-    pub async fn run_command(&self, cmd: &str) {
-        let request = json!({
-            "header": {
-                "version": 1,
-                "requestId": Uuid::new_v4().to_string(),
-                "messageType": "commandRequest",
-                "messagePurpose": "commandRequest"
-            },
-            "body": {
-                "version": 1,
-                "origin": { "type": "player" },
-                "commandLine": cmd,
+    /// Subscribes every current and future client to a game event
+    pub async fn subscribe(&self, event_name: &str) {
+        {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            if subscriptions.iter().any(|name| name == event_name) {
+                return;
             }
-        });
-        for entry in self.peers.iter() {
-            let (addr, tx) = entry.pair();
+            subscriptions.push(event_name.to_owned());
+        }
+        let request = subscribe_request(event_name);
+        for (addr, tx) in self.get_senders() {
             if let Err(e) = tx.send(request.to_string().into()).await {
                 log!("<red>[SERVER]</> Failed to send message: {e} (client {addr})");
             }
         }
     }
+
+    /// Waits for the next event of a subscribed type from any client
+    pub async fn wait_event(&self) -> GameEvent {
+        match self.events_rx.recv().await {
+            Ok(event) => event,
+            Err(_) => smol::future::pending().await,
+        }
+    }
+
+    pub async fn run_command(&self, cmd: &str) {
+        self.run_player_command(|_| cmd.to_owned()).await
+    }
+
+    /// Runs a command built for the player of each client, e.g. to only notify the player that
+    /// connected instead of everyone in the world. The selector is `@s` while the name of the
+    /// player is unknown.
+    pub async fn run_player_command(&self, cmd: impl Fn(&str) -> String) {
+        // Collected first, so the map isn't locked while sending
+        let requests: Vec<_> = self
+            .peers
+            .iter()
+            .map(|entry| {
+                let (addr, peer) = entry.pair();
+                let target = match &peer.player_name {
+                    Some(name) => format!("\"{}\"", name.replace('"', "\\\"")),
+                    None => "@s".to_owned(),
+                };
+                let request = command_request(&cmd(&target), &Uuid::new_v4().to_string());
+                (*addr, peer.tx.clone(), request)
+            })
+            .collect();
+        for (addr, tx, request) in requests {
+            if let Err(e) = tx.send(request.to_string().into()).await {
+                log!("<red>[SERVER]</> Failed to send message: {e} (client {addr})");
+            }
+        }
+    }
+
+    /// Senders of the connected clients, so the map isn't locked while sending
+    fn get_senders(&self) -> Vec<(SocketAddr, Arc<WebSocketSender<TcpStream>>)> {
+        self.peers
+            .iter()
+            .map(|entry| (*entry.key(), entry.tx.clone()))
+            .collect()
+    }
+}
+
+fn command_request(cmd: &str, request_id: &str) -> Value {
+    json!({
+        "header": {
+            "version": 1,
+            "requestId": request_id,
+            "messageType": "commandRequest",
+            "messagePurpose": "commandRequest"
+        },
+        "body": {
+            "version": 1,
+            "origin": { "type": "player" },
+            "commandLine": cmd,
+        }
+    })
+}
+
+/// `/connect` commands for the address the server listens on, with the device they work on
+fn get_connect_commands(addr: SocketAddr) -> Vec<(&'static str, String)> {
+    if !addr.ip().is_unspecified() {
+        return vec![("This device", format!("/connect {addr}"))];
+    }
+    let mut commands = vec![("This device", format!("/connect 127.0.0.1:{}", addr.port()))];
+    if let Some(ip) = get_lan_ip() {
+        commands.push((LAN_DEVICE, format!("/connect {ip}:{}", addr.port())));
+    }
+    commands
+}
+
+/// Address of this machine in the local network, from the route used to reach the internet. No
+/// packet is sent.
+fn get_lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    Some(socket.local_addr().ok()?.ip()).filter(|ip| !ip.is_loopback())
+}
+
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let (program, args): (&str, &[&str]) = if cfg!(windows) {
+        ("clip", &[])
+    } else if cfg!(target_os = "macos") {
+        ("pbcopy", &[])
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        ("wl-copy", &[])
+    } else {
+        ("xclip", &["-selection", "clipboard"])
+    };
+    let mut child = process::Command::new(program)
+        .args(args)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {program}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        bail!("{program} exited with non-zero status code");
+    }
+    Ok(())
+}
+
+fn subscribe_request(event_name: &str) -> Value {
+    json!({
+        "header": {
+            "version": 1,
+            "requestId": Uuid::new_v4().to_string(),
+            "messageType": "commandRequest",
+            "messagePurpose": "subscribe"
+        },
+        "body": {
+            "eventName": event_name,
+        }
+    })
+}
+
+fn get_event(text: &str) -> Option<GameEvent> {
+    let mut message = serde_json::from_str::<Value>(text).ok()?;
+    if message["header"]["messagePurpose"] != "event" {
+        return None;
+    }
+    let name = message["header"]["eventName"]
+        .as_str()
+        .or_else(|| message["body"]["eventName"].as_str())?
+        .to_owned();
+    Some(GameEvent {
+        name,
+        body: message["body"].take(),
+    })
+}
+
+/// Player name from the response to the `getlocalplayername` command with the given request id
+fn get_player_name(text: &str, request_id: &str) -> Option<String> {
+    let response = serde_json::from_str::<Value>(text).ok()?;
+    if response["header"]["requestId"] != request_id {
+        return None;
+    }
+    response["body"]["localplayername"]
+        .as_str()
+        .map(str::to_owned)
+}
+
+/// Prints the message of a failed command response, e.g. `reload` failing on a script error
+fn print_command_error(text: &str, source_maps: Option<&SourceMaps>) {
+    let Ok(response) = serde_json::from_str::<Value>(text) else {
+        return;
+    };
+    if response["header"]["messagePurpose"] != "commandResponse" {
+        return;
+    }
+    let body = &response["body"];
+    let is_error = body["statusCode"].as_i64().is_some_and(|code| code < 0);
+    let Some(message) = body["statusMessage"].as_str().filter(|_| is_error) else {
+        return;
+    };
+    let message = match source_maps {
+        Some(source_maps) => source_maps.translate(message),
+        None => message.to_owned(),
+    };
+    log!("<red>[SERVER]</> {message}");
 }
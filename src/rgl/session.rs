@@ -1,27 +1,157 @@
-use anyhow::{bail, Result};
+use crate::info;
+use crate::progress::Progress;
+use anyhow::{Context, Result};
 use fslock::LockFile;
-use std::fs;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
+/// Profile this instance is limited to while several profiles are watched at the same time
+static PROFILE_SCOPE: OnceLock<String> = OnceLock::new();
+
+/// Gives this instance its own session lock and temp directory, so it can run next to instances
+/// working on other profiles of the project.
+pub fn set_profile_scope(profile_name: &str) {
+    let _ = PROFILE_SCOPE.set(profile_name.to_owned());
+}
+
+pub fn get_profile_scope() -> Option<&'static str> {
+    PROFILE_SCOPE.get().map(String::as_str)
+}
+
+pub fn get_session_lock_path() -> PathBuf {
+    match get_profile_scope() {
+        Some(profile_name) => Path::new(".regolith").join(format!("session_lock_{profile_name}")),
+        None => Path::new(".regolith").join("session_lock"),
+    }
+}
+
+/// Session locks of the project held by running instances, with the PID of the instance if it
+/// can be read.
+pub fn find_active_sessions() -> Result<Vec<(PathBuf, Option<String>)>> {
+    let dir = Path::new(".regolith");
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut sessions = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_session_lock = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("session_lock"));
+        if !is_session_lock {
+            continue;
+        }
+        let mut file = LockFile::open(&path)?;
+        if file.try_lock()? {
+            file.unlock()?;
+            continue;
+        }
+        let pid = fs::read_to_string(&path)
+            .map(|pid| pid.trim().to_owned())
+            .ok()
+            .filter(|pid| !pid.is_empty());
+        sessions.push((path, pid));
+    }
+    Ok(sessions)
+}
+
+/// Lock files of the scoped instances of the project
+fn find_scope_locks() -> Result<Vec<PathBuf>> {
+    let dir = Path::new(".regolith");
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_scope_lock = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("session_lock_"));
+        if is_scope_lock {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Lock held while rgl works in a directory. Other instances wait for the lock to be released
+/// instead of failing, so concurrent runs in the same project are queued. The lock is released
+/// by the OS when the process exits, so a crashed instance never leaves a stale lock behind.
 pub struct Session {
     file: LockFile,
+    /// Locks of the scoped instances, held by an unscoped instance until it's done
+    scopes: Vec<LockFile>,
 }
 
 impl Session {
+    /// Locks the current project. Scoped instances only exclude each other per profile, an
+    /// unscoped instance works on the state shared by all of them, so it excludes every scope.
     pub fn lock() -> Result<Self> {
-        let _ = fs::create_dir(".regolith");
-        let mut file = LockFile::open(".regolith/session_lock")?;
-        file.try_lock_with_pid()?;
-        if !file.owns_lock() {
-            bail!(
-                "Failed to acquire session lock\n\
-                 <yellow> >></> Another instance of rgl is already running\n\
-                 <yellow> >></> If you are sure that this is not the case, delete the lock file manually"
-            );
+        let mut project_lock = Self::lock_path(Path::new(".regolith").join("session_lock"))?;
+        if get_profile_scope().is_some() {
+            // The project lock is only held until the scope is locked, so an unscoped instance
+            // that starts later finds the scope lock and waits for it
+            let session = Self::lock_path(get_session_lock_path())?;
+            project_lock.unlock()?;
+            return Ok(session);
+        }
+        let mut session = project_lock;
+        for path in find_scope_locks()? {
+            session.scopes.push(Self::lock_path(path)?.file);
         }
-        Ok(Self { file })
+        Ok(session)
+    }
+
+    /// Locks a directory shared between projects, e.g. a cached repository
+    pub fn lock_shared(dir: &Path) -> Result<Self> {
+        let mut path = dir.as_os_str().to_owned();
+        path.push(".lock");
+        Self::lock_path(path)
+    }
+
+    fn lock_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = LockFile::open(path).with_context(|| {
+            format!(
+                "Failed to open lock file\n\
+                 <yellow> >></> Path: {}",
+                path.display()
+            )
+        })?;
+        if file.try_lock_with_pid()? {
+            return Ok(Self {
+                file,
+                scopes: vec![],
+            });
+        }
+        // The file can't be read on all platforms while it is locked
+        let message = match fs::read_to_string(path).map(|pid| pid.trim().to_owned()) {
+            Ok(pid) if !pid.is_empty() => {
+                format!("Waiting for another instance of rgl (PID {pid}) to finish")
+            }
+            _ => "Waiting for another instance of rgl to finish".to_owned(),
+        };
+        info!("{message}...");
+        let progress = Progress::spinner(message);
+        let result = file.lock_with_pid();
+        progress.finish();
+        result?;
+        Ok(Self {
+            file,
+            scopes: vec![],
+        })
     }
 
     pub fn unlock(&mut self) -> Result<()> {
+        for mut file in self.scopes.drain(..) {
+            file.unlock()?;
+        }
         self.file.unlock()?;
         Ok(())
     }
@@ -0,0 +1,70 @@
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+
+/// Validates filter settings against the `settingsSchema` from its `filter.json`.
+pub fn validate_settings(filter_name: &str, schema: &Value, settings: &Value) -> Result<()> {
+    let validator = jsonschema::validator_for(schema).map_err(|e| {
+        anyhow!(
+            "Invalid settings schema for filter <filter>{filter_name}</>\n\
+             <yellow> >></> {e}"
+        )
+    })?;
+    let errors: Vec<_> = validator
+        .iter_errors(settings)
+        .map(|e| {
+            let path = e.instance_path().to_string();
+            match path.is_empty() {
+                true => format!("<yellow> >></> {e}"),
+                false => format!("<yellow> >></> {path}: {e}"),
+            }
+        })
+        .collect();
+    if !errors.is_empty() {
+        bail!(
+            "Invalid settings for filter <filter>{filter_name}</>\n{}",
+            errors.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// A setting with its default value and description, used to fill in the settings of a newly added
+/// filter.
+pub struct SettingsSkeletonEntry {
+    pub name: String,
+    pub value: Value,
+    pub description: Option<String>,
+}
+
+/// Lists the properties of the settings schema, using the schema defaults where available.
+pub fn get_settings_skeleton(schema: &Value) -> Vec<SettingsSkeletonEntry> {
+    let Some(properties) = schema["properties"].as_object() else {
+        return vec![];
+    };
+    properties
+        .iter()
+        .map(|(name, property)| SettingsSkeletonEntry {
+            name: name.to_owned(),
+            value: get_default_value(property),
+            description: property["description"].as_str().map(str::to_owned),
+        })
+        .collect()
+}
+
+fn get_default_value(property: &Value) -> Value {
+    if let Some(default) = property.get("default") {
+        return default.to_owned();
+    }
+    let ty = match &property["type"] {
+        Value::Array(types) => types.first().and_then(Value::as_str),
+        ty => ty.as_str(),
+    };
+    match ty {
+        Some("string") => Value::String(String::new()),
+        Some("number" | "integer") => Value::from(0),
+        Some("boolean") => Value::Bool(false),
+        Some("array") => Value::Array(vec![]),
+        Some("object") => Value::Object(Map::new()),
+        _ => Value::Null,
+    }
+}
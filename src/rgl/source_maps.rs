@@ -0,0 +1,136 @@
+use super::{Config, Export, ExportPaths};
+use anyhow::Result;
+use oxc_sourcemap::SourceMap;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Maps locations in the exported scripts back to their original source. Source maps are read
+/// when a location is translated, as they change on every run.
+#[derive(Clone)]
+pub struct SourceMaps {
+    /// Scripts directory of the exported behavior pack
+    scripts_dir: PathBuf,
+    /// Scripts directory of the project behavior pack
+    source_dir: PathBuf,
+}
+
+impl SourceMaps {
+    pub fn new(scripts_dir: PathBuf, source_dir: PathBuf) -> Self {
+        Self {
+            scripts_dir,
+            source_dir,
+        }
+    }
+
+    /// Source maps of the scripts exported by a profile, if it exports a behavior pack
+    pub fn from_profile(config: &Config, profile_name: &str) -> Result<Option<Self>> {
+        let Some(bp) = config.get_behavior_pack() else {
+            return Ok(None);
+        };
+        let profile = config.get_profile(profile_name)?;
+        if matches!(profile.export, Export::None(_)) {
+            return Ok(None);
+        }
+        let (target_bp, _) = profile.export.get_paths(config, profile_name)?;
+        Ok(Some(Self::new(
+            target_bp.join("scripts"),
+            bp.join("scripts"),
+        )))
+    }
+
+    /// Replaces script locations in a message, e.g. `(main.js:12)` in a stack trace, with the
+    /// location in the original source. Locations without a source map are kept as is.
+    pub fn translate(&self, text: &str) -> String {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(index) = rest.find(".js:") {
+            let start = rest[..index]
+                .rfind(|c: char| c.is_whitespace() || c == '(' || c == '@')
+                .map_or(0, |i| i + 1);
+            let file = &rest[start..index + 3];
+            let mut end = index + 4;
+            let Some(line) = parse_number(rest, &mut end) else {
+                result.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            };
+            let mut column = None;
+            if rest[end..].starts_with(':') {
+                let mut column_end = end + 1;
+                if let Some(value) = parse_number(rest, &mut column_end) {
+                    column = Some(value);
+                    end = column_end;
+                }
+            }
+            match self.find_original(file, line, column) {
+                Some(location) => {
+                    result.push_str(&rest[..start]);
+                    result.push_str(&location);
+                }
+                None => result.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Original location of a 1-based line and column in a script
+    fn find_original(&self, file: &str, line: u32, column: Option<u32>) -> Option<String> {
+        let data = fs::read_to_string(self.scripts_dir.join(format!("{file}.map"))).ok()?;
+        let map = SourceMap::from_json_string(&data).ok()?;
+        let line = line.checked_sub(1)?;
+        let mut tokens = map
+            .get_tokens()
+            .filter(|token| token.get_dst_line() == line);
+        // The game only reports lines, in which case the first mapping of the line is used
+        let token = match column {
+            Some(column) => tokens
+                .take_while(|token| token.get_dst_col() < column)
+                .last()?,
+            None => tokens.next()?,
+        };
+        let source = map.get_source(token.get_source_id()?)?;
+        let path = self.get_source_path(file, source);
+        let line = token.get_src_line() + 1;
+        Some(match column {
+            Some(_) => format!("{path}:{line}:{}", token.get_src_col() + 1),
+            None => format!("{path}:{line}"),
+        })
+    }
+
+    /// Sources are relative to the script. Sources inside the pack are shown at their location
+    /// in the project, others relative to the project.
+    fn get_source_path(&self, file: &str, source: &str) -> String {
+        let dir = Path::new(file).parent().unwrap_or(Path::new(""));
+        let mut path = PathBuf::new();
+        let mut is_outside = false;
+        for component in dir.join(source).components() {
+            match component {
+                Component::ParentDir if !path.pop() => is_outside = true,
+                Component::ParentDir | Component::CurDir => {}
+                component => path.push(component),
+            }
+        }
+        let path = match is_outside {
+            true => path,
+            false => self.source_dir.join(path),
+        };
+        path.components()
+            .filter(|component| *component != Component::CurDir)
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+fn parse_number(text: &str, end: &mut usize) -> Option<u32> {
+    let digits = text[*end..]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len() - *end);
+    let number = text[*end..*end + digits].parse().ok()?;
+    *end += digits;
+    Some(number)
+}
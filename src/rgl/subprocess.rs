@@ -1,9 +1,90 @@
-use super::get_current_dir;
+use super::{
+    get_changed_files_path, get_current_dir, get_filter_log_path, ErrorCode, FilterContext,
+    RuntimePackage, UserConfig,
+};
+use crate::logger::Logger;
 use anyhow::{anyhow, bail, Context, Result};
-use std::{ffi::OsStr, io, path::Path, process};
+use serde_json::json;
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{mpsc, Arc, LazyLock, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// PIDs of the subprocesses that are currently running
+static CHILDREN: LazyLock<Mutex<HashSet<u32>>> = LazyLock::new(Default::default);
+
+thread_local! {
+    /// PIDs of the subprocesses spawned by the function [`with_timeout`] runs on this thread
+    static TIMEOUT_PIDS: RefCell<Option<Arc<Mutex<HashSet<u32>>>>> = const { RefCell::new(None) };
+}
+
+/// The current run, if any
+static RUN_ENV: Mutex<Option<RunEnv>> = Mutex::new(None);
+
+/// Information about the current run, passed to filters as environment variables:
+///
+/// - `RGL_VERSION`: version of rgl
+/// - `RGL_PROJECT_NAME`: name of the project
+/// - `RGL_PROFILE`: name of the profile being run
+/// - `RGL_FILTER_NAME`: name of the filter
+/// - `RGL_ROOT_DIR`: absolute path of the project root
+/// - `RGL_TEMP_DIR`: absolute path of the temp directory the filter runs in
+/// - `RGL_DATA_DIR`: absolute path of the project's data directory
+/// - `RGL_EXPORT_BP`, `RGL_EXPORT_RP`: absolute paths the packs are exported to, if any
+pub struct RunEnv {
+    pub project_name: String,
+    pub profile_name: String,
+    pub temp_dir: PathBuf,
+    pub data_dir: PathBuf,
+    pub export_paths: Option<(PathBuf, PathBuf)>,
+}
+
+impl RunEnv {
+    pub fn set(self) {
+        *RUN_ENV.lock().unwrap() = Some(self);
+    }
+}
+
+/// The `RGL_*` environment variables of the filter, including `RGL_CHANGED_FILES` if the run
+/// has a list of changed files.
+pub fn get_run_env_vars(context: &FilterContext) -> Vec<(&'static str, OsString)> {
+    let mut vars = vec![
+        ("RGL_VERSION", env!("CARGO_PKG_VERSION").into()),
+        ("RGL_FILTER_NAME", context.name.as_str().into()),
+    ];
+    let current_dir = get_current_dir().ok();
+    if let Some(current_dir) = &current_dir {
+        vars.push(("RGL_ROOT_DIR", current_dir.into()));
+    }
+    if let Some(run) = RUN_ENV.lock().unwrap().as_ref() {
+        vars.push(("RGL_PROJECT_NAME", run.project_name.as_str().into()));
+        vars.push(("RGL_PROFILE", run.profile_name.as_str().into()));
+        vars.push(("RGL_TEMP_DIR", run.temp_dir.as_os_str().into()));
+        vars.push(("RGL_DATA_DIR", run.data_dir.as_os_str().into()));
+        if let Some((bp, rp)) = &run.export_paths {
+            vars.push(("RGL_EXPORT_BP", bp.as_os_str().into()));
+            vars.push(("RGL_EXPORT_RP", rp.as_os_str().into()));
+        }
+    }
+    let changed_files = get_changed_files_path();
+    if let (true, Some(current_dir)) = (changed_files.is_file(), &current_dir) {
+        vars.push(("RGL_CHANGED_FILES", current_dir.join(changed_files).into()));
+    }
+    vars
+}
 
 pub struct Subprocess {
     command: process::Command,
+    /// Filter name to prefix each line of output with
+    prefix: Option<String>,
 }
 
 impl Subprocess {
@@ -13,7 +94,34 @@ impl Subprocess {
     {
         Self {
             command: process::Command::new(command),
+            prefix: None,
+        }
+    }
+
+    /// Runs the command line with the system shell, `sh` or PowerShell on Windows. The arguments
+    /// are passed after the command line, `sh` sets them as the positional parameters `$0`, `$1`,
+    /// etc. while PowerShell gets them quoted at the end of the command line.
+    pub fn shell<I, S>(command: &str, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut subprocess = Self::new(if cfg!(windows) { "powershell" } else { "sh" });
+        match cfg!(windows) {
+            // PowerShell parses the whole command line, unquoted arguments would run as code
+            true => {
+                let mut command = command.to_owned();
+                for arg in args {
+                    command.push(' ');
+                    command.push_str(&quote_shell_arg(&arg.as_ref().to_string_lossy()));
+                }
+                subprocess.arg("-c").arg(command);
+            }
+            false => {
+                subprocess.arg("-c").arg(command).args(args);
+            }
         }
+        subprocess
     }
 
     pub fn arg<S>(&mut self, arg: S) -> &mut Self
@@ -38,13 +146,25 @@ impl Subprocess {
         self
     }
 
-    pub fn setup_env(&mut self, filter_dir: impl AsRef<Path>) -> &mut Self {
-        self.command.env("FILTER_DIR", filter_dir.as_ref());
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.command.env(key, value);
+        self
+    }
+
+    pub fn setup_env(&mut self, context: &FilterContext) -> &mut Self {
+        self.command.env("FILTER_DIR", &context.filter_dir);
+        self.prefix = Some(context.name.to_owned());
+        self.command.envs(get_run_env_vars(context));
         self
     }
 
     pub fn run(&mut self) -> Result<process::Output> {
-        let output = self
+        if self.prefix.is_some() {
+            self.command
+                .stderr(process::Stdio::piped())
+                .stdout(process::Stdio::piped());
+        }
+        let mut child = self
             .command
             .env("ROOT_DIR", get_current_dir()?)
             .spawn()
@@ -52,17 +172,31 @@ impl Subprocess {
                 io::ErrorKind::NotFound => self.program_not_found_error(),
                 _ => anyhow!(err),
             })
-            .context("Failed spawning subprocess")?
-            .wait_with_output()
-            .context("Failed running subprocess")?;
+            .context("Failed spawning subprocess")?;
+        let log_path = self.prefix.as_deref().and_then(get_filter_log_path);
+        let forwarder = self.prefix.as_ref().map(|prefix| {
+            let group = UserConfig::group_filter_output();
+            forward_output(&mut child, prefix, group, log_path.as_ref())
+        });
+        let output = wait_with_output(child)?;
+        if let Some(forwarder) = forwarder {
+            let _ = forwarder.join();
+        }
         if !output.status.success() {
-            bail!("Process exited with non-zero status code");
+            match log_path.filter(|path| path.is_file()) {
+                Some(path) => bail!(
+                    "Process exited with non-zero status code\n\
+                     <yellow> >></> Log: {}",
+                    path.display()
+                ),
+                None => bail!("Process exited with non-zero status code"),
+            }
         }
         Ok(output)
     }
 
     pub fn run_silent(&mut self) -> Result<process::Output> {
-        let output = self
+        let child = self
             .command
             .env("ROOT_DIR", get_current_dir()?)
             .stderr(process::Stdio::piped())
@@ -72,11 +206,10 @@ impl Subprocess {
                 io::ErrorKind::NotFound => self.program_not_found_error(),
                 _ => anyhow!(err),
             })
-            .context("Failed spawning subprocess")?
-            .wait_with_output()
-            .context("Failed running subprocess")?;
+            .context("Failed spawning subprocess")?;
+        let output = wait_with_output(child)?;
         if !output.status.success() {
-            println!("{}", String::from_utf8_lossy(&output.stderr));
+            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
             bail!("Process exited with non-zero status code");
         }
         Ok(output)
@@ -85,18 +218,187 @@ impl Subprocess {
     fn program_not_found_error(&self) -> anyhow::Error {
         let program = self.command.get_program();
         let mut message = format!("Program {:?} not found", program);
+        let package = program.to_str().and_then(RuntimePackage::find);
         let install_link = match program.to_str() {
-            Some("bun") => Some("https://bun.sh/docs/installation"),
-            Some("deno") => Some("https://docs.deno.com/runtime/#install-deno"),
+            Some("docker") => Some("https://docs.docker.com/get-started/get-docker"),
             Some("git") => Some("https://git-scm.com/downloads"),
-            Some("go") => Some("https://go.dev/doc/install"),
-            Some("node") => Some("https://nodejs.org/en/download/prebuilt-installer"),
-            Some("python") => Some("https://www.python.org/downloads"),
-            _ => None,
+            _ => package.map(|package| package.download),
         };
         if let Some(link) = install_link {
             message.push_str(&format!(". Install it from {link}"));
         }
-        anyhow!(message)
+        if package.is_some() {
+            message.push_str(", or run <b>rgl env install</>");
+        }
+        ErrorCode::ProgramNotFound.error(message)
+    }
+}
+
+/// Quotes the argument for the shell used by [`Subprocess::shell`], unless it only contains
+/// characters that are safe as is.
+pub fn quote_shell_arg(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.to_owned();
+    }
+    // Single quotes don't expand anything in both shells, only the quote itself needs escaping
+    match cfg!(windows) {
+        true => format!("'{}'", arg.replace('\'', "''")),
+        false => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
+/// Prints the output of the child process line by line, prefixed with a colored tag. Grouped output
+/// is printed all at once after the process exits, instead of interleaving with other output.
+///
+/// The output is also appended to the log file, if any.
+fn forward_output(
+    child: &mut process::Child,
+    prefix: &str,
+    group: bool,
+    log_path: Option<&PathBuf>,
+) -> thread::JoinHandle<()> {
+    let tag = format!("\x1b[92m[{prefix}]\x1b[0m");
+    let filter = prefix.to_owned();
+    let grouped = Mutex::new(vec![]);
+    let log_file = log_path.and_then(|path| {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .ok()
+            .map(Mutex::new)
+    });
+    // Each line is written at once so lines from stdout and stderr don't get mixed up
+    let print = move |is_stderr: bool, line: &str| {
+        if Logger::get_json() {
+            let stream = if is_stderr { "stderr" } else { "stdout" };
+            Logger::json(json!({
+                "level": "filter",
+                "filter": filter,
+                "stream": stream,
+                "message": line,
+            }));
+            return;
+        }
+        let line = format!("{tag} {line}\n");
+        let _ = match is_stderr {
+            true => io::stderr().write_all(line.as_bytes()),
+            false => io::stdout().write_all(line.as_bytes()),
+        };
+    };
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::spawn(move || {
+        let read = |reader: Option<Box<dyn Read + Send>>, is_stderr: bool| {
+            let Some(reader) = reader else {
+                return;
+            };
+            let mut reader = BufReader::new(reader);
+            let mut buf = vec![];
+            while reader.read_until(b'\n', &mut buf).is_ok_and(|n| n > 0) {
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(['\r', '\n']);
+                if let Some(log_file) = &log_file {
+                    let _ = writeln!(log_file.lock().unwrap(), "{line}");
+                }
+                if group {
+                    grouped.lock().unwrap().push((is_stderr, line.to_owned()));
+                } else {
+                    print(is_stderr, line);
+                }
+                buf.clear();
+            }
+        };
+        thread::scope(|s| {
+            s.spawn(|| read(stdout.map(|r| Box::new(r) as _), false));
+            s.spawn(|| read(stderr.map(|r| Box::new(r) as _), true));
+        });
+        for (is_stderr, line) in grouped.into_inner().unwrap() {
+            print(is_stderr, &line);
+        }
+    })
+}
+
+fn wait_with_output(child: process::Child) -> Result<process::Output> {
+    let pid = child.id();
+    let timeout_pids = TIMEOUT_PIDS.with_borrow(|pids| pids.clone());
+    CHILDREN.lock().unwrap().insert(pid);
+    if let Some(pids) = &timeout_pids {
+        pids.lock().unwrap().insert(pid);
+    }
+    let output = child.wait_with_output();
+    CHILDREN.lock().unwrap().remove(&pid);
+    if let Some(pids) = &timeout_pids {
+        pids.lock().unwrap().remove(&pid);
+    }
+    output.context("Failed running subprocess")
+}
+
+/// Kills every running subprocess along with the processes they spawned.
+pub fn kill_subprocesses() {
+    for pid in CHILDREN.lock().unwrap().drain() {
+        kill_tree(&pid.to_string());
+    }
+}
+
+/// Runs the function, killing the subprocesses it spawned on this thread if it takes longer than
+/// the timeout. Work done inside rgl itself can't be interrupted. The result of the function is
+/// returned as is, unless it failed because its subprocesses were killed.
+pub fn with_timeout<T>(timeout: Option<Duration>, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let Some(timeout) = timeout else {
+        return f();
+    };
+    let pids = Arc::new(Mutex::new(HashSet::<u32>::new()));
+    let (sender, receiver) = mpsc::channel::<()>();
+    let watchdog = thread::spawn({
+        let pids = pids.clone();
+        move || {
+            if receiver.recv_timeout(timeout) != Err(mpsc::RecvTimeoutError::Timeout) {
+                return false;
+            }
+            let pids: Vec<_> = pids.lock().unwrap().iter().copied().collect();
+            for pid in &pids {
+                kill_tree(&pid.to_string());
+            }
+            !pids.is_empty()
+        }
+    });
+    let previous = TIMEOUT_PIDS.replace(Some(pids));
+    let result = f();
+    TIMEOUT_PIDS.set(previous);
+    let _ = sender.send(());
+    let killed = watchdog.join().unwrap_or(false);
+    match result {
+        Err(e) if killed => Err(e.context(format!("Timed out after {}s", timeout.as_secs_f32()))),
+        result => result,
+    }
+}
+
+#[cfg(windows)]
+fn kill_tree(pid: &str) {
+    let _ = process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", pid])
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status();
+}
+
+#[cfg(unix)]
+fn kill_tree(pid: &str) {
+    // Stop the process first so it can't spawn new children while we're looking for them
+    let signal = |signal: &str| {
+        let _ = process::Command::new("kill")
+            .args([signal, pid])
+            .stderr(process::Stdio::null())
+            .status();
+    };
+    signal("-STOP");
+    if let Ok(output) = process::Command::new("pgrep").args(["-P", pid]).output() {
+        for child in String::from_utf8_lossy(&output.stdout).split_whitespace() {
+            kill_tree(child);
+        }
     }
+    signal("-TERM");
+    signal("-CONT");
 }
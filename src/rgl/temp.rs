@@ -1,4 +1,19 @@
-use std::path::PathBuf;
+use super::{get_current_dir, get_profile_scope};
+use crate::fs::{
+    copy_dir_with_symlinks, is_dir_empty, move_path, rimraf, sync_dir, SymlinkPolicy, SyncOptions,
+};
+use crate::{debug, warn};
+use anyhow::{bail, Result};
+use fslock::LockFile;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+/// In-memory temp directories used by this instance, with the lock that marks them as in use
+static MEMORY_TEMPS: Mutex<Vec<(PathBuf, LockFile)>> = Mutex::new(vec![]);
 
 pub struct Temp {
     pub bp: PathBuf,
@@ -8,9 +23,7 @@ pub struct Temp {
 }
 
 impl Temp {
-    pub fn from_dot_regolith() -> Self {
-        let dot_regolith = PathBuf::from(".regolith");
-        let temp = dot_regolith.join("tmp");
+    pub fn new(temp: PathBuf) -> Self {
         Self {
             bp: temp.join("BP"),
             rp: temp.join("RP"),
@@ -18,4 +31,220 @@ impl Temp {
             root: temp,
         }
     }
+
+    pub fn from_dot_regolith() -> Self {
+        let dot_regolith = PathBuf::from(".regolith");
+        match get_profile_scope() {
+            Some(profile_name) => Self::new(dot_regolith.join(format!("tmp_{profile_name}"))),
+            None => Self::new(dot_regolith.join("tmp")),
+        }
+    }
+
+    /// Temp directory set with `tempDir` in the profile. It's removed by `--clean`, so it must be
+    /// inside `.regolith`.
+    pub fn from_temp_dir(temp_dir: &Path) -> Result<Self> {
+        let mut components = temp_dir
+            .components()
+            .skip_while(|component| *component == Component::CurDir);
+        let is_valid = components.next() == Some(Component::Normal(".regolith".as_ref()))
+            && components.clone().next().is_some()
+            && components.all(|component| matches!(component, Component::Normal(_)));
+        if !is_valid {
+            bail!(
+                "The temp directory must be inside .regolith\n\
+                 <yellow> >></> Path: {}",
+                temp_dir.display()
+            );
+        }
+        let temp = match get_profile_scope() {
+            Some(profile_name) => {
+                let mut name = temp_dir.file_name().unwrap_or_default().to_owned();
+                name.push(format!("_{profile_name}"));
+                temp_dir.with_file_name(name)
+            }
+            None => temp_dir.to_owned(),
+        };
+        Ok(Self::new(temp))
+    }
+
+    /// Temp directory inside a RAM-backed filesystem, if one is available.
+    pub fn in_memory(project_name: &str) -> Option<Self> {
+        let shm = Path::new("/dev/shm");
+        if !cfg!(target_os = "linux") || !shm.is_dir() {
+            return None;
+        }
+        // Projects may share the same name, so the project path is part of the directory name
+        let current_dir = get_current_dir().ok()?;
+        let hash = format!(
+            "{:x}",
+            Sha256::digest(current_dir.as_os_str().as_encoded_bytes())
+        );
+        let name = match get_profile_scope() {
+            Some(profile_name) => format!("{project_name}-{}-{profile_name}", &hash[..8]),
+            None => format!("{project_name}-{}", &hash[..8]),
+        };
+        Some(Self::new(shm.join("rgl").join(name)))
+    }
+
+    /// Marks an in-memory temp directory as used by this instance, so it's removed when the
+    /// instance exits. Directories left behind by instances that crashed are removed first.
+    pub fn claim_in_memory(&self) -> Result<()> {
+        let mut claimed = MEMORY_TEMPS.lock().unwrap();
+        if claimed.iter().any(|(root, _)| *root == self.root) {
+            return Ok(());
+        }
+        let mut lock = LockFile::open(&get_memory_lock_path(&self.root))?;
+        // The session lock keeps other instances from using the same directory at the same time,
+        // the lock file only tells running instances from crashed ones
+        if lock.try_lock_with_pid()? {
+            claimed.push((self.root.to_owned(), lock));
+        }
+        if let Some(parent) = self.root.parent() {
+            remove_stale_memory_temps(parent);
+        }
+        Ok(())
+    }
+
+    /// Hides the data of other filters from the filter, leaving only `data/<name>` in the temp
+    /// directory until the returned guard is restored or dropped.
+    pub fn isolate_data(&self, name: &str) -> Result<IsolatedData> {
+        // Outside of the temp directory so filters can't reach it with a relative path
+        let hidden = self.hidden_data();
+        rimraf(&hidden)?;
+        move_path(&self.data, &hidden)?;
+        fs::create_dir_all(&self.data)?;
+        let filter_data = hidden.join(name);
+        if filter_data.exists() {
+            move_path(filter_data, self.data.join(name))?;
+        }
+        Ok(IsolatedData {
+            root: self.root.to_owned(),
+            name: name.to_owned(),
+            restored: false,
+        })
+    }
+
+    /// Moves the data of other filters back, discarding anything the filter wrote outside of
+    /// `data/<name>`.
+    fn restore_data(&self, name: &str) -> Result<()> {
+        let hidden = self.hidden_data();
+        let filter_data = self.data.join(name);
+        if filter_data.exists() {
+            move_path(filter_data, hidden.join(name))?;
+        }
+        if !is_dir_empty(&self.data)? {
+            warn!(
+                "Filter <filter>{name}</> wrote data outside of <b>data/{name}</>, discarding it"
+            );
+        }
+        rimraf(&self.data)?;
+        move_path(hidden, &self.data)
+    }
+
+    fn hidden_data(&self) -> PathBuf {
+        let mut path = self.root.as_os_str().to_owned();
+        path.push("-data");
+        PathBuf::from(path)
+    }
+}
+
+/// Data of other filters hidden by [`Temp::isolate_data`]. It is moved back when the guard is
+/// dropped, so a filter that fails doesn't leave the data folder in its isolated state.
+pub struct IsolatedData {
+    root: PathBuf,
+    name: String,
+    restored: bool,
+}
+
+impl IsolatedData {
+    /// Moves the data back, reporting errors that dropping the guard can only warn about
+    pub fn restore(mut self) -> Result<()> {
+        self.restored = true;
+        Temp::new(self.root.to_owned()).restore_data(&self.name)
+    }
+}
+
+impl Drop for IsolatedData {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        if let Err(e) = Temp::new(self.root.to_owned()).restore_data(&self.name) {
+            warn!("Failed to restore the data of other filters: {e}");
+        }
+    }
+}
+
+/// Removes the in-memory temp directories used by this instance, they take up memory until the
+/// system restarts otherwise.
+pub fn remove_memory_temps() {
+    for (root, mut lock) in MEMORY_TEMPS.lock().unwrap().drain(..) {
+        let hidden_data = Temp::new(root.to_owned()).hidden_data();
+        for path in [&root, &hidden_data] {
+            if let Err(e) = rimraf(path) {
+                debug!("Failed to remove in-memory temp directory: {e}");
+            }
+        }
+        let _ = lock.unlock();
+        let _ = fs::remove_file(get_memory_lock_path(&root));
+    }
+}
+
+fn get_memory_lock_path(root: &Path) -> PathBuf {
+    let mut path = root.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Removes the in-memory temp directories whose lock isn't held by a running instance
+fn remove_stale_memory_temps(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let is_in_use = |root: &Path| {
+        let lock_path = get_memory_lock_path(root);
+        lock_path.is_file()
+            && LockFile::open(&lock_path)
+                .and_then(|mut lock| {
+                    let locked = lock.try_lock()?;
+                    if locked {
+                        lock.unlock()?;
+                    }
+                    Ok(!locked)
+                })
+                .unwrap_or(true)
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        // The data hidden from isolated filters belongs to the temp directory next to it
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let owner = name
+            .strip_suffix("-data")
+            .map(|name| path.with_file_name(name));
+        if is_in_use(&path) || owner.is_some_and(|owner| is_in_use(&owner)) {
+            continue;
+        }
+        debug!(
+            "Removing stale in-memory temp directory <b>{}</>",
+            path.display()
+        );
+        if rimraf(&path).is_ok() {
+            let _ = fs::remove_file(get_memory_lock_path(&path));
+        }
+    }
+}
+
+/// Copies a source directory into temp for filters to change it, see [`apply_temp_dir`]
+pub fn copy_to_temp(source: &Path, temp: &Path, symlinks: SymlinkPolicy) -> Result<()> {
+    copy_dir_with_symlinks(source, temp, symlinks)
+}
+
+/// Applies the changes the filters made in temp back to the source directory, including the files
+/// they removed
+pub fn apply_temp_dir(temp: &Path, source: &Path, symlinks: SymlinkPolicy) -> Result<()> {
+    sync_dir(temp, source, SyncOptions::default().symlinks(symlinks))?;
+    Ok(())
 }
@@ -0,0 +1,74 @@
+use super::{
+    add_world_pack, enable_experiments, find_mojang_dir, get_pack_header, write_test_level_dat,
+    Config,
+};
+use crate::fs::{copy_dir, write_file};
+use crate::info;
+use anyhow::{bail, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+/// Template used by `rgl world create` when no other template is given
+pub fn get_world_template_path() -> PathBuf {
+    PathBuf::from("world_template")
+}
+
+/// Creates a world in the com.mojang directory of the profile's Minecraft build, with the project
+/// packs attached and the profile experiments enabled. Returns the path of the world.
+pub fn create_test_world(
+    config: &Config,
+    profile_name: &str,
+    name: &str,
+    template: Option<&Path>,
+    flat: bool,
+) -> Result<PathBuf> {
+    let profile = config.get_profile(profile_name)?;
+    let mojang_dir = find_mojang_dir(profile.export.get_build().as_ref())?;
+    if !mojang_dir.exists() {
+        bail!("Failed to find com.mojang directory");
+    }
+    let worlds_dir = mojang_dir.join("minecraftWorlds");
+    if worlds_dir.is_dir() {
+        for entry in worlds_dir.read_dir()? {
+            let level_name = entry?.path().join("levelname.txt");
+            if fs::read_to_string(level_name).is_ok_and(|level_name| level_name == name) {
+                bail!("World <yellow>{name}</> already exists");
+            }
+        }
+    }
+
+    let world_dir = worlds_dir.join(format!("rgl-{}", Uuid::new_v4().simple()));
+    match template {
+        Some(template) => {
+            if !template.join("level.dat").is_file() {
+                bail!(
+                    "World template has no level.dat\n\
+                     <yellow> >></> Path: {}",
+                    template.display()
+                );
+            }
+            copy_dir(template, &world_dir)?;
+        }
+        None => fs::create_dir_all(&world_dir)?,
+    }
+    write_test_level_dat(&world_dir, name, flat)?;
+    write_file(world_dir.join("levelname.txt"), name)?;
+
+    let packs = [
+        (config.get_behavior_pack(), "world_behavior_packs.json"),
+        (config.get_resource_pack(), "world_resource_packs.json"),
+    ];
+    for (pack, file_name) in packs {
+        let Some(pack) = pack else {
+            continue;
+        };
+        let (pack_id, version) = get_pack_header(&pack)?;
+        add_world_pack(&world_dir.join(file_name), &pack_id, version)?;
+    }
+    enable_experiments(&world_dir, &profile.experiments)?;
+    info!("Created world <b>{name}</>");
+    Ok(world_dir)
+}
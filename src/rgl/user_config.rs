@@ -2,7 +2,7 @@ use super::get_user_config_path;
 use crate::fs::{read_json, write_json};
 use crate::warn;
 use serde::{Deserialize, Serialize};
-use std::sync::OnceLock;
+use std::{collections::BTreeMap, sync::OnceLock};
 
 #[derive(Serialize, Deserialize)]
 pub struct UserConfig {
@@ -14,12 +14,29 @@ pub struct UserConfig {
     pub resolver_update_interval: u64,
     #[serde(default = "default_websocket_port")]
     pub websocket_port: u16,
+    /// Copy the `/connect` command of the WebSocket server to the clipboard
+    #[serde(default)]
+    pub copy_connect_command: bool,
     #[serde(default)]
     pub force_compat: bool,
+    #[serde(default)]
+    pub group_filter_output: bool,
+    /// Update the modification time of the exported manifests after a run, so the game notices
+    /// the changed packs without a restart
+    #[serde(default)]
+    pub touch_manifests: bool,
     pub mojang_dir: Option<String>,
     pub nodejs_runtime: Option<String>,
     pub nodejs_package_manager: Option<String>,
     pub python_command: Option<String>,
+    /// Path of each filter runtime by program name, e.g. `node`, instead of looking it up in PATH
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub runtime_paths: BTreeMap<String, String>,
+    /// Remote that stores build cache artifacts, shared between machines
+    pub build_cache_url: Option<String>,
+    pub build_cache_token: Option<String>,
+    /// Repository to fetch vanilla files from, e.g. a mirror of bedrock-samples
+    pub vanilla_samples_url: Option<String>,
 }
 
 impl UserConfig {
@@ -29,11 +46,18 @@ impl UserConfig {
             resolvers: default_resolvers(),
             resolver_update_interval: default_resolver_update_interval(),
             websocket_port: default_websocket_port(),
+            copy_connect_command: false,
             force_compat: false,
+            group_filter_output: false,
+            touch_manifests: false,
             mojang_dir: None,
             nodejs_runtime: None,
             nodejs_package_manager: None,
             python_command: None,
+            runtime_paths: BTreeMap::new(),
+            build_cache_url: None,
+            build_cache_token: None,
+            vanilla_samples_url: None,
         }
     }
 
@@ -53,10 +77,22 @@ impl UserConfig {
         get_user_config().websocket_port
     }
 
+    pub fn copy_connect_command() -> bool {
+        get_user_config().copy_connect_command
+    }
+
     pub fn force_compat() -> bool {
         get_user_config().force_compat
     }
 
+    pub fn group_filter_output() -> bool {
+        get_user_config().group_filter_output
+    }
+
+    pub fn touch_manifests() -> bool {
+        get_user_config().touch_manifests
+    }
+
     pub fn mojang_dir() -> Option<String> {
         get_user_config().mojang_dir.to_owned()
     }
@@ -84,6 +120,25 @@ impl UserConfig {
             .to_owned()
             .unwrap_or("python".to_owned())
     }
+
+    pub fn runtime_path(program: &str) -> Option<String> {
+        get_user_config().runtime_paths.get(program).cloned()
+    }
+
+    pub fn build_cache_url() -> Option<String> {
+        get_user_config().build_cache_url.to_owned()
+    }
+
+    pub fn build_cache_token() -> Option<String> {
+        get_user_config().build_cache_token.to_owned()
+    }
+
+    pub fn vanilla_samples_url() -> String {
+        get_user_config()
+            .vanilla_samples_url
+            .to_owned()
+            .unwrap_or("https://github.com/Mojang/bedrock-samples".to_owned())
+    }
 }
 
 fn default_username() -> String {
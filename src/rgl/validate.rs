@@ -0,0 +1,174 @@
+use super::{
+    validate_biomes, validate_client_entities, validate_fog_settings, validate_functions,
+    validate_identifiers, validate_script_modules, validate_sounds, validate_spawn_rules,
+    validate_texture_atlases, validate_trade_tables, validate_volumes, Config, MinecraftBuild,
+    Profile,
+};
+use crate::debug;
+use crate::fs::read_json;
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+type ValidateFn = fn(&ValidationContext, &mut Diagnostics) -> Result<()>;
+
+/// Validation passes, by the name used to report them and to turn them off in the config
+const VALIDATORS: [(&str, ValidateFn); 11] = [
+    ("sounds", validate_sounds),
+    ("texture_atlases", validate_texture_atlases),
+    ("client_entities", validate_client_entities),
+    ("identifiers", validate_identifiers),
+    ("script_modules", validate_script_modules),
+    ("functions", validate_functions),
+    ("spawn_rules", validate_spawn_rules),
+    ("trade_tables", validate_trade_tables),
+    ("volumes", validate_volumes),
+    ("fog_settings", validate_fog_settings),
+    ("biomes", validate_biomes),
+];
+
+pub struct ValidationContext {
+    pub bp: Option<PathBuf>,
+    pub rp: Option<PathBuf>,
+    /// Minecraft build targeted by the export of the validated profile
+    pub build: Option<MinecraftBuild>,
+    /// Validation passes turned on or off in the config
+    pub validators: BTreeMap<String, bool>,
+}
+
+impl ValidationContext {
+    pub fn new(config: &Config, profile: Option<&Profile>) -> Self {
+        Self {
+            bp: config.get_behavior_pack(),
+            rp: config.get_resource_pack(),
+            build: profile.and_then(|profile| profile.export.get_build()),
+            validators: config.get_validators().to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub path: PathBuf,
+    /// 1-based line in the file, if the issue can be tied to one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "<b>{}:{line}</>: {}", self.path.display(), self.message),
+            None => write!(f, "<b>{}</>: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn error(&mut self, path: &Path, message: impl Into<String>) {
+        self.push(Severity::Error, path, None, message.into());
+    }
+
+    pub fn warning(&mut self, path: &Path, message: impl Into<String>) {
+        self.push(Severity::Warning, path, None, message.into());
+    }
+
+    pub fn push(&mut self, severity: Severity, path: &Path, line: Option<usize>, message: String) {
+        self.0.push(Diagnostic {
+            severity,
+            path: path.to_owned(),
+            line,
+            message,
+        });
+    }
+
+    /// Reads a JSON file, reporting it as an error if it can't be parsed.
+    pub fn read_json(&mut self, path: &Path) -> Option<Value> {
+        match read_json(path) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error(path, format!("Invalid JSON: {}", e.root_cause()));
+                None
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.0.iter()
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.0.iter().filter(|d| d.severity == severity).count()
+    }
+}
+
+/// JSON files in a directory of a pack, e.g. `models`, in a stable order
+pub fn json_files(dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect()
+}
+
+/// Identifier and root object of a definition file, e.g. the `minecraft:spawn_rules` object of a
+/// spawn rules file. Files without them are reported as errors.
+pub fn get_definition<'a>(
+    diagnostics: &mut Diagnostics,
+    path: &Path,
+    value: &'a Value,
+    key: &str,
+) -> Option<(&'a str, &'a Value)> {
+    let definition = &value[key];
+    if !definition.is_object() {
+        diagnostics.error(path, format!("Missing <b>{key}</> object"));
+        return None;
+    }
+    let Some(id) = definition["description"]["identifier"].as_str() else {
+        diagnostics.error(path, "Missing <b>description.identifier</>");
+        return None;
+    };
+    Some((id, definition))
+}
+
+/// Runs every validation pass that is not turned off over the packs of the project.
+pub fn validate(context: &ValidationContext) -> Result<Diagnostics> {
+    if let Some(name) = context
+        .validators
+        .keys()
+        .find(|name| VALIDATORS.iter().all(|(known, _)| known != name))
+    {
+        let names = VALIDATORS.map(|(name, _)| name);
+        bail!(
+            "Unknown validator <b>{name}</> in config, expected one of {}",
+            names.join(", ")
+        );
+    }
+    let mut diagnostics = Diagnostics::default();
+    for (name, validator) in VALIDATORS {
+        if context.validators.get(name) == Some(&false) {
+            debug!("Skipping <b>{name}</> validation");
+            continue;
+        }
+        debug!("Running <b>{name}</> validation");
+        validator(context, &mut diagnostics)?;
+    }
+    Ok(diagnostics)
+}
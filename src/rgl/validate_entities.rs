@@ -0,0 +1,179 @@
+use super::{json_files, Diagnostics, ValidationContext};
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Image formats the game can load for textures
+pub const TEXTURE_FORMATS: [&str; 4] = ["png", "tga", "jpg", "jpeg"];
+
+/// Identifiers defined in the resource pack and the file that defines them
+#[derive(Default)]
+struct Definitions(HashMap<String, PathBuf>);
+
+impl Definitions {
+    /// Reports identifiers that are defined in more than one file, the game only loads one.
+    fn insert(&mut self, kind: &str, id: &str, path: &Path, diagnostics: &mut Diagnostics) {
+        match self.0.get(id) {
+            Some(other) => diagnostics.error(
+                path,
+                format!(
+                    "{kind} <b>{id}</> is also defined in <b>{}</>",
+                    other.display()
+                ),
+            ),
+            None => {
+                self.0.insert(id.to_owned(), path.to_owned());
+            }
+        }
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
+}
+
+/// Checks that client entities reference geometries, textures, animations and render
+/// controllers that exist, and that identifiers are unique across the resource pack.
+pub fn validate_client_entities(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(rp) = &context.rp else {
+        return Ok(());
+    };
+
+    let mut geometries = Definitions::default();
+    for path in json_files(&rp.join("models")) {
+        let Some(value) = diagnostics.read_json(&path) else {
+            continue;
+        };
+        for id in get_geometry_ids(&value) {
+            geometries.insert("Geometry", id, &path, diagnostics);
+        }
+    }
+    let mut animations = Definitions::default();
+    for path in json_files(&rp.join("animations")) {
+        if let Some(value) = diagnostics.read_json(&path) {
+            for id in get_keys(&value, "animations") {
+                animations.insert("Animation", id, &path, diagnostics);
+            }
+        }
+    }
+    let mut animation_controllers = Definitions::default();
+    for path in json_files(&rp.join("animation_controllers")) {
+        if let Some(value) = diagnostics.read_json(&path) {
+            for id in get_keys(&value, "animation_controllers") {
+                animation_controllers.insert("Animation controller", id, &path, diagnostics);
+            }
+        }
+    }
+    let mut render_controllers = Definitions::default();
+    for path in json_files(&rp.join("render_controllers")) {
+        if let Some(value) = diagnostics.read_json(&path) {
+            for id in get_keys(&value, "render_controllers") {
+                render_controllers.insert("Render controller", id, &path, diagnostics);
+            }
+        }
+    }
+
+    let mut entities = Definitions::default();
+    for path in json_files(&rp.join("entity")) {
+        let Some(value) = diagnostics.read_json(&path) else {
+            continue;
+        };
+        let description = &value["minecraft:client_entity"]["description"];
+        let Some(identifier) = description["identifier"].as_str() else {
+            diagnostics.error(&path, "Client entity has no identifier");
+            continue;
+        };
+        entities.insert("Client entity", identifier, &path, diagnostics);
+
+        let mut missing = |kind: &str, id: &str| {
+            diagnostics.warning(
+                &path,
+                format!("{kind} <b>{id}</> is not in the resource pack"),
+            )
+        };
+        for id in get_values(&description["geometry"]) {
+            if !geometries.contains(id) {
+                missing("Geometry", id);
+            }
+        }
+        for id in get_values(&description["animations"]) {
+            match id.starts_with("controller.") {
+                true if !animation_controllers.contains(id) => missing("Animation controller", id),
+                false if !animations.contains(id) => missing("Animation", id),
+                _ => {}
+            }
+        }
+        for id in get_render_controllers(&description["render_controllers"]) {
+            if !render_controllers.contains(id) {
+                missing("Render controller", id);
+            }
+        }
+        for texture in get_values(&description["textures"]) {
+            let exists = TEXTURE_FORMATS
+                .iter()
+                .any(|ext| rp.join(format!("{texture}.{ext}")).is_file());
+            if !exists {
+                missing("Texture", texture);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Geometry identifiers from either the current format with a `minecraft:geometry` array, or the
+/// legacy format with `geometry.name:geometry.parent` keys at the top level.
+fn get_geometry_ids(value: &Value) -> Vec<&str> {
+    if let Some(geometries) = value["minecraft:geometry"].as_array() {
+        return geometries
+            .iter()
+            .filter_map(|geometry| geometry["description"]["identifier"].as_str())
+            .collect();
+    }
+    value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| key.starts_with("geometry."))
+        .map(|(key, _)| key.split(':').next().unwrap_or(key))
+        .collect()
+}
+
+fn get_keys<'a>(value: &'a Value, key: &str) -> Vec<&'a str> {
+    value[key]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, _)| key.as_str())
+        .collect()
+}
+
+/// String values of a short name map, e.g. `{ "default": "geometry.pig" }`
+fn get_values(value: &Value) -> Vec<&str> {
+    value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, value)| value.as_str())
+        .collect()
+}
+
+/// Render controllers are either an identifier, or an object with the identifier as the key and
+/// the condition as the value.
+fn get_render_controllers(value: &Value) -> Vec<&str> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|entry| match entry {
+            Value::String(id) => vec![id.as_str()],
+            Value::Object(entry) => entry.keys().map(|id| id.as_str()).collect(),
+            _ => vec![],
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+use super::{get_definition, json_files, Diagnostics, ValidationContext};
+use crate::fs::read_json;
+use anyhow::Result;
+use serde_json::Value;
+use std::{collections::HashSet, path::Path};
+
+/// Where the distance fog of a fog definition applies
+const FOG_LOCATIONS: [&str; 6] = [
+    "air",
+    "lava",
+    "lava_resistance",
+    "powder_snow",
+    "water",
+    "weather",
+];
+
+const RENDER_DISTANCE_TYPES: [&str; 2] = ["fixed", "render"];
+
+/// Checks the bounds of the volumes in the behavior pack, and that the fogs they apply exist.
+pub fn validate_volumes(context: &ValidationContext, diagnostics: &mut Diagnostics) -> Result<()> {
+    let Some(bp) = &context.bp else {
+        return Ok(());
+    };
+    let fogs = match &context.rp {
+        Some(rp) => get_fog_identifiers(rp),
+        None => HashSet::new(),
+    };
+    for path in json_files(&bp.join("volumes")) {
+        let Some(value) = diagnostics.read_json(&path) else {
+            continue;
+        };
+        let Some((_, volume)) = get_definition(diagnostics, &path, &value, "minecraft:volume")
+        else {
+            continue;
+        };
+        let components = &volume["components"];
+        let bounds = &components["minecraft:bounds"];
+        if bounds.is_null() {
+            diagnostics.error(&path, "Missing <b>minecraft:bounds</> component");
+        } else {
+            match (get_position(&bounds["min"]), get_position(&bounds["max"])) {
+                (Some(min), Some(max)) => {
+                    if min.iter().zip(max).any(|(min, max)| *min > max) {
+                        diagnostics.error(
+                            &path,
+                            "<b>minecraft:bounds</> has a <b>min</> greater than its <b>max</>",
+                        );
+                    }
+                }
+                _ => diagnostics.error(
+                    &path,
+                    "<b>minecraft:bounds</> needs a <b>min</> and <b>max</> position, e.g. [0, 0, 0]",
+                ),
+            }
+        }
+        if let Some(fog) = components["minecraft:fog"].as_object() {
+            match fog.get("fog_identifier").and_then(Value::as_str) {
+                Some(id) if id.starts_with("minecraft:") || fogs.contains(id) => {}
+                Some(id) => diagnostics.warning(
+                    &path,
+                    format!("Fog <b>{id}</> is not defined in the resource pack"),
+                ),
+                None => diagnostics.error(
+                    &path,
+                    "<b>minecraft:fog</> is missing its <b>fog_identifier</>",
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the distance fog settings of the fog definitions in the resource pack.
+pub fn validate_fog_settings(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(rp) = &context.rp else {
+        return Ok(());
+    };
+    for path in json_files(&rp.join("fogs")) {
+        let Some(value) = diagnostics.read_json(&path) else {
+            continue;
+        };
+        let Some((_, fog)) = get_definition(diagnostics, &path, &value, "minecraft:fog_settings")
+        else {
+            continue;
+        };
+        let Some(distance) = fog["distance"].as_object() else {
+            continue;
+        };
+        for (location, settings) in distance {
+            check_distance_fog(&path, location, settings, diagnostics);
+        }
+    }
+    Ok(())
+}
+
+fn check_distance_fog(
+    path: &Path,
+    location: &str,
+    settings: &Value,
+    diagnostics: &mut Diagnostics,
+) {
+    if !FOG_LOCATIONS.contains(&location) {
+        diagnostics.error(
+            path,
+            format!(
+                "Unknown fog location <b>{location}</>, expected one of {}",
+                FOG_LOCATIONS.join(", ")
+            ),
+        );
+        return;
+    }
+    let start = settings["fog_start"].as_f64();
+    let end = settings["fog_end"].as_f64();
+    match (start, end) {
+        (Some(start), Some(end)) if start > end => diagnostics.error(
+            path,
+            format!("Fog of <b>{location}</> starts after it ends"),
+        ),
+        (Some(_), Some(_)) => {}
+        _ => diagnostics.error(
+            path,
+            format!("Fog of <b>{location}</> needs a <b>fog_start</> and <b>fog_end</> number"),
+        ),
+    }
+    match settings["render_distance_type"].as_str() {
+        Some(kind) if RENDER_DISTANCE_TYPES.contains(&kind) => {}
+        _ => diagnostics.error(
+            path,
+            format!(
+                "Fog of <b>{location}</> has an invalid <b>render_distance_type</>, expected one of {}",
+                RENDER_DISTANCE_TYPES.join(", ")
+            ),
+        ),
+    }
+    if let Some(color) = settings["fog_color"].as_str() {
+        let hex = color.strip_prefix('#').unwrap_or_default();
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            diagnostics.error(
+                path,
+                format!("Fog of <b>{location}</> has an invalid color <b>{color}</>, expected e.g. #ABCDEF"),
+            );
+        }
+    }
+}
+
+/// Checks the biomes in the behavior pack have components and a valid climate.
+pub fn validate_biomes(context: &ValidationContext, diagnostics: &mut Diagnostics) -> Result<()> {
+    let Some(bp) = &context.bp else {
+        return Ok(());
+    };
+    for path in json_files(&bp.join("biomes")) {
+        let Some(value) = diagnostics.read_json(&path) else {
+            continue;
+        };
+        let Some((_, biome)) = get_definition(diagnostics, &path, &value, "minecraft:biome") else {
+            continue;
+        };
+        let Some(components) = biome["components"].as_object() else {
+            diagnostics.error(&path, "Missing <b>components</> object");
+            continue;
+        };
+        let Some(climate) = components.get("minecraft:climate") else {
+            continue;
+        };
+        if climate["downfall"]
+            .as_f64()
+            .is_some_and(|downfall| !(0.0..=1.0).contains(&downfall))
+        {
+            diagnostics.error(
+                &path,
+                "<b>minecraft:climate</> has a <b>downfall</> outside of 0 to 1",
+            );
+        }
+        if !climate["temperature"].is_null() && !climate["temperature"].is_number() {
+            diagnostics.error(
+                &path,
+                "<b>minecraft:climate</> has an invalid <b>temperature</>, expected a number",
+            );
+        }
+    }
+    Ok(())
+}
+
+fn get_position(value: &Value) -> Option<[f64; 3]> {
+    match value.as_array()?.as_slice() {
+        [x, y, z] => Some([x.as_f64()?, y.as_f64()?, z.as_f64()?]),
+        _ => None,
+    }
+}
+
+/// Identifiers of the fogs defined by the resource pack
+fn get_fog_identifiers(rp: &Path) -> HashSet<String> {
+    json_files(&rp.join("fogs"))
+        .into_iter()
+        .filter_map(|path| read_json::<Value>(path).ok())
+        .filter_map(|value| {
+            let id = value["minecraft:fog_settings"]["description"]["identifier"].as_str()?;
+            Some(id.to_owned())
+        })
+        .collect()
+}
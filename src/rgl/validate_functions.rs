@@ -0,0 +1,439 @@
+use super::{get_min_engine_version, Diagnostics, Severity, ValidationContext};
+use anyhow::Result;
+use semver::Version;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+const VANILLA_COMMANDS: &str = include_str!("vanilla_commands.txt");
+
+const SELECTOR_TYPES: [&str; 6] = ["a", "e", "initiator", "p", "r", "s"];
+
+const SELECTOR_ARGUMENTS: [&str; 24] = [
+    "c",
+    "dx",
+    "dy",
+    "dz",
+    "family",
+    "has_property",
+    "hasitem",
+    "haspermission",
+    "l",
+    "lm",
+    "m",
+    "name",
+    "r",
+    "rm",
+    "rx",
+    "rxm",
+    "ry",
+    "rym",
+    "scores",
+    "tag",
+    "type",
+    "x",
+    "y",
+    "z",
+];
+
+/// Selector arguments that only take a number, the coordinates also accept relative positions
+const NUMERIC_ARGUMENTS: [&str; 12] = [
+    "c", "dx", "dy", "dz", "l", "lm", "r", "rm", "rx", "rxm", "ry", "rym",
+];
+
+const EXECUTE_SUBCOMMANDS: [&str; 11] = [
+    "align",
+    "anchored",
+    "as",
+    "at",
+    "facing",
+    "if",
+    "in",
+    "positioned",
+    "rotated",
+    "run",
+    "unless",
+];
+
+/// Commands that end with a free text message, by the number of arguments before the message.
+/// Words starting with `@` in the message are not selectors.
+const TEXT_COMMANDS: [(&str, usize); 5] =
+    [("me", 0), ("msg", 1), ("say", 0), ("tell", 1), ("w", 1)];
+
+/// Functions in behavior packs with a later `min_engine_version` only accept the new syntax
+const LEGACY_EXECUTE_REMOVED: Version = Version::new(1, 19, 50);
+
+struct CommandSchema {
+    added: Version,
+    min_args: usize,
+}
+
+/// Checks the commands of the `.mcfunction` files in the behavior pack against the commands
+/// available at the pack's `min_engine_version`, and that the functions they run and the ones in
+/// `tick.json` exist.
+pub fn validate_functions(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(bp) = &context.bp else {
+        return Ok(());
+    };
+    let dir = bp.join("functions");
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let files = function_files(&dir);
+    let linter = Linter {
+        commands: get_vanilla_commands(),
+        min_engine_version: get_min_engine_version(bp),
+        functions: files
+            .iter()
+            .filter_map(|path| get_function_name(&dir, path))
+            .collect(),
+    };
+
+    for path in &files {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                diagnostics.error(path, format!("Failed to read function: {e}"));
+                continue;
+            }
+        };
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut issues = vec![];
+            linter.check_command(line, &mut issues);
+            for (severity, message) in issues {
+                diagnostics.push(severity, path, Some(i + 1), message);
+            }
+        }
+    }
+
+    let tick_path = dir.join("tick.json");
+    if tick_path.is_file() {
+        let Some(tick) = diagnostics.read_json(&tick_path) else {
+            return Ok(());
+        };
+        let Some(values) = tick["values"].as_array() else {
+            diagnostics.error(&tick_path, "Missing <b>values</> array of functions");
+            return Ok(());
+        };
+        for value in values {
+            match value.as_str() {
+                Some(name) if linter.functions.contains(name) => {}
+                Some(name) => {
+                    diagnostics.error(&tick_path, format!("Function <b>{name}</> doesn't exist"))
+                }
+                None => diagnostics.error(
+                    &tick_path,
+                    format!("Invalid function {value}, expected a path like <b>folder/name</>"),
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Linter {
+    commands: HashMap<&'static str, CommandSchema>,
+    min_engine_version: Option<Version>,
+    /// Functions of the pack, by their path in the functions directory without extension
+    functions: HashSet<String>,
+}
+
+impl Linter {
+    fn check_command(&self, command: &str, issues: &mut Vec<(Severity, String)>) {
+        let command = match command.strip_prefix('/') {
+            Some(command) => {
+                issues.push((
+                    Severity::Warning,
+                    "Commands in functions don't start with <b>/</>".to_owned(),
+                ));
+                command
+            }
+            None => command,
+        };
+        let args = match split_arguments(command) {
+            Ok(args) => args,
+            Err(message) => {
+                issues.push((Severity::Error, message));
+                return;
+            }
+        };
+        let Some((name, args)) = args.split_first() else {
+            return;
+        };
+        let name = name.to_lowercase();
+        // Custom commands registered by scripts are namespaced
+        if name.contains(':') {
+            return;
+        }
+        let Some(schema) = self.commands.get(name.as_str()) else {
+            issues.push((Severity::Error, format!("Unknown command <b>{name}</>")));
+            return;
+        };
+        if let Some(min) = &self.min_engine_version {
+            if min < &schema.added {
+                issues.push((
+                    Severity::Warning,
+                    format!(
+                        "<b>{name}</> was added in {}, but min_engine_version is {min}",
+                        schema.added
+                    ),
+                ));
+            }
+        }
+        if args.len() < schema.min_args {
+            issues.push((
+                Severity::Error,
+                format!(
+                    "<b>{name}</> expects at least {} argument(s), found {}",
+                    schema.min_args,
+                    args.len()
+                ),
+            ));
+            return;
+        }
+
+        match name.as_str() {
+            "execute" => self.check_execute(args, issues),
+            "function" => {
+                let function = args[0].trim_matches('"');
+                if !self.functions.contains(function) {
+                    issues.push((
+                        Severity::Error,
+                        format!("Function <b>{function}</> doesn't exist"),
+                    ));
+                }
+            }
+            _ => {
+                let args = match TEXT_COMMANDS.iter().find(|(text, _)| *text == name) {
+                    Some((_, targets)) => &args[..*targets],
+                    None => args,
+                };
+                check_selectors(args, issues);
+            }
+        }
+    }
+
+    fn check_execute(&self, args: &[&str], issues: &mut Vec<(Severity, String)>) {
+        if EXECUTE_SUBCOMMANDS.contains(&args[0]) {
+            let Some(run) = args.iter().position(|arg| *arg == "run") else {
+                check_selectors(args, issues);
+                return;
+            };
+            check_selectors(&args[..run], issues);
+            match args.get(run + 1..).filter(|command| !command.is_empty()) {
+                Some(command) => self.check_command(&command.join(" "), issues),
+                None => issues.push((Severity::Error, "<b>run</> is missing a command".to_owned())),
+            }
+            return;
+        }
+
+        // `execute <target> <x> <y> <z> [detect <x> <y> <z> <block> <data>] <command>`
+        if self
+            .min_engine_version
+            .as_ref()
+            .is_some_and(|min| min >= &LEGACY_EXECUTE_REMOVED)
+        {
+            issues.push((
+                Severity::Error,
+                format!("The legacy execute syntax was removed in {LEGACY_EXECUTE_REMOVED}, use <b>execute as <target> at @s run <command></>"),
+            ));
+            return;
+        }
+        let command_start = match args.get(4) {
+            Some(&"detect") => 10,
+            _ => 4,
+        };
+        check_selectors(&args[..command_start.min(args.len())], issues);
+        match args
+            .get(command_start..)
+            .filter(|command| !command.is_empty())
+        {
+            Some(command) => self.check_command(&command.join(" "), issues),
+            None => issues.push((
+                Severity::Error,
+                "<b>execute</> is missing a command".to_owned(),
+            )),
+        }
+    }
+}
+
+fn check_selectors(args: &[&str], issues: &mut Vec<(Severity, String)>) {
+    for arg in args {
+        if arg.starts_with('@') {
+            check_selector(arg, issues);
+        }
+    }
+}
+
+fn check_selector(selector: &str, issues: &mut Vec<(Severity, String)>) {
+    let (kind, arguments) = match selector[1..].split_once('[') {
+        Some((kind, arguments)) => (kind, Some(arguments)),
+        None => (&selector[1..], None),
+    };
+    if !SELECTOR_TYPES.contains(&kind) {
+        issues.push((Severity::Error, format!("Unknown selector <b>@{kind}</>")));
+        return;
+    }
+    let Some(arguments) = arguments else {
+        return;
+    };
+    let Some(arguments) = arguments.strip_suffix(']') else {
+        issues.push((
+            Severity::Error,
+            format!("Invalid selector <b>{selector}</>, expected it to end with <b>]</>"),
+        ));
+        return;
+    };
+    for argument in split_top_level(arguments, ',') {
+        let argument = argument.trim();
+        if argument.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = argument.split_once('=') else {
+            issues.push((
+                Severity::Error,
+                format!("Invalid selector argument <b>{argument}</>, expected <b>key=value</>"),
+            ));
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if !SELECTOR_ARGUMENTS.contains(&key) {
+            issues.push((
+                Severity::Error,
+                format!("Unknown selector argument <b>{key}</> in <b>{selector}</>"),
+            ));
+            continue;
+        }
+        let is_number = match key {
+            "c" => value.parse::<i32>().is_ok(),
+            _ => value.parse::<f64>().is_ok(),
+        };
+        if NUMERIC_ARGUMENTS.contains(&key) && !is_number {
+            issues.push((
+                Severity::Error,
+                format!("Selector argument <b>{key}</> expects a number, found <b>{value}</>"),
+            ));
+        }
+    }
+}
+
+/// Splits a command into its name and arguments. Quoted strings, selector arguments and JSON
+/// can contain spaces.
+fn split_arguments(command: &str) -> Result<Vec<&str>, String> {
+    let mut args = vec![];
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = None;
+    for (i, c) in command.char_indices() {
+        if start.is_none() {
+            if c.is_whitespace() {
+                continue;
+            }
+            start = Some(i);
+        }
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '[' | '{' => depth += 1,
+            ']' | '}' if depth == 0 => return Err(format!("Unexpected <b>{c}</>")),
+            ']' | '}' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                args.extend(start.take().map(|start| &command[start..i]));
+            }
+            _ => {}
+        }
+    }
+    if quoted {
+        return Err("Unclosed quote".to_owned());
+    }
+    if depth > 0 {
+        return Err("Unclosed bracket".to_owned());
+    }
+    args.extend(start.map(|start| &command[start..]));
+    Ok(args)
+}
+
+/// Splits on the separator outside of quotes and brackets, e.g. the arguments of
+/// `@e[hasitem=[{item=apple},{item=stick}],c=1]`
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Vanilla commands and the version that added them
+fn get_vanilla_commands() -> HashMap<&'static str, CommandSchema> {
+    let mut commands = HashMap::new();
+    let mut version = Version::new(1, 0, 0);
+    for line in VANILLA_COMMANDS.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.strip_prefix('@') {
+            Some(line) => version = Version::parse(line).expect("Invalid version"),
+            None => {
+                let (name, min_args) = line.split_once(' ').expect("Invalid command");
+                commands.insert(
+                    name,
+                    CommandSchema {
+                        added: version.clone(),
+                        min_args: min_args.parse().expect("Invalid argument count"),
+                    },
+                );
+            }
+        }
+    }
+    commands
+}
+
+/// `.mcfunction` files in the functions directory, in a stable order
+fn function_files(dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mcfunction"))
+        .collect()
+}
+
+/// Name used to run a function, its path in the functions directory without extension, e.g.
+/// `folder/name`
+fn get_function_name(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?.with_extension("");
+    let parts = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    Some(parts.join("/"))
+}
@@ -0,0 +1,203 @@
+use super::{json_files, Diagnostics, ValidationContext};
+use crate::fs::read_json;
+use anyhow::Result;
+use semver::Version;
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::Path,
+};
+
+const VANILLA_IDENTIFIERS: &str = include_str!("vanilla_identifiers.txt");
+
+/// Recipe keys whose value can be an item identifier
+const RECIPE_ITEM_KEYS: [&str; 7] = [
+    "input", "output", "reagent", "template", "base", "addition", "result",
+];
+
+/// Checks that the items and blocks used by recipes, loot tables and trade tables are defined in
+/// the packs or exist in the vanilla game at the pack's `min_engine_version`.
+pub fn validate_identifiers(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(bp) = &context.bp else {
+        return Ok(());
+    };
+    let vanilla = get_vanilla_identifiers();
+    let min_engine_version = get_min_engine_version(bp);
+    let defined = get_defined_identifiers(context, diagnostics);
+
+    let sources = [
+        ("recipes", FileKind::Recipe),
+        ("loot_tables", FileKind::LootTable),
+        ("trading", FileKind::TradeTable),
+    ];
+    for (dir, kind) in sources {
+        for path in json_files(&bp.join(dir)) {
+            let Some(value) = diagnostics.read_json(&path) else {
+                continue;
+            };
+            let mut used = BTreeSet::new();
+            collect_identifiers(&value, kind, &mut used);
+            for id in used {
+                let (namespace, name) = id.split_once(':').unwrap_or(("minecraft", id));
+                let id = format!("{namespace}:{name}");
+                if defined.contains(&id) {
+                    continue;
+                }
+                if namespace != "minecraft" {
+                    diagnostics.error(&path, format!("<b>{id}</> is not defined in the packs"));
+                    continue;
+                }
+                match vanilla.get(name) {
+                    None => diagnostics.warning(
+                        &path,
+                        format!("<b>{id}</> is not a known vanilla item or block"),
+                    ),
+                    Some(added) => {
+                        if let Some(min) = &min_engine_version {
+                            if min < added {
+                                diagnostics.warning(
+                                    &path,
+                                    format!("<b>{id}</> was added in {added}, but min_engine_version is {min}"),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FileKind {
+    Recipe,
+    LootTable,
+    TradeTable,
+}
+
+/// Vanilla identifiers without namespace and the version that added them
+fn get_vanilla_identifiers() -> HashMap<&'static str, Version> {
+    let mut identifiers = HashMap::new();
+    let mut version = Version::new(1, 0, 0);
+    for line in VANILLA_IDENTIFIERS.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.strip_prefix('@') {
+            Some(line) => version = Version::parse(line).expect("Invalid version"),
+            None => {
+                identifiers.insert(line, version.clone());
+            }
+        }
+    }
+    identifiers
+}
+
+/// `min_engine_version` from the manifest of a pack
+pub fn get_min_engine_version(pack: &Path) -> Option<Version> {
+    let manifest: Value = read_json(pack.join("manifest.json")).ok()?;
+    let version = manifest["header"]["min_engine_version"].as_array()?;
+    let [major, minor, patch] = version.as_slice() else {
+        return None;
+    };
+    Some(Version::new(
+        major.as_u64()?,
+        minor.as_u64()?,
+        patch.as_u64()?,
+    ))
+}
+
+/// Identifiers of the items, blocks and spawn eggs defined by the packs
+fn get_defined_identifiers(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> HashSet<String> {
+    let mut defined = HashSet::new();
+    let sources = [
+        (&context.bp, "items", "minecraft:item"),
+        (&context.bp, "blocks", "minecraft:block"),
+        (&context.bp, "entities", "minecraft:entity"),
+        // Items in the resource pack use the legacy format
+        (&context.rp, "items", "minecraft:item"),
+    ];
+    for (pack, dir, key) in sources {
+        let Some(pack) = pack else {
+            continue;
+        };
+        for path in json_files(&pack.join(dir)) {
+            let Some(value) = diagnostics.read_json(&path) else {
+                continue;
+            };
+            let Some(id) = value[key]["description"]["identifier"].as_str() else {
+                continue;
+            };
+            if key == "minecraft:entity" {
+                defined.insert(format!("{id}_spawn_egg"));
+            } else {
+                defined.insert(id.to_owned());
+            }
+        }
+    }
+    defined
+}
+
+fn collect_identifiers<'a>(value: &'a Value, kind: FileKind, used: &mut BTreeSet<&'a str>) {
+    match value {
+        Value::Object(object) => {
+            if kind == FileKind::LootTable && object.get("type") == Some(&"item".into()) {
+                if let Some(Value::String(name)) = object.get("name") {
+                    add_identifier(name, used);
+                }
+            }
+            for (key, value) in object {
+                match (key.as_str(), value) {
+                    ("description" | "tags", _) => {}
+                    ("item", Value::String(id)) => add_identifier(id, used),
+                    (key, Value::String(id))
+                        if kind == FileKind::Recipe && RECIPE_ITEM_KEYS.contains(&key) =>
+                    {
+                        add_identifier(id, used)
+                    }
+                    // Shaped recipe keys and shapeless ingredients can be identifiers
+                    ("key" | "ingredients", value) if kind == FileKind::Recipe => {
+                        let values: Vec<_> = match value {
+                            Value::Object(object) => object.values().collect(),
+                            Value::Array(array) => array.iter().collect(),
+                            _ => vec![],
+                        };
+                        for value in values {
+                            match value {
+                                Value::String(id) => add_identifier(id, used),
+                                value => collect_identifiers(value, kind, used),
+                            }
+                        }
+                    }
+                    (_, value) => collect_identifiers(value, kind, used),
+                }
+            }
+        }
+        Value::Array(array) => {
+            for value in array {
+                collect_identifiers(value, kind, used);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn add_identifier<'a>(id: &'a str, used: &mut BTreeSet<&'a str>) {
+    // Potions are referenced by type in brewing recipes, e.g. `minecraft:potion_type:water`
+    if id.contains("potion_type:") {
+        return;
+    }
+    // Legacy identifiers can end with the data value, e.g. `minecraft:dye:4`
+    let id = match id.rsplit_once(':') {
+        Some((id, data)) if data.chars().all(|c| c.is_ascii_digit()) => id,
+        _ => id,
+    };
+    used.insert(id);
+}
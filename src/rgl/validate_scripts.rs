@@ -0,0 +1,139 @@
+use super::{Diagnostics, MinecraftBuild, ValidationContext};
+use anyhow::Result;
+use semver::Version;
+
+/// Latest stable and beta version of a script module in a Minecraft build
+struct ModuleVersions {
+    stable: Option<&'static str>,
+    beta: Option<&'static str>,
+}
+
+const fn versions(stable: Option<&'static str>, beta: Option<&'static str>) -> ModuleVersions {
+    ModuleVersions { stable, beta }
+}
+
+/// Script modules shipped with Minecraft 1.21.100 and the 1.21.110 preview, update with new
+/// releases.
+const SCRIPT_MODULES: [(&str, ModuleVersions, ModuleVersions); 7] = [
+    (
+        "@minecraft/server",
+        versions(Some("2.1.0"), Some("2.2.0-beta")),
+        versions(Some("2.2.0"), Some("2.3.0-beta")),
+    ),
+    (
+        "@minecraft/server-ui",
+        versions(Some("2.0.0"), Some("2.1.0-beta")),
+        versions(Some("2.0.0"), Some("2.1.0-beta")),
+    ),
+    (
+        "@minecraft/common",
+        versions(Some("1.2.0"), None),
+        versions(Some("1.2.0"), None),
+    ),
+    (
+        "@minecraft/server-gametest",
+        versions(None, Some("1.0.0-beta")),
+        versions(None, Some("1.0.0-beta")),
+    ),
+    (
+        "@minecraft/debug-utilities",
+        versions(None, Some("1.0.0-beta")),
+        versions(None, Some("1.0.0-beta")),
+    ),
+    (
+        "@minecraft/server-net",
+        versions(None, Some("1.0.0-beta")),
+        versions(None, Some("1.0.0-beta")),
+    ),
+    (
+        "@minecraft/server-admin",
+        versions(None, Some("1.0.0-beta")),
+        versions(None, Some("1.0.0-beta")),
+    ),
+];
+
+/// Checks that the script modules in the behavior pack manifest are available in the Minecraft
+/// build of the export target. Beta versions only load on the build that ships them.
+pub fn validate_script_modules(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(bp) = &context.bp else {
+        return Ok(());
+    };
+    let manifest_path = bp.join("manifest.json");
+    if !manifest_path.is_file() {
+        return Ok(());
+    }
+    let Some(manifest) = diagnostics.read_json(&manifest_path) else {
+        return Ok(());
+    };
+    let (build, preview) = match &context.build {
+        Some(MinecraftBuild::Preview | MinecraftBuild::Preview_GDK) => ("preview", true),
+        _ => ("stable", false),
+    };
+
+    let dependencies = manifest["dependencies"].as_array().into_iter().flatten();
+    for dependency in dependencies {
+        let (Some(name), Some(version)) = (
+            dependency["module_name"].as_str(),
+            dependency["version"].as_str(),
+        ) else {
+            continue;
+        };
+        let Some((_, stable_versions, preview_versions)) =
+            SCRIPT_MODULES.iter().find(|(module, ..)| *module == name)
+        else {
+            continue;
+        };
+        let available = match preview {
+            true => preview_versions,
+            false => stable_versions,
+        };
+        let module = format!("<b>{name}@{version}</>");
+
+        if version.ends_with("-beta") {
+            if context.build == Some(MinecraftBuild::Education) {
+                diagnostics.error(
+                    &manifest_path,
+                    format!(
+                        "{module} won't load on Education Edition, it doesn't support beta APIs"
+                    ),
+                );
+                continue;
+            }
+            if available.beta == Some(version) {
+                continue;
+            }
+            let message = match available.beta {
+                Some(beta) => format!(
+                    "{module} won't load on the {build} build, it ships <b>{name}@{beta}</>"
+                ),
+                None => format!(
+                    "{module} won't load on the {build} build, it has no beta version of the module"
+                ),
+            };
+            diagnostics.warning(&manifest_path, message);
+            continue;
+        }
+        let Ok(parsed) = Version::parse(version) else {
+            diagnostics.error(&manifest_path, format!("{module} has an invalid version"));
+            continue;
+        };
+        let latest = available.stable.and_then(|v| Version::parse(v).ok());
+        match latest {
+            Some(latest) if parsed <= latest => {}
+            Some(latest) => diagnostics.warning(
+                &manifest_path,
+                format!(
+                    "{module} is not available on the {build} build, the latest stable version is <b>{latest}</>"
+                ),
+            ),
+            None => diagnostics.warning(
+                &manifest_path,
+                format!("{module} is not available on the {build} build, the module is beta only"),
+            ),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use super::{Diagnostics, ValidationContext};
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Audio formats the game can load, in the order it looks for them
+const SUPPORTED_FORMATS: [&str; 3] = ["ogg", "wav", "fsb"];
+
+/// Audio formats that are commonly exported by mistake
+const UNSUPPORTED_FORMATS: [&str; 6] = ["mp3", "flac", "m4a", "aac", "wma", "aiff"];
+
+/// Checks that the sounds referenced by `sound_definitions.json` exist in a supported format, that
+/// `sounds.json` only uses defined sounds, and reports audio files that are never used.
+pub fn validate_sounds(context: &ValidationContext, diagnostics: &mut Diagnostics) -> Result<()> {
+    let Some(rp) = &context.rp else {
+        return Ok(());
+    };
+    let sounds_dir = rp.join("sounds");
+    let definitions_path = sounds_dir.join("sound_definitions.json");
+
+    let mut defined = HashSet::new();
+    // Paths of the referenced audio files relative to the pack, without extension
+    let mut referenced = HashSet::new();
+    if definitions_path.is_file() {
+        let Some(definitions) = diagnostics.read_json(&definitions_path) else {
+            return Ok(());
+        };
+        for (id, definition) in get_definitions(&definitions) {
+            defined.insert(id.to_owned());
+            let sounds = match &definition["sounds"] {
+                Value::Array(sounds) => sounds.iter().collect(),
+                Value::Null => vec![],
+                sound => vec![sound],
+            };
+            for sound in sounds {
+                let name = match sound {
+                    Value::String(name) => name,
+                    Value::Object(sound) => match &sound.get("name") {
+                        Some(Value::String(name)) => name,
+                        _ => {
+                            diagnostics.error(
+                                &definitions_path,
+                                format!("Sound <b>{id}</> has an entry without a name"),
+                            );
+                            continue;
+                        }
+                    },
+                    _ => {
+                        diagnostics.error(
+                            &definitions_path,
+                            format!("Sound <b>{id}</> has an invalid entry, expected a string or an object"),
+                        );
+                        continue;
+                    }
+                };
+                let name = strip_extension(name);
+                check_sound_file(rp, &definitions_path, id, name, diagnostics);
+                referenced.insert(name.to_owned());
+            }
+        }
+    }
+
+    let sounds_json = rp.join("sounds.json");
+    if sounds_json.is_file() {
+        if let Some(sounds) = diagnostics.read_json(&sounds_json) {
+            let mut used = vec![];
+            collect_sound_events(&sounds, &mut used);
+            for id in used {
+                if !id.is_empty() && !defined.contains(id) {
+                    diagnostics.warning(
+                        &sounds_json,
+                        format!("Sound <b>{id}</> is not defined in sound_definitions.json, ignore if it's a vanilla sound"),
+                    );
+                }
+            }
+        }
+    }
+
+    for entry in WalkDir::new(&sounds_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let path = entry.path();
+        let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+            continue;
+        };
+        let extension = extension.to_lowercase();
+        if UNSUPPORTED_FORMATS.contains(&extension.as_str()) {
+            diagnostics.error(
+                path,
+                format!("Unsupported audio format <b>.{extension}</>, convert it to .ogg or .wav"),
+            );
+        } else if SUPPORTED_FORMATS.contains(&extension.as_str()) {
+            let name = to_sound_name(rp, path);
+            if !referenced.contains(&name) {
+                diagnostics.warning(path, "Audio file is not used by any sound definition");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sound definitions from either the current format with a `sound_definitions` object, or the
+/// legacy format with the definitions at the top level.
+fn get_definitions(value: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    let definitions = value
+        .get("sound_definitions")
+        .and_then(Value::as_object)
+        .or(value.as_object());
+    definitions
+        .into_iter()
+        .flatten()
+        .filter(|(id, definition)| *id != "format_version" && definition.is_object())
+}
+
+fn check_sound_file(
+    rp: &Path,
+    definitions_path: &Path,
+    id: &str,
+    name: &str,
+    diagnostics: &mut Diagnostics,
+) {
+    let path = rp.join(name);
+    let exists = |extension: &str| with_extension(&path, extension).is_file();
+    if SUPPORTED_FORMATS.into_iter().any(exists) {
+        return;
+    }
+    if let Some(extension) = UNSUPPORTED_FORMATS.into_iter().find(|ext| exists(ext)) {
+        diagnostics.error(
+            definitions_path,
+            format!("Sound <b>{id}</> uses <b>{name}.{extension}</>, which is an unsupported audio format"),
+        );
+    } else {
+        diagnostics.warning(
+            definitions_path,
+            format!("Sound <b>{id}</> references <b>{name}</>, which is not in the resource pack, ignore if it's a vanilla sound"),
+        );
+    }
+}
+
+/// Collects the sound IDs used by events in `sounds.json`. Events are either an ID, or an object
+/// with the ID in `sound`.
+fn collect_sound_events<'a>(value: &'a Value, used: &mut Vec<&'a str>) {
+    let Value::Object(object) = value else {
+        return;
+    };
+    for (key, value) in object {
+        match (key.as_str(), value) {
+            ("sound", Value::String(id)) => used.push(id),
+            ("events", Value::Object(events)) => {
+                for event in events.values() {
+                    match event {
+                        Value::String(id) => used.push(id),
+                        event => collect_sound_events(event, used),
+                    }
+                }
+            }
+            (_, value) => collect_sound_events(value, used),
+        }
+    }
+}
+
+/// Removes the extension of a sound name if it's an audio format, names usually leave it out.
+fn strip_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, extension))
+            if SUPPORTED_FORMATS.contains(&extension)
+                || UNSUPPORTED_FORMATS.contains(&extension) =>
+        {
+            stem
+        }
+        _ => name,
+    }
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Name of the audio file as used in `sound_definitions.json`, e.g. `sounds/mob/cow`
+fn to_sound_name(rp: &Path, path: &Path) -> String {
+    let path = path.strip_prefix(rp).unwrap_or(path).with_extension("");
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,189 @@
+use super::{get_definition, json_files, Diagnostics, ValidationContext};
+use crate::fs::read_json;
+use anyhow::Result;
+use serde_json::Value;
+use std::{collections::HashSet, path::Path};
+
+const POPULATION_CONTROLS: [&str; 6] = [
+    "ambient",
+    "animal",
+    "cat",
+    "monster",
+    "pillager",
+    "water_animal",
+];
+
+/// Components that set where a condition spawns, a condition without one never spawns anything
+const SPAWN_POSITIONS: [&str; 4] = [
+    "minecraft:spawns_lava",
+    "minecraft:spawns_on_surface",
+    "minecraft:spawns_underground",
+    "minecraft:spawns_underwater",
+];
+
+const DIFFICULTIES: [&str; 4] = ["peaceful", "easy", "normal", "hard"];
+
+/// Checks the structure of the spawn rules, and that they are for entities of the behavior pack.
+pub fn validate_spawn_rules(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(bp) = &context.bp else {
+        return Ok(());
+    };
+    let entities = get_entity_identifiers(bp);
+    for path in json_files(&bp.join("spawn_rules")) {
+        let Some(value) = diagnostics.read_json(&path) else {
+            continue;
+        };
+        let Some((id, rules)) = get_definition(diagnostics, &path, &value, "minecraft:spawn_rules")
+        else {
+            continue;
+        };
+        if !id.starts_with("minecraft:") && !entities.contains(id) {
+            diagnostics.warning(
+                &path,
+                format!("Entity <b>{id}</> is not defined in the behavior pack"),
+            );
+        }
+        match rules["description"]["population_control"].as_str() {
+            Some(control) if POPULATION_CONTROLS.contains(&control) => {}
+            Some(control) => diagnostics.error(
+                &path,
+                format!(
+                    "Unknown population_control <b>{control}</>, expected one of {}",
+                    POPULATION_CONTROLS.join(", ")
+                ),
+            ),
+            None => diagnostics.error(&path, "Missing <b>description.population_control</>"),
+        }
+        let Some(conditions) = rules["conditions"].as_array() else {
+            diagnostics.error(&path, "Missing <b>conditions</> array");
+            continue;
+        };
+        for (i, condition) in conditions.iter().enumerate() {
+            check_condition(&path, i, condition, diagnostics);
+        }
+    }
+    Ok(())
+}
+
+fn check_condition(path: &Path, i: usize, condition: &Value, diagnostics: &mut Diagnostics) {
+    let Some(components) = condition.as_object() else {
+        diagnostics.error(path, format!("Condition {i} must be an object"));
+        return;
+    };
+    if !SPAWN_POSITIONS
+        .iter()
+        .any(|key| components.contains_key(*key))
+    {
+        diagnostics.warning(
+            path,
+            format!(
+                "Condition {i} never spawns, it needs one of {}",
+                SPAWN_POSITIONS.join(", ")
+            ),
+        );
+    }
+    if let Some(weight) = components.get("minecraft:weight") {
+        if weight["default"].as_f64().is_none_or(|weight| weight < 0.0) {
+            diagnostics.error(
+                path,
+                format!("Condition {i} has an invalid <b>minecraft:weight</>, expected a positive <b>default</>"),
+            );
+        }
+    }
+    // Herds can be a list, to spawn different events with different sizes
+    let herds = match components.get("minecraft:herd") {
+        Some(Value::Array(herds)) => herds.iter().collect(),
+        Some(herd) => vec![herd],
+        None => vec![],
+    };
+    for herd in herds {
+        check_range(
+            path,
+            i,
+            "minecraft:herd",
+            herd,
+            "min_size",
+            "max_size",
+            diagnostics,
+        );
+    }
+    for component in ["minecraft:brightness_filter", "minecraft:height_filter"] {
+        if let Some(filter) = components.get(component) {
+            check_range(path, i, component, filter, "min", "max", diagnostics);
+        }
+    }
+    if let Some(filter) = components.get("minecraft:brightness_filter") {
+        let out_of_range = ["min", "max"]
+            .iter()
+            .filter_map(|key| filter[key].as_f64())
+            .any(|brightness| !(0.0..=15.0).contains(&brightness));
+        if out_of_range {
+            diagnostics.error(
+                path,
+                format!("Condition {i} has a <b>minecraft:brightness_filter</> outside of the light levels 0 to 15"),
+            );
+        }
+    }
+    if let Some(filter) = components.get("minecraft:difficulty_filter") {
+        let mut levels = vec![];
+        for key in ["min", "max"] {
+            let level = filter[key].as_str().unwrap_or_default();
+            match DIFFICULTIES.iter().position(|difficulty| *difficulty == level) {
+                Some(level) => levels.push(level),
+                None => diagnostics.error(
+                    path,
+                    format!(
+                        "Condition {i} has an invalid <b>minecraft:difficulty_filter</> {key}, expected one of {}",
+                        DIFFICULTIES.join(", ")
+                    ),
+                ),
+            }
+        }
+        if let [min, max] = levels[..] {
+            if min > max {
+                diagnostics.error(
+                    path,
+                    format!("Condition {i} has a <b>minecraft:difficulty_filter</> with <b>min</> harder than <b>max</>"),
+                );
+            }
+        }
+    }
+}
+
+/// Reports a component whose minimum is greater than its maximum, which never matches
+fn check_range(
+    path: &Path,
+    i: usize,
+    component: &str,
+    value: &Value,
+    min: &str,
+    max: &str,
+    diagnostics: &mut Diagnostics,
+) {
+    let (Some(min_value), Some(max_value)) = (value[min].as_f64(), value[max].as_f64()) else {
+        return;
+    };
+    if min_value > max_value {
+        diagnostics.error(
+            path,
+            format!(
+                "Condition {i} has a <b>{component}</> with <b>{min}</> greater than <b>{max}</>"
+            ),
+        );
+    }
+}
+
+/// Identifiers of the entities defined by the behavior pack
+fn get_entity_identifiers(bp: &Path) -> HashSet<String> {
+    json_files(&bp.join("entities"))
+        .into_iter()
+        .filter_map(|path| read_json::<Value>(path).ok())
+        .filter_map(|value| {
+            let id = value["minecraft:entity"]["description"]["identifier"].as_str()?;
+            Some(id.to_owned())
+        })
+        .collect()
+}
@@ -0,0 +1,263 @@
+use super::{Diagnostics, ValidationContext, TEXTURE_FORMATS};
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Checks the tiles of `terrain_texture.json` and the animations of `flipbook_textures.json`.
+/// Invalid entries don't fail to load, they corrupt the whole terrain atlas instead, which is hard
+/// to trace back to the entry that caused it.
+pub fn validate_texture_atlases(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(rp) = &context.rp else {
+        return Ok(());
+    };
+    let terrain_path = rp.join("textures").join("terrain_texture.json");
+    let flipbook_path = rp.join("textures").join("flipbook_textures.json");
+    let flipbooks = match flipbook_path.is_file() {
+        true => diagnostics.read_json(&flipbook_path),
+        false => None,
+    };
+    if flipbooks
+        .as_ref()
+        .is_some_and(|flipbooks| !flipbooks.is_array())
+    {
+        diagnostics.error(&flipbook_path, "Expected an array of flipbooks");
+    }
+    let flipbooks = flipbooks
+        .as_ref()
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    // Flipbook textures are the only tiles that can be taller than wide
+    let animated = flipbooks
+        .iter()
+        .filter_map(|flipbook| flipbook["flipbook_texture"].as_str())
+        .collect::<HashSet<_>>();
+
+    let mut tiles = None;
+    if terrain_path.is_file() {
+        if let Some(terrain) = diagnostics.read_json(&terrain_path) {
+            tiles = Some(check_terrain_atlas(
+                rp,
+                &terrain_path,
+                &terrain,
+                &animated,
+                diagnostics,
+            ));
+        }
+    }
+    if flipbook_path.is_file() {
+        check_flipbooks(rp, &flipbook_path, flipbooks, tiles.as_ref(), diagnostics);
+    }
+    Ok(())
+}
+
+/// Returns the names of the tiles in the atlas
+fn check_terrain_atlas(
+    rp: &Path,
+    path: &Path,
+    terrain: &Value,
+    animated: &HashSet<&str>,
+    diagnostics: &mut Diagnostics,
+) -> HashSet<String> {
+    let mip_levels = terrain["num_mip_levels"].as_u64().unwrap_or(0);
+    let Some(texture_data) = terrain["texture_data"].as_object() else {
+        diagnostics.error(path, "Missing <b>texture_data</> object");
+        return HashSet::new();
+    };
+    // The same texture is often used by many tiles, only report it for the first one
+    let mut checked = HashSet::new();
+    for (tile, entry) in texture_data {
+        let mut textures = vec![];
+        collect_texture_paths(&entry["textures"], &mut textures);
+        if textures.is_empty() {
+            diagnostics.error(path, format!("Tile <b>{tile}</> has no textures"));
+        }
+        for texture in textures {
+            if !checked.insert(texture) {
+                continue;
+            }
+            let Some(file) = find_texture(rp, texture) else {
+                diagnostics.warning(
+                    path,
+                    format!("Tile <b>{tile}</> references <b>{texture}</>, which is not in the resource pack, ignore if it's a vanilla texture"),
+                );
+                continue;
+            };
+            let Some((width, height)) = get_image_size(&file) else {
+                continue;
+            };
+            if width != height && !animated.contains(texture) {
+                diagnostics.error(
+                    &file,
+                    format!("Tile <b>{tile}</> is {width}x{height}, atlas tiles must be square unless they're a flipbook texture"),
+                );
+            }
+            if !width.is_power_of_two() {
+                diagnostics.warning(
+                    &file,
+                    format!("Tile <b>{tile}</> is {width} pixels wide, which is not a power of two and gets blurred by mipmapping"),
+                );
+            } else if u64::from(width.trailing_zeros()) < mip_levels {
+                diagnostics.warning(
+                    &file,
+                    format!("Tile <b>{tile}</> is {width} pixels wide, which is too small for the {mip_levels} mip levels of the atlas"),
+                );
+            }
+        }
+    }
+    texture_data.keys().cloned().collect()
+}
+
+fn check_flipbooks(
+    rp: &Path,
+    path: &Path,
+    flipbooks: &[Value],
+    tiles: Option<&HashSet<String>>,
+    diagnostics: &mut Diagnostics,
+) {
+    let mut seen = HashSet::new();
+    for (i, flipbook) in flipbooks.iter().enumerate() {
+        let Some(tile) = flipbook["atlas_tile"].as_str() else {
+            diagnostics.error(path, format!("Flipbook {i} has no <b>atlas_tile</>"));
+            continue;
+        };
+        let Some(texture) = flipbook["flipbook_texture"].as_str() else {
+            diagnostics.error(
+                path,
+                format!("Flipbook of <b>{tile}</> has no <b>flipbook_texture</>"),
+            );
+            continue;
+        };
+        if !seen.insert((tile, flipbook["atlas_index"].as_u64())) {
+            diagnostics.error(
+                path,
+                format!("Tile <b>{tile}</> has more than one flipbook, the game only plays one"),
+            );
+        }
+        if tiles.is_none_or(|tiles| !tiles.contains(tile)) {
+            diagnostics.warning(
+                path,
+                format!("Flipbook tile <b>{tile}</> is not in terrain_texture.json, ignore if it's a vanilla tile"),
+            );
+        }
+        if let Some(ticks) = flipbook.get("ticks_per_frame") {
+            if ticks.as_u64().is_none_or(|ticks| ticks == 0) {
+                diagnostics.error(
+                    path,
+                    format!("Flipbook of <b>{tile}</> has an invalid <b>ticks_per_frame</>, expected a positive integer"),
+                );
+            }
+        }
+        if let Some(replicate) = flipbook["replicate"].as_u64() {
+            if !replicate.is_power_of_two() {
+                diagnostics.error(
+                    path,
+                    format!("Flipbook of <b>{tile}</> replicates {replicate} times, which must be a power of two"),
+                );
+            }
+        }
+
+        let Some(file) = find_texture(rp, texture) else {
+            diagnostics.warning(
+                path,
+                format!("Flipbook of <b>{tile}</> references <b>{texture}</>, which is not in the resource pack, ignore if it's a vanilla texture"),
+            );
+            continue;
+        };
+        let Some((width, height)) = get_image_size(&file) else {
+            continue;
+        };
+        if width == 0 || height % width != 0 {
+            diagnostics.error(
+                &file,
+                format!("Flipbook of <b>{tile}</> is {width}x{height}, the height must be a multiple of the width to split it into square frames"),
+            );
+            continue;
+        }
+        if !width.is_power_of_two() {
+            diagnostics.warning(
+                &file,
+                format!("Flipbook of <b>{tile}</> has {width} pixel wide frames, which is not a power of two and gets blurred by mipmapping"),
+            );
+        }
+        let frame_count = u64::from(height / width);
+        let frames = match &flipbook["frames"] {
+            Value::Array(frames) => frames.iter().collect(),
+            Value::Null => vec![],
+            frame => vec![frame],
+        };
+        for frame in frames {
+            match frame.as_u64() {
+                Some(frame) if frame < frame_count => {}
+                Some(frame) => diagnostics.error(
+                    path,
+                    format!("Flipbook of <b>{tile}</> uses frame {frame}, but the texture only has {frame_count} frames"),
+                ),
+                None => diagnostics.error(
+                    path,
+                    format!("Flipbook of <b>{tile}</> has an invalid frame {frame}, expected a frame index"),
+                ),
+            }
+        }
+    }
+}
+
+/// Texture paths of a tile, which are either a path, an object with a `path`, an object with
+/// weighted `variations`, or an array of those for each variant.
+pub fn collect_texture_paths<'a>(value: &'a Value, paths: &mut Vec<&'a str>) {
+    match value {
+        Value::String(path) => paths.push(path),
+        Value::Array(values) => {
+            for value in values {
+                collect_texture_paths(value, paths);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(path) = object.get("path") {
+                collect_texture_paths(path, paths);
+            }
+            if let Some(variations) = object.get("variations") {
+                collect_texture_paths(variations, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn find_texture(rp: &Path, texture: &str) -> Option<PathBuf> {
+    TEXTURE_FORMATS
+        .iter()
+        .map(|ext| rp.join(format!("{texture}.{ext}")))
+        .find(|path| path.is_file())
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Width and height from the header of a PNG or TGA image. Other formats, and images that can't
+/// be read, have none.
+pub fn get_image_size(path: &Path) -> Option<(u32, u32)> {
+    let mut header = [0; 24];
+    let len = fs::File::open(path).ok()?.read(&mut header).ok()?;
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" if len == 24 && header.starts_with(PNG_SIGNATURE) && &header[12..16] == b"IHDR" => {
+            let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+            let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+            Some((width, height))
+        }
+        "tga" if len >= 18 => {
+            let width = u16::from_le_bytes(header[12..14].try_into().ok()?);
+            let height = u16::from_le_bytes(header[14..16].try_into().ok()?);
+            Some((width.into(), height.into()))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,125 @@
+use super::{json_files, Diagnostics, ValidationContext};
+use anyhow::Result;
+use serde_json::Value;
+use std::path::Path;
+
+/// Villagers only take up to two stacks of items for a trade
+const MAX_WANTED_ITEMS: usize = 2;
+
+/// Checks the structure of the trade tables: their tiers, groups and the items of each trade.
+/// Broken trades are silently left out of the villager's offers.
+pub fn validate_trade_tables(
+    context: &ValidationContext,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let Some(bp) = &context.bp else {
+        return Ok(());
+    };
+    for path in json_files(&bp.join("trading")) {
+        let Some(value) = diagnostics.read_json(&path) else {
+            continue;
+        };
+        let Some(tiers) = value["tiers"].as_array().filter(|tiers| !tiers.is_empty()) else {
+            diagnostics.error(&path, "Missing <b>tiers</> array");
+            continue;
+        };
+        let mut previous_exp = 0;
+        for (i, tier) in tiers.iter().enumerate() {
+            if let Some(exp) = tier["total_exp_required"].as_u64() {
+                if exp < previous_exp {
+                    diagnostics.warning(
+                        &path,
+                        format!("Tier {i} requires less experience than the tier before it, tiers unlock in order"),
+                    );
+                }
+                previous_exp = exp;
+            }
+            let trades = match (&tier["trades"], &tier["groups"]) {
+                (Value::Array(trades), _) => trades.iter().collect::<Vec<_>>(),
+                (_, Value::Array(groups)) => {
+                    for (j, group) in groups.iter().enumerate() {
+                        if !group["trades"].is_array() {
+                            diagnostics.error(
+                                &path,
+                                format!("Group {j} of tier {i} has no <b>trades</> array"),
+                            );
+                        }
+                    }
+                    groups
+                        .iter()
+                        .filter_map(|group| group["trades"].as_array())
+                        .flatten()
+                        .collect()
+                }
+                _ => {
+                    diagnostics.error(
+                        &path,
+                        format!("Tier {i} has no <b>trades</> or <b>groups</> array"),
+                    );
+                    continue;
+                }
+            };
+            for trade in trades {
+                check_trade(&path, i, trade, diagnostics);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_trade(path: &Path, tier: usize, trade: &Value, diagnostics: &mut Diagnostics) {
+    for key in ["wants", "gives"] {
+        let Some(items) = trade[key].as_array().filter(|items| !items.is_empty()) else {
+            diagnostics.error(
+                path,
+                format!("A trade of tier {tier} has no <b>{key}</> items"),
+            );
+            continue;
+        };
+        if key == "wants" && items.len() > MAX_WANTED_ITEMS {
+            diagnostics.error(
+                path,
+                format!(
+                    "A trade of tier {tier} wants {} items, villagers take at most {MAX_WANTED_ITEMS}",
+                    items.len()
+                ),
+            );
+        }
+        for item in items {
+            check_item(path, tier, key, item, diagnostics);
+        }
+    }
+}
+
+fn check_item(path: &Path, tier: usize, key: &str, item: &Value, diagnostics: &mut Diagnostics) {
+    // One of the items of a `choice` is picked when the trade is offered
+    if let Some(choices) = item["choice"].as_array() {
+        for choice in choices {
+            check_item(path, tier, key, choice, diagnostics);
+        }
+        return;
+    }
+    let Some(name) = item["item"].as_str() else {
+        diagnostics.error(
+            path,
+            format!("A trade of tier {tier} has an entry in <b>{key}</> without an <b>item</> or <b>choice</>"),
+        );
+        return;
+    };
+    let quantity = &item["quantity"];
+    let is_valid = match quantity {
+        Value::Null => true,
+        Value::Number(quantity) => quantity.as_f64().is_some_and(|quantity| quantity > 0.0),
+        Value::Object(_) => match (quantity["min"].as_f64(), quantity["max"].as_f64()) {
+            (Some(min), Some(max)) => min > 0.0 && min <= max,
+            _ => false,
+        },
+        _ => false,
+    };
+    if !is_valid {
+        diagnostics.error(
+            path,
+            format!("<b>{name}</> in a trade of tier {tier} has an invalid quantity, expected a positive number or a <b>min</> and <b>max</>"),
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use super::{get_vanilla_cache_dir, Session, Subprocess, UserConfig};
+use crate::fs::empty_dir;
+use crate::{debug, info, warn};
+use anyhow::{bail, Context, Result};
+use semver::Version;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Picks the release of bedrock-samples matching the `min_engine_version` of a pack: the latest
+/// release of the same or an older version. Previews are never picked.
+pub fn resolve_vanilla_samples_tag(min_engine_version: Option<&Version>) -> Result<String> {
+    let mut tags = list_release_tags()?;
+    tags.sort_by_key(|(version, _)| version.to_owned());
+    let tag = match min_engine_version {
+        Some(min) => tags
+            .into_iter()
+            .rev()
+            .find(|(version, _)| version[..3] <= [min.major, min.minor, min.patch][..]),
+        None => tags.pop(),
+    };
+    match (tag, min_engine_version) {
+        (Some((_, tag)), _) => Ok(tag),
+        (None, Some(min)) => {
+            bail!("No release of bedrock-samples matches min_engine_version {min}")
+        }
+        (None, None) => bail!("Failed to find a release of bedrock-samples"),
+    }
+}
+
+/// Release tags of bedrock-samples like `v1.21.40.3`, with their version numbers. The tags are
+/// cached so files that were already fetched can be used offline.
+fn list_release_tags() -> Result<Vec<(Vec<u64>, String)>> {
+    let url = UserConfig::vanilla_samples_url();
+    let cache = get_vanilla_cache_dir()?.join("tags.txt");
+    let output = Subprocess::new("git")
+        .args(["ls-remote", "--tags", &url])
+        .run_silent()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned());
+    let output = match output {
+        Ok(output) => {
+            fs::create_dir_all(get_vanilla_cache_dir()?)?;
+            fs::write(&cache, &output)?;
+            output
+        }
+        Err(e) => match fs::read_to_string(&cache) {
+            Ok(output) => {
+                warn!("Failed to list the releases of bedrock-samples, using the cached list");
+                output
+            }
+            Err(_) => return Err(e.context(format!("Failed to list the releases of `{url}`"))),
+        },
+    };
+    let tags = output
+        .lines()
+        .filter_map(|line| line.split("refs/tags/").nth(1))
+        .filter(|tag| !tag.ends_with("^{}"))
+        .filter_map(|tag| {
+            let version = tag
+                .strip_prefix('v')?
+                .split('.')
+                .map(|part| part.parse().ok())
+                .collect::<Option<Vec<u64>>>()?;
+            (version.len() == 4).then(|| (version, tag.to_owned()))
+        })
+        .collect();
+    Ok(tags)
+}
+
+/// Fetches a file or directory of bedrock-samples at the given tag or branch, only downloading
+/// the requested files. Branches are updated on every fetch. Returns its path in the cache.
+pub fn fetch_vanilla_samples(git_ref: &str, path: &str) -> Result<PathBuf> {
+    let url = UserConfig::vanilla_samples_url();
+    let path = path.trim_matches('/');
+    if git_ref.is_empty() {
+        bail!("The bedrock-samples version is empty");
+    }
+    check_cache_path("Version", git_ref)?;
+    check_cache_path("Path", path)?;
+    let repo_dir = get_vanilla_cache_dir()?.join(git_ref);
+    // Other projects may be fetching from the same release
+    let mut repo_lock = Session::lock_shared(&repo_dir)?;
+    let git = |args: &[&str]| {
+        Subprocess::new("git")
+            .args(args)
+            .current_dir(&repo_dir)
+            .run_silent()
+    };
+    if !repo_dir.join(".git").is_dir() {
+        // Leftovers of a failed clone
+        empty_dir(&repo_dir)?;
+        info!("Cloning bedrock-samples at <b>{git_ref}</>");
+        // Only the files that are asked for are downloaded
+        git(&[
+            "clone",
+            "--depth=1",
+            "--filter=blob:none",
+            "--no-checkout",
+            "--branch",
+            git_ref,
+            &url,
+            ".",
+        ])
+        .with_context(|| format!("Failed to clone `{url}` at `{git_ref}`"))?;
+        git(&["sparse-checkout", "set", "--no-cone"])?;
+        git(&["checkout"]).with_context(|| format!("Failed to checkout `{git_ref}`"))?;
+    } else if git(&["symbolic-ref", "--quiet", "HEAD"]).is_ok() {
+        // Tags are checked out detached and never change, branches move on
+        debug!("Updating bedrock-samples at {git_ref}");
+        let fetched = git(&[
+            "fetch",
+            "--depth=1",
+            "--filter=blob:none",
+            "origin",
+            git_ref,
+        ])
+        .and_then(|_| git(&["reset", "--hard", "FETCH_HEAD"]));
+        if let Err(e) = fetched {
+            warn!("Failed to update bedrock-samples at <b>{git_ref}</>, using the cached files");
+            debug!("{e:#}");
+        }
+    }
+    // Checking the path alone would miss the rest of a directory after one of its files
+    let pattern = format!("/{path}");
+    let patterns = git(&["sparse-checkout", "list"])?;
+    if !String::from_utf8_lossy(&patterns.stdout)
+        .lines()
+        .any(|line| line == pattern)
+    {
+        debug!("Checkout path: {path}");
+        git(&["sparse-checkout", "add", &pattern])
+            .with_context(|| format!("Failed to download `{path}`"))?;
+    }
+    let target = repo_dir.join(path);
+    repo_lock.unlock()?;
+    if !target.exists() {
+        bail!(
+            "Path not found in bedrock-samples\n\
+             <yellow> >></> Path: {path}\n\
+             <yellow> >></> Version: {git_ref}"
+        );
+    }
+    Ok(target)
+}
+
+/// Rejects a path or ref that would resolve outside of the cache directory
+fn check_cache_path(name: &str, value: &str) -> Result<()> {
+    let escapes = Path::new(value)
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        bail!(
+            "Invalid bedrock-samples path, `..` and absolute paths are not allowed\n\
+             <yellow> >></> {name}: {value}"
+        );
+    }
+    Ok(())
+}
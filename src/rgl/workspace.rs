@@ -0,0 +1,131 @@
+use super::Subprocess;
+use crate::fs::read_json;
+use crate::{error, info, log};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, env, thread};
+
+/// Multiple rgl projects that are built together, defined in `rgl-workspace.json`
+#[derive(Serialize, Deserialize)]
+pub struct Workspace {
+    members: Vec<WorkspaceMember>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WorkspaceMember {
+    Path(String),
+    Detailed {
+        path: String,
+        #[serde(default, rename = "dependsOn")]
+        depends_on: Vec<String>,
+    },
+}
+
+impl WorkspaceMember {
+    pub fn path(&self) -> &str {
+        match self {
+            WorkspaceMember::Path(path) => path,
+            WorkspaceMember::Detailed { path, .. } => path,
+        }
+    }
+
+    pub fn depends_on(&self) -> &[String] {
+        match self {
+            WorkspaceMember::Path(_) => &[],
+            WorkspaceMember::Detailed { depends_on, .. } => depends_on,
+        }
+    }
+}
+
+impl Workspace {
+    pub fn load() -> Result<Self> {
+        read_json("./rgl-workspace.json")
+    }
+
+    pub fn members(&self) -> &[WorkspaceMember] {
+        &self.members
+    }
+
+    /// Groups members into batches where each member only depends on members of previous batches.
+    pub fn get_batches(&self) -> Result<Vec<Vec<&WorkspaceMember>>> {
+        let paths: HashSet<_> = self.members.iter().map(|m| m.path()).collect();
+        for member in &self.members {
+            if let Some(dep) = member
+                .depends_on()
+                .iter()
+                .find(|d| !paths.contains(d.as_str()))
+            {
+                bail!(
+                    "Workspace member <b>{}</> depends on <b>{dep}</>, which is not a member",
+                    member.path()
+                );
+            }
+        }
+        let mut done = HashSet::new();
+        let mut batches = vec![];
+        while done.len() < self.members.len() {
+            let batch: Vec<_> = self
+                .members
+                .iter()
+                .filter(|m| !done.contains(m.path()))
+                .filter(|m| m.depends_on().iter().all(|d| done.contains(d.as_str())))
+                .collect();
+            if batch.is_empty() {
+                bail!("Found circular dependency between workspace members");
+            }
+            done.extend(batch.iter().map(|m| m.path()));
+            batches.push(batch);
+        }
+        Ok(batches)
+    }
+
+    /// Runs rgl with the given arguments in every member, in dependency order. Members that don't
+    /// depend on each other run in parallel.
+    pub fn run(&self, args: &[String]) -> Result<()> {
+        for batch in self.get_batches()? {
+            run_parallel(&batch, args)?;
+        }
+        Ok(())
+    }
+
+    /// Runs rgl with the given arguments in every member at the same time, e.g. to watch them all.
+    pub fn run_all(&self, args: &[String]) -> Result<()> {
+        run_parallel(&self.members.iter().collect::<Vec<_>>(), args)
+    }
+}
+
+fn run_parallel(members: &[&WorkspaceMember], args: &[String]) -> Result<()> {
+    let exe = env::current_exe()?;
+    let failed: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = members
+            .iter()
+            .map(|member| {
+                let exe = &exe;
+                s.spawn(move || {
+                    info!("Running workspace member <b>{}</>", member.path());
+                    let result = Subprocess::new(exe)
+                        .args(args)
+                        .current_dir(member.path())
+                        .run();
+                    (member.path(), result)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| match handle.join().unwrap() {
+                (path, Err(e)) => Some((path, e)),
+                _ => None,
+            })
+            .collect()
+    });
+    if failed.is_empty() {
+        return Ok(());
+    }
+    for (path, e) in &failed {
+        error!("Workspace member <b>{path}</> failed");
+        e.chain().for_each(|e| log!("<red>[+]</> {e}"));
+    }
+    bail!("{} workspace member(s) failed", failed.len())
+}
@@ -0,0 +1,138 @@
+use super::{enable_experiments, Config, Export, ExportPaths, WorldRef};
+use crate::fs::{read_json, rimraf, write_json};
+use crate::info;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Pack attached to a world by rgl, removed again when the project is cleaned
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachedPack {
+    /// The `world_behavior_packs.json` or `world_resource_packs.json` file of the world
+    world_file: PathBuf,
+    pack_id: String,
+}
+
+fn get_attached_packs_path() -> PathBuf {
+    PathBuf::from(".regolith").join("attached_packs.json")
+}
+
+/// Attaches the development packs exported by a profile to a world, so the latest build is
+/// active in it without adding the packs manually. Packs that are already attached only get
+/// their version updated.
+pub fn attach_packs(config: &Config, profile_name: &str, world: &WorldRef) -> Result<()> {
+    let profile = config.get_profile(profile_name)?;
+    if !matches!(profile.export, Export::Development(_)) {
+        bail!("Attaching packs to a world requires the <b>development</> export target");
+    }
+    let world_dir = world.find(profile.export.get_build().as_ref())?;
+    let (target_bp, target_rp) = profile.export.get_paths(config, profile_name)?;
+
+    let path = get_attached_packs_path();
+    let mut attached: Vec<AttachedPack> = match path.is_file() {
+        true => read_json(&path)?,
+        false => vec![],
+    };
+    let packs = [
+        (
+            config.get_behavior_pack(),
+            target_bp,
+            "world_behavior_packs.json",
+        ),
+        (
+            config.get_resource_pack(),
+            target_rp,
+            "world_resource_packs.json",
+        ),
+    ];
+    for (pack, target, file_name) in packs {
+        if pack.is_none() {
+            continue;
+        }
+        let (pack_id, version) = get_pack_header(&target)?;
+        let world_file = world_dir.join(file_name);
+        if add_world_pack(&world_file, &pack_id, version)? {
+            attached.push(AttachedPack {
+                world_file,
+                pack_id,
+            });
+        }
+    }
+    write_json(&path, &attached)?;
+    // The world may have been picked from a list, or selected by its folder
+    let world_name = std::fs::read_to_string(world_dir.join("levelname.txt"))
+        .unwrap_or_else(|_| world.to_string());
+    info!("Attached packs to world <b>{world_name}</>");
+    enable_experiments(&world_dir, &profile.experiments)
+}
+
+/// Removes the packs attached by [`attach_packs`] from their worlds.
+pub fn detach_packs() -> Result<()> {
+    let path = get_attached_packs_path();
+    if !path.is_file() {
+        return Ok(());
+    }
+    let attached: Vec<AttachedPack> = read_json(&path)?;
+    for pack in &attached {
+        // The world may have been deleted since
+        if !pack.world_file.is_file() {
+            continue;
+        }
+        let mut world_packs: Vec<Value> = read_json(&pack.world_file)?;
+        world_packs.retain(|entry| entry["pack_id"] != pack.pack_id.as_str());
+        write_json(&pack.world_file, &world_packs)?;
+    }
+    if !attached.is_empty() {
+        info!("Detached packs from worlds");
+    }
+    rimraf(path)
+}
+
+/// Adds a pack to the `world_behavior_packs.json` or `world_resource_packs.json` file of a world.
+/// Returns `false` if the pack was already in the file, in which case its version is updated.
+pub fn add_world_pack(world_file: &Path, pack_id: &str, version: Value) -> Result<bool> {
+    let mut world_packs: Vec<Value> = match world_file.is_file() {
+        true => read_json(world_file)?,
+        false => vec![],
+    };
+    let entry = world_packs
+        .iter_mut()
+        .find(|entry| entry["pack_id"] == pack_id);
+    let is_added = match entry {
+        Some(entry) => {
+            entry["version"] = version;
+            false
+        }
+        None => {
+            world_packs.push(json!({ "pack_id": pack_id, "version": version }));
+            true
+        }
+    };
+    write_json(world_file, &world_packs)?;
+    Ok(is_added)
+}
+
+/// UUID and version from the header of a pack manifest
+pub fn get_pack_header(pack: &Path) -> Result<(String, Value)> {
+    let manifest_path = pack.join("manifest.json");
+    let manifest: Value = read_json(&manifest_path)?;
+    let header = &manifest["header"];
+    let Some(uuid) = header["uuid"].as_str() else {
+        bail!(
+            "Pack manifest has no UUID\n\
+             <yellow> >></> Path: {}",
+            manifest_path.display()
+        );
+    };
+    let version = &header["version"];
+    if version.is_null() {
+        bail!(
+            "Pack manifest has no version\n\
+             <yellow> >></> Path: {}",
+            manifest_path.display()
+        );
+    }
+    Ok((uuid.to_owned(), version.to_owned()))
+}